@@ -0,0 +1,15 @@
+use serde_json::Value;
+use tauri::{AppHandle, Emitter};
+
+use dacm_lib::event_sink::EventSink;
+
+/// Sends library events to the app's windows.
+pub struct AppEvents<'a>(pub &'a AppHandle);
+
+impl EventSink for AppEvents<'_> {
+    fn emit_value(&self, event: &str, payload: Value) {
+        if let Err(e) = self.0.emit(event, payload) {
+            eprintln!("[events] Failed to emit {event}: {e}");
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use std::path::PathBuf;
+
+use tauri::{AppHandle, State};
+
+use dacm_lib::backup;
+use dacm_lib::backup_models::{BackupInfo, ExportBundle, ExportSummary, ImportReport};
+use dacm_lib::db::{self, DbState};
+use dacm_lib::error::{AppError, ResultExt};
+use dacm_lib::settings_registry;
+
+use crate::app_events::AppEvents;
+
+#[tauri::command]
+pub async fn backup_database(
+    state: State<'_, DbState>,
+    dest: String,
+) -> Result<BackupInfo, AppError> {
+    let db_path = state.path.clone();
+    db::blocking(move || {
+        let size_bytes = backup::backup_to(&db_path, &PathBuf::from(&dest))?;
+        Ok(BackupInfo {
+            path: dest,
+            size_bytes,
+        })
+    })
+    .await
+}
+
+/// Write workspaces, projects and settings to a JSON file that
+/// `import_database` can load on another machine.
+#[tauri::command]
+pub async fn export_database(
+    state: State<'_, DbState>,
+    dest: String,
+) -> Result<ExportSummary, AppError> {
+    state
+        .run(move |conn| {
+            let bundle = backup::export(conn).context("Failed to export data")?;
+            let json = serde_json::to_string_pretty(&bundle)?;
+            std::fs::write(&dest, json).context("Failed to write export file")?;
+
+            Ok(ExportSummary {
+                path: dest,
+                workspaces: bundle.workspaces.len(),
+                projects: bundle.projects.len(),
+                settings: bundle.settings.len(),
+                plans: bundle.plans.len(),
+                review_comments: bundle.review_comments.len(),
+                decisions: bundle.decisions.len(),
+            })
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn import_database(
+    app_handle: AppHandle,
+    state: State<'_, DbState>,
+    src: String,
+) -> Result<ImportReport, AppError> {
+    let report = state
+        .run(move |conn| {
+            let json = std::fs::read_to_string(&src).context("Failed to read import file")?;
+            let bundle: ExportBundle =
+                serde_json::from_str(&json).context("Import file is not a valid export")?;
+            backup::import(conn, &bundle)
+        })
+        .await?;
+
+    settings_registry::broadcast(&AppEvents(&app_handle), &report.settings_changed);
+    Ok(report)
+}
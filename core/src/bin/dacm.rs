@@ -0,0 +1,490 @@
+//! `dacm`: drive the running DACM app from a terminal or from inside an
+//! agent. Every command is one JSON-RPC request over the app's socket (see
+//! `rpc.rs`); `--json` prints the result as JSON for scripts.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use serde_json::{json, Map, Value};
+
+/// Must match `identifier` in tauri.conf.json, which names the app data dir.
+const APP_IDENTIFIER: &str = "com.dacm.desktop";
+const SOCKET_NAME: &str = "dacm.sock";
+
+/// Exit status of `wait` when it gives up, as with timeout(1).
+const TIMED_OUT: u8 = 124;
+
+const USAGE: &str = "\
+Usage: dacm [--json] [--socket PATH] <command>
+
+Commands:
+  status                               Workspaces, projects, sessions and the queue
+  workspace list
+  project list
+  project new <name> [--description TEXT] [--workspace ID|NAME|PATH]
+                                       Without --workspace, the one containing
+                                       the current directory
+  session list
+  session spawn <project> [--prompt TEXT] [--cli CMD] [--model MODEL]
+                [--env KEY=VALUE]...
+  session kill <project>
+  send <project> <text> [--no-enter]   Type into the project's session
+  wait <project> [--timeout SECS]      Wait for the project's session to end,
+                                       exiting with its exit code
+  mcp --project <project> [--session ID]
+                                       Serve DACM's MCP tools on stdio for an
+                                       agent working on the project
+
+Projects are given by id or name. The socket defaults to $DACM_SOCKET, then
+the app's data directory.";
+
+struct Invocation {
+    method: &'static str,
+    params: Value,
+}
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() || take_flag(&mut args, "--help") || take_flag(&mut args, "-h") {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+    let json_output = take_flag(&mut args, "--json");
+    let socket = match take_option(&mut args, "--socket") {
+        Ok(socket) => socket.map(PathBuf::from).or_else(default_socket),
+        Err(e) => return usage_error(&e),
+    };
+
+    if args[0] == "mcp" {
+        args.remove(0);
+        let Some(socket) = socket else {
+            eprintln!("dacm: can't find the app's data directory; pass --socket");
+            return ExitCode::FAILURE;
+        };
+        return serve_mcp(&socket, args);
+    }
+
+    let invocation = match parse_command(args) {
+        Ok(invocation) => invocation,
+        Err(e) => return usage_error(&e),
+    };
+    let Some(socket) = socket else {
+        eprintln!("dacm: can't find the app's data directory; pass --socket");
+        return ExitCode::FAILURE;
+    };
+
+    let response = match request(&socket, invocation.method, invocation.params) {
+        Ok(response) => response,
+        Err(e) => {
+            eprintln!("dacm: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if let Some(error) = response.get("error") {
+        if json_output {
+            println!("{}", pretty(&json!({ "error": error })));
+        } else {
+            let message = error["message"].as_str().unwrap_or("request failed");
+            eprintln!("dacm: {message}");
+        }
+        return ExitCode::FAILURE;
+    }
+
+    let result = response.get("result").cloned().unwrap_or(Value::Null);
+    if json_output {
+        println!("{}", pretty(&result));
+    } else {
+        print_result(invocation.method, &result);
+    }
+    exit_code(invocation.method, &result)
+}
+
+fn parse_command(mut args: Vec<String>) -> Result<Invocation, String> {
+    let command = args.remove(0);
+    let sub = if matches!(command.as_str(), "workspace" | "project" | "session") {
+        if args.is_empty() {
+            return Err(format!("'{command}' needs a subcommand"));
+        }
+        Some(args.remove(0))
+    } else {
+        None
+    };
+
+    let mut params = Map::new();
+    let method = match (command.as_str(), sub.as_deref()) {
+        ("status", None) => "status",
+        ("workspace", Some("list")) => "workspace.list",
+        ("project", Some("list")) => "project.list",
+        ("project", Some("new")) => {
+            insert_option(&mut params, &mut args, "--description", "description")?;
+            insert_option(&mut params, &mut args, "--workspace", "workspace")?;
+            if let Ok(cwd) = std::env::current_dir() {
+                params.insert("cwd".into(), json!(cwd.to_string_lossy()));
+            }
+            params.insert("name".into(), json!(positional(&mut args, "name")?));
+            "project.new"
+        }
+        ("session", Some("list")) => "session.list",
+        ("session", Some("spawn")) => {
+            insert_option(&mut params, &mut args, "--prompt", "prompt")?;
+            insert_option(&mut params, &mut args, "--cli", "cli")?;
+            insert_option(&mut params, &mut args, "--model", "model")?;
+            let mut env = Map::new();
+            while let Some(pair) = take_option(&mut args, "--env")? {
+                let (key, value) = pair
+                    .split_once('=')
+                    .ok_or_else(|| format!("--env expects KEY=VALUE, got '{pair}'"))?;
+                env.insert(key.to_string(), json!(value));
+            }
+            params.insert("env".into(), Value::Object(env));
+            params.insert("project".into(), json!(positional(&mut args, "project")?));
+            "session.spawn"
+        }
+        ("session", Some("kill")) => {
+            params.insert("project".into(), json!(positional(&mut args, "project")?));
+            "session.kill"
+        }
+        ("send", None) => {
+            let enter = !take_flag(&mut args, "--no-enter");
+            params.insert("project".into(), json!(positional(&mut args, "project")?));
+            // Unquoted words are sent as one line, like echo(1)
+            let text = std::mem::take(&mut args).join(" ");
+            params.insert("text".into(), json!(text));
+            params.insert("enter".into(), json!(enter));
+            "session.send"
+        }
+        ("wait", None) => {
+            if let Some(secs) = take_option(&mut args, "--timeout")? {
+                let secs: u64 = secs
+                    .parse()
+                    .map_err(|_| format!("--timeout expects seconds, got '{secs}'"))?;
+                params.insert("timeout_secs".into(), json!(secs));
+            }
+            params.insert("project".into(), json!(positional(&mut args, "project")?));
+            "session.wait"
+        }
+        (command, None) => return Err(format!("unknown command '{command}'")),
+        (command, Some(sub)) => return Err(format!("unknown command '{command} {sub}'")),
+    };
+
+    if let Some(extra) = args.first() {
+        return Err(format!("unexpected argument '{extra}'"));
+    }
+    Ok(Invocation {
+        method,
+        params: Value::Object(params),
+    })
+}
+
+fn default_socket() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("DACM_SOCKET").filter(|p| !p.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+    dirs::data_dir().map(|dir| dir.join(APP_IDENTIFIER).join(SOCKET_NAME))
+}
+
+#[cfg(unix)]
+fn request(socket: &std::path::Path, method: &str, params: Value) -> Result<Value, String> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let mut stream = UnixStream::connect(socket).map_err(|e| {
+        format!(
+            "can't reach DACM at {} ({e}); is the app running?",
+            socket.display()
+        )
+    })?;
+    let mut line =
+        json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }).to_string();
+    line.push('\n');
+    stream
+        .write_all(line.as_bytes())
+        .map_err(|e| format!("failed to send request: {e}"))?;
+
+    let mut response = String::new();
+    BufReader::new(stream)
+        .read_line(&mut response)
+        .map_err(|e| format!("failed to read response: {e}"))?;
+    if response.is_empty() {
+        return Err("the app closed the connection".to_string());
+    }
+    serde_json::from_str(&response).map_err(|e| format!("bad response from the app: {e}"))
+}
+
+#[cfg(not(unix))]
+fn request(_socket: &std::path::Path, _method: &str, _params: Value) -> Result<Value, String> {
+    Err("the command line interface needs Unix sockets".to_string())
+}
+
+/// Relay MCP between an agent on stdio and the app. Nothing but protocol
+/// messages may go to stdout, so problems are reported on stderr.
+#[cfg(unix)]
+fn serve_mcp(socket: &std::path::Path, mut args: Vec<String>) -> ExitCode {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let parsed = take_option(&mut args, "--project").and_then(|project| {
+        let session = take_option(&mut args, "--session")?;
+        match (project, args.first()) {
+            (_, Some(extra)) => Err(format!("unexpected argument '{extra}'")),
+            (None, None) => Err("mcp needs --project".to_string()),
+            (Some(project), None) => Ok((project, session)),
+        }
+    });
+    let (project, session) = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => return usage_error(&e),
+    };
+
+    let mut stream = match UnixStream::connect(socket) {
+        Ok(stream) => stream,
+        Err(e) => {
+            eprintln!("dacm: can't reach DACM at {} ({e})", socket.display());
+            return ExitCode::FAILURE;
+        }
+    };
+    let attach = json!({
+        "jsonrpc": "2.0",
+        "id": 0,
+        "method": "mcp.attach",
+        "params": { "project": project, "session": session },
+    });
+    let mut reader = match stream.try_clone() {
+        Ok(reader) => BufReader::new(reader),
+        Err(e) => {
+            eprintln!("dacm: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut response = String::new();
+    let attached = writeln!(stream, "{attach}")
+        .and_then(|_| reader.read_line(&mut response))
+        .map_err(|e| e.to_string())
+        .and_then(|_| serde_json::from_str::<Value>(&response).map_err(|e| e.to_string()));
+    match attached {
+        Ok(response) if response.get("error").is_none() => {}
+        Ok(response) => {
+            let message = response["error"]["message"]
+                .as_str()
+                .unwrap_or("attach failed");
+            eprintln!("dacm: {message}");
+            return ExitCode::FAILURE;
+        }
+        Err(e) => {
+            eprintln!("dacm: failed to attach: {e}");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    // Replies go straight out; once the app hangs up there's nothing left
+    // to serve
+    let relay = std::thread::spawn(move || {
+        let mut stdout = std::io::stdout();
+        for line in reader.lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if writeln!(stdout, "{line}")
+                .and_then(|_| stdout.flush())
+                .is_err()
+            {
+                break;
+            }
+        }
+        std::process::exit(0);
+    });
+
+    for line in std::io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if writeln!(stream, "{line}").is_err() {
+            break;
+        }
+    }
+    // Let the app answer what's in flight before going
+    let _ = stream.shutdown(std::net::Shutdown::Write);
+    let _ = relay.join();
+    ExitCode::SUCCESS
+}
+
+#[cfg(not(unix))]
+fn serve_mcp(_socket: &std::path::Path, _args: Vec<String>) -> ExitCode {
+    eprintln!("dacm: the MCP server needs Unix sockets");
+    ExitCode::FAILURE
+}
+
+fn print_result(method: &str, result: &Value) {
+    match method {
+        "status" => {
+            let queue = &result["queue"];
+            println!(
+                "queue: {} running, {} pending{}",
+                field(queue, "running"),
+                field(queue, "pending"),
+                if queue["paused"] == json!(true) {
+                    ", paused"
+                } else {
+                    ""
+                }
+            );
+            let projects = result["projects"].as_array().cloned().unwrap_or_default();
+            let sessions = result["sessions"].as_array().cloned().unwrap_or_default();
+            if !sessions.is_empty() {
+                println!("\nsessions:");
+                for session in &sessions {
+                    let project = projects
+                        .iter()
+                        .find(|p| p["id"] == session["project_id"])
+                        .map(|p| field(p, "name"))
+                        .unwrap_or_else(|| field(session, "project_id"));
+                    println!(
+                        "  {}  {}  {}  {}",
+                        field(session, "session_id"),
+                        project,
+                        field(session, "status"),
+                        field(session, "working_dir")
+                    );
+                }
+            }
+            if !projects.is_empty() {
+                println!("\nprojects:");
+                print_projects(&projects, "  ");
+            }
+        }
+        "workspace.list" => {
+            for workspace in result.as_array().into_iter().flatten() {
+                println!(
+                    "{}\t{}\t{}",
+                    field(workspace, "id"),
+                    field(workspace, "name"),
+                    field(workspace, "path")
+                );
+            }
+        }
+        "project.list" => print_projects(result.as_array().map_or(&[], |v| v), ""),
+        "project.new" => println!("{}", field(result, "id")),
+        "session.list" => {
+            for session in result.as_array().into_iter().flatten() {
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    field(session, "session_id"),
+                    field(session, "project_id"),
+                    field(session, "status"),
+                    field(session, "working_dir")
+                );
+            }
+        }
+        "session.spawn" => println!("{}", field(result, "session_id")),
+        "session.wait" => {
+            if result["timed_out"] == json!(true) {
+                eprintln!("dacm: timed out");
+            } else if let Some(run) = result.get("run").filter(|r| !r.is_null()) {
+                println!(
+                    "{} (exit {})",
+                    field(run, "outcome"),
+                    field(run, "exit_code")
+                );
+            }
+        }
+        // kill and send have nothing to say when they work
+        _ => {}
+    }
+}
+
+fn print_projects(projects: &[Value], indent: &str) {
+    for project in projects {
+        println!(
+            "{indent}{}\t{}\t{}",
+            field(project, "id"),
+            field(project, "status"),
+            field(project, "name")
+        );
+    }
+}
+
+/// `wait` exits the way the session did, so `dacm wait p && next` works.
+fn exit_code(method: &str, result: &Value) -> ExitCode {
+    if method != "session.wait" {
+        return ExitCode::SUCCESS;
+    }
+    if result["timed_out"] == json!(true) {
+        return ExitCode::from(TIMED_OUT);
+    }
+    let run = &result["run"];
+    if run.is_null() {
+        return ExitCode::SUCCESS;
+    }
+    match run["exit_code"].as_u64() {
+        Some(code) => ExitCode::from(code.min(255) as u8),
+        None if run["outcome"] == json!("succeeded") => ExitCode::SUCCESS,
+        None => ExitCode::FAILURE,
+    }
+}
+
+fn field(value: &Value, key: &str) -> String {
+    match &value[key] {
+        Value::String(s) => s.clone(),
+        Value::Null => "-".to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn pretty(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+}
+
+fn usage_error(message: &str) -> ExitCode {
+    eprintln!("dacm: {message}\n\n{USAGE}");
+    ExitCode::from(2)
+}
+
+fn take_flag(args: &mut Vec<String>, name: &str) -> bool {
+    match args.iter().position(|a| a == name) {
+        Some(i) => {
+            args.remove(i);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Remove `name VALUE` or `name=VALUE` from `args`, returning the value.
+fn take_option(args: &mut Vec<String>, name: &str) -> Result<Option<String>, String> {
+    let prefix = format!("{name}=");
+    let Some(i) = args
+        .iter()
+        .position(|a| a == name || a.starts_with(&prefix))
+    else {
+        return Ok(None);
+    };
+    let arg = args.remove(i);
+    if let Some(value) = arg.strip_prefix(&prefix) {
+        return Ok(Some(value.to_string()));
+    }
+    if i < args.len() {
+        Ok(Some(args.remove(i)))
+    } else {
+        Err(format!("{name} needs a value"))
+    }
+}
+
+fn insert_option(
+    params: &mut Map<String, Value>,
+    args: &mut Vec<String>,
+    name: &str,
+    key: &str,
+) -> Result<(), String> {
+    if let Some(value) = take_option(args, name)? {
+        params.insert(key.to_string(), json!(value));
+    }
+    Ok(())
+}
+
+fn positional(args: &mut Vec<String>, what: &str) -> Result<String, String> {
+    match args.iter().position(|a| !a.starts_with("--")) {
+        Some(i) => Ok(args.remove(i)),
+        None => Err(format!("missing <{what}>")),
+    }
+}
@@ -0,0 +1,52 @@
+use std::process::Command;
+
+use tauri::{AppHandle, Manager, State};
+
+use dacm_lib::db::{self, DbState, DbStatus};
+use dacm_lib::error::{AppError, ResultExt};
+
+use crate::scheduler;
+
+#[tauri::command]
+pub fn get_db_status(state: State<'_, DbState>) -> DbStatus {
+    state.status()
+}
+
+/// Try opening and migrating the database again after a startup failure.
+#[tauri::command]
+pub async fn retry_db_init(app_handle: AppHandle) -> Result<DbStatus, AppError> {
+    db::blocking(move || {
+        let status = app_handle.state::<DbState>().init();
+        if matches!(status, DbStatus::Ready { .. }) {
+            scheduler::pump(&app_handle);
+        }
+        Ok(status)
+    })
+    .await
+}
+
+/// Move the current database file aside and start with an empty one.
+#[tauri::command]
+pub async fn reset_database(app_handle: AppHandle) -> Result<DbStatus, AppError> {
+    db::blocking(move || app_handle.state::<DbState>().reset()).await
+}
+
+/// Show the database file in the platform's file manager.
+#[tauri::command]
+pub fn reveal_db_file(state: State<'_, DbState>) -> Result<(), AppError> {
+    let path = &state.path;
+
+    #[cfg(target_os = "macos")]
+    let result = Command::new("open").arg("-R").arg(path).spawn();
+    #[cfg(target_os = "windows")]
+    let result = Command::new("explorer")
+        .arg(format!("/select,{}", path.display()))
+        .spawn();
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let result = Command::new("xdg-open")
+        .arg(path.parent().unwrap_or(path))
+        .spawn();
+
+    result.context("Failed to open file manager")?;
+    Ok(())
+}
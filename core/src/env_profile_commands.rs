@@ -0,0 +1,66 @@
+use std::collections::BTreeMap;
+
+use tauri::State;
+
+use dacm_lib::db::DbState;
+use dacm_lib::env_profile_models::EnvProfile;
+use dacm_lib::env_profiles;
+use dacm_lib::error::AppError;
+
+#[tauri::command]
+pub async fn list_env_profiles(state: State<'_, DbState>) -> Result<Vec<EnvProfile>, AppError> {
+    state.run(env_profiles::list).await
+}
+
+#[tauri::command]
+pub async fn create_env_profile(
+    state: State<'_, DbState>,
+    name: String,
+    vars: BTreeMap<String, String>,
+) -> Result<EnvProfile, AppError> {
+    state
+        .run(move |conn| env_profiles::create(conn, &name, &vars))
+        .await
+}
+
+/// Fields left out are kept as they are.
+#[tauri::command]
+pub async fn update_env_profile(
+    state: State<'_, DbState>,
+    id: i32,
+    name: Option<String>,
+    vars: Option<BTreeMap<String, String>>,
+) -> Result<EnvProfile, AppError> {
+    state
+        .run(move |conn| env_profiles::update(conn, id, name.as_deref(), vars.as_ref()))
+        .await
+}
+
+#[tauri::command]
+pub async fn delete_env_profile(state: State<'_, DbState>, id: i32) -> Result<(), AppError> {
+    state.run(move |conn| env_profiles::delete(conn, id)).await
+}
+
+/// Attach a profile to a workspace, or detach with `None`.
+#[tauri::command]
+pub async fn set_workspace_env_profile(
+    state: State<'_, DbState>,
+    workspace_id: i32,
+    profile_id: Option<i32>,
+) -> Result<(), AppError> {
+    state
+        .run(move |conn| env_profiles::attach_to_workspace(conn, workspace_id, profile_id))
+        .await
+}
+
+/// Attach a profile to a project, or detach with `None`.
+#[tauri::command]
+pub async fn set_project_env_profile(
+    state: State<'_, DbState>,
+    project_id: i32,
+    profile_id: Option<i32>,
+) -> Result<(), AppError> {
+    state
+        .run(move |conn| env_profiles::attach_to_project(conn, project_id, profile_id))
+        .await
+}
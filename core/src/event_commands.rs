@@ -0,0 +1,18 @@
+use tauri::State;
+
+use dacm_lib::db::DbState;
+use dacm_lib::error::{AppError, ResultExt};
+use dacm_lib::event_models::{EventFilter, EventPage};
+use dacm_lib::events;
+
+#[tauri::command]
+pub async fn list_events(
+    state: State<'_, DbState>,
+    filter: Option<EventFilter>,
+) -> Result<EventPage, AppError> {
+    state
+        .run(move |conn| {
+            events::list(conn, &filter.unwrap_or_default()).context("Failed to list events")
+        })
+        .await
+}
@@ -0,0 +1,35 @@
+use diesel::prelude::*;
+use tauri::State;
+
+use dacm_lib::db::DbState;
+use dacm_lib::error::{AppError, ResultExt};
+use dacm_lib::hook_models::HookRun;
+use dacm_lib::schema::hook_runs;
+
+const DEFAULT_LIMIT: i64 = 50;
+
+/// Recent hook runs, newest first, for a project or a whole workspace.
+#[tauri::command]
+pub async fn list_hook_runs(
+    state: State<'_, DbState>,
+    project_id: Option<i32>,
+    workspace_id: Option<i32>,
+    limit: Option<i64>,
+) -> Result<Vec<HookRun>, AppError> {
+    state
+        .run(move |conn| {
+            let mut query = hook_runs::table
+                .select(HookRun::as_select())
+                .order(hook_runs::id.desc())
+                .limit(limit.unwrap_or(DEFAULT_LIMIT).clamp(1, 500))
+                .into_boxed();
+            if let Some(project_id) = project_id {
+                query = query.filter(hook_runs::project_id.eq(project_id));
+            }
+            if let Some(workspace_id) = workspace_id {
+                query = query.filter(hook_runs::workspace_id.eq(workspace_id));
+            }
+            query.load(conn).context("Failed to list hook runs")
+        })
+        .await
+}
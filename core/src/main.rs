@@ -1,21 +1,41 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-mod workspace_commands;
-mod constants;
-mod db;
-mod models;
-mod schema;
-mod session;
+mod app_events;
+mod backup_commands;
+mod db_commands;
+mod env_profile_commands;
+mod event_commands;
+mod hook_commands;
+mod output_stream;
+mod plan_commands;
+mod project_commands;
+mod recording_commands;
+mod replay;
+mod retry;
+mod retry_commands;
+mod review_commands;
+mod rpc;
+mod rpc_models;
+mod scheduler;
+mod scheduler_commands;
+mod secret_commands;
 mod session_commands;
 mod settings_commands;
-mod system_commands;
-mod settings_models;
 mod sleep_commands;
-mod project_commands;
-mod project_models;
+mod system_commands;
+mod tmux_commands;
+mod workspace_commands;
+mod worktree_commands;
 
 use tauri::{Manager, RunEvent};
 
+use dacm_lib::db::DbState;
+use dacm_lib::recording::Recordings;
+use dacm_lib::retry::Retries;
+use dacm_lib::scheduler::Scheduler;
+use dacm_lib::secrets::SecretStore;
+use dacm_lib::session::SessionManager;
+
 fn main() {
     let app = tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
@@ -25,12 +45,41 @@ fn main() {
                 .app_data_dir()
                 .expect("failed to resolve app_data_dir");
 
-            let db_state =
-                db::init_db(&app_data_dir).expect("failed to initialize database");
-
-            app.manage(db_state);
-            app.manage(session::SessionManager::new());
+            // A database that fails to open leaves the app in a degraded mode
+            // the frontend reports, rather than panicking here
+            app.manage(DbState::open(&app_data_dir));
+            // Agents report back through an MCP server the `dacm` binary relays
+            let mut session_mgr = SessionManager::new(&app_data_dir);
+            if let Some(server) = rpc::mcp_server(&app_data_dir) {
+                session_mgr = session_mgr.with_mcp(server);
+            }
+            app.manage(session_mgr);
             app.manage(sleep_commands::SleepState::new());
+            app.manage(Scheduler::new());
+            app.manage(Retries::new());
+            app.manage(SecretStore::new(&app_data_dir));
+            app.manage(Recordings::new(&app_data_dir));
+            app.manage(replay::Replays::new());
+            app.manage(output_stream::OutputStreams::new());
+
+            // Subsystems that follow settings as they change
+            sleep_commands::watch_settings(app.handle());
+            scheduler::watch_settings(app.handle());
+
+            // Persistent sessions kept running while the app was closed
+            session_commands::reattach_persistent(app.handle());
+
+            // The `dacm` command line client talks to the app through this
+            rpc::start(app.handle(), &app_data_dir);
+
+            // Pick up spawn requests left pending by a previous run, and
+            // those it quit while starting
+            if let Ok(mut conn) = app.state::<DbState>().conn() {
+                if let Err(e) = dacm_lib::scheduler::requeue_interrupted(&mut conn) {
+                    eprintln!("[scheduler] Failed to requeue interrupted starts: {e}");
+                }
+            }
+            scheduler::pump_in_background(app.handle());
 
             Ok(())
         })
@@ -38,8 +87,15 @@ fn main() {
             workspace_commands::add_workspace,
             workspace_commands::remove_workspace,
             workspace_commands::list_workspaces,
+            workspace_commands::refresh_workspace,
+            workspace_commands::add_workspaces,
+            workspace_commands::scan_for_workspaces,
+            workspace_commands::get_workspace_config,
+            worktree_commands::create_worktree,
+            hook_commands::list_hook_runs,
             project_commands::create_project,
             project_commands::update_project_status,
+            project_commands::list_project_status_history,
             project_commands::rename_project,
             project_commands::archive_project,
             project_commands::list_projects_by_workspace,
@@ -51,20 +107,70 @@ fn main() {
             session_commands::kill_session,
             session_commands::list_sessions,
             session_commands::start_session_stream,
+            session_commands::attach_session_output,
+            session_commands::detach_session_output,
+            tmux_commands::list_tmux_sessions,
+            tmux_commands::attach_tmux_session,
+            scheduler_commands::enqueue_session,
+            scheduler_commands::list_queue,
+            scheduler_commands::cancel_queued_session,
+            scheduler_commands::get_queue_state,
+            scheduler_commands::pause_queue,
+            scheduler_commands::resume_queue,
+            retry_commands::set_retry_policy,
+            retry_commands::get_retry_policy,
+            retry_commands::get_effective_retry_policy,
+            retry_commands::delete_retry_policy,
+            retry_commands::list_session_runs,
             settings_commands::get_setting,
             settings_commands::set_setting,
             settings_commands::list_settings,
+            settings_commands::get_setting_value,
+            settings_commands::set_setting_value,
+            settings_commands::get_settings_schema,
             sleep_commands::set_prevent_sleep,
+            env_profile_commands::list_env_profiles,
+            env_profile_commands::create_env_profile,
+            env_profile_commands::update_env_profile,
+            env_profile_commands::delete_env_profile,
+            env_profile_commands::set_workspace_env_profile,
+            env_profile_commands::set_project_env_profile,
+            recording_commands::list_recordings,
+            recording_commands::delete_recording,
+            recording_commands::replay_recording,
+            recording_commands::stop_replay,
+            secret_commands::set_secret,
+            secret_commands::delete_secret,
+            secret_commands::list_secrets,
+            secret_commands::get_secrets_status,
+            secret_commands::get_secret_mappings,
+            secret_commands::set_secret_mappings,
             project_commands::list_archived_projects,
             project_commands::delete_project,
             system_commands::get_system_stats,
+            event_commands::list_events,
+            backup_commands::backup_database,
+            backup_commands::export_database,
+            backup_commands::import_database,
+            db_commands::get_db_status,
+            db_commands::retry_db_init,
+            db_commands::reset_database,
+            db_commands::reveal_db_file,
+            plan_commands::get_plan,
+            plan_commands::list_plans,
+            plan_commands::save_plan,
+            plan_commands::update_plan_step,
+            review_commands::list_review_comments,
+            review_commands::add_review_comment,
+            review_commands::resolve_review_comment,
+            review_commands::list_decisions,
         ])
         .build(tauri::generate_context!())
         .expect("error while building tauri application");
 
     app.run(|app_handle, event| {
         if let RunEvent::ExitRequested { .. } = event {
-            let session_mgr = app_handle.state::<session::SessionManager>();
+            let session_mgr = app_handle.state::<SessionManager>();
             session_mgr.kill_all();
             let sleep_state = app_handle.state::<sleep_commands::SleepState>();
             sleep_state.kill();
@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::mpsc::{self, RecvTimeoutError, TrySendError};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::ipc::{Channel, InvokeResponseBody};
+use tauri::{AppHandle, Emitter, Manager};
+
+use dacm_lib::error::AppError;
+use dacm_lib::output_frames::Coalescer;
+use dacm_lib::recording::Recordings;
+
+const READ_SIZE: usize = 8192;
+
+/// Reads waiting for the emitter. When it's full the reader blocks, which
+/// stops draining the PTY and so slows the child down instead of letting
+/// output pile up in memory.
+const QUEUE_CHUNKS: usize = 64;
+
+/// How long a frame may collect output while more keeps arriving.
+const FLUSH_INTERVAL: Duration = Duration::from_millis(16);
+
+/// A frame this big goes out without waiting for the interval.
+const FLUSH_BYTES: usize = 64 * 1024;
+
+/// Delivery counters for one session's output, shown in the perf overlay.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StreamStats {
+    pub session_id: String,
+    /// Whether frames go through a binary channel rather than events
+    pub binary: bool,
+    pub bytes: u64,
+    /// PTY reads, before coalescing
+    pub chunks: u64,
+    /// Events or channel messages sent to the webview
+    pub frames: u64,
+    /// Frames that failed to reach the webview
+    pub dropped_frames: u64,
+    /// Times the reader waited on a full queue
+    pub stalls: u64,
+    /// Time from reading a frame's first byte to sending it
+    pub last_lag_ms: u64,
+    pub max_lag_ms: u64,
+}
+
+struct Stream {
+    channel: Option<Channel<InvokeResponseBody>>,
+    stats: StreamStats,
+}
+
+impl Stream {
+    fn new(session_id: &str) -> Self {
+        Self {
+            channel: None,
+            stats: StreamStats {
+                session_id: session_id.to_string(),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Where each session's output goes and how delivery is keeping up.
+/// Output is sent as `session-output-{id}` events unless a terminal has
+/// attached a channel, which carries raw bytes instead of a JSON array.
+pub struct OutputStreams {
+    streams: Mutex<HashMap<String, Stream>>,
+}
+
+impl OutputStreams {
+    pub fn new() -> Self {
+        Self {
+            streams: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Send the session's output through `channel` from the next frame on.
+    /// Can be called before the stream starts; a later attach replaces it.
+    pub fn attach(
+        &self,
+        session_id: &str,
+        channel: Channel<InvokeResponseBody>,
+    ) -> Result<(), AppError> {
+        let mut streams = self.streams.lock()?;
+        let stream = streams
+            .entry(session_id.to_string())
+            .or_insert_with(|| Stream::new(session_id));
+        stream.stats.binary = true;
+        stream.channel = Some(channel);
+        Ok(())
+    }
+
+    /// Go back to events, unless another terminal has attached since.
+    pub fn detach(&self, session_id: &str, channel_id: u32) -> Result<(), AppError> {
+        let mut streams = self.streams.lock()?;
+        if let Some(stream) = streams.get_mut(session_id) {
+            if stream
+                .channel
+                .as_ref()
+                .is_some_and(|c| c.id() == channel_id)
+            {
+                stream.channel = None;
+                stream.stats.binary = false;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn stats(&self) -> Vec<StreamStats> {
+        let Ok(streams) = self.streams.lock() else {
+            return Vec::new();
+        };
+        let mut stats: Vec<StreamStats> = streams.values().map(|s| s.stats.clone()).collect();
+        stats.sort_by(|a, b| a.session_id.cmp(&b.session_id));
+        stats
+    }
+
+    fn begin(&self, session_id: &str) {
+        if let Ok(mut streams) = self.streams.lock() {
+            streams
+                .entry(session_id.to_string())
+                .or_insert_with(|| Stream::new(session_id));
+        }
+    }
+
+    fn end(&self, session_id: &str) {
+        if let Ok(mut streams) = self.streams.lock() {
+            streams.remove(session_id);
+        }
+    }
+
+    fn update(&self, session_id: &str, f: impl FnOnce(&mut StreamStats)) {
+        if let Ok(mut streams) = self.streams.lock() {
+            if let Some(stream) = streams.get_mut(session_id) {
+                f(&mut stream.stats);
+            }
+        }
+    }
+
+    /// Send one frame. A channel that fails is dropped so the next frame
+    /// falls back to events.
+    fn deliver(&self, app_handle: &AppHandle, session_id: &str, frame: Vec<u8>, read_at: Instant) {
+        let channel = self
+            .streams
+            .lock()
+            .ok()
+            .and_then(|streams| streams.get(session_id).and_then(|s| s.channel.clone()));
+
+        let len = frame.len() as u64;
+        let sent = match &channel {
+            Some(channel) => channel.send(InvokeResponseBody::Raw(frame)),
+            None => app_handle.emit(&format!("session-output-{session_id}"), frame),
+        };
+        let lag_ms = read_at.elapsed().as_millis() as u64;
+
+        let Ok(mut streams) = self.streams.lock() else {
+            return;
+        };
+        let Some(stream) = streams.get_mut(session_id) else {
+            return;
+        };
+        match sent {
+            Ok(()) => {
+                stream.stats.frames += 1;
+                stream.stats.bytes += len;
+            }
+            Err(e) => {
+                eprintln!("[stream {session_id}] Dropped {len} byte frame: {e}");
+                stream.stats.dropped_frames += 1;
+                if channel.is_some() {
+                    stream.channel = None;
+                    stream.stats.binary = false;
+                }
+            }
+        }
+        stream.stats.last_lag_ms = lag_ms;
+        stream.stats.max_lag_ms = stream.stats.max_lag_ms.max(lag_ms);
+    }
+}
+
+/// Forward a session's PTY output to the webview until EOF.
+///
+/// The reader thread records each read and queues it; this thread gathers
+/// queued reads into frames (see `dacm_lib::output_frames`) and delivers
+/// them.
+pub fn pump(reader: Box<dyn Read + Send>, session_id: &str, app_handle: &AppHandle) {
+    let streams = app_handle.state::<OutputStreams>();
+    streams.begin(session_id);
+
+    let (tx, rx) = mpsc::sync_channel::<(Instant, Vec<u8>)>(QUEUE_CHUNKS);
+    let reader_thread = {
+        let id = session_id.to_string();
+        let handle = app_handle.clone();
+        std::thread::spawn(move || read_chunks(reader, &id, &handle, tx))
+    };
+
+    let mut frames = Coalescer::new(FLUSH_INTERVAL, FLUSH_BYTES);
+    loop {
+        let next = match frames.wait(Instant::now()) {
+            Some(timeout) => rx.recv_timeout(timeout),
+            None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+
+        let (frame, done) = match next {
+            Ok((read_at, chunk)) => (frames.push(read_at, &chunk), false),
+            Err(RecvTimeoutError::Timeout) => (frames.flush(Instant::now()), false),
+            Err(RecvTimeoutError::Disconnected) => (frames.flush(Instant::now()), true),
+        };
+        if let Some(frame) = frame {
+            streams.deliver(app_handle, session_id, frame.bytes, frame.read_at);
+        }
+        if done {
+            break;
+        }
+    }
+
+    let _ = reader_thread.join();
+    streams.end(session_id);
+}
+
+fn read_chunks(
+    mut reader: Box<dyn Read + Send>,
+    session_id: &str,
+    app_handle: &AppHandle,
+    tx: mpsc::SyncSender<(Instant, Vec<u8>)>,
+) {
+    let recordings = app_handle.state::<Recordings>();
+    let streams = app_handle.state::<OutputStreams>();
+    let mut buf = [0u8; READ_SIZE];
+    let mut total_bytes = 0usize;
+
+    eprintln!("[stream {session_id}] Reader started");
+
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) => {
+                eprintln!("[stream {session_id}] EOF after {total_bytes} total bytes");
+                break;
+            }
+            Ok(n) => {
+                if total_bytes == 0 {
+                    let preview = String::from_utf8_lossy(&buf[..n.min(500)]);
+                    eprintln!("[stream {session_id}] First output ({n} bytes): {preview:?}");
+                }
+                total_bytes += n;
+                recordings.output(session_id, &buf[..n]);
+                streams.update(session_id, |stats| stats.chunks += 1);
+
+                let item = (Instant::now(), buf[..n].to_vec());
+                let queued = match tx.try_send(item) {
+                    Ok(()) => Ok(()),
+                    Err(TrySendError::Full(item)) => {
+                        streams.update(session_id, |stats| stats.stalls += 1);
+                        tx.send(item).map_err(|_| ())
+                    }
+                    Err(TrySendError::Disconnected(_)) => Err(()),
+                };
+                if queued.is_err() {
+                    break;
+                }
+            }
+            Err(e) => {
+                eprintln!("[stream {session_id}] Read error after {total_bytes} bytes: {e}");
+                break;
+            }
+        }
+    }
+}
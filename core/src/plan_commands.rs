@@ -0,0 +1,50 @@
+use tauri::State;
+
+use dacm_lib::db::DbState;
+use dacm_lib::error::AppError;
+use dacm_lib::plan_models::{Plan, PlanContent, StepStatus};
+use dacm_lib::plans;
+
+/// The project's current plan, if it has one.
+#[tauri::command]
+pub async fn get_plan(
+    state: State<'_, DbState>,
+    project_id: i32,
+) -> Result<Option<Plan>, AppError> {
+    state
+        .run(move |conn| plans::current(conn, project_id))
+        .await
+}
+
+/// Every plan saved for the project, newest first.
+#[tauri::command]
+pub async fn list_plans(state: State<'_, DbState>, project_id: i32) -> Result<Vec<Plan>, AppError> {
+    state
+        .run(move |conn| plans::history(conn, project_id))
+        .await
+}
+
+#[tauri::command]
+pub async fn save_plan(
+    state: State<'_, DbState>,
+    project_id: i32,
+    name: String,
+    content: PlanContent,
+) -> Result<Plan, AppError> {
+    state
+        .run(move |conn| plans::save(conn, project_id, &name, &content))
+        .await
+}
+
+#[tauri::command]
+pub async fn update_plan_step(
+    state: State<'_, DbState>,
+    project_id: i32,
+    step_id: String,
+    status: StepStatus,
+    note: Option<String>,
+) -> Result<Plan, AppError> {
+    state
+        .run(move |conn| plans::update_step(conn, project_id, &step_id, status, note.as_deref()))
+        .await
+}
@@ -1,207 +1,151 @@
-use diesel::prelude::*;
 use rand::Rng;
-use tauri::{AppHandle, Emitter, Manager, State};
+use tauri::{AppHandle, Manager, State};
 
-use crate::db::DbState;
-use crate::schema::projects;
-use crate::project_models::{NewProject, Project, ProjectStatusChanged};
+use dacm_lib::db::{self, DbState};
+use dacm_lib::error::AppError;
+use dacm_lib::project_models::{Project, ProjectStatus, ProjectStatusHistory};
+use dacm_lib::project_status;
+use dacm_lib::projects;
+use dacm_lib::retry::Retries;
 
-const VALID_STATUSES: &[&str] = &["running", "waiting", "completed", "failed", "archived"];
+use crate::app_events::AppEvents;
 
 #[tauri::command]
-pub fn create_project(
+pub async fn create_project(
     state: State<'_, DbState>,
     workspace_id: i32,
     name: String,
     description: Option<String>,
-) -> Result<Project, String> {
-    let desc = description.unwrap_or_default();
-    let new_project = NewProject {
-        name: &name,
-        workspace_id,
-        description: &desc,
-    };
-
-    let mut conn = state.conn.lock().map_err(|e| e.to_string())?;
-
-    diesel::insert_into(projects::table)
-        .values(&new_project)
-        .execute(&mut *conn)
-        .map_err(|e| format!("Failed to create project: {e}"))?;
-
-    projects::table
-        .order(projects::id.desc())
-        .select(Project::as_select())
-        .first(&mut *conn)
-        .map_err(|e| format!("Failed to fetch project: {e}"))
+) -> Result<Project, AppError> {
+    state
+        .run(move |conn| {
+            projects::create(conn, workspace_id, &name, &description.unwrap_or_default())
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn update_project_status(
+    app_handle: AppHandle,
+    project_id: i32,
+    status: ProjectStatus,
+    reason: Option<String>,
+) -> Result<Project, AppError> {
+    let reason = reason.unwrap_or_else(|| "updated by user".to_string());
+    // The user took over, so a retry waiting to start isn't wanted
+    app_handle.state::<Retries>().cancel(project_id);
+    db::blocking(move || transition(&app_handle, project_id, status, &reason)).await
 }
 
 #[tauri::command]
-pub fn update_project_status(
+pub async fn list_project_status_history(
     state: State<'_, DbState>,
     project_id: i32,
-    status: String,
-) -> Result<Project, String> {
-    if !VALID_STATUSES.contains(&status.as_str()) {
-        return Err(format!("Invalid status: {status}. Must be one of: {VALID_STATUSES:?}"));
-    }
-
-    let mut conn = state.conn.lock().map_err(|e| e.to_string())?;
-
-    diesel::update(projects::table.filter(projects::id.eq(project_id)))
-        .set(projects::status.eq(&status))
-        .execute(&mut *conn)
-        .map_err(|e| format!("Failed to update project status: {e}"))?;
-
-    projects::table
-        .filter(projects::id.eq(project_id))
-        .select(Project::as_select())
-        .first(&mut *conn)
-        .map_err(|e| format!("Failed to fetch project: {e}"))
+) -> Result<Vec<ProjectStatusHistory>, AppError> {
+    state
+        .run(move |conn| projects::status_history(conn, project_id))
+        .await
 }
 
 #[tauri::command]
-pub fn rename_project(
+pub async fn rename_project(
     state: State<'_, DbState>,
     project_id: i32,
     name: String,
-) -> Result<Project, String> {
-    let mut conn = state.conn.lock().map_err(|e| e.to_string())?;
-
-    diesel::update(projects::table.filter(projects::id.eq(project_id)))
-        .set(projects::name.eq(&name))
-        .execute(&mut *conn)
-        .map_err(|e| format!("Failed to rename project: {e}"))?;
-
-    projects::table
-        .filter(projects::id.eq(project_id))
-        .select(Project::as_select())
-        .first(&mut *conn)
-        .map_err(|e| format!("Failed to fetch project: {e}"))
+) -> Result<Project, AppError> {
+    state
+        .run(move |conn| projects::rename(conn, project_id, &name))
+        .await
 }
 
 #[tauri::command]
-pub fn archive_project(state: State<'_, DbState>, project_id: i32) -> Result<Project, String> {
-    let mut conn = state.conn.lock().map_err(|e| e.to_string())?;
-
-    diesel::update(projects::table.filter(projects::id.eq(project_id)))
-        .set(projects::status.eq("archived"))
-        .execute(&mut *conn)
-        .map_err(|e| format!("Failed to archive project: {e}"))?;
-
-    projects::table
-        .filter(projects::id.eq(project_id))
-        .select(Project::as_select())
-        .first(&mut *conn)
-        .map_err(|e| format!("Failed to fetch project: {e}"))
+pub async fn archive_project(app_handle: AppHandle, project_id: i32) -> Result<Project, AppError> {
+    app_handle.state::<Retries>().cancel(project_id);
+    db::blocking(move || {
+        transition(
+            &app_handle,
+            project_id,
+            ProjectStatus::Archived,
+            "archived by user",
+        )
+    })
+    .await
 }
 
 #[tauri::command]
-pub fn list_projects_by_workspace(
+pub async fn list_projects_by_workspace(
     state: State<'_, DbState>,
     workspace_id: i32,
-) -> Result<Vec<Project>, String> {
-    let mut conn = state.conn.lock().map_err(|e| e.to_string())?;
-
-    projects::table
-        .filter(projects::workspace_id.eq(workspace_id))
-        .filter(projects::status.ne("archived"))
-        .select(Project::as_select())
-        .order(projects::created_at.desc())
-        .load(&mut *conn)
-        .map_err(|e| format!("Failed to list projects: {e}"))
+) -> Result<Vec<Project>, AppError> {
+    state
+        .run(move |conn| projects::list_by_workspace(conn, workspace_id))
+        .await
 }
 
 #[tauri::command]
-pub fn list_archived_projects(state: State<'_, DbState>) -> Result<Vec<Project>, String> {
-    let mut conn = state.conn.lock().map_err(|e| e.to_string())?;
-
-    projects::table
-        .filter(projects::status.eq("archived"))
-        .select(Project::as_select())
-        .order(projects::created_at.desc())
-        .load(&mut *conn)
-        .map_err(|e| format!("Failed to list archived projects: {e}"))
+pub async fn list_archived_projects(state: State<'_, DbState>) -> Result<Vec<Project>, AppError> {
+    state.run(projects::list_archived).await
 }
 
 #[tauri::command]
-pub fn delete_project(state: State<'_, DbState>, project_id: i32) -> Result<(), String> {
-    let mut conn = state.conn.lock().map_err(|e| e.to_string())?;
-
-    diesel::delete(projects::table.filter(projects::id.eq(project_id)))
-        .execute(&mut *conn)
-        .map_err(|e| format!("Failed to delete project: {e}"))?;
-
-    Ok(())
+pub async fn delete_project(
+    state: State<'_, DbState>,
+    retries: State<'_, Retries>,
+    project_id: i32,
+) -> Result<(), AppError> {
+    retries.cancel(project_id);
+    state
+        .run(move |conn| projects::delete(conn, project_id))
+        .await
 }
 
 #[tauri::command]
-pub fn list_all_projects(state: State<'_, DbState>) -> Result<Vec<Project>, String> {
-    let mut conn = state.conn.lock().map_err(|e| e.to_string())?;
-
-    projects::table
-        .filter(projects::status.ne("archived"))
-        .select(Project::as_select())
-        .order(projects::created_at.desc())
-        .load(&mut *conn)
-        .map_err(|e| format!("Failed to list projects: {e}"))
+pub async fn list_all_projects(state: State<'_, DbState>) -> Result<Vec<Project>, AppError> {
+    state.run(projects::list_active).await
 }
 
 #[tauri::command]
-pub async fn simulate_project(app_handle: AppHandle, project_id: i32) -> Result<(), String> {
-    {
-        let db = app_handle.state::<DbState>();
-        let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
-
-        diesel::update(projects::table.filter(projects::id.eq(project_id)))
-            .set(projects::status.eq("running"))
-            .execute(&mut *conn)
-            .map_err(|e| format!("Failed to set running: {e}"))?;
-    }
-
-    app_handle
-        .emit(
-            "project-status-changed",
-            ProjectStatusChanged {
-                project_id,
-                status: "running".to_string(),
-            },
+pub async fn simulate_project(app_handle: AppHandle, project_id: i32) -> Result<(), AppError> {
+    let handle = app_handle.clone();
+    db::blocking(move || {
+        transition(
+            &handle,
+            project_id,
+            ProjectStatus::Running,
+            "simulation started",
         )
-        .map_err(|e| format!("Failed to emit event: {e}"))?;
+    })
+    .await?;
 
-    let handle = app_handle.clone();
-    tauri::async_runtime::spawn(async move {
-        tauri::async_runtime::spawn_blocking(|| {
-            std::thread::sleep(std::time::Duration::from_secs(10));
-        })
-        .await
-        .ok();
+    tauri::async_runtime::spawn_blocking(move || {
+        std::thread::sleep(std::time::Duration::from_secs(10));
 
         let new_status = if rand::thread_rng().gen_bool(0.5) {
-            "completed"
+            ProjectStatus::Completed
         } else {
-            "waiting"
+            ProjectStatus::Waiting
         };
 
-        {
-            let db = handle.state::<DbState>();
-            let mut conn = match db.conn.lock() {
-                Ok(c) => c,
-                Err(_) => return,
-            };
-            let _ = diesel::update(projects::table.filter(projects::id.eq(project_id)))
-                .set(projects::status.eq(new_status))
-                .execute(&mut *conn);
+        if let Err(e) = transition(&app_handle, project_id, new_status, "simulation finished") {
+            eprintln!("[simulate] {e}");
         }
-
-        let _ = handle.emit(
-            "project-status-changed",
-            ProjectStatusChanged {
-                project_id,
-                status: new_status.to_string(),
-            },
-        );
     });
 
     Ok(())
 }
+
+/// Move a project to `to` and tell the app's windows.
+pub fn transition(
+    app_handle: &AppHandle,
+    project_id: i32,
+    to: ProjectStatus,
+    reason: &str,
+) -> Result<Project, AppError> {
+    Ok(project_status::transition_and_emit(
+        &app_handle.state::<DbState>(),
+        &AppEvents(app_handle),
+        project_id,
+        to,
+        reason,
+    )?)
+}
@@ -0,0 +1,42 @@
+use tauri::{AppHandle, Manager, State};
+
+use dacm_lib::db::DbState;
+use dacm_lib::error::AppError;
+use dacm_lib::recording::{self, Recordings};
+use dacm_lib::recording_models::RecordingInfo;
+
+use crate::replay::Replays;
+
+#[tauri::command]
+pub async fn list_recordings(
+    app_handle: AppHandle,
+    state: State<'_, DbState>,
+) -> Result<Vec<RecordingInfo>, AppError> {
+    state
+        .run(move |conn| recording::list(conn, &app_handle.state::<Recordings>()))
+        .await
+}
+
+#[tauri::command]
+pub fn delete_recording(recordings: State<'_, Recordings>, id: String) -> Result<(), AppError> {
+    recording::delete(&recordings, &id)
+}
+
+/// Prepare a replay and return its id. Attach a terminal to that id like
+/// any session; playback begins when it calls `start_session_stream`.
+/// `speed` multiplies the original pace and defaults to 1.
+#[tauri::command]
+pub fn replay_recording(
+    recordings: State<'_, Recordings>,
+    replays: State<'_, Replays>,
+    id: String,
+    speed: Option<f64>,
+) -> Result<String, AppError> {
+    let path = recordings.existing_path(&id)?;
+    replays.prepare(path, speed.unwrap_or(1.0))
+}
+
+#[tauri::command]
+pub fn stop_replay(replays: State<'_, Replays>, replay_id: String) -> Result<(), AppError> {
+    replays.stop(&replay_id)
+}
@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use dacm_lib::error::AppError;
+use dacm_lib::recording;
+use dacm_lib::retry_models::SessionExited;
+
+/// Replays look like sessions to the terminal view: their ids go through
+/// the same `session-output-{id}` and `session-exit-{id}` events, plus
+/// `session-resize-{id}` to hold the view at the recorded size.
+const ID_PREFIX: &str = "replay-";
+pub const MAX_SPEED: f64 = 100.0;
+
+/// Longest sleep between checks for a stop request.
+const STOP_POLL: Duration = Duration::from_millis(50);
+
+pub fn is_replay(id: &str) -> bool {
+    id.starts_with(ID_PREFIX)
+}
+
+/// Payload of `session-resize-{id}`.
+#[derive(Serialize, Clone, Debug)]
+struct Resized {
+    rows: u16,
+    cols: u16,
+}
+
+struct Replay {
+    path: PathBuf,
+    speed: f64,
+    started: bool,
+    stop: Arc<AtomicBool>,
+}
+
+/// Replays waiting for a terminal to attach, or playing.
+pub struct Replays {
+    replays: Mutex<HashMap<String, Replay>>,
+}
+
+impl Replays {
+    pub fn new() -> Self {
+        Self {
+            replays: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Set up a replay of the cast at `path`. Nothing is emitted until
+    /// `start`, so the caller can attach its listeners first.
+    pub fn prepare(&self, path: PathBuf, speed: f64) -> Result<String, AppError> {
+        if !(speed > 0.0 && speed <= MAX_SPEED) {
+            return Err(AppError::validation(format!(
+                "Replay speed must be above 0 and at most {MAX_SPEED}"
+            )));
+        }
+        recording::read_header(&path)?;
+
+        let id = format!("{ID_PREFIX}{}", uuid::Uuid::new_v4());
+        self.replays.lock()?.insert(
+            id.clone(),
+            Replay {
+                path,
+                speed,
+                started: false,
+                stop: Arc::new(AtomicBool::new(false)),
+            },
+        );
+        Ok(id)
+    }
+
+    /// Begin emitting. Starting a replay that's already playing is a no-op,
+    /// like starting a session's stream twice.
+    pub fn start(&self, app_handle: &AppHandle, id: &str) -> Result<(), AppError> {
+        let mut replays = self.replays.lock()?;
+        let replay = replays
+            .get_mut(id)
+            .ok_or_else(|| AppError::not_found(format!("Replay not found: {id}")))?;
+        if replay.started {
+            return Ok(());
+        }
+        replay.started = true;
+
+        let handle = app_handle.clone();
+        let id = id.to_string();
+        let (path, speed, stop) = (replay.path.clone(), replay.speed, replay.stop.clone());
+        tauri::async_runtime::spawn_blocking(move || {
+            if let Err(e) = play(&handle, &id, &path, speed, &stop) {
+                eprintln!("[replay {id}] Stopped: {e}");
+            }
+            let _ = handle.emit(
+                &format!("session-exit-{id}"),
+                SessionExited {
+                    exit_code: None,
+                    signal: None,
+                    will_retry: false,
+                },
+            );
+            if let Ok(mut replays) = handle.state::<Replays>().replays.lock() {
+                replays.remove(&id);
+            }
+        });
+        Ok(())
+    }
+
+    pub fn stop(&self, id: &str) -> Result<(), AppError> {
+        let mut replays = self.replays.lock()?;
+        let replay = replays
+            .get(id)
+            .ok_or_else(|| AppError::not_found(format!("Replay not found: {id}")))?;
+        replay.stop.store(true, Ordering::Relaxed);
+        // Never started, so there's no thread to clean up after it
+        if !replay.started {
+            replays.remove(id);
+        }
+        Ok(())
+    }
+}
+
+/// Emit output and resize events with the recorded gaps between them,
+/// divided by `speed`. The view is sized from the header before anything
+/// is written, since the output was laid out for that size.
+fn play(
+    app_handle: &AppHandle,
+    id: &str,
+    path: &std::path::Path,
+    speed: f64,
+    stop: &AtomicBool,
+) -> Result<(), AppError> {
+    let file = File::open(path)
+        .map_err(|e| AppError::io(format!("Failed to open {}: {e}", path.display())))?;
+    let event_name = format!("session-output-{id}");
+    let resize_name = format!("session-resize-{id}");
+    let mut last_time = 0.0;
+
+    let header = recording::read_header(path)?;
+    let _ = app_handle.emit(
+        &resize_name,
+        Resized {
+            rows: header.height,
+            cols: header.width,
+        },
+    );
+
+    // The first line is the header
+    for line in BufReader::new(file).lines().skip(1) {
+        let line = line?;
+        let Some((time, code, data)) = recording::parse_event(&line) else {
+            continue;
+        };
+
+        let mut wait = Duration::from_secs_f64(((time - last_time) / speed).max(0.0));
+        last_time = time;
+        while !wait.is_zero() {
+            if stop.load(Ordering::Relaxed) {
+                return Ok(());
+            }
+            let step = wait.min(STOP_POLL);
+            std::thread::sleep(step);
+            wait -= step;
+        }
+        if stop.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        match code.as_str() {
+            "o" => {
+                let _ = app_handle.emit(&event_name, data.into_bytes());
+            }
+            "r" => {
+                if let Some(size) = parse_size(&data) {
+                    let _ = app_handle.emit(&resize_name, size);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Read a resize event's `{cols}x{rows}`.
+fn parse_size(data: &str) -> Option<Resized> {
+    let (cols, rows) = data.split_once('x')?;
+    Some(Resized {
+        rows: rows.trim().parse().ok()?,
+        cols: cols.trim().parse().ok()?,
+    })
+}
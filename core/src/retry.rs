@@ -0,0 +1,43 @@
+use tauri::{AppHandle, Manager};
+
+use dacm_lib::db::DbState;
+use dacm_lib::retry::{self, Retries};
+use dacm_lib::session::SessionExit;
+
+use crate::app_events::AppEvents;
+use crate::scheduler;
+use crate::session_commands;
+
+/// Record how a session ended and, if its policy says so, schedule a retry
+/// (see `dacm_lib::retry`). Returns true when a retry has been scheduled.
+pub fn on_session_exit(app_handle: &AppHandle, session_id: &str, exit: &SessionExit) -> bool {
+    let db = app_handle.state::<DbState>();
+    let retries = app_handle.state::<Retries>();
+    let Some(next) =
+        retry::on_session_exit(&db, &AppEvents(app_handle), &retries, session_id, exit)
+    else {
+        return false;
+    };
+
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(next.delay).await;
+
+        // Retries count against the same limits as everything else, so
+        // one only starts once the scheduler has a slot for it
+        let (project_id, workspace_id) =
+            (next.exit.request.project_id, next.exit.request.workspace_id);
+        scheduler::start_when_room(&handle, project_id, workspace_id, move |app_handle| {
+            let started = session_commands::with_spawner(app_handle, |spawner| {
+                retry::respawn(spawner, &app_handle.state::<Retries>(), &next)
+            });
+            if let Some(session_id) = started {
+                if let Err(e) = session_commands::start_stream(app_handle, &session_id) {
+                    eprintln!("[retry] Failed to start stream for {session_id}: {e}");
+                }
+            }
+        });
+    });
+
+    true
+}
@@ -0,0 +1,139 @@
+use diesel::prelude::*;
+use tauri::State;
+
+use dacm_lib::db::DbState;
+use dacm_lib::error::{AppError, ResultExt};
+use dacm_lib::project_models::Project;
+use dacm_lib::retry;
+use dacm_lib::retry_models::{
+    NewRetryPolicy, RetryPolicy, RetryPolicyInput, SessionRun, SCOPE_PROJECT, SCOPE_WORKSPACE,
+};
+use dacm_lib::schema::{projects, retry_policies, session_runs};
+
+/// Create or replace the retry policy for a workspace or project.
+///
+/// `retry_prompt` may use `{exit_code}`, `{attempt}` and `{max_attempts}`.
+#[tauri::command]
+pub async fn set_retry_policy(
+    state: State<'_, DbState>,
+    policy: RetryPolicyInput,
+) -> Result<RetryPolicy, AppError> {
+    state
+        .run(move |conn| {
+            if policy.scope != SCOPE_WORKSPACE && policy.scope != SCOPE_PROJECT {
+                return Err(AppError::validation(format!(
+                    "Invalid scope: {}. Must be '{SCOPE_WORKSPACE}' or '{SCOPE_PROJECT}'",
+                    policy.scope
+                )));
+            }
+            let max_attempts = policy.max_attempts.unwrap_or(3);
+            if max_attempts < 1 {
+                return Err(AppError::validation("max_attempts must be at least 1"));
+            }
+
+            let codes = policy.retryable_exit_codes.unwrap_or_default();
+            let values = NewRetryPolicy {
+                scope: &policy.scope,
+                scope_id: policy.scope_id,
+                enabled: policy.enabled.unwrap_or(true),
+                max_attempts,
+                backoff_secs: policy.backoff_secs.unwrap_or(10).max(0),
+                backoff_multiplier: policy.backoff_multiplier.unwrap_or(2.0).max(1.0),
+                max_backoff_secs: policy.max_backoff_secs.unwrap_or(600).max(0),
+                retryable_exit_codes: serde_json::to_string(&codes)?,
+                retry_on_signal: policy.retry_on_signal.unwrap_or(false),
+                retry_prompt: policy.retry_prompt.as_deref(),
+            };
+
+            diesel::insert_into(retry_policies::table)
+                .values(&values)
+                .on_conflict((retry_policies::scope, retry_policies::scope_id))
+                .do_update()
+                .set((
+                    &values,
+                    retry_policies::updated_at.eq(chrono::Utc::now().naive_utc()),
+                ))
+                .execute(conn)
+                .context("Failed to save retry policy")?;
+
+            retry_policies::table
+                .filter(retry_policies::scope.eq(&policy.scope))
+                .filter(retry_policies::scope_id.eq(policy.scope_id))
+                .select(RetryPolicy::as_select())
+                .first(conn)
+                .context("Failed to fetch retry policy")
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn get_retry_policy(
+    state: State<'_, DbState>,
+    scope: String,
+    scope_id: i32,
+) -> Result<Option<RetryPolicy>, AppError> {
+    state
+        .run(move |conn| {
+            retry_policies::table
+                .filter(retry_policies::scope.eq(&scope))
+                .filter(retry_policies::scope_id.eq(scope_id))
+                .select(RetryPolicy::as_select())
+                .first(conn)
+                .optional()
+                .context("Failed to get retry policy")
+        })
+        .await
+}
+
+/// The policy that applies to a project: its own, else its workspace's.
+#[tauri::command]
+pub async fn get_effective_retry_policy(
+    state: State<'_, DbState>,
+    project_id: i32,
+) -> Result<Option<RetryPolicy>, AppError> {
+    state
+        .run(move |conn| {
+            let project = projects::table
+                .filter(projects::id.eq(project_id))
+                .select(Project::as_select())
+                .first(conn)
+                .context("Failed to fetch project")?;
+
+            retry::effective_policy(conn, project.id, project.workspace_id)
+                .context("Failed to get retry policy")
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn delete_retry_policy(
+    state: State<'_, DbState>,
+    policy_id: i32,
+) -> Result<(), AppError> {
+    state
+        .run(move |conn| {
+            diesel::delete(retry_policies::table.filter(retry_policies::id.eq(policy_id)))
+                .execute(conn)
+                .context("Failed to delete retry policy")?;
+
+            Ok(())
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn list_session_runs(
+    state: State<'_, DbState>,
+    project_id: i32,
+) -> Result<Vec<SessionRun>, AppError> {
+    state
+        .run(move |conn| {
+            session_runs::table
+                .filter(session_runs::project_id.eq(project_id))
+                .select(SessionRun::as_select())
+                .order(session_runs::id.desc())
+                .load(conn)
+                .context("Failed to list session runs")
+        })
+        .await
+}
@@ -0,0 +1,60 @@
+use tauri::State;
+
+use dacm_lib::db::DbState;
+use dacm_lib::decision_models::Decision;
+use dacm_lib::decisions;
+use dacm_lib::error::AppError;
+use dacm_lib::review_models::{NewReviewComment, ReviewComment};
+use dacm_lib::reviews;
+
+#[tauri::command]
+pub async fn list_review_comments(
+    state: State<'_, DbState>,
+    project_id: i32,
+    include_resolved: bool,
+) -> Result<Vec<ReviewComment>, AppError> {
+    state
+        .run(move |conn| reviews::list(conn, project_id, include_resolved))
+        .await
+}
+
+/// Leave a comment for the project's agent, optionally on a line of a file.
+#[tauri::command]
+pub async fn add_review_comment(
+    state: State<'_, DbState>,
+    project_id: i32,
+    file_path: Option<String>,
+    line: Option<i32>,
+    body: String,
+) -> Result<ReviewComment, AppError> {
+    let comment = NewReviewComment {
+        project_id,
+        file_path,
+        line,
+        body,
+    };
+    state
+        .run(move |conn| reviews::add_comment(conn, &comment))
+        .await
+}
+
+#[tauri::command]
+pub async fn resolve_review_comment(
+    state: State<'_, DbState>,
+    comment_id: i32,
+    resolved: bool,
+) -> Result<ReviewComment, AppError> {
+    state
+        .run(move |conn| reviews::set_resolved(conn, comment_id, resolved))
+        .await
+}
+
+#[tauri::command]
+pub async fn list_decisions(
+    state: State<'_, DbState>,
+    project_id: i32,
+) -> Result<Vec<Decision>, AppError> {
+    state
+        .run(move |conn| decisions::list(conn, project_id))
+        .await
+}
@@ -0,0 +1,478 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use diesel::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager};
+
+use dacm_lib::db::DbState;
+use dacm_lib::error::{AppError, ResultExt};
+use dacm_lib::mcp::{McpServer, ServerCommand};
+use dacm_lib::mcp_models::Agent;
+use dacm_lib::models::Workspace;
+use dacm_lib::project_models::{Project, ProjectStatus};
+use dacm_lib::projects;
+use dacm_lib::retry::Retries;
+use dacm_lib::retry_models::SessionRun;
+use dacm_lib::scheduler::{self, Scheduler};
+use dacm_lib::schema::{self, session_runs, workspaces};
+use dacm_lib::session::{SessionInfo, SessionManager, SpawnRequest};
+
+use crate::app_events::AppEvents;
+use crate::rpc_models::{
+    McpAttachParams, ProjectNewParams, ProjectParams, RpcError, RpcRequest, RpcResponse,
+    SendParams, SpawnParams, Spawned, Status, WaitParams, WaitResult,
+};
+use crate::session_commands;
+
+// The `dacm` binary drives the running app through a Unix socket in the
+// app data dir. Each line a client writes is a JSON-RPC 2.0 request and
+// gets one line back. Methods go through the same functions as the
+// frontend's commands, so a session spawned from a terminal is handled
+// exactly like one spawned from the UI.
+//
+// `dacm mcp` sends `mcp.attach` first, naming the agent's project; from
+// then on the connection carries MCP messages for `dacm_lib::mcp`.
+//
+// The socket is only accessible to the user running the app.
+
+pub const SOCKET_NAME: &str = "dacm.sock";
+
+/// How often `session.wait` checks on the project's sessions.
+const WAIT_POLL: Duration = Duration::from_millis(250);
+
+/// Start listening on `app_data_dir/dacm.sock`. Failing to only means the
+/// CLI can't connect, so errors are logged rather than returned.
+pub fn start(app_handle: &AppHandle, app_data_dir: &Path) {
+    let path = app_data_dir.join(SOCKET_NAME);
+    if let Err(e) = listen(app_handle, &path) {
+        eprintln!("[rpc] Not listening on {}: {e}", path.display());
+    }
+}
+
+#[cfg(unix)]
+fn listen(app_handle: &AppHandle, path: &Path) -> Result<(), AppError> {
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    if path.exists() {
+        // Another instance still answering keeps its socket
+        if UnixStream::connect(path).is_ok() {
+            return Err(AppError::conflict("another instance is listening"));
+        }
+        std::fs::remove_file(path)?;
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    eprintln!("[rpc] Listening on {}", path.display());
+
+    let handle = app_handle.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let handle = handle.clone();
+                    // `session.wait` can block for as long as a session runs
+                    std::thread::spawn(move || serve(stream, &handle));
+                }
+                Err(e) => eprintln!("[rpc] Accept failed: {e}"),
+            }
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn listen(_app_handle: &AppHandle, _path: &Path) -> Result<(), AppError> {
+    Err(AppError::unavailable(
+        "the command line interface needs Unix sockets",
+    ))
+}
+
+#[cfg(unix)]
+fn serve(stream: std::os::unix::net::UnixStream, app_handle: &AppHandle) {
+    use std::io::{BufRead, BufReader, Write};
+
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(e) => {
+            eprintln!("[rpc] Failed to set up connection: {e}");
+            return;
+        }
+    };
+    // Set by `mcp.attach`, after which the connection speaks MCP
+    let mut agent: Option<Agent> = None;
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match &agent {
+            Some(agent) => match mcp_message(app_handle, agent, &line) {
+                Some(response) => serde_json::to_vec(&response),
+                None => continue,
+            },
+            None => {
+                let response = match serde_json::from_str::<RpcRequest>(&line) {
+                    Ok(request) if request.method == "mcp.attach" => {
+                        let outcome = attach(app_handle, request.params);
+                        if let Ok((attached, _)) = &outcome {
+                            agent = Some(attached.clone());
+                        }
+                        RpcResponse::new(request.id, outcome.map(|(_, result)| result))
+                    }
+                    Ok(request) => {
+                        let outcome = call(app_handle, &request.method, request.params);
+                        RpcResponse::new(request.id, outcome)
+                    }
+                    Err(e) => RpcResponse::new(
+                        Value::Null,
+                        Err(RpcError::new(RpcError::PARSE_ERROR, e.to_string())),
+                    ),
+                };
+                serde_json::to_vec(&response)
+            }
+        };
+        let Ok(mut out) = response else {
+            break;
+        };
+        out.push(b'\n');
+        if writer.write_all(&out).is_err() {
+            break;
+        }
+    }
+}
+
+/// Turn the connection over to the MCP server, for `dacm mcp`.
+fn attach(app_handle: &AppHandle, params: Value) -> Result<(Agent, Value), RpcError> {
+    let params: McpAttachParams = parse(params)?;
+    let project = {
+        let mut conn = app_handle.state::<DbState>().conn()?;
+        find_project(&mut conn, &params.project)?
+    };
+    let agent = Agent {
+        project_id: project.id,
+        session_id: params.session,
+    };
+    Ok((agent, reply(project)?))
+}
+
+fn mcp_message(app_handle: &AppHandle, agent: &Agent, line: &str) -> Option<Value> {
+    let message = match serde_json::from_str::<Value>(line) {
+        Ok(message) => message,
+        Err(e) => {
+            let error = RpcError::new(RpcError::PARSE_ERROR, e.to_string());
+            return serde_json::to_value(RpcResponse::new(Value::Null, Err(error))).ok();
+        }
+    };
+    let server = McpServer {
+        db: &app_handle.state::<DbState>(),
+        events: &AppEvents(app_handle),
+        agent,
+    };
+    server.handle(message)
+}
+
+/// How agents reach the MCP server: through the `dacm` binary installed
+/// next to the app's. `None` if it isn't there, as in a dev build that
+/// hasn't built it.
+pub fn mcp_server(app_data_dir: &Path) -> Option<ServerCommand> {
+    if cfg!(not(unix)) {
+        return None;
+    }
+    let exe = std::env::current_exe().ok()?;
+    let program = exe
+        .parent()?
+        .join(format!("dacm{}", std::env::consts::EXE_SUFFIX));
+    if !program.is_file() {
+        eprintln!(
+            "[rpc] {} not found; agents won't get the MCP server",
+            program.display()
+        );
+        return None;
+    }
+    Some(ServerCommand {
+        program,
+        socket: app_data_dir.join(SOCKET_NAME),
+    })
+}
+
+fn call(app_handle: &AppHandle, method: &str, params: Value) -> Result<Value, RpcError> {
+    match method {
+        "status" => reply(status(app_handle)?),
+        "workspace.list" => reply(list_workspaces(app_handle)?),
+        "project.list" => {
+            let mut conn = app_handle.state::<DbState>().conn()?;
+            reply(projects::list_active(&mut conn)?)
+        }
+        "project.new" => reply(new_project(app_handle, parse(params)?)?),
+        "session.list" => reply(app_handle.state::<SessionManager>().list()?),
+        "session.spawn" => reply(spawn(app_handle, parse(params)?)?),
+        "session.kill" => reply(kill(app_handle, parse(params)?)?),
+        "session.send" => reply(send(app_handle, parse(params)?)?),
+        "session.wait" => reply(wait(app_handle, parse(params)?)?),
+        _ => Err(RpcError::new(
+            RpcError::METHOD_NOT_FOUND,
+            format!("Unknown method '{method}'"),
+        )),
+    }
+}
+
+fn reply(result: impl Serialize) -> Result<Value, RpcError> {
+    serde_json::to_value(result)
+        .map_err(|e| AppError::internal(format!("Failed to encode result: {e}")).into())
+}
+
+fn parse<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    let params = match params {
+        Value::Null => Value::Object(Default::default()),
+        params => params,
+    };
+    serde_json::from_value(params)
+        .map_err(|e| RpcError::new(RpcError::INVALID_PARAMS, e.to_string()))
+}
+
+fn status(app_handle: &AppHandle) -> Result<Status, AppError> {
+    let session_mgr = app_handle.state::<SessionManager>();
+    let mut conn = app_handle.state::<DbState>().conn()?;
+    Ok(Status {
+        workspaces: load_workspaces(&mut conn)?,
+        projects: projects::list_active(&mut conn)?,
+        sessions: session_mgr.list()?,
+        queue: scheduler::queue_state(&mut conn, &session_mgr)
+            .context("Failed to read queue state")?,
+    })
+}
+
+fn list_workspaces(app_handle: &AppHandle) -> Result<Vec<Workspace>, AppError> {
+    let mut conn = app_handle.state::<DbState>().conn()?;
+    load_workspaces(&mut conn)
+}
+
+fn load_workspaces(conn: &mut SqliteConnection) -> Result<Vec<Workspace>, AppError> {
+    workspaces::table
+        .select(Workspace::as_select())
+        .order(workspaces::created_at.desc())
+        .load(conn)
+        .context("Failed to list workspaces")
+}
+
+fn new_project(app_handle: &AppHandle, params: ProjectNewParams) -> Result<Project, AppError> {
+    let project = {
+        let mut conn = app_handle.state::<DbState>().conn()?;
+        let workspace = find_workspace(
+            &mut conn,
+            params.workspace.as_deref(),
+            params.cwd.as_deref(),
+        )?;
+        projects::create(
+            &mut conn,
+            workspace.id,
+            &params.name,
+            &params.description.unwrap_or_default(),
+        )?
+    };
+    let _ = app_handle.emit("projects-changed", ());
+    Ok(project)
+}
+
+/// Spawn a session for a project the way the UI does: in its worktree if
+/// it has one, and under the scheduler's limits (see `Scheduler::start_now`).
+fn spawn(app_handle: &AppHandle, params: SpawnParams) -> Result<Spawned, AppError> {
+    let (project, working_dir) = {
+        let mut conn = app_handle.state::<DbState>().conn()?;
+        let project = find_project(&mut conn, &params.project)?;
+        let workspace_id = project.workspace_id.ok_or_else(|| {
+            AppError::validation(format!("Project '{}' has no workspace", project.name))
+        })?;
+        let working_dir = match &project.worktree_path {
+            Some(path) => path.clone(),
+            None => workspaces::table
+                .filter(workspaces::id.eq(workspace_id))
+                .select(workspaces::path)
+                .first(&mut conn)
+                .context("Failed to fetch workspace")?,
+        };
+        (project, working_dir)
+    };
+    let prompt = params
+        .prompt
+        .or_else(|| Some(project.description.clone()).filter(|d| !d.is_empty()));
+    let request = SpawnRequest {
+        project_id: project.id,
+        workspace_id: project.workspace_id.unwrap_or_default(),
+        working_dir,
+        initial_prompt: prompt,
+        cli_command: params.cli,
+        model: params.model,
+        rows: params.rows.unwrap_or(24),
+        cols: params.cols.unwrap_or(80),
+        resume_session_id: None,
+        continue_last: false,
+        env: params.env,
+        secret_env: Default::default(),
+        persistent: false,
+    };
+
+    app_handle.state::<Retries>().cancel(project.id);
+    let session_id = session_commands::with_spawner(app_handle, |spawner| {
+        app_handle
+            .state::<Scheduler>()
+            .start_now(spawner, request, "started from the command line")
+    })?;
+
+    // Nothing else reads the output of a session no window has opened yet
+    session_commands::start_stream(app_handle, &session_id)?;
+    let _ = app_handle.emit("projects-changed", ());
+
+    Ok(Spawned {
+        project_id: project.id,
+        session_id,
+    })
+}
+
+fn kill(app_handle: &AppHandle, params: ProjectParams) -> Result<String, AppError> {
+    let session = require_session(app_handle, &params.project)?;
+    session_commands::kill(app_handle, &session.session_id)?;
+    Ok(session.session_id)
+}
+
+/// Type into the project's session, as if in its terminal.
+fn send(app_handle: &AppHandle, params: SendParams) -> Result<String, AppError> {
+    let session = require_session(app_handle, &params.project)?;
+    let mut data = params.text.into_bytes();
+    if params.enter.unwrap_or(true) {
+        data.push(b'\r');
+    }
+    app_handle
+        .state::<SessionManager>()
+        .write(&session.session_id, &data)?;
+    Ok(session.session_id)
+}
+
+/// Block until the project has no session left, exited ones included, so
+/// its last run's outcome has been recorded by the time this returns.
+fn wait(app_handle: &AppHandle, params: WaitParams) -> Result<WaitResult, AppError> {
+    let project_id = {
+        let mut conn = app_handle.state::<DbState>().conn()?;
+        find_project(&mut conn, &params.project)?.id
+    };
+    let deadline = params
+        .timeout_secs
+        .map(|secs| Instant::now() + Duration::from_secs(secs));
+
+    let session_mgr = app_handle.state::<SessionManager>();
+    while session_mgr
+        .list()?
+        .iter()
+        .any(|s| s.project_id == project_id)
+    {
+        if deadline.is_some_and(|d| Instant::now() >= d) {
+            return Ok(WaitResult {
+                timed_out: true,
+                run: None,
+            });
+        }
+        std::thread::sleep(WAIT_POLL);
+    }
+
+    let mut conn = app_handle.state::<DbState>().conn()?;
+    let run = session_runs::table
+        .filter(session_runs::project_id.eq(project_id))
+        .order(session_runs::id.desc())
+        .select(SessionRun::as_select())
+        .first(&mut conn)
+        .optional()
+        .context("Failed to fetch session run")?;
+    Ok(WaitResult {
+        timed_out: false,
+        run,
+    })
+}
+
+fn require_session(app_handle: &AppHandle, project: &str) -> Result<SessionInfo, AppError> {
+    let project = {
+        let mut conn = app_handle.state::<DbState>().conn()?;
+        find_project(&mut conn, project)?
+    };
+    running_session(app_handle, project.id)?.ok_or_else(|| {
+        AppError::not_found(format!("Project '{}' has no running session", project.name))
+    })
+}
+
+fn running_session(
+    app_handle: &AppHandle,
+    project_id: i32,
+) -> Result<Option<SessionInfo>, AppError> {
+    Ok(app_handle
+        .state::<SessionManager>()
+        .list()?
+        .into_iter()
+        .find(|s| s.project_id == project_id && s.status == "running"))
+}
+
+/// A project by id, or by exact name among those that aren't archived.
+fn find_project(conn: &mut SqliteConnection, project: &str) -> Result<Project, AppError> {
+    if let Ok(id) = project.parse::<i32>() {
+        return schema::projects::table
+            .filter(schema::projects::id.eq(id))
+            .select(Project::as_select())
+            .first(conn)
+            .optional()
+            .context("Failed to fetch project")?
+            .ok_or_else(|| AppError::not_found(format!("Project {id} not found")));
+    }
+
+    let mut matches = schema::projects::table
+        .filter(schema::projects::name.eq(project))
+        .filter(schema::projects::status.ne(ProjectStatus::Archived))
+        .select(Project::as_select())
+        .load(conn)
+        .context("Failed to fetch project")?;
+    match matches.len() {
+        0 => Err(AppError::not_found(format!(
+            "Project '{project}' not found"
+        ))),
+        1 => Ok(matches.remove(0)),
+        n => Err(
+            AppError::conflict(format!("{n} projects are named '{project}'; use its id"))
+                .with_details(serde_json::json!({
+                    "ids": matches.iter().map(|p| p.id).collect::<Vec<_>>()
+                })),
+        ),
+    }
+}
+
+/// A workspace by id, name or path, or else the one `cwd` is inside. The
+/// deepest match wins, so a workspace nested in another is picked from
+/// inside it.
+fn find_workspace(
+    conn: &mut SqliteConnection,
+    workspace: Option<&str>,
+    cwd: Option<&str>,
+) -> Result<Workspace, AppError> {
+    let all = load_workspaces(conn)?;
+
+    if let Some(wanted) = workspace {
+        let id = wanted.parse::<i32>().ok();
+        return all
+            .into_iter()
+            .find(|w| {
+                Some(w.id) == id || w.name == wanted || Path::new(&w.path) == Path::new(wanted)
+            })
+            .ok_or_else(|| AppError::not_found(format!("Workspace '{wanted}' not found")));
+    }
+
+    let cwd = cwd.ok_or_else(|| AppError::validation("Name a workspace or pass a directory"))?;
+    all.into_iter()
+        .filter(|w| Path::new(cwd).starts_with(&w.path))
+        .max_by_key(|w| w.path.len())
+        .ok_or_else(|| AppError::not_found(format!("No workspace contains {cwd}")))
+}
@@ -0,0 +1,164 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use dacm_lib::error::AppError;
+use dacm_lib::models::Workspace;
+use dacm_lib::project_models::Project;
+use dacm_lib::retry_models::SessionRun;
+use dacm_lib::scheduler_models::QueueState;
+use dacm_lib::session::SessionInfo;
+
+/// One line from a client. `id` is echoed back; `params` is an object or
+/// absent.
+#[derive(Deserialize, Debug)]
+pub struct RpcRequest {
+    #[serde(default)]
+    pub id: Value,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+#[derive(Serialize, Debug)]
+pub struct RpcResponse {
+    pub jsonrpc: &'static str,
+    pub id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+impl RpcResponse {
+    pub fn new(id: Value, outcome: Result<Value, RpcError>) -> Self {
+        let (result, error) = match outcome {
+            Ok(result) => (Some(result), None),
+            Err(error) => (None, Some(error)),
+        };
+        Self {
+            jsonrpc: "2.0",
+            id,
+            result,
+            error,
+        }
+    }
+}
+
+/// A JSON-RPC error. Failures from the app itself all use `APP_ERROR`, with
+/// the `AppError` (`{ code, message, details }`) as `data`.
+#[derive(Serialize, Debug)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl RpcError {
+    pub const PARSE_ERROR: i64 = -32700;
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+    pub const INVALID_PARAMS: i64 = -32602;
+    pub const APP_ERROR: i64 = -32000;
+
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+}
+
+impl From<AppError> for RpcError {
+    fn from(e: AppError) -> Self {
+        Self {
+            code: Self::APP_ERROR,
+            message: e.message.clone(),
+            data: serde_json::to_value(&e).ok(),
+        }
+    }
+}
+
+/// Params naming a project, by id or by name.
+#[derive(Deserialize, Debug)]
+pub struct ProjectParams {
+    pub project: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ProjectNewParams {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Id, name or path. Without it the workspace containing `cwd` is used.
+    #[serde(default)]
+    pub workspace: Option<String>,
+    #[serde(default)]
+    pub cwd: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SpawnParams {
+    pub project: String,
+    /// Defaults to the project's description
+    #[serde(default)]
+    pub prompt: Option<String>,
+    #[serde(default)]
+    pub cli: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub rows: Option<u16>,
+    #[serde(default)]
+    pub cols: Option<u16>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SendParams {
+    pub project: String,
+    pub text: String,
+    /// Press Enter after the text. Defaults to true.
+    #[serde(default)]
+    pub enter: Option<bool>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct McpAttachParams {
+    pub project: String,
+    /// The session the agent runs in, if DACM spawned it
+    #[serde(default)]
+    pub session: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct WaitParams {
+    pub project: String,
+    /// Give up after this long. Waits indefinitely without it.
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Status {
+    pub workspaces: Vec<Workspace>,
+    pub projects: Vec<Project>,
+    pub sessions: Vec<SessionInfo>,
+    pub queue: QueueState,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Spawned {
+    pub project_id: i32,
+    pub session_id: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct WaitResult {
+    pub timed_out: bool,
+    /// The project's latest run, once it has no session left
+    pub run: Option<SessionRun>,
+}
@@ -0,0 +1,82 @@
+use tauri::{AppHandle, Emitter, Listener, Manager};
+
+use dacm_lib::db::DbState;
+use dacm_lib::scheduler::{self, Scheduler, Start};
+use dacm_lib::session::SessionManager;
+use dacm_lib::settings_registry::{self, SettingChanged};
+
+use crate::session_commands;
+
+/// Emit `queue-state-changed` with the state as it is now.
+pub fn emit_queue_state(app_handle: &AppHandle) {
+    let db = app_handle.state::<DbState>();
+    let Ok(mut conn) = db.conn() else {
+        return;
+    };
+    let session_mgr = app_handle.state::<SessionManager>();
+    if let Ok(queue_state) = scheduler::queue_state(&mut conn, &session_mgr) {
+        let _ = app_handle.emit("queue-state-changed", queue_state);
+    }
+}
+
+/// Re-read the limits whenever a queue setting changes. A resumed queue or
+/// a raised limit starts waiting requests right away.
+pub fn watch_settings(app_handle: &AppHandle) {
+    let handle = app_handle.clone();
+    app_handle.listen_any(settings_registry::SETTINGS_CHANGED, move |event| {
+        let Ok(change) = serde_json::from_str::<SettingChanged>(event.payload()) else {
+            return;
+        };
+        if scheduler::QUEUE_SETTINGS.contains(&change.key.as_str()) {
+            emit_queue_state(&handle);
+            pump_in_background(&handle);
+        }
+    });
+}
+
+/// Run `start` once `workspace_id` has a free slot (see
+/// `Scheduler::start_when_room`).
+pub fn start_when_room(
+    app_handle: &AppHandle,
+    project_id: i32,
+    workspace_id: i32,
+    start: impl FnOnce(&AppHandle) + Send + 'static,
+) {
+    let handle = app_handle.clone();
+    app_handle
+        .state::<Scheduler>()
+        .start_when_room(project_id, workspace_id, move || start(&handle));
+    pump_in_background(app_handle);
+}
+
+/// Start whatever the scheduler has room for. Each start runs on its own
+/// blocking task, and the scheduler is pumped again once it's done, since
+/// a start that failed leaves its slot behind.
+pub fn pump(app_handle: &AppHandle) {
+    let starts = session_commands::with_spawner(app_handle, |spawner| {
+        app_handle.state::<Scheduler>().pump(spawner)
+    });
+    for start in starts {
+        let handle = app_handle.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            run(&handle, start);
+            pump(&handle);
+        });
+    }
+}
+
+fn run(app_handle: &AppHandle, start: Start) {
+    let started = session_commands::with_spawner(app_handle, |spawner| start.run(spawner));
+    if let Some(session_id) = started {
+        if let Err(e) = session_commands::start_stream(app_handle, &session_id) {
+            eprintln!("[scheduler] Failed to start stream for {session_id}: {e}");
+        }
+    }
+}
+
+/// Pump on a blocking task, for callers on the main and IPC threads
+/// where even the database work of a pump shouldn't run.
+pub fn pump_in_background(app_handle: &AppHandle) {
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn_blocking(move || pump(&handle));
+}
@@ -0,0 +1,109 @@
+use tauri::{AppHandle, Manager, State};
+
+use dacm_lib::db::{self, DbState};
+use dacm_lib::error::{AppError, ResultExt};
+use dacm_lib::project_status;
+use dacm_lib::scheduler;
+use dacm_lib::scheduler_models::{NewQueuedSpawn, QueueState, QueuedSpawn};
+use dacm_lib::session::SessionManager;
+use dacm_lib::settings_registry;
+
+use crate::app_events::AppEvents;
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn enqueue_session(
+    app_handle: AppHandle,
+    state: State<'_, DbState>,
+    project_id: i32,
+    workspace_id: i32,
+    working_dir: String,
+    initial_prompt: Option<String>,
+    cli_command: Option<String>,
+    model: Option<String>,
+    rows: Option<u16>,
+    cols: Option<u16>,
+    priority: Option<i32>,
+) -> Result<QueuedSpawn, AppError> {
+    let (queued, transition) = state
+        .run(move |conn| {
+            scheduler::enqueue(
+                conn,
+                &NewQueuedSpawn {
+                    project_id,
+                    workspace_id,
+                    working_dir: &working_dir,
+                    initial_prompt: initial_prompt.as_deref(),
+                    cli_command: cli_command.as_deref(),
+                    model: model.as_deref(),
+                    rows: i32::from(rows.unwrap_or(24)),
+                    cols: i32::from(cols.unwrap_or(80)),
+                    priority: priority.unwrap_or(0),
+                },
+            )
+        })
+        .await?;
+
+    project_status::emit_change(&AppEvents(&app_handle), &transition);
+
+    crate::scheduler::pump_in_background(&app_handle);
+
+    Ok(queued)
+}
+
+#[tauri::command]
+pub async fn list_queue(state: State<'_, DbState>) -> Result<Vec<QueuedSpawn>, AppError> {
+    state
+        .run(|conn| {
+            let settings = scheduler::load_queue_settings(conn);
+            scheduler::load_pending(conn, &settings.mode).context("Failed to list queue")
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn cancel_queued_session(
+    app_handle: AppHandle,
+    state: State<'_, DbState>,
+    queue_id: i32,
+) -> Result<(), AppError> {
+    let transition = state
+        .run(move |conn| scheduler::cancel(conn, queue_id))
+        .await?;
+    project_status::emit_change(&AppEvents(&app_handle), &transition);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_queue_state(app_handle: AppHandle) -> Result<QueueState, AppError> {
+    db::blocking(move || queue_state(&app_handle)).await
+}
+
+#[tauri::command]
+pub async fn pause_queue(app_handle: AppHandle) -> Result<QueueState, AppError> {
+    db::blocking(move || set_paused(&app_handle, true)).await
+}
+
+#[tauri::command]
+pub async fn resume_queue(app_handle: AppHandle) -> Result<QueueState, AppError> {
+    db::blocking(move || set_paused(&app_handle, false)).await
+}
+
+fn queue_state(app_handle: &AppHandle) -> Result<QueueState, AppError> {
+    let mut conn = app_handle.state::<DbState>().conn()?;
+    let session_mgr = app_handle.state::<SessionManager>();
+    scheduler::queue_state(&mut conn, &session_mgr).context("Failed to read queue state")
+}
+
+/// The scheduler's settings watcher emits `queue-state-changed` and pumps
+/// on resume, so this only has to store the flag.
+fn set_paused(app_handle: &AppHandle, paused: bool) -> Result<QueueState, AppError> {
+    let (_, change) = settings_registry::update(
+        &mut *app_handle.state::<DbState>().conn()?,
+        "queue_paused",
+        &serde_json::Value::Bool(paused),
+    )?;
+
+    settings_registry::broadcast(&AppEvents(app_handle), change.as_slice());
+    queue_state(app_handle)
+}
@@ -0,0 +1,71 @@
+use std::collections::BTreeMap;
+
+use tauri::{AppHandle, Manager, State};
+
+use dacm_lib::db::DbState;
+use dacm_lib::error::AppError;
+use dacm_lib::secret_models::{SecretInfo, SecretsStatus};
+use dacm_lib::secrets::{self, SecretStore};
+
+/// Store a secret. Only its name comes back; there is no command that
+/// returns a value.
+#[tauri::command]
+pub async fn set_secret(
+    app_handle: AppHandle,
+    state: State<'_, DbState>,
+    name: String,
+    value: String,
+) -> Result<SecretInfo, AppError> {
+    state
+        .run(move |conn| app_handle.state::<SecretStore>().set(conn, &name, &value))
+        .await
+}
+
+#[tauri::command]
+pub async fn delete_secret(
+    app_handle: AppHandle,
+    state: State<'_, DbState>,
+    name: String,
+) -> Result<(), AppError> {
+    state
+        .run(move |conn| app_handle.state::<SecretStore>().delete(conn, &name))
+        .await
+}
+
+#[tauri::command]
+pub async fn list_secrets(state: State<'_, DbState>) -> Result<Vec<SecretInfo>, AppError> {
+    state.run(secrets::list).await
+}
+
+#[tauri::command]
+pub async fn get_secrets_status(
+    app_handle: AppHandle,
+    state: State<'_, DbState>,
+) -> Result<SecretsStatus, AppError> {
+    state
+        .run(move |conn| app_handle.state::<SecretStore>().status(conn))
+        .await
+}
+
+/// A workspace's secret mappings, env var to secret name.
+#[tauri::command]
+pub async fn get_secret_mappings(
+    state: State<'_, DbState>,
+    workspace_id: i32,
+) -> Result<BTreeMap<String, String>, AppError> {
+    state
+        .run(move |conn| secrets::mappings(conn, workspace_id))
+        .await
+}
+
+/// Replace which secrets a workspace's sessions get.
+#[tauri::command]
+pub async fn set_secret_mappings(
+    state: State<'_, DbState>,
+    workspace_id: i32,
+    mappings: BTreeMap<String, String>,
+) -> Result<BTreeMap<String, String>, AppError> {
+    state
+        .run(move |conn| secrets::set_mappings(conn, workspace_id, &mappings))
+        .await
+}
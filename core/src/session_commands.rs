@@ -1,12 +1,46 @@
+use std::collections::BTreeMap;
 use std::io::Read;
+use std::path::Path;
 
+use serde_json::json;
+use tauri::ipc::{Channel, InvokeResponseBody};
 use tauri::{AppHandle, Emitter, Manager, State};
 
-use crate::session::SessionManager;
+use dacm_lib::db::{self, DbState};
+use dacm_lib::error::AppError;
+use dacm_lib::event_models::{EventType, NewEvent};
+use dacm_lib::events;
+use dacm_lib::hook_models::HookStage;
+use dacm_lib::hooks::{self, HookContext};
+use dacm_lib::persistent;
+use dacm_lib::project_models::ProjectStatus;
+use dacm_lib::recording::Recordings;
+use dacm_lib::retry::Retries;
+use dacm_lib::retry_models::SessionExited;
+use dacm_lib::scheduler::Scheduler;
+use dacm_lib::secrets::SecretStore;
+use dacm_lib::session::{SessionExit, SessionManager, SessionSource, SpawnRequest};
+use dacm_lib::settings_registry;
+use dacm_lib::spawn::{self, Spawner};
+use dacm_lib::tmux;
+use dacm_lib::workspace_config;
 
+use crate::app_events::AppEvents;
+use crate::output_stream::{self, OutputStreams};
+use crate::project_commands;
+use crate::replay::{self, Replays};
+use crate::retry;
+use crate::scheduler;
+
+/// Start a session right away, under the session limits: a conflict if
+/// they leave no slot or the project is already running. Runs the
+/// workspace's pre-spawn hooks first, so this is async to keep them off the
+/// IPC thread. `env` overrides every other source of variables except
+/// secret mappings.
 #[tauri::command]
-pub fn spawn_session(
-    session_mgr: State<'_, SessionManager>,
+#[allow(clippy::too_many_arguments)]
+pub async fn spawn_session(
+    app_handle: AppHandle,
     project_id: i32,
     workspace_id: i32,
     working_dir: String,
@@ -15,17 +49,45 @@ pub fn spawn_session(
     model: Option<String>,
     rows: Option<u16>,
     cols: Option<u16>,
-) -> Result<String, String> {
-    session_mgr.spawn(
+    env: Option<BTreeMap<String, String>>,
+) -> Result<String, AppError> {
+    let request = SpawnRequest {
         project_id,
         workspace_id,
         working_dir,
         initial_prompt,
         cli_command,
         model,
-        rows.unwrap_or(24),
-        cols.unwrap_or(80),
-    )
+        rows: rows.unwrap_or(24),
+        cols: cols.unwrap_or(80),
+        resume_session_id: None,
+        continue_last: false,
+        env: env.unwrap_or_default(),
+        secret_env: Default::default(),
+        persistent: false,
+    };
+    db::blocking(move || spawn(&app_handle, request)).await
+}
+
+/// Everything `spawn_session` does once it has a request (see
+/// `Scheduler::start_now`). Blocks for as long as the pre-spawn hooks run.
+pub fn spawn(app_handle: &AppHandle, request: SpawnRequest) -> Result<String, AppError> {
+    app_handle.state::<Retries>().cancel(request.project_id);
+    with_spawner(app_handle, |spawner| {
+        app_handle
+            .state::<Scheduler>()
+            .start_now(spawner, request, "session started")
+    })
+}
+
+/// Run `f` with a `Spawner` over the app's state and windows.
+pub fn with_spawner<T>(app_handle: &AppHandle, f: impl FnOnce(&Spawner) -> T) -> T {
+    f(&Spawner {
+        db: app_handle.state::<DbState>().inner(),
+        secrets: app_handle.state::<SecretStore>().inner(),
+        sessions: app_handle.state::<SessionManager>().inner(),
+        events: &AppEvents(app_handle),
+    })
 }
 
 #[tauri::command]
@@ -33,44 +95,181 @@ pub fn write_to_session(
     session_mgr: State<'_, SessionManager>,
     session_id: String,
     data: Vec<u8>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    // Replays are read-only
+    if replay::is_replay(&session_id) {
+        return Ok(());
+    }
     session_mgr.write(&session_id, &data)
 }
 
 #[tauri::command]
 pub fn resize_session(
     session_mgr: State<'_, SessionManager>,
+    recordings: State<'_, Recordings>,
     session_id: String,
     rows: u16,
     cols: u16,
-) -> Result<(), String> {
-    session_mgr.resize(&session_id, rows, cols)
+) -> Result<(), AppError> {
+    if replay::is_replay(&session_id) {
+        return Ok(());
+    }
+    session_mgr.resize(&session_id, rows, cols)?;
+    recordings.resize(&session_id, rows, cols);
+    Ok(())
 }
 
+/// Async so recording the kill stays off the IPC thread.
 #[tauri::command]
-pub fn kill_session(
-    session_mgr: State<'_, SessionManager>,
-    session_id: String,
-) -> Result<(), String> {
-    session_mgr.kill(&session_id)
+pub async fn kill_session(app_handle: AppHandle, session_id: String) -> Result<(), AppError> {
+    db::blocking(move || kill(&app_handle, &session_id)).await
+}
+
+/// Stop a session, recording the kill, and let queued work take its slot.
+/// Blocks on the database.
+pub fn kill(app_handle: &AppHandle, session_id: &str) -> Result<(), AppError> {
+    let session_mgr = app_handle.state::<SessionManager>();
+    let info = session_mgr.get(session_id)?;
+    session_mgr.kill(session_id)?;
+    app_handle.state::<Retries>().cancel(info.project_id);
+
+    // Detaching from tmux is recorded when the client exits
+    if info.source == SessionSource::Direct {
+        let mut conn = app_handle.state::<DbState>().conn()?;
+        events::record(
+            &mut conn,
+            NewEvent::new(EventType::SessionKilled)
+                .workspace(info.workspace_id)
+                .project(info.project_id)
+                .session(session_id)
+                .payload(json!({ "uptime_secs": info.uptime_secs })),
+        );
+    }
+
+    // A slot just opened up — let queued work start
+    scheduler::pump_in_background(app_handle);
+    Ok(())
 }
 
 #[tauri::command]
 pub fn list_sessions(
     session_mgr: State<'_, SessionManager>,
-) -> Result<Vec<crate::session::SessionInfo>, String> {
+) -> Result<Vec<dacm_lib::session::SessionInfo>, AppError> {
     session_mgr.list()
 }
 
+/// Sessions started by the scheduler, a retry, the CLI or a reattach are
+/// already streaming when a terminal opens on them. Starting one again
+/// returns its scrollback instead, for the terminal to write before the
+/// events it's listening for; the two can overlap by a chunk. A stream
+/// started here has nothing to catch up on.
 #[tauri::command]
 pub async fn start_session_stream(
     app_handle: AppHandle,
     session_id: String,
-) -> Result<(), String> {
+) -> Result<Vec<u8>, AppError> {
+    if replay::is_replay(&session_id) {
+        let replays = app_handle.state::<Replays>();
+        return replays.start(&app_handle, &session_id).map(|()| Vec::new());
+    }
+    let session_mgr = app_handle.state::<SessionManager>();
+    if session_mgr.is_streaming(&session_id)? {
+        return session_mgr.scrollback(&session_id);
+    }
+    start_stream(&app_handle, &session_id)?;
+    Ok(Vec::new())
+}
+
+/// Reattach to persistent sessions still running from a previous run of
+/// the app, and stream them so their exits are handled as usual.
+pub fn reattach_persistent(app_handle: &AppHandle) {
+    if !tmux::available() {
+        return;
+    }
+    let session_mgr = app_handle.state::<SessionManager>();
+    for (session_id, mut request) in persistent::list() {
+        let (project_id, workspace_id) = (request.project_id, request.workspace_id);
+        // The stored request has no env, which a retry would need. It's
+        // rebuilt from the project's profile and the workspace config, so
+        // any env the spawn was given directly is lost.
+        let prepared = app_handle
+            .state::<DbState>()
+            .conn()
+            .and_then(|mut conn| spawn::prepare(&mut conn, &mut request));
+        if let Err(e) = prepared {
+            eprintln!("[session] No env for reattached {session_id}: {e}");
+        }
+        if let Err(e) = session_mgr.reattach(session_id.clone(), request) {
+            eprintln!("[session] Failed to reattach {session_id}: {e}");
+            continue;
+        }
+        eprintln!("[session] Reattached persistent session {session_id}");
+
+        if let Ok(mut conn) = app_handle.state::<DbState>().conn() {
+            events::record(
+                &mut conn,
+                NewEvent::new(EventType::SessionAttached)
+                    .workspace(workspace_id)
+                    .project(project_id)
+                    .session(&session_id)
+                    .payload(json!({ "source": "persistent" })),
+            );
+        }
+        if let Err(e) = start_stream(app_handle, &session_id) {
+            eprintln!("[session] Failed to start stream for {session_id}: {e}");
+        }
+    }
+}
+
+/// Receive a session's output as raw bytes on `channel` instead of
+/// `session-output-{id}` events. Replays always use events, so attaching to
+/// one is a no-op.
+#[tauri::command]
+pub fn attach_session_output(
+    streams: State<'_, OutputStreams>,
+    session_id: String,
+    channel: Channel<InvokeResponseBody>,
+) -> Result<(), AppError> {
+    if replay::is_replay(&session_id) {
+        return Ok(());
+    }
+    streams.attach(&session_id, channel)
+}
+
+#[tauri::command]
+pub fn detach_session_output(
+    streams: State<'_, OutputStreams>,
+    session_id: String,
+    channel_id: u32,
+) -> Result<(), AppError> {
+    streams.detach(&session_id, channel_id)
+}
+
+/// Start forwarding PTY output to the webview (see `output_stream`). Sessions
+/// started by the scheduler call this directly so their exit is noticed
+/// even when no window is attached.
+pub fn start_stream(app_handle: &AppHandle, session_id: &str) -> Result<(), AppError> {
     let session_mgr = app_handle.state::<SessionManager>();
-    let reader = session_mgr.take_reader(&session_id)?;
+    let reader = session_mgr.take_reader(session_id)?;
 
-    let id = session_id.clone();
+    let record = app_handle
+        .state::<DbState>()
+        .conn()
+        .and_then(|mut conn| settings_registry::get_bool(&mut conn, "record_sessions"))
+        .unwrap_or(false);
+    if record {
+        // A session that can't be recorded still runs
+        let started = session_mgr.size(session_id).and_then(|(rows, cols)| {
+            app_handle
+                .state::<Recordings>()
+                .start(session_id, rows, cols)
+        });
+        if let Err(e) = started {
+            eprintln!("[stream {session_id}] Not recording: {e}");
+        }
+    }
+
+    let id = session_id.to_string();
     let handle = app_handle.clone();
 
     tauri::async_runtime::spawn_blocking(move || {
@@ -80,47 +279,127 @@ pub async fn start_session_stream(
     Ok(())
 }
 
-fn stream_pty_output(
-    mut reader: Box<dyn Read + Send>,
-    session_id: &str,
-    app_handle: &AppHandle,
-) {
-    let mut buf = [0u8; 4096];
-    let mut total_bytes = 0usize;
-
-    eprintln!("[stream {session_id}] Reader started");
-
-    loop {
-        match reader.read(&mut buf) {
-            Ok(0) => {
-                eprintln!("[stream {session_id}] EOF after {total_bytes} total bytes");
-                break;
-            }
-            Ok(n) => {
-                if total_bytes == 0 {
-                    let preview = String::from_utf8_lossy(&buf[..n.min(500)]);
-                    eprintln!("[stream {session_id}] First output ({n} bytes): {preview:?}");
-                }
-                total_bytes += n;
-                let chunk = buf[..n].to_vec();
-                let event_name = format!("session-output-{session_id}");
-                let _ = app_handle.emit(&event_name, chunk);
-            }
-            Err(e) => {
-                eprintln!("[stream {session_id}] Read error after {total_bytes} bytes: {e}");
-                break;
-            }
-        }
-    }
+fn stream_pty_output(reader: Box<dyn Read + Send>, session_id: &str, app_handle: &AppHandle) {
+    output_stream::pump(reader, session_id, app_handle);
+
+    app_handle.state::<Recordings>().finish(session_id);
 
     let session_mgr = app_handle.state::<SessionManager>();
+    let exit = session_mgr.wait_exit(session_id);
     session_mgr.mark_exited(session_id);
 
+    // A tmux client ending on its own is only a detach; the work carries
+    // on in tmux, so there's nothing to clean up after or retry
+    let exit = match exit {
+        Some(exit) if exit.detached => {
+            record_detach(app_handle, session_id, &exit);
+            None
+        }
+        exit => exit,
+    };
+
+    if let Some(exit) = &exit {
+        after_exit(app_handle, session_id, exit);
+    }
+
+    let will_retry = exit
+        .as_ref()
+        .is_some_and(|e| retry::on_session_exit(app_handle, session_id, e));
+
     let exit_event = format!("session-exit-{session_id}");
-    let _ = app_handle.emit(&exit_event, ());
+    let _ = app_handle.emit(
+        &exit_event,
+        SessionExited {
+            exit_code: exit.as_ref().and_then(|e| e.exit_code),
+            signal: exit.as_ref().and_then(|e| e.signal.clone()),
+            will_retry,
+        },
+    );
     eprintln!("[stream {session_id}] Exit event emitted");
 
     // Free PTY handles (master, writer, child) now that the stream is done
     let _ = session_mgr.remove(session_id);
     eprintln!("[stream {session_id}] Session removed from manager");
+
+    scheduler::pump(app_handle);
+}
+
+fn record_detach(app_handle: &AppHandle, session_id: &str, exit: &SessionExit) {
+    let db = app_handle.state::<DbState>();
+    if let Ok(mut conn) = db.conn() {
+        events::record(
+            &mut conn,
+            NewEvent::new(EventType::SessionDetached)
+                .workspace(exit.request.workspace_id)
+                .project(exit.request.project_id)
+                .session(session_id)
+                .payload(json!({ "exit_code": exit.exit_code })),
+        );
+    }
+}
+
+/// Post-exit hooks run before any retry so cleanup happens first. Their
+/// failures are recorded but don't change how the exit is handled. After a
+/// clean exit the workspace's exit criteria then decide whether the project
+/// is done or needs a look.
+fn after_exit(app_handle: &AppHandle, session_id: &str, exit: &SessionExit) {
+    let request = &exit.request;
+    let config = {
+        let db = app_handle.state::<DbState>();
+        db.conn()
+            .and_then(|mut conn| workspace_config::load(&mut conn, request.workspace_id))
+    };
+    let config = match config {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("[stream {session_id}] Skipping post-exit hooks: {e}");
+            return;
+        }
+    };
+
+    let db = app_handle.state::<DbState>();
+    let stage_hooks = config.hooks.for_stage(HookStage::PostSessionExit);
+    if !stage_hooks.is_empty() {
+        let mut env = request.env.clone();
+        env.insert(
+            "DACM_EXIT_CODE".to_string(),
+            exit.exit_code.map(|c| c.to_string()).unwrap_or_default(),
+        );
+        let ran = hooks::run_stage(
+            &db,
+            &AppEvents(app_handle),
+            &HookContext {
+                stage: HookStage::PostSessionExit,
+                workspace_id: Some(request.workspace_id),
+                project_id: Some(request.project_id),
+                session_id: Some(session_id),
+                dir: Path::new(&request.working_dir),
+                source_dir: Path::new(&config.workspace_path),
+                env: &env,
+                timeout: config.hook_timeout(),
+            },
+            stage_hooks,
+        );
+        if let Err(e) = ran {
+            eprintln!("[stream {session_id}] Post-exit hooks: {e}");
+        }
+    }
+
+    if !exit.succeeded() {
+        return;
+    }
+    let passed =
+        hooks::check_exit_criteria(&db, &AppEvents(app_handle), &config, request, session_id);
+    let (status, reason) = match passed {
+        Ok(Some(true)) => (ProjectStatus::Completed, "exit criteria passed"),
+        Ok(Some(false)) => (ProjectStatus::Waiting, "exit criteria failed"),
+        Ok(None) => return,
+        Err(e) => {
+            eprintln!("[stream {session_id}] Exit criteria: {e}");
+            return;
+        }
+    };
+    if let Err(e) = project_commands::transition(app_handle, request.project_id, status, reason) {
+        eprintln!("[stream {session_id}] {e}");
+    }
 }
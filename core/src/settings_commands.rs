@@ -1,45 +1,79 @@
-use diesel::prelude::*;
-use tauri::State;
+use serde_json::Value;
+use tauri::{AppHandle, State};
 
-use crate::db::DbState;
-use crate::schema::settings;
-use crate::settings_models::{NewSetting, Setting};
+use dacm_lib::db::DbState;
+use dacm_lib::error::AppError;
+use dacm_lib::settings_registry::{self, SettingSchema, SettingValue};
 
+use crate::app_events::AppEvents;
+
+/// A setting in its stored string form, or its default if it was never set.
 #[tauri::command]
-pub fn get_setting(state: State<'_, DbState>, key: String) -> Result<String, String> {
-    let mut conn = state.conn.lock().map_err(|e| e.to_string())?;
-
-    settings::table
-        .filter(settings::key.eq(&key))
-        .select(settings::value)
-        .first::<String>(&mut *conn)
-        .map_err(|e| format!("Failed to get setting '{key}': {e}"))
+pub async fn get_setting(state: State<'_, DbState>, key: String) -> Result<String, AppError> {
+    state
+        .run(move |conn| settings_registry::get_raw(conn, &key))
+        .await
 }
 
+/// Store a setting given as a string, after checking it against the registry.
 #[tauri::command]
-pub fn set_setting(state: State<'_, DbState>, key: String, value: String) -> Result<(), String> {
-    let mut conn = state.conn.lock().map_err(|e| e.to_string())?;
+pub async fn set_setting(
+    app_handle: AppHandle,
+    state: State<'_, DbState>,
+    key: String,
+    value: String,
+) -> Result<(), AppError> {
+    let change = state
+        .run(move |conn| {
+            let value = settings_registry::parse_raw(&key, &value)?;
+            settings_registry::update(conn, &key, &value).map(|(_, change)| change)
+        })
+        .await?;
 
-    let new_setting = NewSetting {
-        key: &key,
-        value: &value,
-    };
+    settings_registry::broadcast(&AppEvents(&app_handle), change.as_slice());
+    Ok(())
+}
 
-    diesel::replace_into(settings::table)
-        .values(&new_setting)
-        .execute(&mut *conn)
-        .map_err(|e| format!("Failed to set setting '{key}': {e}"))?;
+#[tauri::command]
+pub async fn get_setting_value(
+    state: State<'_, DbState>,
+    key: String,
+) -> Result<SettingValue, AppError> {
+    state
+        .run(move |conn| settings_registry::get(conn, &key))
+        .await
+}
 
-    Ok(())
+/// Store a typed value; `null` resets a nullable setting.
+#[tauri::command]
+pub async fn set_setting_value(
+    app_handle: AppHandle,
+    state: State<'_, DbState>,
+    key: String,
+    value: Value,
+) -> Result<SettingValue, AppError> {
+    let (setting, change) = state
+        .run(move |conn| settings_registry::update(conn, &key, &value))
+        .await?;
+
+    settings_registry::broadcast(&AppEvents(&app_handle), change.as_slice());
+    Ok(setting)
+}
+
+/// Every known setting with its current value.
+#[tauri::command]
+pub async fn list_settings(state: State<'_, DbState>) -> Result<Vec<SettingValue>, AppError> {
+    state
+        .run(|conn| {
+            settings_registry::REGISTRY
+                .iter()
+                .map(|def| settings_registry::get(conn, def.key))
+                .collect()
+        })
+        .await
 }
 
 #[tauri::command]
-pub fn list_settings(state: State<'_, DbState>) -> Result<Vec<Setting>, String> {
-    let mut conn = state.conn.lock().map_err(|e| e.to_string())?;
-
-    settings::table
-        .select(Setting::as_select())
-        .order(settings::key.asc())
-        .load(&mut *conn)
-        .map_err(|e| format!("Failed to list settings: {e}"))
+pub fn get_settings_schema() -> Vec<SettingSchema> {
+    settings_registry::schema()
 }
@@ -1,6 +1,15 @@
 use std::process::{Child, Command};
 use std::sync::Mutex;
 
+use serde_json::Value;
+use tauri::{AppHandle, Listener, Manager, State};
+
+use dacm_lib::db::DbState;
+use dacm_lib::error::{AppError, ResultExt};
+use dacm_lib::settings_registry::{self, SettingChanged};
+
+use crate::app_events::AppEvents;
+
 pub struct SleepState {
     pub child: Mutex<Option<Child>>,
 }
@@ -21,29 +30,69 @@ impl SleepState {
             *guard = None;
         }
     }
+
+    /// Start or stop inhibiting sleep.
+    pub fn apply(&self, prevent: bool) -> Result<(), AppError> {
+        let mut guard = self.child.lock()?;
+
+        // Kill existing process if any
+        if let Some(ref mut child) = *guard {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        *guard = None;
+
+        if prevent {
+            let child = Command::new("caffeinate")
+                .args(["-d", "-i"])
+                .spawn()
+                .context("Failed to start caffeinate")?;
+            *guard = Some(child);
+        }
+
+        Ok(())
+    }
+}
+
+/// Apply the stored `prevent_sleep` setting at startup, then follow it as
+/// it changes.
+pub fn watch_settings(app_handle: &AppHandle) {
+    let stored = app_handle
+        .state::<DbState>()
+        .conn()
+        .and_then(|mut conn| settings_registry::get_bool(&mut conn, "prevent_sleep"));
+    if let Ok(true) = stored {
+        if let Err(e) = app_handle.state::<SleepState>().apply(true) {
+            eprintln!("[sleep] Failed to prevent sleep: {e}");
+        }
+    }
+
+    let handle = app_handle.clone();
+    app_handle.listen_any(settings_registry::SETTINGS_CHANGED, move |event| {
+        let Ok(change) = serde_json::from_str::<SettingChanged>(event.payload()) else {
+            return;
+        };
+        if change.key != "prevent_sleep" {
+            return;
+        }
+        let prevent = change.new_value.as_bool().unwrap_or(false);
+        if let Err(e) = handle.state::<SleepState>().apply(prevent) {
+            eprintln!("[sleep] Failed to apply prevent_sleep: {e}");
+        }
+    });
 }
 
+/// Store the `prevent_sleep` setting. Kept for callers that predate the
+/// settings broadcast; the change is applied by `watch_settings`.
 #[tauri::command]
 pub fn set_prevent_sleep(
-    state: tauri::State<'_, SleepState>,
+    app_handle: AppHandle,
+    state: State<'_, DbState>,
     prevent: bool,
-) -> Result<(), String> {
-    let mut guard = state.child.lock().map_err(|e| e.to_string())?;
-
-    // Kill existing process if any
-    if let Some(ref mut child) = *guard {
-        let _ = child.kill();
-        let _ = child.wait();
-    }
-    *guard = None;
-
-    if prevent {
-        let child = Command::new("caffeinate")
-            .args(["-d", "-i"])
-            .spawn()
-            .map_err(|e| format!("Failed to start caffeinate: {e}"))?;
-        *guard = Some(child);
-    }
+) -> Result<(), AppError> {
+    let (_, change) =
+        settings_registry::update(&mut *state.conn()?, "prevent_sleep", &Value::Bool(prevent))?;
 
+    settings_registry::broadcast(&AppEvents(&app_handle), change.as_slice());
     Ok(())
 }
@@ -2,7 +2,10 @@ use serde::Serialize;
 use sysinfo::{Pid, ProcessesToUpdate, System};
 use tauri::State;
 
-use crate::session::SessionManager;
+use dacm_lib::error::AppError;
+use dacm_lib::session::SessionManager;
+
+use crate::output_stream::{OutputStreams, StreamStats};
 
 #[derive(Serialize)]
 pub struct SystemStats {
@@ -12,10 +15,14 @@ pub struct SystemStats {
     pub memory_percent: f32,
     pub child_memory_mb: u64,
     pub child_count: u32,
+    pub streams: Vec<StreamStats>,
 }
 
 #[tauri::command]
-pub fn get_system_stats(session_mgr: State<'_, SessionManager>) -> Result<SystemStats, String> {
+pub fn get_system_stats(
+    session_mgr: State<'_, SessionManager>,
+    output_streams: State<'_, OutputStreams>,
+) -> Result<SystemStats, AppError> {
     let mut sys = System::new();
     sys.refresh_memory();
 
@@ -53,5 +60,6 @@ pub fn get_system_stats(session_mgr: State<'_, SessionManager>) -> Result<System
         memory_percent,
         child_memory_mb: child_memory_bytes / (1024 * 1024),
         child_count,
+        streams: output_streams.stats(),
     })
 }
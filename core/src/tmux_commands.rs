@@ -0,0 +1,65 @@
+use serde_json::json;
+use tauri::{AppHandle, Manager, State};
+
+use dacm_lib::db::{self, DbState};
+use dacm_lib::error::AppError;
+use dacm_lib::event_models::{EventType, NewEvent};
+use dacm_lib::events;
+use dacm_lib::session::{SessionManager, SpawnRequest};
+use dacm_lib::tmux;
+use dacm_lib::tmux_models::TmuxSession;
+
+#[tauri::command]
+pub async fn list_tmux_sessions() -> Result<Vec<TmuxSession>, AppError> {
+    db::blocking(tmux::list).await
+}
+
+/// Open a tmux session or pane (`%7`) as a session of the project. The
+/// returned id works like a spawned session's; killing it detaches.
+#[tauri::command]
+pub async fn attach_tmux_session(
+    app_handle: AppHandle,
+    state: State<'_, DbState>,
+    project_id: i32,
+    workspace_id: i32,
+    target: String,
+    rows: Option<u16>,
+    cols: Option<u16>,
+) -> Result<String, AppError> {
+    let handle = app_handle.clone();
+    let attach_target = target.clone();
+    let session_id = db::blocking(move || {
+        let working_dir = tmux::resolve(&attach_target)?;
+        let request = SpawnRequest {
+            project_id,
+            workspace_id,
+            working_dir,
+            initial_prompt: None,
+            cli_command: Some("tmux".to_string()),
+            model: None,
+            rows: rows.unwrap_or(24),
+            cols: cols.unwrap_or(80),
+            resume_session_id: None,
+            continue_last: false,
+            env: Default::default(),
+            secret_env: Default::default(),
+            persistent: false,
+        };
+        handle
+            .state::<SessionManager>()
+            .attach_tmux(request, &attach_target)
+    })
+    .await?;
+
+    let mut conn = state.conn()?;
+    events::record(
+        &mut conn,
+        NewEvent::new(EventType::SessionAttached)
+            .workspace(workspace_id)
+            .project(project_id)
+            .session(&session_id)
+            .payload(json!({ "source": "tmux", "target": target })),
+    );
+
+    Ok(session_id)
+}
@@ -1,59 +1,124 @@
 use diesel::prelude::*;
-use tauri::State;
+use serde_json::json;
+use tauri::{AppHandle, Emitter, Manager, State};
 
-use crate::db::DbState;
-use crate::models::{NewWorkspace, Workspace};
-use crate::schema::workspaces;
+use dacm_lib::db::{self, DbState};
+use dacm_lib::error::{AppError, ResultExt};
+use dacm_lib::models::{Workspace, WorkspaceBulkAdd, WorkspaceCandidate};
+use dacm_lib::project_status;
+use dacm_lib::schema::workspaces;
+use dacm_lib::session::SessionManager;
+use dacm_lib::workspace::{self, RemoveMode, WorkspaceRemoval};
+use dacm_lib::workspace_config::{self, WorkspaceConfig};
+use dacm_lib::workspace_scan;
 
+use crate::app_events::AppEvents;
+
+/// Register a git repository as a workspace.
+#[tauri::command]
+pub async fn add_workspace(state: State<'_, DbState>, path: String) -> Result<Workspace, AppError> {
+    state.run(move |conn| workspace::add(conn, &path)).await
+}
+
+/// Register several repositories at once, typically picked from
+/// `scan_for_workspaces`. Either every new path is added or none is.
 #[tauri::command]
-pub fn add_workspace(state: State<'_, DbState>, path: String) -> Result<Workspace, String> {
-    let name = std::path::Path::new(&path)
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("unnamed")
-        .to_string();
-
-    let new_workspace = NewWorkspace {
-        name: &name,
-        path: &path,
-    };
-
-    let mut conn = state.conn.lock().map_err(|e| e.to_string())?;
-
-    diesel::insert_into(workspaces::table)
-        .values(&new_workspace)
-        .execute(&mut *conn)
-        .map_err(|e| format!("Failed to add workspace: {e}"))?;
-
-    workspaces::table
-        .filter(workspaces::path.eq(&path))
-        .select(Workspace::as_select())
-        .first(&mut *conn)
-        .map_err(|e| format!("Failed to fetch workspace: {e}"))
+pub async fn add_workspaces(
+    state: State<'_, DbState>,
+    paths: Vec<String>,
+) -> Result<WorkspaceBulkAdd, AppError> {
+    state
+        .run(move |conn| workspace::add_many(conn, &paths))
+        .await
+}
+
+/// Walk `root` looking for git repositories that could become workspaces.
+/// Progress is streamed as `workspace-scan-progress` events.
+#[tauri::command]
+pub async fn scan_for_workspaces(
+    app_handle: AppHandle,
+    state: State<'_, DbState>,
+    root: String,
+    max_depth: Option<usize>,
+) -> Result<Vec<WorkspaceCandidate>, AppError> {
+    let max_depth = max_depth
+        .unwrap_or(workspace_scan::DEFAULT_MAX_DEPTH)
+        .min(workspace_scan::MAX_DEPTH);
+    let registered = state
+        .run(|conn| workspace::registered_roots(conn).context("Failed to list workspaces"))
+        .await?;
+
+    db::blocking(move || {
+        workspace_scan::scan(&root, max_depth, &registered, |progress| {
+            let _ = app_handle.emit("workspace-scan-progress", progress);
+        })
+    })
+    .await
 }
 
+/// The workspace's `.dacm.toml` merged over the global settings.
 #[tauri::command]
-pub fn remove_workspace(state: State<'_, DbState>, id: i32) -> Result<(), String> {
-    let mut conn = state.conn.lock().map_err(|e| e.to_string())?;
+pub async fn get_workspace_config(
+    state: State<'_, DbState>,
+    id: i32,
+) -> Result<WorkspaceConfig, AppError> {
+    state
+        .run(move |conn| workspace_config::load(conn, id))
+        .await
+}
+
+/// Re-read a workspace's branch, HEAD and remotes from git.
+#[tauri::command]
+pub async fn refresh_workspace(state: State<'_, DbState>, id: i32) -> Result<Workspace, AppError> {
+    state.run(move |conn| workspace::refresh(conn, id)).await
+}
+
+/// Remove a workspace. `mode` decides what happens to its projects and
+/// defaults to refusing while any remain.
+#[tauri::command]
+pub async fn remove_workspace(
+    app_handle: AppHandle,
+    state: State<'_, DbState>,
+    session_mgr: State<'_, SessionManager>,
+    id: i32,
+    mode: Option<RemoveMode>,
+) -> Result<WorkspaceRemoval, AppError> {
+    if session_mgr.running_count_for_workspace(id) > 0 {
+        return Err(
+            AppError::conflict("Workspace has running sessions; stop them first")
+                .with_details(json!({ "workspace_id": id })),
+        );
+    }
 
-    let deleted = diesel::delete(workspaces::table.filter(workspaces::id.eq(id)))
-        .execute(&mut *conn)
-        .map_err(|e| format!("Failed to remove workspace: {e}"))?;
+    let mode = mode.unwrap_or_default();
+    let mut removal = state
+        .run(move |conn| workspace::remove(conn, id, mode))
+        .await?;
 
-    if deleted == 0 {
-        return Err(format!("Workspace with id {id} not found"));
+    for transition in &removal.transitions {
+        project_status::emit_change(&AppEvents(&app_handle), transition);
     }
 
-    Ok(())
+    db::blocking(move || {
+        workspace::remove_worktrees(
+            &app_handle.state::<DbState>(),
+            &AppEvents(&app_handle),
+            &mut removal,
+        );
+        Ok(removal.report)
+    })
+    .await
 }
 
 #[tauri::command]
-pub fn list_workspaces(state: State<'_, DbState>) -> Result<Vec<Workspace>, String> {
-    let mut conn = state.conn.lock().map_err(|e| e.to_string())?;
-
-    workspaces::table
-        .select(Workspace::as_select())
-        .order(workspaces::created_at.desc())
-        .load(&mut *conn)
-        .map_err(|e| format!("Failed to list workspaces: {e}"))
+pub async fn list_workspaces(state: State<'_, DbState>) -> Result<Vec<Workspace>, AppError> {
+    state
+        .run(|conn| {
+            workspaces::table
+                .select(Workspace::as_select())
+                .order(workspaces::created_at.desc())
+                .load(conn)
+                .context("Failed to list workspaces")
+        })
+        .await
 }
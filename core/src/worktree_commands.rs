@@ -0,0 +1,123 @@
+use std::path::Path;
+
+use diesel::prelude::*;
+use serde_json::json;
+use tauri::{AppHandle, Manager, State};
+
+use dacm_lib::db::{self, DbState};
+use dacm_lib::error::{AppError, ResultExt};
+use dacm_lib::event_models::{EventType, NewEvent};
+use dacm_lib::events;
+use dacm_lib::hook_models::HookStage;
+use dacm_lib::hooks::{self, HookContext};
+use dacm_lib::models::Workspace;
+use dacm_lib::project_models::Project;
+use dacm_lib::schema::{projects, workspaces};
+use dacm_lib::workspace_config;
+use dacm_lib::worktree::{self, WorktreeCreated};
+
+use crate::app_events::AppEvents;
+
+/// Give a project its own worktree, named and placed by the workspace
+/// config, then run its post-create hooks in it.
+#[tauri::command]
+pub async fn create_worktree(
+    app_handle: AppHandle,
+    state: State<'_, DbState>,
+    project_id: i32,
+) -> Result<WorktreeCreated, AppError> {
+    let (project, workspace, config) = state
+        .run(move |conn| {
+            let project = projects::table
+                .filter(projects::id.eq(project_id))
+                .select(Project::as_select())
+                .first(conn)
+                .optional()
+                .context("Failed to fetch project")?
+                .ok_or_else(|| {
+                    AppError::not_found(format!("Project with id {project_id} not found"))
+                })?;
+            if let Some(path) = &project.worktree_path {
+                return Err(AppError::conflict("Project already has a worktree")
+                    .with_details(json!({ "project_id": project_id, "path": path })));
+            }
+            let workspace_id = project.workspace_id.ok_or_else(|| {
+                AppError::conflict("Project's workspace has been removed")
+                    .with_details(json!({ "project_id": project_id }))
+            })?;
+
+            let workspace = workspaces::table
+                .filter(workspaces::id.eq(workspace_id))
+                .select(Workspace::as_select())
+                .first(conn)
+                .context("Failed to fetch workspace")?;
+            let config = workspace_config::load(conn, workspace_id)?;
+            Ok((project, workspace, config))
+        })
+        .await?;
+
+    // Git and hooks can take minutes; keep them off the pool
+    let (path, branch, hooks) = db::blocking(move || {
+        let (path, branch) = worktree::plan(&workspace.path, &project, &config);
+        worktree::create(
+            &workspace.path,
+            &path,
+            &branch,
+            workspace.default_branch.as_deref(),
+        )?;
+        let hooks = hooks::run_stage(
+            &app_handle.state::<DbState>(),
+            &AppEvents(&app_handle),
+            &HookContext {
+                stage: HookStage::PostWorktreeCreate,
+                workspace_id: Some(config.workspace_id),
+                project_id: Some(project.id),
+                session_id: None,
+                dir: &path,
+                source_dir: Path::new(&workspace.path),
+                env: &config.env,
+                timeout: config.hook_timeout(),
+            },
+            config.hooks.for_stage(HookStage::PostWorktreeCreate),
+        )?;
+        Ok((path.to_string_lossy().to_string(), branch, hooks))
+    })
+    .await?;
+
+    state
+        .run(move |conn| {
+            diesel::update(projects::table.filter(projects::id.eq(project_id)))
+                .set((
+                    projects::worktree_path.eq(&path),
+                    projects::branch_name.eq(&branch),
+                ))
+                .execute(conn)
+                .context("Failed to save worktree")?;
+
+            let project = projects::table
+                .filter(projects::id.eq(project_id))
+                .select(Project::as_select())
+                .first(conn)
+                .context("Failed to fetch project")?;
+
+            events::record(
+                conn,
+                NewEvent::new(EventType::WorktreeCreated)
+                    .workspace(project.workspace_id)
+                    .project(project_id)
+                    .payload(json!({
+                        "path": path,
+                        "branch": branch,
+                        "hooks_failed": hooks::failure(&hooks).is_some(),
+                    })),
+            );
+
+            Ok(WorktreeCreated {
+                project,
+                path,
+                branch,
+                hooks,
+            })
+        })
+        .await
+}
@@ -0,0 +1,3 @@
+DELETE FROM settings WHERE key IN
+    ('max_concurrent_sessions', 'max_sessions_per_workspace', 'queue_mode', 'queue_paused');
+DROP TABLE spawn_queue;
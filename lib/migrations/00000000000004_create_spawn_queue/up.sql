@@ -0,0 +1,23 @@
+CREATE TABLE spawn_queue (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    workspace_id INTEGER NOT NULL,
+    working_dir TEXT NOT NULL,
+    initial_prompt TEXT,
+    cli_command TEXT,
+    model TEXT,
+    rows INTEGER NOT NULL DEFAULT 24,
+    cols INTEGER NOT NULL DEFAULT 80,
+    priority INTEGER NOT NULL DEFAULT 0,
+    status TEXT NOT NULL DEFAULT 'pending',
+    session_id TEXT,
+    error TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    started_at TIMESTAMP
+);
+CREATE INDEX idx_spawn_queue_status ON spawn_queue(status);
+INSERT INTO settings (key, value) VALUES
+    ('max_concurrent_sessions', '4'),
+    ('max_sessions_per_workspace', '0'),
+    ('queue_mode', 'fifo'),
+    ('queue_paused', 'false');
@@ -0,0 +1,2 @@
+DROP TABLE session_runs;
+DROP TABLE retry_policies;
@@ -0,0 +1,31 @@
+CREATE TABLE retry_policies (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    scope TEXT NOT NULL,
+    scope_id INTEGER NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT 1,
+    max_attempts INTEGER NOT NULL DEFAULT 3,
+    backoff_secs INTEGER NOT NULL DEFAULT 10,
+    backoff_multiplier DOUBLE NOT NULL DEFAULT 2.0,
+    max_backoff_secs INTEGER NOT NULL DEFAULT 600,
+    retryable_exit_codes TEXT NOT NULL DEFAULT '[]',
+    retry_on_signal BOOLEAN NOT NULL DEFAULT 0,
+    retry_prompt TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (scope, scope_id)
+);
+
+CREATE TABLE session_runs (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    session_id TEXT NOT NULL UNIQUE,
+    project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    workspace_id INTEGER NOT NULL,
+    attempt INTEGER NOT NULL DEFAULT 1,
+    resumed BOOLEAN NOT NULL DEFAULT 0,
+    exit_code INTEGER,
+    exit_signal TEXT,
+    outcome TEXT NOT NULL DEFAULT 'running',
+    started_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    ended_at TIMESTAMP
+);
+CREATE INDEX idx_session_runs_project_id ON session_runs(project_id);
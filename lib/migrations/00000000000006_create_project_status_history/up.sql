@@ -0,0 +1,13 @@
+CREATE TABLE project_status_history (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    from_status TEXT,
+    to_status TEXT NOT NULL,
+    reason TEXT NOT NULL DEFAULT '',
+    changed_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX idx_project_status_history_project_id ON project_status_history(project_id);
+
+-- Anything outside the known set would fail to load into the enum
+UPDATE projects SET status = 'waiting'
+  WHERE status NOT IN ('queued', 'running', 'waiting', 'completed', 'failed', 'archived');
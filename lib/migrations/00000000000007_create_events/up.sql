@@ -0,0 +1,23 @@
+CREATE TABLE events (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    event_type TEXT NOT NULL,
+    workspace_id INTEGER,
+    project_id INTEGER,
+    session_id TEXT,
+    payload TEXT NOT NULL DEFAULT '{}',
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX idx_events_project_id ON events(project_id, id);
+CREATE INDEX idx_events_workspace_id ON events(workspace_id, id);
+CREATE INDEX idx_events_event_type ON events(event_type, id);
+CREATE INDEX idx_events_created_at ON events(created_at);
+
+-- The audit log is append-only; rows outlive the projects they describe
+CREATE TRIGGER events_no_update BEFORE UPDATE ON events
+BEGIN
+    SELECT RAISE(ABORT, 'events are append-only');
+END;
+CREATE TRIGGER events_no_delete BEFORE DELETE ON events
+BEGIN
+    SELECT RAISE(ABORT, 'events are append-only');
+END;
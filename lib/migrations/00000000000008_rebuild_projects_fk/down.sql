@@ -0,0 +1,23 @@
+CREATE TABLE projects_old (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    summary TEXT NOT NULL DEFAULT '',
+    task_id TEXT,
+    workspace_id INTEGER NOT NULL REFERENCES workspaces(id),
+    status TEXT NOT NULL DEFAULT 'running',
+    start_time TIMESTAMP,
+    iteration_count INTEGER NOT NULL DEFAULT 0,
+    worktree_path TEXT,
+    branch_name TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO projects_old
+SELECT id, name, description, summary, task_id, workspace_id, status, start_time,
+       iteration_count, worktree_path, branch_name, created_at
+  FROM projects
+ WHERE workspace_id IS NOT NULL;
+
+DROP TABLE projects;
+ALTER TABLE projects_old RENAME TO projects;
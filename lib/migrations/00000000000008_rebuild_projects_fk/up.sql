@@ -0,0 +1,45 @@
+-- The rename migration left projects.workspace_id without a usable
+-- reference. Rebuild the table so it points at workspaces; removing a
+-- workspace in archive mode detaches its projects rather than deleting them.
+CREATE TABLE projects_new (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    summary TEXT NOT NULL DEFAULT '',
+    task_id TEXT,
+    workspace_id INTEGER REFERENCES workspaces(id) ON DELETE SET NULL,
+    status TEXT NOT NULL DEFAULT 'running',
+    start_time TIMESTAMP,
+    iteration_count INTEGER NOT NULL DEFAULT 0,
+    worktree_path TEXT,
+    branch_name TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Projects whose workspace was already removed keep their history but
+-- can't point at anything
+INSERT INTO projects_new (id, name, description, summary, task_id, workspace_id,
+                          status, start_time, iteration_count, worktree_path,
+                          branch_name, created_at)
+SELECT id, name, description, summary, task_id,
+       CASE WHEN workspace_id IN (SELECT id FROM workspaces) THEN workspace_id END,
+       status, start_time, iteration_count, worktree_path, branch_name, created_at
+  FROM projects;
+
+DROP TABLE projects;
+ALTER TABLE projects_new RENAME TO projects;
+
+CREATE INDEX idx_projects_workspace_id ON projects(workspace_id);
+
+INSERT INTO project_status_history (project_id, from_status, to_status, reason)
+SELECT id, status, 'archived', 'workspace removed'
+  FROM projects
+ WHERE workspace_id IS NULL AND status != 'archived';
+
+UPDATE projects SET status = 'archived'
+ WHERE workspace_id IS NULL AND status != 'archived';
+
+-- Rows left behind by project deletes made before foreign keys were enforced
+DELETE FROM spawn_queue WHERE project_id NOT IN (SELECT id FROM projects);
+DELETE FROM session_runs WHERE project_id NOT IN (SELECT id FROM projects);
+DELETE FROM project_status_history WHERE project_id NOT IN (SELECT id FROM projects);
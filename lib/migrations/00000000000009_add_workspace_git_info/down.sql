@@ -0,0 +1,6 @@
+ALTER TABLE workspaces DROP COLUMN git_refreshed_at;
+ALTER TABLE workspaces DROP COLUMN head_commit;
+ALTER TABLE workspaces DROP COLUMN head_branch;
+ALTER TABLE workspaces DROP COLUMN remotes;
+ALTER TABLE workspaces DROP COLUMN default_branch;
+ALTER TABLE workspaces DROP COLUMN repo_root;
@@ -0,0 +1,7 @@
+ALTER TABLE workspaces ADD COLUMN repo_root TEXT;
+ALTER TABLE workspaces ADD COLUMN default_branch TEXT;
+-- JSON array of { name, url }
+ALTER TABLE workspaces ADD COLUMN remotes TEXT NOT NULL DEFAULT '[]';
+ALTER TABLE workspaces ADD COLUMN head_branch TEXT;
+ALTER TABLE workspaces ADD COLUMN head_commit TEXT;
+ALTER TABLE workspaces ADD COLUMN git_refreshed_at TIMESTAMP;
@@ -0,0 +1,19 @@
+-- One row per lifecycle hook run. Like events, rows outlive the projects
+-- they ran for (pre-worktree-remove hooks run after a cascade delete).
+CREATE TABLE hook_runs (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    workspace_id INTEGER,
+    project_id INTEGER,
+    session_id TEXT,
+    stage TEXT NOT NULL,
+    hook TEXT NOT NULL,
+    working_dir TEXT NOT NULL,
+    exit_code INTEGER,
+    -- NULL while the hook is still running
+    success BOOLEAN,
+    output TEXT NOT NULL DEFAULT '',
+    started_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    finished_at TIMESTAMP
+);
+CREATE INDEX idx_hook_runs_project_id ON hook_runs(project_id, id);
+CREATE INDEX idx_hook_runs_workspace_id ON hook_runs(workspace_id, id);
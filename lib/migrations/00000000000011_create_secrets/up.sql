@@ -0,0 +1,9 @@
+-- Names of stored secrets and where their values live. Values themselves
+-- never touch this database; they are in the OS keyring or the encrypted
+-- secrets file.
+CREATE TABLE secrets (
+    name TEXT NOT NULL PRIMARY KEY,
+    backend TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
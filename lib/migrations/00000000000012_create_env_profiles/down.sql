@@ -0,0 +1,4 @@
+ALTER TABLE session_runs DROP COLUMN env;
+ALTER TABLE projects DROP COLUMN env_profile_id;
+ALTER TABLE workspaces DROP COLUMN env_profile_id;
+DROP TABLE env_profiles;
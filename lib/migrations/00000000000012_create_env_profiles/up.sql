@@ -0,0 +1,17 @@
+-- Named sets of environment variables shared by workspaces and projects.
+CREATE TABLE env_profiles (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    -- JSON object of variable name to value
+    vars TEXT NOT NULL DEFAULT '{}',
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- No REFERENCES: SQLite can't drop a column that is part of a foreign key.
+-- Deleting a profile clears these itself.
+ALTER TABLE workspaces ADD COLUMN env_profile_id INTEGER;
+ALTER TABLE projects ADD COLUMN env_profile_id INTEGER;
+
+-- JSON object of the variables a session was started with, secrets redacted
+ALTER TABLE session_runs ADD COLUMN env TEXT NOT NULL DEFAULT '{}';
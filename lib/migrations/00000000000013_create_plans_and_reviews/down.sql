@@ -0,0 +1,3 @@
+DROP TABLE decisions;
+DROP TABLE review_comments;
+DROP TABLE plans;
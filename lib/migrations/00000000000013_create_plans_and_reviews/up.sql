@@ -0,0 +1,37 @@
+-- A project's plan. Saving a new one keeps the old rows as history; the
+-- latest is the one agents read and update.
+CREATE TABLE plans (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    -- JSON: goal, constraints and phases of steps
+    content TEXT NOT NULL DEFAULT '{}',
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX idx_plans_project_id ON plans(project_id, id);
+
+-- Feedback on a project's changes, for its agent to pick up
+CREATE TABLE review_comments (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    file_path TEXT,
+    line INTEGER,
+    body TEXT NOT NULL,
+    resolved BOOLEAN NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX idx_review_comments_project_id ON review_comments(project_id, id);
+
+-- What was decided while working on a project, and why
+CREATE TABLE decisions (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    session_id TEXT,
+    decision TEXT NOT NULL,
+    rationale TEXT NOT NULL DEFAULT '',
+    -- JSON array of the options that weren't taken
+    alternatives TEXT NOT NULL DEFAULT '[]',
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX idx_decisions_project_id ON decisions(project_id, id);
@@ -0,0 +1,9 @@
+-- Which stored secrets a workspace's sessions get, and under which
+-- environment variable. Kept here rather than in the repository's own
+-- config so a checked-out repo can't ask for secrets it wasn't given.
+CREATE TABLE secret_mappings (
+    workspace_id INTEGER NOT NULL REFERENCES workspaces(id) ON DELETE CASCADE,
+    env_var TEXT NOT NULL,
+    secret_name TEXT NOT NULL,
+    PRIMARY KEY (workspace_id, env_var)
+);
@@ -0,0 +1,359 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use diesel::prelude::*;
+use rusqlite::{DatabaseName, OpenFlags};
+
+use crate::backup_models::{
+    ExportBundle, ExportedDecision, ExportedPlan, ExportedProject, ExportedReviewComment,
+    ExportedSetting, ExportedWorkspace, ImportReport, ImportedDecision, ImportedPlan,
+    ImportedProject, ImportedReviewComment, EXPORT_VERSION,
+};
+use crate::error::AppError;
+use crate::models::{NewWorkspace, Workspace};
+use crate::project_models::{NewProjectStatusHistory, ProjectStatus};
+use crate::schema::{
+    decisions, plans, project_status_history, projects, review_comments, settings, workspaces,
+};
+use crate::settings_models::Setting;
+use crate::settings_registry;
+
+const BACKUP_DIR: &str = "backups";
+const MAX_ROTATING_BACKUPS: usize = 5;
+
+/// Copy the database with SQLite's online backup API, which gives a
+/// consistent copy even while other connections are writing.
+pub fn backup_to(db_path: &Path, dest: &Path) -> Result<u64, AppError> {
+    if dest == db_path {
+        return Err(AppError::validation(
+            "Backup destination is the database itself",
+        ));
+    }
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let conn = rusqlite::Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| AppError::db(format!("Failed to open database for backup: {e}")))?;
+    conn.backup(DatabaseName::Main, dest, None)
+        .map_err(|e| AppError::db(format!("Backup failed: {e}")))?;
+
+    Ok(std::fs::metadata(dest)?.len())
+}
+
+/// Take a timestamped backup under `<data dir>/backups`, keeping only the
+/// newest few.
+pub fn rotate(app_data_dir: &Path, db_path: &Path, label: &str) -> Result<PathBuf, AppError> {
+    let dir = app_data_dir.join(BACKUP_DIR);
+    let dest = dir.join(format!(
+        "dacm-{}-{label}.db",
+        Utc::now().format("%Y%m%d-%H%M%S")
+    ));
+    backup_to(db_path, &dest)?;
+
+    // Names sort by timestamp, so the oldest come first
+    let mut existing: Vec<PathBuf> = std::fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with("dacm-") && n.ends_with(".db"))
+        })
+        .collect();
+    existing.sort();
+    let excess = existing.len().saturating_sub(MAX_ROTATING_BACKUPS);
+    for old in &existing[..excess] {
+        if let Err(e) = std::fs::remove_file(old) {
+            eprintln!("[backup] Failed to remove {}: {e}", old.display());
+        }
+    }
+
+    Ok(dest)
+}
+
+pub fn export(conn: &mut SqliteConnection) -> QueryResult<ExportBundle> {
+    let workspaces = workspaces::table
+        .select(Workspace::as_select())
+        .order(workspaces::id.asc())
+        .load(conn)?
+        .into_iter()
+        .map(|w| ExportedWorkspace {
+            id: w.id,
+            name: w.name,
+            path: w.path,
+            created_at: w.created_at,
+        })
+        .collect();
+
+    let projects = projects::table
+        .select(ExportedProject::as_select())
+        .order(projects::id.asc())
+        .load(conn)?;
+
+    let plans = plans::table
+        .select(ExportedPlan::as_select())
+        .order(plans::id.asc())
+        .load(conn)?;
+    let review_comments = review_comments::table
+        .select(ExportedReviewComment::as_select())
+        .order(review_comments::id.asc())
+        .load(conn)?;
+    let decisions = decisions::table
+        .select(ExportedDecision::as_select())
+        .order(decisions::id.asc())
+        .load(conn)?;
+
+    let settings = settings::table
+        .select(Setting::as_select())
+        .order(settings::key.asc())
+        .load(conn)?
+        .into_iter()
+        .map(|s| ExportedSetting {
+            key: s.key,
+            value: s.value,
+        })
+        .collect();
+
+    Ok(ExportBundle {
+        version: EXPORT_VERSION,
+        exported_at: Utc::now().naive_utc(),
+        workspaces,
+        projects,
+        settings,
+        plans,
+        review_comments,
+        decisions,
+    })
+}
+
+/// Check a bundle before touching the database, collecting every problem
+/// so they can be reported together.
+fn validate(bundle: &ExportBundle) -> Result<(), AppError> {
+    if bundle.version == 0 || bundle.version > EXPORT_VERSION {
+        return Err(AppError::validation(format!(
+            "Unsupported export version {} (this build reads up to {EXPORT_VERSION})",
+            bundle.version
+        )));
+    }
+
+    let mut errors = Vec::new();
+    let mut workspace_ids = HashSet::new();
+    let mut paths = HashSet::new();
+    for workspace in &bundle.workspaces {
+        if !workspace_ids.insert(workspace.id) {
+            errors.push(format!("Duplicate workspace id {}", workspace.id));
+        }
+        if workspace.path.trim().is_empty() {
+            errors.push(format!("Workspace {} has an empty path", workspace.id));
+        } else if !paths.insert(workspace.path.as_str()) {
+            errors.push(format!("Duplicate workspace path {}", workspace.path));
+        }
+    }
+
+    let mut project_ids = HashSet::new();
+    for project in &bundle.projects {
+        if !project_ids.insert(project.id) {
+            errors.push(format!("Duplicate project id {}", project.id));
+        }
+        if project.name.trim().is_empty() {
+            errors.push(format!("Project {} has an empty name", project.id));
+        }
+        if let Some(workspace_id) = project.workspace_id {
+            if !workspace_ids.contains(&workspace_id) {
+                errors.push(format!(
+                    "Project {} refers to unknown workspace {workspace_id}",
+                    project.id
+                ));
+            }
+        }
+    }
+
+    // Plans, review comments and decisions all hang off a project
+    let owners = bundle
+        .plans
+        .iter()
+        .map(|p| ("Plan", p.id, p.project_id))
+        .chain(
+            bundle
+                .review_comments
+                .iter()
+                .map(|c| ("Review comment", c.id, c.project_id)),
+        )
+        .chain(
+            bundle
+                .decisions
+                .iter()
+                .map(|d| ("Decision", d.id, d.project_id)),
+        );
+    for (kind, id, project_id) in owners {
+        if !project_ids.contains(&project_id) {
+            errors.push(format!(
+                "{kind} {id} refers to unknown project {project_id}"
+            ));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(
+            AppError::validation(format!("Import file has {} problem(s)", errors.len()))
+                .with_details(serde_json::json!({ "errors": errors })),
+        )
+    }
+}
+
+/// Merge a bundle into this database in one transaction. Every row gets a
+/// fresh id; workspaces whose path is already registered are reused.
+pub fn import(
+    conn: &mut SqliteConnection,
+    bundle: &ExportBundle,
+) -> Result<ImportReport, AppError> {
+    validate(bundle)?;
+
+    conn.transaction(|conn| {
+        let mut report = ImportReport::default();
+        let mut workspace_map = HashMap::new();
+        let mut project_map = HashMap::new();
+
+        for workspace in &bundle.workspaces {
+            let existing = workspaces::table
+                .filter(workspaces::path.eq(&workspace.path))
+                .select(workspaces::id)
+                .first::<i32>(conn)
+                .optional()?;
+
+            let new_id = match existing {
+                Some(id) => {
+                    report.workspaces_merged += 1;
+                    id
+                }
+                None => {
+                    report.workspaces_created += 1;
+                    diesel::insert_into(workspaces::table)
+                        .values(&NewWorkspace {
+                            name: &workspace.name,
+                            path: &workspace.path,
+                        })
+                        .returning(workspaces::id)
+                        .get_result(conn)?
+                }
+            };
+            workspace_map.insert(workspace.id, new_id);
+        }
+
+        for project in &bundle.projects {
+            let workspace_id = project
+                .workspace_id
+                .and_then(|id| workspace_map.get(&id).copied());
+            // A project without a workspace can only live in the archive, and
+            // nothing is running on this machine yet
+            let status = match project.status {
+                _ if workspace_id.is_none() => ProjectStatus::Archived,
+                ProjectStatus::Queued | ProjectStatus::Running => ProjectStatus::Waiting,
+                status => status,
+            };
+
+            let new_id = diesel::insert_into(projects::table)
+                .values(&ImportedProject {
+                    name: &project.name,
+                    description: &project.description,
+                    summary: &project.summary,
+                    task_id: project.task_id.as_deref(),
+                    workspace_id,
+                    status,
+                    start_time: project.start_time,
+                    iteration_count: project.iteration_count,
+                    worktree_path: project.worktree_path.as_deref(),
+                    branch_name: project.branch_name.as_deref(),
+                    created_at: project.created_at,
+                })
+                .returning(projects::id)
+                .get_result(conn)?;
+
+            diesel::insert_into(project_status_history::table)
+                .values(&NewProjectStatusHistory {
+                    project_id: new_id,
+                    from_status: None,
+                    to_status: status,
+                    reason: "imported",
+                })
+                .execute(conn)?;
+
+            project_map.insert(project.id, new_id);
+            report.projects_imported += 1;
+        }
+
+        // Validation made sure every owner is in the bundle, so it's in the map
+        for plan in &bundle.plans {
+            diesel::insert_into(plans::table)
+                .values(&ImportedPlan {
+                    project_id: project_map[&plan.project_id],
+                    name: &plan.name,
+                    content: &plan.content,
+                    created_at: plan.created_at,
+                    updated_at: plan.updated_at,
+                })
+                .execute(conn)?;
+            report.plans_imported += 1;
+        }
+        for comment in &bundle.review_comments {
+            diesel::insert_into(review_comments::table)
+                .values(&ImportedReviewComment {
+                    project_id: project_map[&comment.project_id],
+                    file_path: comment.file_path.as_deref(),
+                    line: comment.line,
+                    body: &comment.body,
+                    resolved: comment.resolved,
+                    created_at: comment.created_at,
+                })
+                .execute(conn)?;
+            report.review_comments_imported += 1;
+        }
+        for decision in &bundle.decisions {
+            diesel::insert_into(decisions::table)
+                .values(&ImportedDecision {
+                    project_id: project_map[&decision.project_id],
+                    session_id: decision.session_id.as_deref(),
+                    decision: &decision.decision,
+                    rationale: &decision.rationale,
+                    alternatives: &decision.alternatives,
+                    created_at: decision.created_at,
+                })
+                .execute(conn)?;
+            report.decisions_imported += 1;
+        }
+
+        for setting in &bundle.settings {
+            // Settings that point at rows follow them to their new ids
+            let value = match setting.key.as_str() {
+                "last_workspace_id" => remap(&setting.value, &workspace_map),
+                "last_project_id" => remap(&setting.value, &project_map),
+                _ => Some(setting.value.clone()),
+            };
+            let Some(value) = value else {
+                continue;
+            };
+
+            // Keys this build doesn't know, or values it wouldn't accept,
+            // are left out rather than failing the whole import
+            let updated = settings_registry::parse_raw(&setting.key, &value)
+                .and_then(|value| settings_registry::update(conn, &setting.key, &value));
+            match updated {
+                Ok((_, change)) => {
+                    report.settings_imported += 1;
+                    report.settings_changed.extend(change);
+                }
+                Err(e) => eprintln!("[import] Skipping setting '{}': {e}", setting.key),
+            }
+        }
+
+        Ok(report)
+    })
+}
+
+fn remap(value: &str, ids: &HashMap<i32, i32>) -> Option<String> {
+    let old: i32 = value.parse().ok()?;
+    ids.get(&old).map(|id| id.to_string())
+}
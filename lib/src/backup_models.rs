@@ -0,0 +1,181 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::project_models::ProjectStatus;
+use crate::schema::{decisions, plans, projects, review_comments};
+use crate::settings_registry::SettingChanged;
+
+/// Bumped whenever the export layout changes incompatibly.
+pub const EXPORT_VERSION: u32 = 1;
+
+/// Portable snapshot of user data, written by `export_database`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ExportBundle {
+    pub version: u32,
+    pub exported_at: NaiveDateTime,
+    pub workspaces: Vec<ExportedWorkspace>,
+    pub projects: Vec<ExportedProject>,
+    pub settings: Vec<ExportedSetting>,
+    // Added after version 1 shipped; older bundles just have none
+    #[serde(default)]
+    pub plans: Vec<ExportedPlan>,
+    #[serde(default)]
+    pub review_comments: Vec<ExportedReviewComment>,
+    #[serde(default)]
+    pub decisions: Vec<ExportedDecision>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ExportedWorkspace {
+    pub id: i32,
+    pub name: String,
+    pub path: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Queryable, Selectable, Serialize, Deserialize, Debug)]
+#[diesel(table_name = projects)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ExportedProject {
+    pub id: i32,
+    pub name: String,
+    pub description: String,
+    pub summary: String,
+    pub task_id: Option<String>,
+    pub workspace_id: Option<i32>,
+    pub status: ProjectStatus,
+    pub start_time: Option<NaiveDateTime>,
+    pub iteration_count: i32,
+    pub worktree_path: Option<String>,
+    pub branch_name: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+/// A plan as stored, with its content left as JSON text.
+#[derive(Queryable, Selectable, Serialize, Deserialize, Debug)]
+#[diesel(table_name = plans)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ExportedPlan {
+    pub id: i32,
+    pub project_id: i32,
+    pub name: String,
+    pub content: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Queryable, Selectable, Serialize, Deserialize, Debug)]
+#[diesel(table_name = review_comments)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ExportedReviewComment {
+    pub id: i32,
+    pub project_id: i32,
+    pub file_path: Option<String>,
+    pub line: Option<i32>,
+    pub body: String,
+    pub resolved: bool,
+    pub created_at: NaiveDateTime,
+}
+
+/// A decision as stored, with its alternatives left as JSON text.
+#[derive(Queryable, Selectable, Serialize, Deserialize, Debug)]
+#[diesel(table_name = decisions)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ExportedDecision {
+    pub id: i32,
+    pub project_id: i32,
+    pub session_id: Option<String>,
+    pub decision: String,
+    pub rationale: String,
+    pub alternatives: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ExportedSetting {
+    pub key: String,
+    pub value: String,
+}
+
+/// A project from a bundle, with its workspace id remapped to this database.
+#[derive(Insertable)]
+#[diesel(table_name = projects)]
+pub struct ImportedProject<'a> {
+    pub name: &'a str,
+    pub description: &'a str,
+    pub summary: &'a str,
+    pub task_id: Option<&'a str>,
+    pub workspace_id: Option<i32>,
+    pub status: ProjectStatus,
+    pub start_time: Option<NaiveDateTime>,
+    pub iteration_count: i32,
+    pub worktree_path: Option<&'a str>,
+    pub branch_name: Option<&'a str>,
+    pub created_at: NaiveDateTime,
+}
+
+/// The rows below belong to a project and follow it to its new id.
+#[derive(Insertable)]
+#[diesel(table_name = plans)]
+pub struct ImportedPlan<'a> {
+    pub project_id: i32,
+    pub name: &'a str,
+    pub content: &'a str,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = review_comments)]
+pub struct ImportedReviewComment<'a> {
+    pub project_id: i32,
+    pub file_path: Option<&'a str>,
+    pub line: Option<i32>,
+    pub body: &'a str,
+    pub resolved: bool,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = decisions)]
+pub struct ImportedDecision<'a> {
+    pub project_id: i32,
+    pub session_id: Option<&'a str>,
+    pub decision: &'a str,
+    pub rationale: &'a str,
+    pub alternatives: &'a str,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Serialize, Debug)]
+pub struct BackupInfo {
+    pub path: String,
+    pub size_bytes: u64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ExportSummary {
+    pub path: String,
+    pub workspaces: usize,
+    pub projects: usize,
+    pub settings: usize,
+    pub plans: usize,
+    pub review_comments: usize,
+    pub decisions: usize,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct ImportReport {
+    pub workspaces_created: usize,
+    /// Bundle workspaces whose path was already registered here
+    pub workspaces_merged: usize,
+    pub projects_imported: usize,
+    pub settings_imported: usize,
+    pub plans_imported: usize,
+    pub review_comments_imported: usize,
+    pub decisions_imported: usize,
+    /// Broadcast by the command once the import has committed
+    #[serde(skip)]
+    pub settings_changed: Vec<SettingChanged>,
+}
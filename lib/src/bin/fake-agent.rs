@@ -0,0 +1,161 @@
+//! `fake-agent`: stands in for the agent CLI in the integration tests, so
+//! sessions can be exercised in a real PTY without Claude installed.
+//!
+//! What it does comes from `FAKE_AGENT_SCRIPT`, a `;`-separated list of
+//! steps run in order:
+//!
+//!   print:TEXT      print a line
+//!   count:N         print `line 1` to `line N`
+//!   args            print the arguments it was started with
+//!   env:NAME        print a variable from its environment
+//!   size            print the terminal size as `size ROWSxCOLS`
+//!   read            read a line and print `got: LINE`
+//!   prompt:TEXT     ask a yes/no question, printing `allowed` or `denied`
+//!   echo            `read` until end of input or `/exit`
+//!   sleep:MS        sleep for MS milliseconds
+//!   ignore-sigint   ignore SIGINT from here on, as Ctrl-C in the terminal
+//!   spawn:N         start N copies of itself that sleep, printing `child PID`
+//!   exit:CODE       exit with CODE
+//!
+//! Without a script it prints a banner and echoes its input. Running off the
+//! end of the script exits with 0.
+
+use std::io::{BufRead, Write};
+use std::process::{Command, ExitCode};
+use std::time::Duration;
+
+const SCRIPT_VAR: &str = "FAKE_AGENT_SCRIPT";
+const DEFAULT_SCRIPT: &str = "print:fake agent ready;echo";
+
+/// What the grandchildren started by `spawn` run.
+const CHILD_SCRIPT: &str = "sleep:600000";
+
+fn main() -> ExitCode {
+    let script = std::env::var(SCRIPT_VAR).unwrap_or_else(|_| DEFAULT_SCRIPT.to_string());
+    for step in script.split(';').filter(|s| !s.is_empty()) {
+        let (name, arg) = step.split_once(':').unwrap_or((step, ""));
+        match run_step(name, arg) {
+            Ok(Some(code)) => return ExitCode::from(code),
+            Ok(None) => {}
+            Err(e) => {
+                eprintln!("fake-agent: {step}: {e}");
+                return ExitCode::from(101);
+            }
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+/// Run one step, returning the exit code if it ends the agent.
+fn run_step(name: &str, arg: &str) -> Result<Option<u8>, String> {
+    match name {
+        "print" => say(arg),
+        "count" => {
+            let mut stdout = std::io::stdout().lock();
+            for i in 1..=number(arg)? {
+                let _ = writeln!(stdout, "line {i}");
+            }
+            let _ = stdout.flush();
+        }
+        "args" => {
+            let args: Vec<String> = std::env::args().skip(1).collect();
+            say(&format!("args: {}", args.join(" ")));
+        }
+        "env" => match std::env::var(arg) {
+            Ok(value) => say(&format!("{arg}={value}")),
+            Err(_) => say(&format!("{arg} unset")),
+        },
+        "size" => {
+            let (rows, cols) = terminal_size()?;
+            say(&format!("size {rows}x{cols}"));
+        }
+        "read" => match read_line() {
+            Some(line) => say(&format!("got: {line}")),
+            None => return Err("end of input".to_string()),
+        },
+        "prompt" => {
+            print!("{arg} [y/n] ");
+            let _ = std::io::stdout().flush();
+            let answer = read_line().unwrap_or_default();
+            say(if answer.eq_ignore_ascii_case("y") {
+                "allowed"
+            } else {
+                "denied"
+            });
+        }
+        "echo" => {
+            while let Some(line) = read_line() {
+                if line == "/exit" {
+                    break;
+                }
+                say(&format!("got: {line}"));
+            }
+        }
+        "sleep" => std::thread::sleep(Duration::from_millis(number(arg)?)),
+        "ignore-sigint" => ignore_sigint(),
+        "spawn" => {
+            let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+            for _ in 0..number(arg)? {
+                let child = Command::new(&exe)
+                    .env(SCRIPT_VAR, CHILD_SCRIPT)
+                    .spawn()
+                    .map_err(|e| e.to_string())?;
+                say(&format!("child {}", child.id()));
+            }
+        }
+        "exit" => return Ok(Some(number(arg)?.min(255) as u8)),
+        _ => return Err("unknown step".to_string()),
+    }
+    Ok(None)
+}
+
+fn say(line: &str) {
+    let mut stdout = std::io::stdout();
+    let _ = writeln!(stdout, "{line}");
+    let _ = stdout.flush();
+}
+
+fn read_line() -> Option<String> {
+    let mut line = String::new();
+    match std::io::stdin().lock().read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line.trim_end_matches(['\r', '\n']).to_string()),
+    }
+}
+
+fn number(arg: &str) -> Result<u64, String> {
+    arg.parse()
+        .map_err(|_| format!("expected a number, got '{arg}'"))
+}
+
+#[cfg(unix)]
+fn ignore_sigint() {
+    // SAFETY: SIG_IGN installs no handler, so nothing runs in signal context
+    unsafe {
+        libc::signal(libc::SIGINT, libc::SIG_IGN);
+    }
+}
+
+#[cfg(not(unix))]
+fn ignore_sigint() {}
+
+#[cfg(unix)]
+fn terminal_size() -> Result<(u16, u16), String> {
+    let mut size = libc::winsize {
+        ws_row: 0,
+        ws_col: 0,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    // SAFETY: TIOCGWINSZ only writes a winsize into `size`
+    let rc = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) };
+    if rc != 0 {
+        return Err(std::io::Error::last_os_error().to_string());
+    }
+    Ok((size.ws_row, size.ws_col))
+}
+
+#[cfg(not(unix))]
+fn terminal_size() -> Result<(u16, u16), String> {
+    Err("terminal size needs a Unix terminal".to_string())
+}
@@ -0,0 +1,350 @@
+use diesel::connection::SimpleConnection;
+use diesel::migration::MigrationSource;
+use diesel::prelude::*;
+use diesel::r2d2::{ConnectionManager, CustomizeConnection, Pool, PooledConnection};
+use diesel::sql_types::Text;
+use diesel::sqlite::{Sqlite, SqliteConnection};
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::Duration;
+
+use crate::backup;
+use crate::error::AppError;
+
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+
+const POOL_SIZE: u32 = 8;
+const BUSY_TIMEOUT_MS: u32 = 5000;
+
+pub type DbPool = Pool<ConnectionManager<SqliteConnection>>;
+pub type DbConn = PooledConnection<ConnectionManager<SqliteConnection>>;
+
+/// Why the database couldn't be opened at startup.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DbErrorKind {
+    /// The file or its directory couldn't be opened
+    Open,
+    /// SQLite reports the file as damaged
+    Corrupt,
+    /// Migrations this build doesn't know about have been applied
+    NewerSchema,
+    /// A pending migration failed and was rolled back
+    Migration,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum DbStatus {
+    Ready {
+        path: String,
+    },
+    /// Degraded mode: every database command fails until the user retries
+    /// or starts over with a fresh file.
+    Error {
+        path: String,
+        kind: DbErrorKind,
+        message: String,
+        /// Snapshot copied back over the file after a failed migration
+        restored_snapshot: Option<String>,
+    },
+}
+
+struct InitFailure {
+    kind: DbErrorKind,
+    message: String,
+    restored_snapshot: Option<PathBuf>,
+}
+
+impl InitFailure {
+    fn new(kind: DbErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            restored_snapshot: None,
+        }
+    }
+}
+
+pub struct DbState {
+    pub path: PathBuf,
+    app_data_dir: PathBuf,
+    pool: RwLock<Option<DbPool>>,
+    status: RwLock<DbStatus>,
+}
+
+impl DbState {
+    /// Open the database under `app_data_dir`. Never fails outright: if the
+    /// file can't be opened or migrated the state starts degraded instead.
+    pub fn open(app_data_dir: &Path) -> Self {
+        let path = app_data_dir.join("dacm.db");
+        let state = Self {
+            status: RwLock::new(DbStatus::Ready {
+                path: path.to_string_lossy().to_string(),
+            }),
+            path,
+            app_data_dir: app_data_dir.to_path_buf(),
+            pool: RwLock::new(None),
+        };
+        state.init();
+        state
+    }
+
+    /// (Re)connect and run pending migrations, replacing the current pool.
+    pub fn init(&self) -> DbStatus {
+        let path = self.path.to_string_lossy().to_string();
+        // Drop the old pool first so nothing holds the file during a restore
+        if let Ok(mut pool) = self.pool.write() {
+            *pool = None;
+        }
+
+        let (pool, status) = match connect(&self.app_data_dir, &self.path) {
+            Ok(pool) => (Some(pool), DbStatus::Ready { path }),
+            Err(failure) => {
+                eprintln!("[db] Starting in degraded mode: {}", failure.message);
+                let status = DbStatus::Error {
+                    path,
+                    kind: failure.kind,
+                    message: failure.message,
+                    restored_snapshot: failure
+                        .restored_snapshot
+                        .map(|p| p.to_string_lossy().to_string()),
+                };
+                (None, status)
+            }
+        };
+
+        if let Ok(mut current) = self.pool.write() {
+            *current = pool;
+        }
+        if let Ok(mut current) = self.status.write() {
+            *current = status.clone();
+        }
+        status
+    }
+
+    /// Move the current file aside and start over with an empty database.
+    pub fn reset(&self) -> Result<DbStatus, AppError> {
+        if let Ok(mut pool) = self.pool.write() {
+            *pool = None;
+        }
+
+        if self.path.exists() {
+            let aside = self.app_data_dir.join(format!(
+                "dacm-broken-{}.db",
+                chrono::Utc::now().format("%Y%m%d-%H%M%S")
+            ));
+            std::fs::rename(&self.path, &aside)?;
+            eprintln!("[db] Moved {} to {}", self.path.display(), aside.display());
+        }
+        // Stale WAL files would be replayed into the new database
+        for suffix in ["-wal", "-shm"] {
+            let mut sidecar = self.path.clone().into_os_string();
+            sidecar.push(suffix);
+            let _ = std::fs::remove_file(sidecar);
+        }
+
+        Ok(self.init())
+    }
+
+    pub fn status(&self) -> DbStatus {
+        match self.status.read() {
+            Ok(status) => status.clone(),
+            Err(e) => DbStatus::Error {
+                path: self.path.to_string_lossy().to_string(),
+                kind: DbErrorKind::Open,
+                message: e.to_string(),
+                restored_snapshot: None,
+            },
+        }
+    }
+
+    fn pool(&self) -> Result<DbPool, AppError> {
+        let pool = self.pool.read()?.clone();
+        pool.ok_or_else(|| {
+            let message = match self.status() {
+                DbStatus::Error { message, .. } => message,
+                DbStatus::Ready { .. } => "not connected".to_string(),
+            };
+            AppError::unavailable(format!("Database unavailable: {message}"))
+        })
+    }
+
+    /// Check out a connection for the duration of a short, synchronous job.
+    pub fn conn(&self) -> Result<DbConn, AppError> {
+        self.pool()?
+            .get()
+            .map_err(|e| AppError::db(format!("Database unavailable: {e}")))
+    }
+
+    /// Run `f` on a blocking thread with its own pooled connection so async
+    /// commands never hold up the IPC thread while SQLite works.
+    pub async fn run<T, F>(&self, f: F) -> Result<T, AppError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut SqliteConnection) -> Result<T, AppError> + Send + 'static,
+    {
+        let pool = self.pool()?;
+        blocking(move || {
+            let mut conn = pool
+                .get()
+                .map_err(|e| AppError::db(format!("Database unavailable: {e}")))?;
+            f(&mut conn)
+        })
+        .await
+    }
+}
+
+/// Move synchronous work that touches the database off the async runtime.
+pub async fn blocking<T, F>(f: F) -> Result<T, AppError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, AppError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| AppError::internal(format!("Database task failed: {e}")))?
+}
+
+/// Applied to every pooled connection as it's opened.
+#[derive(Debug)]
+struct ConnectionOptions;
+
+impl CustomizeConnection<SqliteConnection, diesel::r2d2::Error> for ConnectionOptions {
+    fn on_acquire(&self, conn: &mut SqliteConnection) -> Result<(), diesel::r2d2::Error> {
+        conn.batch_execute(&format!(
+            "PRAGMA journal_mode = WAL;
+             PRAGMA synchronous = NORMAL;
+             PRAGMA busy_timeout = {BUSY_TIMEOUT_MS};
+             PRAGMA foreign_keys = ON;"
+        ))
+        .map_err(diesel::r2d2::Error::QueryError)
+    }
+}
+
+#[derive(QueryableByName)]
+struct QuickCheck {
+    #[diesel(sql_type = Text)]
+    quick_check: String,
+}
+
+fn connect(app_data_dir: &Path, db_path: &Path) -> Result<DbPool, InitFailure> {
+    std::fs::create_dir_all(app_data_dir).map_err(|e| {
+        InitFailure::new(
+            DbErrorKind::Open,
+            format!("Failed to create data directory: {e}"),
+        )
+    })?;
+
+    let db_url = db_path.to_string_lossy().to_string();
+    let existed = db_path.exists();
+
+    // Migrations get their own connection, without foreign key enforcement,
+    // so table rebuilds aren't blocked by rows that reference them
+    {
+        let mut conn = SqliteConnection::establish(&db_url).map_err(|e| {
+            InitFailure::new(DbErrorKind::Open, format!("Failed to open database: {e}"))
+        })?;
+
+        check_integrity(&mut conn)?;
+        check_schema_version(&mut conn)?;
+
+        let pending = conn.has_pending_migration(MIGRATIONS).map_err(|e| {
+            InitFailure::new(DbErrorKind::Migration, format!("Migration error: {e}"))
+        })?;
+
+        if pending {
+            let snapshot = if existed {
+                let snapshot =
+                    backup::rotate(app_data_dir, db_path, "pre-migration").map_err(|e| {
+                        InitFailure::new(
+                            DbErrorKind::Migration,
+                            format!("Failed to snapshot database before migrating: {e}"),
+                        )
+                    })?;
+                eprintln!("[db] Backed up to {} before migrating", snapshot.display());
+                Some(snapshot)
+            } else {
+                None
+            };
+
+            // All pending migrations apply together or not at all
+            let result =
+                conn.transaction::<_, Box<dyn std::error::Error + Send + Sync>, _>(|conn| {
+                    conn.run_pending_migrations(MIGRATIONS)?;
+                    Ok(())
+                });
+
+            if let Err(e) = result {
+                drop(conn);
+                let mut failure =
+                    InitFailure::new(DbErrorKind::Migration, format!("Migration error: {e}"));
+                if let Some(snapshot) = snapshot {
+                    match backup::backup_to(&snapshot, db_path) {
+                        Ok(_) => failure.restored_snapshot = Some(snapshot),
+                        Err(e) => eprintln!("[db] Failed to restore snapshot: {e}"),
+                    }
+                }
+                return Err(failure);
+            }
+        }
+    }
+
+    Pool::builder()
+        .max_size(POOL_SIZE)
+        .connection_timeout(Duration::from_secs(10))
+        .connection_customizer(Box::new(ConnectionOptions))
+        .build(ConnectionManager::<SqliteConnection>::new(db_url))
+        .map_err(|e| InitFailure::new(DbErrorKind::Open, format!("Failed to open database: {e}")))
+}
+
+fn check_integrity(conn: &mut SqliteConnection) -> Result<(), InitFailure> {
+    conn.batch_execute(&format!("PRAGMA busy_timeout = {BUSY_TIMEOUT_MS};"))
+        .and_then(|_| diesel::sql_query("PRAGMA quick_check").load::<QuickCheck>(conn))
+        .map_err(|e| InitFailure::new(DbErrorKind::Corrupt, format!("Database is unreadable: {e}")))
+        .and_then(|rows| match rows.first() {
+            Some(row) if row.quick_check == "ok" => Ok(()),
+            Some(row) => Err(InitFailure::new(
+                DbErrorKind::Corrupt,
+                format!("Database failed its integrity check: {}", row.quick_check),
+            )),
+            None => Ok(()),
+        })
+}
+
+/// Refuse a database that has migrations applied which this build doesn't
+/// ship, since it was last opened by a newer version.
+fn check_schema_version(conn: &mut SqliteConnection) -> Result<(), InitFailure> {
+    let known: HashSet<String> = MigrationSource::<Sqlite>::migrations(&MIGRATIONS)
+        .map_err(|e| InitFailure::new(DbErrorKind::Migration, format!("Migration error: {e}")))?
+        .iter()
+        .map(|m| m.name().version().to_string())
+        .collect();
+
+    let applied = conn.applied_migrations().map_err(|e| {
+        InitFailure::new(
+            DbErrorKind::Open,
+            format!("Failed to read schema version: {e}"),
+        )
+    })?;
+
+    let unknown: Vec<String> = applied
+        .iter()
+        .map(|v| v.to_string())
+        .filter(|v| !known.contains(v))
+        .collect();
+    if unknown.is_empty() {
+        Ok(())
+    } else {
+        Err(InitFailure::new(
+            DbErrorKind::NewerSchema,
+            format!(
+                "Database was created by a newer version of DACM (unknown migrations: {})",
+                unknown.join(", ")
+            ),
+        ))
+    }
+}
@@ -0,0 +1,39 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use serde::{Serialize, Serializer};
+
+use crate::schema::decisions;
+
+fn serialize_alternatives<S: Serializer>(raw: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    parse_alternatives(raw).serialize(serializer)
+}
+
+pub fn parse_alternatives(raw: &str) -> Vec<String> {
+    serde_json::from_str(raw).unwrap_or_default()
+}
+
+/// An entry in a project's decision log.
+#[derive(Queryable, Selectable, Serialize, Debug, Clone)]
+#[diesel(table_name = decisions)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Decision {
+    pub id: i32,
+    pub project_id: i32,
+    /// The session that made it, if an agent did
+    pub session_id: Option<String>,
+    pub decision: String,
+    pub rationale: String,
+    #[serde(serialize_with = "serialize_alternatives")]
+    pub alternatives: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = decisions)]
+pub struct NewDecision<'a> {
+    pub project_id: i32,
+    pub session_id: Option<&'a str>,
+    pub decision: &'a str,
+    pub rationale: &'a str,
+    pub alternatives: &'a str,
+}
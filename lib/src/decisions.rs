@@ -0,0 +1,57 @@
+use diesel::prelude::*;
+use serde_json::json;
+
+use crate::decision_models::{Decision, NewDecision};
+use crate::error::{AppError, ResultExt};
+use crate::event_models::{EventType, NewEvent};
+use crate::events;
+use crate::projects;
+use crate::schema::decisions;
+
+pub fn log(
+    conn: &mut SqliteConnection,
+    project_id: i32,
+    session_id: Option<&str>,
+    decision: &str,
+    rationale: &str,
+    alternatives: &[String],
+) -> Result<Decision, AppError> {
+    let decision = decision.trim();
+    if decision.is_empty() {
+        return Err(AppError::validation("Decision is empty"));
+    }
+    let project = projects::get(conn, project_id)?;
+    let encoded = serde_json::to_string(alternatives)
+        .map_err(|e| AppError::internal(format!("Failed to encode alternatives: {e}")))?;
+
+    let logged = diesel::insert_into(decisions::table)
+        .values(&NewDecision {
+            project_id,
+            session_id,
+            decision,
+            rationale: rationale.trim(),
+            alternatives: &encoded,
+        })
+        .returning(Decision::as_returning())
+        .get_result(conn)
+        .context("Failed to log decision")?;
+
+    let mut event = NewEvent::new(EventType::DecisionLogged)
+        .workspace(project.workspace_id)
+        .project(project_id)
+        .payload(json!({ "decision_id": logged.id, "decision": logged.decision }));
+    event.session_id = session_id;
+    events::record(conn, event);
+
+    Ok(logged)
+}
+
+/// A project's decision log, oldest first.
+pub fn list(conn: &mut SqliteConnection, project_id: i32) -> Result<Vec<Decision>, AppError> {
+    decisions::table
+        .filter(decisions::project_id.eq(project_id))
+        .order(decisions::id.asc())
+        .select(Decision::as_select())
+        .load(conn)
+        .context("Failed to list decisions")
+}
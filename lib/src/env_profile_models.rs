@@ -0,0 +1,36 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use serde::{Serialize, Serializer};
+
+use crate::schema::env_profiles;
+
+fn serialize_vars<S: Serializer>(raw: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    parse_vars(raw).serialize(serializer)
+}
+
+pub fn parse_vars(raw: &str) -> BTreeMap<String, String> {
+    serde_json::from_str(raw).unwrap_or_default()
+}
+
+/// A named set of environment variables. Attached to a workspace it applies
+/// to every session there; attached to a project it overrides the workspace.
+#[derive(Queryable, Selectable, Serialize, Debug, Clone)]
+#[diesel(table_name = env_profiles)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct EnvProfile {
+    pub id: i32,
+    pub name: String,
+    #[serde(serialize_with = "serialize_vars")]
+    pub vars: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = env_profiles)]
+pub struct NewEnvProfile<'a> {
+    pub name: &'a str,
+    pub vars: &'a str,
+}
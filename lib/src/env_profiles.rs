@@ -0,0 +1,214 @@
+use std::collections::BTreeMap;
+
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use serde_json::json;
+
+use crate::env_profile_models::{parse_vars, EnvProfile, NewEnvProfile};
+use crate::error::AppError;
+use crate::schema::{env_profiles, projects, workspaces};
+use crate::session::SpawnRequest;
+
+// A session's environment, lowest precedence first:
+//   1. the workspace's env profile
+//   2. `env` in the workspace's .dacm.toml
+//   3. the project's env profile
+//   4. overrides passed to `spawn_session`
+//   5. values of the workspace's `[secrets]` mappings
+
+pub fn list(conn: &mut SqliteConnection) -> Result<Vec<EnvProfile>, AppError> {
+    Ok(env_profiles::table
+        .order(env_profiles::name.asc())
+        .select(EnvProfile::as_select())
+        .load(conn)?)
+}
+
+pub fn get(conn: &mut SqliteConnection, id: i32) -> Result<EnvProfile, AppError> {
+    env_profiles::table
+        .find(id)
+        .select(EnvProfile::as_select())
+        .first(conn)
+        .optional()?
+        .ok_or_else(|| AppError::not_found(format!("Env profile with id {id} not found")))
+}
+
+pub fn create(
+    conn: &mut SqliteConnection,
+    name: &str,
+    vars: &BTreeMap<String, String>,
+) -> Result<EnvProfile, AppError> {
+    let name = validate_name(name)?;
+    validate_vars(vars)?;
+    let vars = serde_json::to_string(vars)
+        .map_err(|e| AppError::internal(format!("Failed to encode variables: {e}")))?;
+
+    diesel::insert_into(env_profiles::table)
+        .values(&NewEnvProfile { name, vars: &vars })
+        .returning(EnvProfile::as_returning())
+        .get_result(conn)
+        .map_err(|e| unique_name(e, name))
+}
+
+/// Rename a profile and/or replace its variables.
+pub fn update(
+    conn: &mut SqliteConnection,
+    id: i32,
+    name: Option<&str>,
+    vars: Option<&BTreeMap<String, String>>,
+) -> Result<EnvProfile, AppError> {
+    let current = get(conn, id)?;
+
+    let name = match name {
+        Some(name) => validate_name(name)?.to_string(),
+        None => current.name,
+    };
+    let vars = match vars {
+        Some(vars) => {
+            validate_vars(vars)?;
+            serde_json::to_string(vars)
+                .map_err(|e| AppError::internal(format!("Failed to encode variables: {e}")))?
+        }
+        None => current.vars,
+    };
+
+    diesel::update(env_profiles::table.find(id))
+        .set((
+            env_profiles::name.eq(&name),
+            env_profiles::vars.eq(&vars),
+            env_profiles::updated_at.eq(Utc::now().naive_utc()),
+        ))
+        .execute(conn)
+        .map_err(|e| unique_name(e, &name))?;
+
+    get(conn, id)
+}
+
+/// Delete a profile, detaching it from any workspace or project using it.
+pub fn delete(conn: &mut SqliteConnection, id: i32) -> Result<(), AppError> {
+    conn.transaction(|conn| {
+        get(conn, id)?;
+        diesel::update(workspaces::table.filter(workspaces::env_profile_id.eq(id)))
+            .set(workspaces::env_profile_id.eq(None::<i32>))
+            .execute(conn)?;
+        diesel::update(projects::table.filter(projects::env_profile_id.eq(id)))
+            .set(projects::env_profile_id.eq(None::<i32>))
+            .execute(conn)?;
+        diesel::delete(env_profiles::table.find(id)).execute(conn)?;
+        Ok(())
+    })
+}
+
+pub fn attach_to_workspace(
+    conn: &mut SqliteConnection,
+    workspace_id: i32,
+    profile_id: Option<i32>,
+) -> Result<(), AppError> {
+    if let Some(profile_id) = profile_id {
+        get(conn, profile_id)?;
+    }
+    let updated = diesel::update(workspaces::table.find(workspace_id))
+        .set(workspaces::env_profile_id.eq(profile_id))
+        .execute(conn)?;
+    if updated == 0 {
+        return Err(AppError::not_found(format!(
+            "Workspace with id {workspace_id} not found"
+        )));
+    }
+    Ok(())
+}
+
+pub fn attach_to_project(
+    conn: &mut SqliteConnection,
+    project_id: i32,
+    profile_id: Option<i32>,
+) -> Result<(), AppError> {
+    if let Some(profile_id) = profile_id {
+        get(conn, profile_id)?;
+    }
+    let updated = diesel::update(projects::table.find(project_id))
+        .set(projects::env_profile_id.eq(profile_id))
+        .execute(conn)?;
+    if updated == 0 {
+        return Err(AppError::not_found(format!(
+            "Project with id {project_id} not found"
+        )));
+    }
+    Ok(())
+}
+
+/// Variables of the profile attached to a workspace, if any.
+pub fn workspace_vars(
+    conn: &mut SqliteConnection,
+    workspace_id: i32,
+) -> Result<BTreeMap<String, String>, AppError> {
+    let vars: Option<String> = workspaces::table
+        .inner_join(
+            env_profiles::table.on(workspaces::env_profile_id.eq(env_profiles::id.nullable())),
+        )
+        .filter(workspaces::id.eq(workspace_id))
+        .select(env_profiles::vars)
+        .first(conn)
+        .optional()?;
+    Ok(vars.as_deref().map(parse_vars).unwrap_or_default())
+}
+
+/// Add the project's profile under whatever the request already sets.
+pub fn apply_project(
+    conn: &mut SqliteConnection,
+    request: &mut SpawnRequest,
+) -> Result<(), AppError> {
+    let vars: Option<String> = projects::table
+        .inner_join(
+            env_profiles::table.on(projects::env_profile_id.eq(env_profiles::id.nullable())),
+        )
+        .filter(projects::id.eq(request.project_id))
+        .select(env_profiles::vars)
+        .first(conn)
+        .optional()?;
+
+    for (key, value) in vars.as_deref().map(parse_vars).unwrap_or_default() {
+        request.env.entry(key).or_insert(value);
+    }
+    Ok(())
+}
+
+/// Names must be usable as environment variables: no `=` and no NUL.
+/// Values can't contain NUL either.
+pub fn validate_vars(vars: &BTreeMap<String, String>) -> Result<(), AppError> {
+    for (key, value) in vars {
+        let reason = if key.is_empty() {
+            Some("name is empty")
+        } else if key.contains('=') || key.contains('\0') {
+            Some("name contains '=' or NUL")
+        } else if value.contains('\0') {
+            Some("value contains NUL")
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            return Err(AppError::validation(format!(
+                "Invalid environment variable '{key}': {reason}"
+            ))
+            .with_details(json!({ "key": key, "reason": reason })));
+        }
+    }
+    Ok(())
+}
+
+fn validate_name(name: &str) -> Result<&str, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::validation("Env profile name can't be empty"));
+    }
+    Ok(name)
+}
+
+fn unique_name(e: DieselError, name: &str) -> AppError {
+    match e {
+        DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+            AppError::conflict(format!("An env profile named '{name}' already exists"))
+        }
+        e => e.into(),
+    }
+}
@@ -0,0 +1,171 @@
+use std::fmt;
+
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::project_status::TransitionError;
+
+/// Machine-readable error category the frontend can branch on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    NotFound,
+    Conflict,
+    Validation,
+    Db,
+    Pty,
+    Git,
+    /// A lifecycle hook exited unsuccessfully
+    Hook,
+    Io,
+    /// The database failed to open and the app is running degraded
+    Unavailable,
+    Internal,
+}
+
+/// Error returned by every command. Serializes as `{ code, message, details }`.
+#[derive(Debug, Serialize)]
+pub struct AppError {
+    pub code: ErrorCode,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+}
+
+impl AppError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Conflict, message)
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Validation, message)
+    }
+
+    pub fn db(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Db, message)
+    }
+
+    pub fn pty(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Pty, message)
+    }
+
+    pub fn git(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Git, message)
+    }
+
+    pub fn hook(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Hook, message)
+    }
+
+    pub fn io(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Io, message)
+    }
+
+    pub fn unavailable(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Unavailable, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Internal, message)
+    }
+
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<DieselError> for AppError {
+    fn from(e: DieselError) -> Self {
+        match e {
+            DieselError::NotFound => AppError::not_found("Record not found"),
+            DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, ref info) => {
+                AppError::conflict(e.to_string())
+                    .with_details(json!({ "constraint": info.constraint_name() }))
+            }
+            DieselError::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _) => {
+                AppError::conflict(e.to_string())
+            }
+            _ => AppError::db(e.to_string()),
+        }
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        AppError::io(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(e: serde_json::Error) -> Self {
+        AppError::validation(e.to_string())
+    }
+}
+
+impl<T> From<std::sync::PoisonError<T>> for AppError {
+    fn from(e: std::sync::PoisonError<T>) -> Self {
+        AppError::internal(format!("Lock poisoned: {e}"))
+    }
+}
+
+impl From<TransitionError> for AppError {
+    fn from(e: TransitionError) -> Self {
+        let message = e.to_string();
+        match e {
+            TransitionError::NotFound(_) => AppError::not_found(message),
+            TransitionError::Detached(project_id) => {
+                AppError::conflict(message).with_details(json!({ "project_id": project_id }))
+            }
+            TransitionError::Illegal {
+                project_id,
+                from,
+                to,
+            } => AppError::conflict(message).with_details(json!({
+                "project_id": project_id,
+                "from": from,
+                "to": to,
+                "allowed": from.allowed_transitions(),
+            })),
+            TransitionError::Db(e) => AppError::from(e),
+            TransitionError::Unavailable(_) => AppError::internal(message),
+        }
+    }
+}
+
+pub trait ResultExt<T> {
+    /// Convert the error into an `AppError`, keeping its code and prefixing
+    /// the message with what was being attempted.
+    fn context(self, what: &str) -> Result<T, AppError>;
+}
+
+impl<T, E: Into<AppError>> ResultExt<T> for Result<T, E> {
+    fn context(self, what: &str) -> Result<T, AppError> {
+        self.map_err(|e| {
+            let mut err: AppError = e.into();
+            err.message = format!("{what}: {}", err.message);
+            err
+        })
+    }
+}
@@ -0,0 +1,203 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::NaiveDateTime;
+use diesel::deserialize::{self, FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::prelude::*;
+use diesel::serialize::{self, IsNull, Output, ToSql};
+use diesel::sql_types::Text;
+use diesel::sqlite::{Sqlite, SqliteValue};
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::schema::events;
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, AsExpression, FromSqlRow,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "snake_case")]
+pub enum EventType {
+    WorkspaceAdded,
+    WorkspaceRemoved,
+    ProjectCreated,
+    ProjectRenamed,
+    ProjectStatusChanged,
+    ProjectDeleted,
+    WorktreeCreated,
+    SessionQueued,
+    SessionSpawned,
+    SessionKilled,
+    SessionExited,
+    SessionRetryScheduled,
+    SessionAttached,
+    SessionDetached,
+    PlanSaved,
+    PlanStepUpdated,
+    ProgressReported,
+    ReviewRequested,
+    ReviewCommentAdded,
+    DecisionLogged,
+}
+
+impl EventType {
+    pub const ALL: [EventType; 20] = [
+        EventType::WorkspaceAdded,
+        EventType::WorkspaceRemoved,
+        EventType::ProjectCreated,
+        EventType::ProjectRenamed,
+        EventType::ProjectStatusChanged,
+        EventType::ProjectDeleted,
+        EventType::WorktreeCreated,
+        EventType::SessionQueued,
+        EventType::SessionSpawned,
+        EventType::SessionKilled,
+        EventType::SessionExited,
+        EventType::SessionRetryScheduled,
+        EventType::SessionAttached,
+        EventType::SessionDetached,
+        EventType::PlanSaved,
+        EventType::PlanStepUpdated,
+        EventType::ProgressReported,
+        EventType::ReviewRequested,
+        EventType::ReviewCommentAdded,
+        EventType::DecisionLogged,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EventType::WorkspaceAdded => "workspace_added",
+            EventType::WorkspaceRemoved => "workspace_removed",
+            EventType::ProjectCreated => "project_created",
+            EventType::ProjectRenamed => "project_renamed",
+            EventType::ProjectStatusChanged => "project_status_changed",
+            EventType::ProjectDeleted => "project_deleted",
+            EventType::WorktreeCreated => "worktree_created",
+            EventType::SessionQueued => "session_queued",
+            EventType::SessionSpawned => "session_spawned",
+            EventType::SessionKilled => "session_killed",
+            EventType::SessionExited => "session_exited",
+            EventType::SessionRetryScheduled => "session_retry_scheduled",
+            EventType::SessionAttached => "session_attached",
+            EventType::SessionDetached => "session_detached",
+            EventType::PlanSaved => "plan_saved",
+            EventType::PlanStepUpdated => "plan_step_updated",
+            EventType::ProgressReported => "progress_reported",
+            EventType::ReviewRequested => "review_requested",
+            EventType::ReviewCommentAdded => "review_comment_added",
+            EventType::DecisionLogged => "decision_logged",
+        }
+    }
+}
+
+impl fmt::Display for EventType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for EventType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        EventType::ALL
+            .into_iter()
+            .find(|t| t.as_str() == s)
+            .ok_or_else(|| format!("Unknown event type: {s}"))
+    }
+}
+
+impl ToSql<Text, Sqlite> for EventType {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Sqlite>) -> serialize::Result {
+        out.set_value(self.as_str());
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<Text, Sqlite> for EventType {
+    fn from_sql(bytes: SqliteValue<'_, '_, '_>) -> deserialize::Result<Self> {
+        let value = <String as FromSql<Text, Sqlite>>::from_sql(bytes)?;
+        value.parse().map_err(Into::into)
+    }
+}
+
+fn serialize_payload<S: Serializer>(raw: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    serde_json::from_str::<serde_json::Value>(raw)
+        .unwrap_or(serde_json::Value::Null)
+        .serialize(serializer)
+}
+
+#[derive(Queryable, Selectable, Serialize, Debug)]
+#[diesel(table_name = events)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Event {
+    pub id: i32,
+    pub event_type: EventType,
+    pub workspace_id: Option<i32>,
+    pub project_id: Option<i32>,
+    pub session_id: Option<String>,
+    #[serde(serialize_with = "serialize_payload")]
+    pub payload: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = events)]
+pub struct NewEvent<'a> {
+    pub event_type: EventType,
+    pub workspace_id: Option<i32>,
+    pub project_id: Option<i32>,
+    pub session_id: Option<&'a str>,
+    pub payload: String,
+}
+
+impl<'a> NewEvent<'a> {
+    pub fn new(event_type: EventType) -> Self {
+        Self {
+            event_type,
+            workspace_id: None,
+            project_id: None,
+            session_id: None,
+            payload: "{}".to_string(),
+        }
+    }
+
+    pub fn workspace(mut self, workspace_id: impl Into<Option<i32>>) -> Self {
+        self.workspace_id = workspace_id.into();
+        self
+    }
+
+    pub fn project(mut self, project_id: i32) -> Self {
+        self.project_id = Some(project_id);
+        self
+    }
+
+    pub fn session(mut self, session_id: &'a str) -> Self {
+        self.session_id = Some(session_id);
+        self
+    }
+
+    pub fn payload(mut self, payload: serde_json::Value) -> Self {
+        self.payload = payload.to_string();
+        self
+    }
+}
+
+/// Filter for `list_events`. Results are newest first; pass the previous
+/// page's `next_cursor` as `before_id` to continue.
+#[derive(Deserialize, Debug, Default)]
+pub struct EventFilter {
+    pub project_id: Option<i32>,
+    pub workspace_id: Option<i32>,
+    pub event_types: Option<Vec<EventType>>,
+    pub since: Option<NaiveDateTime>,
+    pub until: Option<NaiveDateTime>,
+    pub before_id: Option<i32>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct EventPage {
+    pub events: Vec<Event>,
+    pub next_cursor: Option<i32>,
+}
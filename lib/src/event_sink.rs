@@ -0,0 +1,56 @@
+use std::sync::Mutex;
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Where services send the events the UI listens for, like
+/// `project-status-changed` or `hook-output`. The app forwards them to its
+/// windows; a headless host can log or drop them.
+pub trait EventSink: Send + Sync {
+    fn emit_value(&self, event: &str, payload: Value);
+}
+
+impl dyn EventSink + '_ {
+    pub fn emit(&self, event: &str, payload: impl Serialize) {
+        match serde_json::to_value(payload) {
+            Ok(payload) => self.emit_value(event, payload),
+            Err(e) => eprintln!("[events] Failed to encode {event}: {e}"),
+        }
+    }
+}
+
+/// Discards everything.
+pub struct NoEvents;
+
+impl EventSink for NoEvents {
+    fn emit_value(&self, _event: &str, _payload: Value) {}
+}
+
+/// Keeps every event, in order, for callers that want to look at them
+/// afterwards.
+#[derive(Default)]
+pub struct CollectedEvents {
+    events: Mutex<Vec<(String, Value)>>,
+}
+
+impl CollectedEvents {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Everything emitted so far, leaving the list empty.
+    pub fn take(&self) -> Vec<(String, Value)> {
+        self.events
+            .lock()
+            .map(|mut events| std::mem::take(&mut *events))
+            .unwrap_or_default()
+    }
+}
+
+impl EventSink for CollectedEvents {
+    fn emit_value(&self, event: &str, payload: Value) {
+        if let Ok(mut events) = self.events.lock() {
+            events.push((event.to_string(), payload));
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use diesel::prelude::*;
+
+use crate::event_models::{Event, EventFilter, EventPage, NewEvent};
+use crate::schema::events;
+
+const DEFAULT_PAGE_SIZE: i64 = 100;
+const MAX_PAGE_SIZE: i64 = 500;
+
+/// Append an entry to the audit log. Failures are logged rather than
+/// returned so a bookkeeping problem never fails the action being recorded.
+pub fn record(conn: &mut SqliteConnection, event: NewEvent<'_>) {
+    if let Err(e) = diesel::insert_into(events::table)
+        .values(&event)
+        .execute(conn)
+    {
+        eprintln!("[events] Failed to record {}: {e}", event.event_type);
+    }
+}
+
+pub fn list(conn: &mut SqliteConnection, filter: &EventFilter) -> QueryResult<EventPage> {
+    let limit = filter
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+
+    let mut query = events::table.select(Event::as_select()).into_boxed();
+
+    if let Some(project_id) = filter.project_id {
+        query = query.filter(events::project_id.eq(project_id));
+    }
+    if let Some(workspace_id) = filter.workspace_id {
+        query = query.filter(events::workspace_id.eq(workspace_id));
+    }
+    if let Some(ref types) = filter.event_types {
+        query = query.filter(events::event_type.eq_any(types.clone()));
+    }
+    if let Some(since) = filter.since {
+        query = query.filter(events::created_at.ge(since));
+    }
+    if let Some(until) = filter.until {
+        query = query.filter(events::created_at.lt(until));
+    }
+    if let Some(before_id) = filter.before_id {
+        query = query.filter(events::id.lt(before_id));
+    }
+
+    // Fetch one extra row to learn whether another page exists
+    let mut rows = query.order(events::id.desc()).limit(limit + 1).load(conn)?;
+
+    let next_cursor = if rows.len() as i64 > limit {
+        rows.truncate(limit as usize);
+        rows.last().map(|e| e.id)
+    } else {
+        None
+    };
+
+    Ok(EventPage {
+        events: rows,
+        next_cursor,
+    })
+}
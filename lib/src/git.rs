@@ -0,0 +1,128 @@
+use std::path::Path;
+use std::process::Command;
+
+use chrono::Utc;
+
+use crate::error::AppError;
+use crate::models::{GitRemote, WorkspaceGitInfo};
+
+/// What a workspace's repository looks like right now.
+#[derive(Debug, Clone)]
+pub struct RepoInfo {
+    pub root: String,
+    pub default_branch: Option<String>,
+    pub remotes: Vec<GitRemote>,
+    /// `None` when HEAD is detached
+    pub head_branch: Option<String>,
+    /// `None` in a repository with no commits yet
+    pub head_commit: Option<String>,
+}
+
+impl RepoInfo {
+    pub fn changeset(&self) -> WorkspaceGitInfo {
+        WorkspaceGitInfo {
+            repo_root: Some(self.root.clone()),
+            default_branch: self.default_branch.clone(),
+            remotes: serde_json::to_string(&self.remotes).unwrap_or_else(|_| "[]".to_string()),
+            head_branch: self.head_branch.clone(),
+            head_commit: self.head_commit.clone(),
+            git_refreshed_at: Some(Utc::now().naive_utc()),
+        }
+    }
+}
+
+/// Run git in `dir` and return its trimmed stdout.
+pub fn run(dir: &Path, args: &[&str]) -> Result<String, AppError> {
+    let output = command(dir, args)?;
+    if !output.status.success() {
+        return Err(AppError::git(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Like `run`, but a non-zero exit means "no answer" rather than an error.
+fn query(dir: &Path, args: &[&str]) -> Result<Option<String>, AppError> {
+    let output = command(dir, args)?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    Ok(Some(
+        String::from_utf8_lossy(&output.stdout).trim().to_string(),
+    ))
+}
+
+fn command(dir: &Path, args: &[&str]) -> Result<std::process::Output, AppError> {
+    Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| AppError::git(format!("Failed to run git: {e}")))
+}
+
+/// Inspect the repository containing `dir`, or `None` if it isn't in one.
+pub fn inspect(dir: &Path) -> Result<Option<RepoInfo>, AppError> {
+    let Some(root) = query(dir, &["rev-parse", "--show-toplevel"])? else {
+        return Ok(None);
+    };
+
+    let remotes = remotes(dir)?;
+    let default_branch = default_branch(dir)?;
+    let head_branch = query(dir, &["symbolic-ref", "--quiet", "--short", "HEAD"])?;
+    let head_commit = query(dir, &["rev-parse", "--verify", "--quiet", "HEAD"])?;
+
+    Ok(Some(RepoInfo {
+        root,
+        default_branch,
+        remotes,
+        head_branch,
+        head_commit,
+    }))
+}
+
+fn remotes(dir: &Path) -> Result<Vec<GitRemote>, AppError> {
+    let output = run(dir, &["remote", "-v"])?;
+    // Each remote is listed twice, once for fetch and once for push
+    Ok(output
+        .lines()
+        .filter(|line| line.ends_with("(fetch)"))
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            Some(GitRemote {
+                name: parts.next()?.to_string(),
+                url: parts.next()?.to_string(),
+            })
+        })
+        .collect())
+}
+
+/// The branch new work should start from: origin's HEAD when it's known,
+/// otherwise a local `main` or `master`, otherwise git's configured default.
+fn default_branch(dir: &Path) -> Result<Option<String>, AppError> {
+    if let Some(origin_head) = query(
+        dir,
+        &[
+            "symbolic-ref",
+            "--quiet",
+            "--short",
+            "refs/remotes/origin/HEAD",
+        ],
+    )? {
+        if let Some((_, branch)) = origin_head.split_once('/') {
+            return Ok(Some(branch.to_string()));
+        }
+    }
+
+    for candidate in ["main", "master"] {
+        let reference = format!("refs/heads/{candidate}");
+        if query(dir, &["show-ref", "--verify", "--quiet", &reference])?.is_some() {
+            return Ok(Some(candidate.to_string()));
+        }
+    }
+
+    Ok(query(dir, &["config", "--get", "init.defaultBranch"])?.filter(|b| !b.is_empty()))
+}
@@ -0,0 +1,190 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::schema::hook_runs;
+
+/// Points in a workspace's lifecycle where hooks can run.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HookStage {
+    PostWorktreeCreate,
+    /// A failing hook here stops the session from starting
+    PreSessionSpawn,
+    PostSessionExit,
+    PreWorktreeRemove,
+    /// A workspace's `exit_criteria`, checked after a session exits cleanly.
+    /// Recorded like hooks but configured outside `[hooks]`
+    ExitCriteria,
+}
+
+impl HookStage {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HookStage::PostWorktreeCreate => "post_worktree_create",
+            HookStage::PreSessionSpawn => "pre_session_spawn",
+            HookStage::PostSessionExit => "post_session_exit",
+            HookStage::PreWorktreeRemove => "pre_worktree_remove",
+            HookStage::ExitCriteria => "exit_criteria",
+        }
+    }
+}
+
+/// One hook from `.dacm.toml`, either a shell command or a copy rule.
+/// Written as `"npm ci"`, `{ run = "npm ci" }` or
+/// `{ copy = ".env", to = ".env.local" }`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case", try_from = "RawHook")]
+pub enum Hook {
+    Run {
+        command: String,
+    },
+    /// Copy a file from the main checkout into the worktree
+    Copy {
+        from: String,
+        to: String,
+    },
+}
+
+impl Hook {
+    /// How the hook is shown in logs and `hook_runs`.
+    pub fn describe(&self) -> String {
+        match self {
+            Hook::Run { command } => command.clone(),
+            Hook::Copy { from, to } if from == to => format!("copy {from}"),
+            Hook::Copy { from, to } => format!("copy {from} -> {to}"),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawHook {
+    Command(String),
+    Table {
+        run: Option<String>,
+        copy: Option<String>,
+        to: Option<String>,
+    },
+}
+
+impl TryFrom<RawHook> for Hook {
+    type Error = String;
+
+    fn try_from(raw: RawHook) -> Result<Self, Self::Error> {
+        match raw {
+            RawHook::Command(command)
+            | RawHook::Table {
+                run: Some(command),
+                copy: None,
+                to: None,
+            } => {
+                if command.trim().is_empty() {
+                    Err("hook command is empty".to_string())
+                } else {
+                    Ok(Hook::Run { command })
+                }
+            }
+            RawHook::Table {
+                run: None,
+                copy: Some(from),
+                to,
+            } => {
+                if from.trim().is_empty() {
+                    return Err("copy source is empty".to_string());
+                }
+                Ok(Hook::Copy {
+                    to: to.unwrap_or_else(|| from.clone()),
+                    from,
+                })
+            }
+            _ => Err(
+                "a hook needs exactly one of `run` or `copy` (`to` only goes with `copy`)"
+                    .to_string(),
+            ),
+        }
+    }
+}
+
+/// Hooks per stage, as configured for a workspace.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Hooks {
+    #[serde(default)]
+    pub post_worktree_create: Vec<Hook>,
+    #[serde(default)]
+    pub pre_session_spawn: Vec<Hook>,
+    #[serde(default)]
+    pub post_session_exit: Vec<Hook>,
+    #[serde(default)]
+    pub pre_worktree_remove: Vec<Hook>,
+}
+
+impl Hooks {
+    pub fn for_stage(&self, stage: HookStage) -> &[Hook] {
+        match stage {
+            HookStage::PostWorktreeCreate => &self.post_worktree_create,
+            HookStage::PreSessionSpawn => &self.pre_session_spawn,
+            HookStage::PostSessionExit => &self.post_session_exit,
+            HookStage::PreWorktreeRemove => &self.pre_worktree_remove,
+            HookStage::ExitCriteria => &[],
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.post_worktree_create.is_empty()
+            && self.pre_session_spawn.is_empty()
+            && self.post_session_exit.is_empty()
+            && self.pre_worktree_remove.is_empty()
+    }
+}
+
+#[derive(Queryable, Selectable, Serialize, Debug, Clone)]
+#[diesel(table_name = hook_runs)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct HookRun {
+    pub id: i32,
+    pub workspace_id: Option<i32>,
+    pub project_id: Option<i32>,
+    pub session_id: Option<String>,
+    pub stage: String,
+    pub hook: String,
+    pub working_dir: String,
+    pub exit_code: Option<i32>,
+    pub success: Option<bool>,
+    pub output: String,
+    pub started_at: NaiveDateTime,
+    pub finished_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = hook_runs)]
+pub struct NewHookRun<'a> {
+    pub workspace_id: Option<i32>,
+    pub project_id: Option<i32>,
+    pub session_id: Option<&'a str>,
+    pub stage: &'a str,
+    pub hook: &'a str,
+    pub working_dir: &'a str,
+}
+
+/// Emitted as `hook-output` for every line a hook prints.
+#[derive(Serialize, Debug, Clone)]
+pub struct HookOutput {
+    pub run_id: i32,
+    pub project_id: Option<i32>,
+    pub stage: HookStage,
+    pub line: String,
+}
+
+/// Emitted as `hook-finished` once a hook has run.
+#[derive(Serialize, Debug, Clone)]
+pub struct HookFinished {
+    pub run_id: i32,
+    pub workspace_id: Option<i32>,
+    pub project_id: Option<i32>,
+    pub stage: HookStage,
+    pub hook: String,
+    pub exit_code: Option<i32>,
+    pub success: bool,
+}
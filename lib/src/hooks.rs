@@ -0,0 +1,441 @@
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use diesel::prelude::*;
+use serde_json::json;
+
+use crate::db::DbState;
+use crate::error::{AppError, ResultExt};
+use crate::event_sink::EventSink;
+use crate::hook_models::{Hook, HookFinished, HookOutput, HookRun, HookStage, NewHookRun};
+use crate::schema::hook_runs;
+use crate::session::SpawnRequest;
+use crate::workspace_config::WorkspaceConfig;
+
+/// Only the tail of a hook's output is kept in `hook_runs`; the full stream
+/// goes out as `hook-output` events.
+const MAX_STORED_OUTPUT: usize = 64 * 1024;
+
+/// Where and for what a stage's hooks run.
+pub struct HookContext<'a> {
+    pub stage: HookStage,
+    pub workspace_id: Option<i32>,
+    pub project_id: Option<i32>,
+    pub session_id: Option<&'a str>,
+    /// The project's worktree, or the workspace itself when it has none
+    pub dir: &'a Path,
+    /// The main checkout, which copy rules read from
+    pub source_dir: &'a Path,
+    pub env: &'a BTreeMap<String, String>,
+    /// Each hook is killed and fails once it has run this long
+    pub timeout: Option<Duration>,
+}
+
+/// Run a stage's hooks in order, stopping at the first failure. Every run is
+/// recorded in `hook_runs` and its output streamed as `hook-output` events;
+/// a hook whose run can't be recorded isn't run, and that comes back as an
+/// error.
+pub fn run_stage(
+    db: &DbState,
+    events: &dyn EventSink,
+    ctx: &HookContext,
+    hooks: &[Hook],
+) -> Result<Vec<HookRun>, AppError> {
+    let mut runs = Vec::new();
+    for hook in hooks {
+        let run = run_one(db, events, ctx, hook)?;
+        let failed = run.success != Some(true);
+        runs.push(run);
+        if failed {
+            break;
+        }
+    }
+    Ok(runs)
+}
+
+/// The first failed run, if any.
+pub fn failure(runs: &[HookRun]) -> Option<&HookRun> {
+    runs.iter().find(|run| run.success != Some(true))
+}
+
+/// Run the workspace's pre-spawn hooks for `request`. A failure comes back
+/// as an error so the session isn't started.
+pub fn before_spawn(
+    db: &DbState,
+    events: &dyn EventSink,
+    config: &WorkspaceConfig,
+    request: &SpawnRequest,
+) -> Result<(), AppError> {
+    let hooks = config.hooks.for_stage(HookStage::PreSessionSpawn);
+    if hooks.is_empty() {
+        return Ok(());
+    }
+
+    let runs = run_stage(
+        db,
+        events,
+        &HookContext {
+            stage: HookStage::PreSessionSpawn,
+            workspace_id: Some(request.workspace_id),
+            project_id: Some(request.project_id),
+            session_id: None,
+            dir: Path::new(&request.working_dir),
+            source_dir: Path::new(&config.workspace_path),
+            env: &request.env,
+            timeout: config.hook_timeout(),
+        },
+        hooks,
+    )?;
+
+    match failure(&runs) {
+        Some(run) => Err(
+            AppError::hook(format!("Pre-spawn hook failed: {}", run.hook)).with_details(json!({
+                "run_id": run.id,
+                "stage": HookStage::PreSessionSpawn,
+                "exit_code": run.exit_code,
+                "output": run.output,
+            })),
+        ),
+        None => Ok(()),
+    }
+}
+
+/// Run the workspace's exit criteria in the session's working directory,
+/// stopping at the first failure. `None` when the workspace has none,
+/// otherwise whether they all passed.
+pub fn check_exit_criteria(
+    db: &DbState,
+    events: &dyn EventSink,
+    config: &WorkspaceConfig,
+    request: &SpawnRequest,
+    session_id: &str,
+) -> Result<Option<bool>, AppError> {
+    if config.exit_criteria.is_empty() {
+        return Ok(None);
+    }
+    let criteria: Vec<Hook> = config
+        .exit_criteria
+        .iter()
+        .map(|command| Hook::Run {
+            command: command.clone(),
+        })
+        .collect();
+
+    let runs = run_stage(
+        db,
+        events,
+        &HookContext {
+            stage: HookStage::ExitCriteria,
+            workspace_id: Some(request.workspace_id),
+            project_id: Some(request.project_id),
+            session_id: Some(session_id),
+            dir: Path::new(&request.working_dir),
+            source_dir: Path::new(&config.workspace_path),
+            env: &request.env,
+            timeout: config.hook_timeout(),
+        },
+        &criteria,
+    )?;
+    Ok(Some(failure(&runs).is_none()))
+}
+
+fn run_one(
+    db: &DbState,
+    events: &dyn EventSink,
+    ctx: &HookContext,
+    hook: &Hook,
+) -> Result<HookRun, AppError> {
+    let description = hook.describe();
+    let working_dir = ctx.dir.to_string_lossy().to_string();
+    let started_at = Utc::now().naive_utc();
+    let run_id = record_start(db, ctx, &description, &working_dir)
+        .context(&format!("Failed to record hook run: {description}"))?;
+    eprintln!(
+        "[hooks] {} in {working_dir}: {description}",
+        ctx.stage.as_str()
+    );
+
+    let emit_line = |line: String| {
+        events.emit(
+            "hook-output",
+            HookOutput {
+                run_id,
+                project_id: ctx.project_id,
+                stage: ctx.stage,
+                line,
+            },
+        );
+    };
+
+    let (exit_code, success, output) = match hook {
+        Hook::Run { command } => run_command(ctx, command, emit_line),
+        Hook::Copy { from, to } => {
+            let result = copy_file(ctx, from, to);
+            let message = match &result {
+                Ok(message) => message.clone(),
+                Err(e) => e.clone(),
+            };
+            emit_line(message.clone());
+            (None, result.is_ok(), message)
+        }
+    };
+
+    let output = tail(output);
+    let finished_at = Utc::now().naive_utc();
+    if let Ok(mut conn) = db.conn() {
+        let _ = diesel::update(hook_runs::table.filter(hook_runs::id.eq(run_id)))
+            .set((
+                hook_runs::exit_code.eq(exit_code),
+                hook_runs::success.eq(success),
+                hook_runs::output.eq(&output),
+                hook_runs::finished_at.eq(finished_at),
+            ))
+            .execute(&mut *conn);
+    }
+
+    events.emit(
+        "hook-finished",
+        HookFinished {
+            run_id,
+            workspace_id: ctx.workspace_id,
+            project_id: ctx.project_id,
+            stage: ctx.stage,
+            hook: description.clone(),
+            exit_code,
+            success,
+        },
+    );
+
+    Ok(HookRun {
+        id: run_id,
+        workspace_id: ctx.workspace_id,
+        project_id: ctx.project_id,
+        session_id: ctx.session_id.map(str::to_string),
+        stage: ctx.stage.as_str().to_string(),
+        hook: description,
+        working_dir,
+        exit_code,
+        success: Some(success),
+        output,
+        started_at,
+        finished_at: Some(finished_at),
+    })
+}
+
+fn record_start(
+    db: &DbState,
+    ctx: &HookContext,
+    description: &str,
+    working_dir: &str,
+) -> Result<i32, AppError> {
+    let mut conn = db.conn()?;
+    let id = diesel::insert_into(hook_runs::table)
+        .values(&NewHookRun {
+            workspace_id: ctx.workspace_id,
+            project_id: ctx.project_id,
+            session_id: ctx.session_id,
+            stage: ctx.stage.as_str(),
+            hook: description,
+            working_dir,
+        })
+        .returning(hook_runs::id)
+        .get_result(&mut *conn)?;
+    Ok(id)
+}
+
+/// Run a shell command, forwarding each line of stdout and stderr as it
+/// arrives. Returns the exit code, whether it succeeded and the output. A
+/// command still running at the context's timeout is killed and fails.
+fn run_command(
+    ctx: &HookContext,
+    command: &str,
+    emit_line: impl Fn(String),
+) -> (Option<i32>, bool, String) {
+    let mut command = shell(command);
+    // In its own process group, so a timeout also kills what it started
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+
+    let mut child = match command
+        .current_dir(ctx.dir)
+        .envs(ctx.env)
+        .env("DACM_HOOK_STAGE", ctx.stage.as_str())
+        .env("DACM_WORKSPACE_PATH", ctx.source_dir)
+        .env("DACM_WORKSPACE_ID", id_var(ctx.workspace_id))
+        .env("DACM_PROJECT_ID", id_var(ctx.project_id))
+        .env("DACM_SESSION_ID", ctx.session_id.unwrap_or(""))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) => {
+            let message = format!("Failed to run hook: {e}");
+            emit_line(message.clone());
+            return (None, false, message);
+        }
+    };
+
+    let (tx, rx) = mpsc::channel();
+    let mut readers = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        readers.push(forward_lines(stdout, tx.clone()));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        readers.push(forward_lines(stderr, tx.clone()));
+    }
+    drop(tx);
+
+    let deadline = ctx.timeout.map(|timeout| Instant::now() + timeout);
+    let mut output = String::new();
+    let mut timed_out = false;
+    loop {
+        let received = match deadline {
+            Some(deadline) => rx.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+            None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match received {
+            Ok(line) => {
+                output.push_str(&line);
+                output.push('\n');
+                emit_line(line);
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                timed_out = true;
+                break;
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+
+    // Its output can close while it's still running
+    let waited = if timed_out {
+        Ok(None)
+    } else {
+        wait_until(&mut child, deadline)
+    };
+    match waited {
+        Ok(Some(status)) => {
+            for reader in readers {
+                let _ = reader.join();
+            }
+            (status.code(), status.success(), output)
+        }
+        Ok(None) => {
+            // The readers finish once the kill closes the pipes
+            kill(&mut child);
+            let message = format!(
+                "Hook timed out after {}s",
+                ctx.timeout.unwrap_or_default().as_secs()
+            );
+            output.push_str(&message);
+            output.push('\n');
+            emit_line(message);
+            (None, false, output)
+        }
+        Err(e) => {
+            output.push_str(&format!("Failed to wait for hook: {e}\n"));
+            (None, false, output)
+        }
+    }
+}
+
+/// Wait for a hook to exit, giving up at `deadline` with `None`.
+fn wait_until(child: &mut Child, deadline: Option<Instant>) -> std::io::Result<Option<ExitStatus>> {
+    let Some(deadline) = deadline else {
+        return child.wait().map(Some);
+    };
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            return Ok(None);
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// Kill a hook along with anything it started.
+fn kill(child: &mut Child) {
+    #[cfg(unix)]
+    unsafe {
+        // The negative pid addresses the process group
+        libc::kill(-(child.id() as i32), libc::SIGKILL);
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+fn forward_lines(
+    stream: impl Read + Send + 'static,
+    tx: mpsc::Sender<String>,
+) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    })
+}
+
+fn shell(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C").arg(command);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    }
+}
+
+fn id_var(id: Option<i32>) -> String {
+    id.map(|id| id.to_string()).unwrap_or_default()
+}
+
+/// Copy `from` (relative to the main checkout) to `to` (relative to the
+/// hook's directory).
+fn copy_file(ctx: &HookContext, from: &str, to: &str) -> Result<String, String> {
+    let source = ctx.source_dir.join(from);
+    let dest = ctx.dir.join(to);
+
+    if !source.is_file() {
+        return Err(format!("{} does not exist", source.display()));
+    }
+    // Copying a file onto itself would truncate it
+    if let (Ok(a), Ok(b)) = (source.canonicalize(), dest.canonicalize()) {
+        if a == b {
+            return Ok(format!("{} is already in place", dest.display()));
+        }
+    }
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+    }
+    std::fs::copy(&source, &dest)
+        .map(|_| format!("Copied {} to {}", source.display(), dest.display()))
+        .map_err(|e| format!("Failed to copy {}: {e}", source.display()))
+}
+
+fn tail(output: String) -> String {
+    if output.len() <= MAX_STORED_OUTPUT {
+        return output;
+    }
+    let mut start = output.len() - MAX_STORED_OUTPUT;
+    while !output.is_char_boundary(start) {
+        start += 1;
+    }
+    format!("[…truncated]\n{}", &output[start..])
+}
@@ -0,0 +1,53 @@
+//! DACM's services without the desktop shell: the database, sessions,
+//! projects, workspaces and settings, as plain Rust APIs. Anything the UI
+//! should hear about goes through an [`event_sink::EventSink`], so the same
+//! code runs under the Tauri app, the command line or a test.
+
+pub mod backup;
+pub mod backup_models;
+pub mod constants;
+pub mod db;
+pub mod decision_models;
+pub mod decisions;
+pub mod env_profile_models;
+pub mod env_profiles;
+pub mod error;
+pub mod event_models;
+pub mod event_sink;
+pub mod events;
+pub mod git;
+pub mod hook_models;
+pub mod hooks;
+pub mod mcp;
+pub mod mcp_models;
+pub mod models;
+pub mod output_frames;
+pub mod persistent;
+pub mod plan_models;
+pub mod plans;
+pub mod project_models;
+pub mod project_status;
+pub mod projects;
+pub mod recording;
+pub mod recording_models;
+pub mod retry;
+pub mod retry_models;
+pub mod review_models;
+pub mod reviews;
+pub mod scheduler;
+pub mod scheduler_models;
+pub mod schema;
+pub mod secret_file;
+pub mod secret_keyring;
+pub mod secret_models;
+pub mod secrets;
+pub mod session;
+pub mod settings_models;
+pub mod settings_registry;
+pub mod spawn;
+pub mod tmux;
+pub mod tmux_models;
+pub mod workspace;
+pub mod workspace_config;
+pub mod workspace_scan;
+pub mod worktree;
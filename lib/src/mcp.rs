@@ -0,0 +1,349 @@
+use std::path::PathBuf;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::db::DbState;
+use crate::decisions;
+use crate::error::AppError;
+use crate::event_sink::EventSink;
+use crate::mcp_models::{
+    Agent, GetReviewCommentsArgs, LogDecisionArgs, ReportProgressArgs, RequestReviewArgs, ToolCall,
+    UpdatePlanStepArgs,
+};
+use crate::plan_models::PlanChanged;
+use crate::plans;
+use crate::project_models::{ProjectProgress, ProjectStatus};
+use crate::project_status;
+use crate::projects;
+use crate::review_models::ReviewRequested;
+use crate::reviews;
+
+// Agents report back to DACM through a Model Context Protocol server. The
+// app serves it on its socket and `dacm mcp` bridges an agent's stdio to
+// that, so every message here arrives already tied to one project. Tools
+// write to the database and emit the same events the UI's commands do.
+
+/// What the server calls itself in agents' MCP configs.
+pub const SERVER_NAME: &str = "dacm";
+
+/// Newest first; a client asking for another version gets the newest.
+const PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+const INSTRUCTIONS: &str = "DACM is managing this session. Read the project's plan with \
+get_plan and keep it current with update_plan_step as you work. Report progress with \
+report_progress, record significant choices with log_decision, and call request_review \
+when your changes are ready. get_review_comments returns feedback left for you.";
+
+/// How a session's agent starts the server: the `dacm` binary in `mcp`
+/// mode, connected to the app's socket.
+#[derive(Clone, Debug)]
+pub struct ServerCommand {
+    pub program: PathBuf,
+    pub socket: PathBuf,
+}
+
+impl ServerCommand {
+    /// The JSON for the agent CLI's `--mcp-config`.
+    pub fn config(&self, project_id: i32, session_id: &str) -> String {
+        json!({
+            "mcpServers": {
+                SERVER_NAME: {
+                    "type": "stdio",
+                    "command": self.program,
+                    "args": [
+                        "--socket", self.socket,
+                        "mcp",
+                        "--project", project_id.to_string(),
+                        "--session", session_id,
+                    ],
+                }
+            }
+        })
+        .to_string()
+    }
+}
+
+pub struct McpServer<'a> {
+    pub db: &'a DbState,
+    pub events: &'a dyn EventSink,
+    pub agent: &'a Agent,
+}
+
+impl McpServer<'_> {
+    /// Answer one JSON-RPC message. Notifications, and responses to requests
+    /// we never make, get nothing back.
+    pub fn handle(&self, message: Value) -> Option<Value> {
+        let id = message.get("id").cloned()?;
+        let method = message.get("method")?.as_str().unwrap_or_default();
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+        let outcome = match method {
+            "initialize" => Ok(initialize(&params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": tools() })),
+            "tools/call" => self.call_tool(params),
+            _ => Err((METHOD_NOT_FOUND, format!("Unknown method '{method}'"))),
+        };
+        Some(match outcome {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": message },
+            }),
+        })
+    }
+
+    /// Run a tool. Its failures go back to the agent as an error result
+    /// rather than a protocol error, so it can read them and try again.
+    fn call_tool(&self, params: Value) -> Result<Value, (i64, String)> {
+        let call: ToolCall =
+            serde_json::from_value(params).map_err(|e| (INVALID_PARAMS, e.to_string()))?;
+        let args = match call.arguments {
+            Value::Null => json!({}),
+            args => args,
+        };
+        let outcome = match call.name.as_str() {
+            "report_progress" => parse(args).and_then(|a| self.report_progress(a)),
+            "update_plan_step" => parse(args).and_then(|a| self.update_plan_step(a)),
+            "request_review" => parse(args).and_then(|a| self.request_review(a)),
+            "get_plan" => self.get_plan(),
+            "get_review_comments" => parse(args).and_then(|a| self.get_review_comments(a)),
+            "log_decision" => parse(args).and_then(|a| self.log_decision(a)),
+            name => return Err((INVALID_PARAMS, format!("Unknown tool '{name}'"))),
+        };
+        Ok(match outcome {
+            Ok(text) => tool_result(text, false),
+            Err(e) => tool_result(e.message, true),
+        })
+    }
+
+    fn session_id(&self) -> Option<&str> {
+        self.agent.session_id.as_deref()
+    }
+
+    fn report_progress(&self, args: ReportProgressArgs) -> Result<String, AppError> {
+        let project_id = self.agent.project_id;
+        let project = {
+            let mut conn = self.db.conn()?;
+            projects::set_summary(
+                &mut conn,
+                project_id,
+                self.session_id(),
+                &args.summary,
+                args.percent,
+            )?
+        };
+        self.events.emit(
+            "project-progress",
+            ProjectProgress {
+                project_id,
+                session_id: self.agent.session_id.clone(),
+                summary: project.summary,
+                percent: args.percent,
+            },
+        );
+        self.events.emit("projects-changed", ());
+        Ok("Progress recorded.".to_string())
+    }
+
+    fn update_plan_step(&self, args: UpdatePlanStepArgs) -> Result<String, AppError> {
+        let project_id = self.agent.project_id;
+        let plan = {
+            let mut conn = self.db.conn()?;
+            plans::update_step(
+                &mut conn,
+                project_id,
+                &args.step_id,
+                args.status,
+                args.note.as_deref(),
+            )?
+        };
+        self.events
+            .emit("plan-changed", PlanChanged { project_id, plan });
+        Ok(format!("Step '{}' is now {}.", args.step_id, args.status))
+    }
+
+    /// Mark the project as waiting on the user, if it was running.
+    fn request_review(&self, args: RequestReviewArgs) -> Result<String, AppError> {
+        let project_id = self.agent.project_id;
+        let project = {
+            let mut conn = self.db.conn()?;
+            reviews::request(&mut conn, project_id, self.session_id(), &args.summary)?;
+            projects::get(&mut conn, project_id)?
+        };
+        if project.status == ProjectStatus::Running {
+            project_status::transition_and_emit(
+                self.db,
+                self.events,
+                project_id,
+                ProjectStatus::Waiting,
+                "review requested",
+            )?;
+        }
+        self.events.emit(
+            "review-requested",
+            ReviewRequested {
+                project_id,
+                session_id: self.agent.session_id.clone(),
+                summary: args.summary.trim().to_string(),
+            },
+        );
+        Ok("Review requested. The user has been told your changes are ready.".to_string())
+    }
+
+    fn get_plan(&self) -> Result<String, AppError> {
+        let mut conn = self.db.conn()?;
+        match plans::current(&mut conn, self.agent.project_id)? {
+            Some(plan) => to_text(&json!({ "name": plan.name, "plan": plan.content() })),
+            None => Ok("This project has no plan yet.".to_string()),
+        }
+    }
+
+    fn get_review_comments(&self, args: GetReviewCommentsArgs) -> Result<String, AppError> {
+        let mut conn = self.db.conn()?;
+        let comments = reviews::list(&mut conn, self.agent.project_id, args.include_resolved)?;
+        if comments.is_empty() {
+            return Ok("There are no review comments.".to_string());
+        }
+        to_text(&comments)
+    }
+
+    fn log_decision(&self, args: LogDecisionArgs) -> Result<String, AppError> {
+        let decision = {
+            let mut conn = self.db.conn()?;
+            decisions::log(
+                &mut conn,
+                self.agent.project_id,
+                self.session_id(),
+                &args.decision,
+                &args.rationale,
+                &args.alternatives,
+            )?
+        };
+        let id = decision.id;
+        self.events.emit("decision-logged", decision);
+        Ok(format!("Decision {id} logged."))
+    }
+}
+
+fn initialize(params: &Value) -> Value {
+    let requested = params["protocolVersion"].as_str().unwrap_or_default();
+    let version = PROTOCOL_VERSIONS
+        .iter()
+        .find(|v| **v == requested)
+        .unwrap_or(&PROTOCOL_VERSIONS[0]);
+    json!({
+        "protocolVersion": version,
+        "capabilities": { "tools": {} },
+        "serverInfo": { "name": SERVER_NAME, "version": env!("CARGO_PKG_VERSION") },
+        "instructions": INSTRUCTIONS,
+    })
+}
+
+fn tools() -> Value {
+    json!([
+        {
+            "name": "report_progress",
+            "description": "Tell DACM where you are. The summary replaces the project's \
+                            summary in the app, so write it for someone glancing at a list.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "summary": { "type": "string", "description": "One or two sentences" },
+                    "percent": { "type": "integer", "minimum": 0, "maximum": 100 },
+                },
+                "required": ["summary"],
+            },
+        },
+        {
+            "name": "update_plan_step",
+            "description": "Set the status of a step in the project's plan. Step ids come \
+                            from get_plan.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "step_id": { "type": "string" },
+                    "status": {
+                        "type": "string",
+                        "enum": ["pending", "in_progress", "completed", "blocked", "skipped"],
+                    },
+                    "note": {
+                        "type": "string",
+                        "description": "Why, e.g. what a blocked step is waiting on",
+                    },
+                },
+                "required": ["step_id", "status"],
+            },
+        },
+        {
+            "name": "request_review",
+            "description": "Ask the user to review your changes. The project shows as \
+                            waiting until they respond.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "summary": { "type": "string", "description": "What to look at" },
+                },
+                "required": ["summary"],
+            },
+        },
+        {
+            "name": "get_plan",
+            "description": "The project's current plan: its goal, constraints and phases \
+                            of steps with their status.",
+            "inputSchema": { "type": "object", "properties": {} },
+        },
+        {
+            "name": "get_review_comments",
+            "description": "Review comments left on the project's changes. Only unresolved \
+                            ones unless include_resolved is set.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "include_resolved": { "type": "boolean" },
+                },
+            },
+        },
+        {
+            "name": "log_decision",
+            "description": "Record a decision in the project's decision log so later \
+                            sessions know what was decided and why.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "decision": { "type": "string" },
+                    "rationale": { "type": "string" },
+                    "alternatives": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Options considered and not taken",
+                    },
+                },
+                "required": ["decision"],
+            },
+        },
+    ])
+}
+
+fn parse<T: DeserializeOwned>(args: Value) -> Result<T, AppError> {
+    serde_json::from_value(args)
+        .map_err(|e| AppError::validation(format!("Invalid arguments: {e}")))
+}
+
+fn to_text(value: &impl Serialize) -> Result<String, AppError> {
+    serde_json::to_string_pretty(value)
+        .map_err(|e| AppError::internal(format!("Failed to encode result: {e}")))
+}
+
+fn tool_result(text: String, is_error: bool) -> Value {
+    json!({
+        "content": [{ "type": "text", "text": text }],
+        "isError": is_error,
+    })
+}
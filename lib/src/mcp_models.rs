@@ -0,0 +1,53 @@
+use serde::Deserialize;
+
+use crate::plan_models::StepStatus;
+
+/// Who is on the other end of an MCP connection: the agent of a project,
+/// usually from a session DACM spawned.
+#[derive(Clone, Debug)]
+pub struct Agent {
+    pub project_id: i32,
+    pub session_id: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ToolCall {
+    pub name: String,
+    #[serde(default)]
+    pub arguments: serde_json::Value,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ReportProgressArgs {
+    pub summary: String,
+    #[serde(default)]
+    pub percent: Option<u8>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct UpdatePlanStepArgs {
+    pub step_id: String,
+    pub status: StepStatus,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RequestReviewArgs {
+    pub summary: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GetReviewCommentsArgs {
+    #[serde(default)]
+    pub include_resolved: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct LogDecisionArgs {
+    pub decision: String,
+    #[serde(default)]
+    pub rationale: String,
+    #[serde(default)]
+    pub alternatives: Vec<String>,
+}
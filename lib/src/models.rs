@@ -0,0 +1,87 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::schema::workspaces;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct GitRemote {
+    pub name: String,
+    pub url: String,
+}
+
+fn serialize_remotes<S: Serializer>(raw: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    parse_remotes(raw).serialize(serializer)
+}
+
+pub fn parse_remotes(raw: &str) -> Vec<GitRemote> {
+    serde_json::from_str(raw).unwrap_or_default()
+}
+
+#[derive(Queryable, Selectable, Serialize, Debug)]
+#[diesel(table_name = workspaces)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Workspace {
+    pub id: i32,
+    pub name: String,
+    pub path: String,
+    pub created_at: NaiveDateTime,
+    pub repo_root: Option<String>,
+    pub default_branch: Option<String>,
+    #[serde(serialize_with = "serialize_remotes")]
+    pub remotes: String,
+    pub head_branch: Option<String>,
+    pub head_commit: Option<String>,
+    pub git_refreshed_at: Option<NaiveDateTime>,
+    pub env_profile_id: Option<i32>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = workspaces)]
+pub struct NewWorkspace<'a> {
+    pub name: &'a str,
+    pub path: &'a str,
+}
+
+/// Repository details stored on a workspace, rewritten on every refresh.
+#[derive(AsChangeset)]
+#[diesel(table_name = workspaces)]
+#[diesel(treat_none_as_null = true)]
+pub struct WorkspaceGitInfo {
+    pub repo_root: Option<String>,
+    pub default_branch: Option<String>,
+    pub remotes: String,
+    pub head_branch: Option<String>,
+    pub head_commit: Option<String>,
+    pub git_refreshed_at: Option<NaiveDateTime>,
+}
+
+/// A git repository found by `scan_for_workspaces`.
+#[derive(Serialize, Debug, Clone)]
+pub struct WorkspaceCandidate {
+    pub path: String,
+    pub name: String,
+    pub default_branch: Option<String>,
+    pub head_branch: Option<String>,
+    pub remotes: Vec<GitRemote>,
+    /// Set when this repository is already a workspace
+    pub workspace_id: Option<i32>,
+    pub registered: bool,
+}
+
+/// Emitted as `workspace-scan-progress` while a scan walks the tree.
+#[derive(Serialize, Debug, Clone)]
+pub struct ScanProgress {
+    pub root: String,
+    pub dirs_scanned: usize,
+    pub repos_found: usize,
+    pub current: String,
+}
+
+/// What `add_workspaces` did with each requested path.
+#[derive(Serialize, Debug, Default)]
+pub struct WorkspaceBulkAdd {
+    pub added: Vec<Workspace>,
+    /// Paths that were already registered, left untouched
+    pub already_registered: Vec<String>,
+}
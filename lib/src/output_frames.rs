@@ -0,0 +1,74 @@
+use std::time::{Duration, Instant};
+
+// Session output arrives as many small PTY reads. Sending each one to the
+// webview on its own costs more than drawing it, so reads are gathered into
+// frames here. This only decides when a frame goes out; reading and
+// delivering are up to the host, which passes the time in.
+
+/// Reads gathered to send together.
+pub struct Frame {
+    pub bytes: Vec<u8>,
+    /// When the frame's first byte was read
+    pub read_at: Instant,
+}
+
+/// Gathers reads into frames. After a quiet spell of at least `interval`
+/// the first read goes out at once so typing stays responsive; while output
+/// keeps coming it's held for up to `interval` after its first read, or
+/// until it reaches `max_bytes`.
+pub struct Coalescer {
+    interval: Duration,
+    max_bytes: usize,
+    frame: Vec<u8>,
+    frame_read_at: Option<Instant>,
+    last_flush: Option<Instant>,
+}
+
+impl Coalescer {
+    pub fn new(interval: Duration, max_bytes: usize) -> Self {
+        Self {
+            interval,
+            max_bytes,
+            frame: Vec::with_capacity(max_bytes),
+            frame_read_at: None,
+            last_flush: None,
+        }
+    }
+
+    /// How long to wait for the next read before flushing what's held.
+    /// `None` when nothing is held, so the wait can be indefinite.
+    pub fn wait(&self, now: Instant) -> Option<Duration> {
+        let read_at = self.frame_read_at?;
+        Some(
+            self.interval
+                .saturating_sub(now.saturating_duration_since(read_at)),
+        )
+    }
+
+    /// Add a read made at `read_at`. Returns the frame if it should go out
+    /// now.
+    pub fn push(&mut self, read_at: Instant, chunk: &[u8]) -> Option<Frame> {
+        let idle = self.frame_read_at.is_none()
+            && self
+                .last_flush
+                .is_none_or(|last| read_at.saturating_duration_since(last) >= self.interval);
+        self.frame_read_at.get_or_insert(read_at);
+        self.frame.extend_from_slice(chunk);
+
+        if idle || self.frame.len() >= self.max_bytes {
+            self.flush(read_at)
+        } else {
+            None
+        }
+    }
+
+    /// Take whatever is held, because the wait ran out or the output ended.
+    pub fn flush(&mut self, now: Instant) -> Option<Frame> {
+        let read_at = self.frame_read_at.take()?;
+        self.last_flush = Some(now);
+        Some(Frame {
+            bytes: std::mem::replace(&mut self.frame, Vec::with_capacity(self.max_bytes)),
+            read_at,
+        })
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use portable_pty::CommandBuilder;
+
+use crate::error::AppError;
+use crate::session::SpawnRequest;
+use crate::tmux::{self, Server};
+
+// Persistent sessions run the agent in a detached session on our own tmux
+// server and attach a client to it, so closing the app only detaches.
+// Each tmux session carries the spawn request in a user option, which is
+// how the next launch finds and reattaches to the ones still running. Any
+// user of the server can read options, so only the request's ids, size,
+// directory and CLI are stored; its env and prompt are left out and the
+// env is resolved again on reattach.
+//
+// The agent runs under a small `sh` wrapper that loads its environment
+// from a file, keeping secrets out of any command line, and writes the
+// exit status to a file once it finishes, since the tmux client's own
+// status says nothing about the agent.
+
+const NAME_PREFIX: &str = "dacm-";
+const REQUEST_OPTION: &str = "@dacm_request";
+
+/// `$1` is the env file, `$2` the status file, the rest the agent's argv.
+const WRAPPER: &str = r#". "$1"; rm -f "$1"; status="$2"; shift 2; "$@"; code=$?; echo "$code" > "$status"; exit "$code""#;
+
+/// Server-wide options, set whenever a session is created: no status bar
+/// or prefix key, so the attached client looks and types like the agent
+/// alone.
+const SERVER_OPTIONS: &[&[&str]] = &[
+    &["set-option", "-g", "status", "off"],
+    &["set-option", "-g", "prefix", "None"],
+    &["set-option", "-g", "prefix2", "None"],
+    &["set-option", "-g", "escape-time", "0"],
+    &["set-option", "-g", "default-terminal", "screen-256color"],
+];
+
+pub fn session_name(session_id: &str) -> String {
+    format!("{NAME_PREFIX}{session_id}")
+}
+
+/// Start `program` in a new detached tmux session for `session_id`.
+pub fn launch<'a>(
+    dir: &Path,
+    session_id: &str,
+    request: &SpawnRequest,
+    program: &str,
+    args: &[String],
+    env: impl Iterator<Item = (&'a String, &'a String)>,
+) -> Result<(), AppError> {
+    fs::create_dir_all(dir)
+        .map_err(|e| AppError::io(format!("Failed to create {}: {e}", dir.display())))?;
+    let env_path = dir.join(format!("{session_id}.env"));
+    write_env_file(&env_path, env)?;
+
+    let name = session_name(session_id);
+    let request_json = serde_json::to_string(&stored(request))
+        .map_err(|e| AppError::internal(format!("Failed to encode spawn request: {e}")))?;
+    let (cols, rows) = (request.cols.to_string(), request.rows.to_string());
+    let env_arg = env_path.to_string_lossy().into_owned();
+    let status_arg = status_path(dir, session_id).to_string_lossy().into_owned();
+
+    let mut args_list: Vec<&str> = Vec::new();
+    for option in SERVER_OPTIONS {
+        args_list.extend_from_slice(option);
+        args_list.push(";");
+    }
+    args_list.extend_from_slice(&[
+        "new-session",
+        "-d",
+        "-s",
+        &name,
+        "-x",
+        &cols,
+        "-y",
+        &rows,
+        "-c",
+        &request.working_dir,
+        "sh",
+        "-c",
+        WRAPPER,
+        "dacm-agent",
+        &env_arg,
+        &status_arg,
+        program,
+    ]);
+    args_list.extend(args.iter().map(String::as_str));
+    args_list.extend_from_slice(&[
+        ";",
+        "set-option",
+        "-t",
+        &name,
+        REQUEST_OPTION,
+        &request_json,
+    ]);
+
+    // Starting the server is part of the command, so "no server" can't happen
+    let result = tmux::run(Server::Owned, &args_list);
+    if result.is_err() {
+        let _ = fs::remove_file(&env_path);
+    }
+    result.map(|_| ())
+}
+
+/// The part of `request` kept in the session's option.
+fn stored(request: &SpawnRequest) -> SpawnRequest {
+    SpawnRequest {
+        initial_prompt: None,
+        env: BTreeMap::new(),
+        secret_env: BTreeMap::new(),
+        ..request.clone()
+    }
+}
+
+/// A client attached to the session, to run in the session's PTY.
+pub fn attach_command(session_id: &str, working_dir: &str) -> CommandBuilder {
+    tmux::attach_command(Server::Owned, &session_name(session_id), working_dir)
+}
+
+/// Persistent sessions still running, with the requests they were started
+/// from, minus their env and prompt.
+pub fn list() -> Vec<(String, SpawnRequest)> {
+    let format = format!("#{{session_name}}\t#{{{REQUEST_OPTION}}}");
+    let output = match tmux::run(Server::Owned, &["list-sessions", "-F", &format]) {
+        Ok(Some(output)) => output,
+        Ok(None) => return Vec::new(),
+        Err(e) => {
+            eprintln!("[persistent] Failed to list sessions: {e}");
+            return Vec::new();
+        }
+    };
+
+    output
+        .lines()
+        .filter_map(|line| {
+            let (name, request) = line.split_once('\t')?;
+            let session_id = name.strip_prefix(NAME_PREFIX)?;
+            match serde_json::from_str::<SpawnRequest>(request) {
+                Ok(request) => Some((session_id.to_string(), request)),
+                Err(e) => {
+                    eprintln!("[persistent] Skipping {name}: bad spawn request: {e}");
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+pub fn is_running(session_id: &str) -> bool {
+    let target = format!("={}", session_name(session_id));
+    matches!(
+        tmux::run(Server::Owned, &["has-session", "-t", &target]),
+        Ok(Some(_))
+    )
+}
+
+/// Pid of the wrapper running the agent.
+pub fn pid(session_id: &str) -> Option<u32> {
+    // The trailing `:` makes it a pane target: the session's current pane
+    let target = format!("={}:", session_name(session_id));
+    tmux::run(
+        Server::Owned,
+        &["display-message", "-p", "-t", &target, "#{pane_pid}"],
+    )
+    .ok()
+    .flatten()?
+    .trim()
+    .parse()
+    .ok()
+}
+
+/// End the session and the agent in it.
+pub fn kill(session_id: &str) -> Result<(), AppError> {
+    let target = format!("={}", session_name(session_id));
+    tmux::run(Server::Owned, &["kill-session", "-t", &target])?
+        .map(|_| ())
+        .ok_or_else(|| AppError::pty(format!("Persistent session {session_id} is gone")))
+}
+
+/// The agent's exit status, once it has finished. Reading it removes the
+/// file.
+pub fn take_exit_code(dir: &Path, session_id: &str) -> Option<u32> {
+    let path = status_path(dir, session_id);
+    let code = fs::read_to_string(&path).ok()?.trim().parse().ok();
+    let _ = fs::remove_file(&path);
+    code
+}
+
+fn status_path(dir: &Path, session_id: &str) -> PathBuf {
+    dir.join(format!("{session_id}.status"))
+}
+
+/// `export` lines for the wrapper to source. Readable only by us, and
+/// removed by the wrapper as soon as it's loaded.
+fn write_env_file<'a>(
+    path: &Path,
+    env: impl Iterator<Item = (&'a String, &'a String)>,
+) -> Result<(), AppError> {
+    let mut script = String::from("unset CLAUDECODE\n");
+    for (key, value) in env {
+        // The shell can only export names that are identifiers
+        let valid = key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && !key.starts_with(|c: char| c.is_ascii_digit());
+        if !valid {
+            eprintln!("[persistent] Not passing {key}: not a shell variable name");
+            continue;
+        }
+        script.push_str(&format!(
+            "export {key}='{}'\n",
+            value.replace('\'', r"'\''")
+        ));
+    }
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(path)
+        .map_err(|e| AppError::io(format!("Failed to create {}: {e}", path.display())))?;
+    file.write_all(script.as_bytes())
+        .map_err(|e| AppError::io(format!("Failed to write {}: {e}", path.display())))
+}
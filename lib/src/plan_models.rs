@@ -0,0 +1,131 @@
+use std::fmt;
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::schema::plans;
+
+fn serialize_content<S: Serializer>(raw: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    parse_content(raw).serialize(serializer)
+}
+
+pub fn parse_content(raw: &str) -> PlanContent {
+    serde_json::from_str(raw).unwrap_or_default()
+}
+
+/// A project's plan as stored. `content` is JSON, sent to the frontend as a
+/// `PlanContent` object.
+#[derive(Queryable, Selectable, Serialize, Debug, Clone)]
+#[diesel(table_name = plans)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Plan {
+    pub id: i32,
+    pub project_id: i32,
+    pub name: String,
+    #[serde(serialize_with = "serialize_content")]
+    pub content: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl Plan {
+    pub fn content(&self) -> PlanContent {
+        parse_content(&self.content)
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = plans)]
+pub struct NewPlan<'a> {
+    pub project_id: i32,
+    pub name: &'a str,
+    pub content: &'a str,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct PlanContent {
+    #[serde(default)]
+    pub goal: String,
+    #[serde(default)]
+    pub constraints: Vec<String>,
+    #[serde(default)]
+    pub phases: Vec<PlanPhase>,
+}
+
+impl PlanContent {
+    pub fn steps(&self) -> impl Iterator<Item = &PlanStep> {
+        self.phases.iter().flat_map(|phase| &phase.steps)
+    }
+
+    pub fn step_mut(&mut self, step_id: &str) -> Option<&mut PlanStep> {
+        self.phases
+            .iter_mut()
+            .flat_map(|phase| &mut phase.steps)
+            .find(|step| step.id == step_id)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PlanPhase {
+    pub id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub risk: Option<String>,
+    /// How to tell the phase is done, e.g. commands that must pass
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_criteria: Option<String>,
+    #[serde(default)]
+    pub steps: Vec<PlanStep>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PlanStep {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub status: StepStatus,
+    #[serde(default)]
+    pub details: String,
+    #[serde(default)]
+    pub files: Vec<String>,
+    /// Left by whoever last changed the status, e.g. why it's blocked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    #[default]
+    Pending,
+    InProgress,
+    Completed,
+    Blocked,
+    Skipped,
+}
+
+impl StepStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StepStatus::Pending => "pending",
+            StepStatus::InProgress => "in_progress",
+            StepStatus::Completed => "completed",
+            StepStatus::Blocked => "blocked",
+            StepStatus::Skipped => "skipped",
+        }
+    }
+}
+
+impl fmt::Display for StepStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Payload of `plan-changed`.
+#[derive(Serialize, Clone, Debug)]
+pub struct PlanChanged {
+    pub project_id: i32,
+    pub plan: Plan,
+}
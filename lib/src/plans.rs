@@ -0,0 +1,145 @@
+use std::collections::HashSet;
+
+use chrono::Utc;
+use diesel::prelude::*;
+use serde_json::json;
+
+use crate::error::{AppError, ResultExt};
+use crate::event_models::{EventType, NewEvent};
+use crate::events;
+use crate::plan_models::{NewPlan, Plan, PlanContent, StepStatus};
+use crate::projects;
+use crate::schema::plans;
+
+// A project has one current plan: the latest saved. Saving a new plan keeps
+// the earlier ones as history; step updates change the current one in place.
+
+pub fn current(conn: &mut SqliteConnection, project_id: i32) -> Result<Option<Plan>, AppError> {
+    plans::table
+        .filter(plans::project_id.eq(project_id))
+        .order(plans::id.desc())
+        .select(Plan::as_select())
+        .first(conn)
+        .optional()
+        .context("Failed to fetch plan")
+}
+
+pub fn history(conn: &mut SqliteConnection, project_id: i32) -> Result<Vec<Plan>, AppError> {
+    plans::table
+        .filter(plans::project_id.eq(project_id))
+        .order(plans::id.desc())
+        .select(Plan::as_select())
+        .load(conn)
+        .context("Failed to list plans")
+}
+
+/// Make `content` the project's current plan.
+pub fn save(
+    conn: &mut SqliteConnection,
+    project_id: i32,
+    name: &str,
+    content: &PlanContent,
+) -> Result<Plan, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::validation("Plan name is empty"));
+    }
+    validate(content)?;
+    let encoded = encode(content)?;
+
+    conn.immediate_transaction(|conn| {
+        let project = projects::get(conn, project_id)?;
+        let plan = diesel::insert_into(plans::table)
+            .values(&NewPlan {
+                project_id,
+                name,
+                content: &encoded,
+            })
+            .returning(Plan::as_returning())
+            .get_result(conn)
+            .context("Failed to save plan")?;
+
+        events::record(
+            conn,
+            NewEvent::new(EventType::PlanSaved)
+                .workspace(project.workspace_id)
+                .project(project_id)
+                .payload(json!({ "name": name, "steps": content.steps().count() })),
+        );
+        Ok(plan)
+    })
+}
+
+/// Set a step of the project's current plan, with an optional note on why.
+/// The read and write share a transaction, so concurrent updates to other
+/// steps aren't lost.
+pub fn update_step(
+    conn: &mut SqliteConnection,
+    project_id: i32,
+    step_id: &str,
+    status: StepStatus,
+    note: Option<&str>,
+) -> Result<Plan, AppError> {
+    conn.immediate_transaction(|conn| {
+        let plan = current(conn, project_id)?
+            .ok_or_else(|| AppError::not_found(format!("Project {project_id} has no plan")))?;
+        let mut content = plan.content();
+        let step = content.step_mut(step_id).ok_or_else(|| {
+            AppError::not_found(format!("Plan has no step '{step_id}'"))
+                .with_details(json!({ "step_id": step_id }))
+        })?;
+        let previous = step.status;
+        step.status = status;
+        step.note = note.map(str::to_string).filter(|n| !n.trim().is_empty());
+        let encoded = encode(&content)?;
+
+        let updated = diesel::update(plans::table.filter(plans::id.eq(plan.id)))
+            .set((
+                plans::content.eq(&encoded),
+                plans::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .returning(Plan::as_returning())
+            .get_result(conn)
+            .context("Failed to update plan")?;
+
+        let project = projects::get(conn, project_id)?;
+        events::record(
+            conn,
+            NewEvent::new(EventType::PlanStepUpdated)
+                .workspace(project.workspace_id)
+                .project(project_id)
+                .payload(json!({
+                    "step_id": step_id,
+                    "from": previous,
+                    "to": status,
+                    "note": note,
+                })),
+        );
+        Ok(updated)
+    })
+}
+
+/// Steps are addressed by id, so ids must be present and unique.
+fn validate(content: &PlanContent) -> Result<(), AppError> {
+    let mut seen = HashSet::new();
+    for step in content.steps() {
+        if step.id.trim().is_empty() {
+            return Err(AppError::validation(format!(
+                "Step '{}' has no id",
+                step.name
+            )));
+        }
+        if !seen.insert(step.id.as_str()) {
+            return Err(
+                AppError::validation(format!("Step id '{}' is used twice", step.id))
+                    .with_details(json!({ "step_id": step.id })),
+            );
+        }
+    }
+    Ok(())
+}
+
+fn encode(content: &PlanContent) -> Result<String, AppError> {
+    serde_json::to_string(content)
+        .map_err(|e| AppError::internal(format!("Failed to encode plan: {e}")))
+}
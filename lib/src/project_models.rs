@@ -0,0 +1,166 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::NaiveDateTime;
+use diesel::deserialize::{self, FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::prelude::*;
+use diesel::serialize::{self, IsNull, Output, ToSql};
+use diesel::sql_types::Text;
+use diesel::sqlite::{Sqlite, SqliteValue};
+use serde::{Deserialize, Serialize};
+
+use crate::schema::{project_status_history, projects};
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, AsExpression, FromSqlRow,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectStatus {
+    Queued,
+    Running,
+    Waiting,
+    Completed,
+    Failed,
+    Archived,
+}
+
+impl ProjectStatus {
+    pub const ALL: [ProjectStatus; 6] = [
+        ProjectStatus::Queued,
+        ProjectStatus::Running,
+        ProjectStatus::Waiting,
+        ProjectStatus::Completed,
+        ProjectStatus::Failed,
+        ProjectStatus::Archived,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ProjectStatus::Queued => "queued",
+            ProjectStatus::Running => "running",
+            ProjectStatus::Waiting => "waiting",
+            ProjectStatus::Completed => "completed",
+            ProjectStatus::Failed => "failed",
+            ProjectStatus::Archived => "archived",
+        }
+    }
+
+    /// Statuses reachable from this one. Staying put is always allowed and
+    /// isn't listed here.
+    pub fn allowed_transitions(self) -> &'static [ProjectStatus] {
+        use ProjectStatus::*;
+        match self {
+            Queued => &[Running, Waiting, Failed, Archived],
+            Running => &[Waiting, Completed, Failed, Archived],
+            Waiting => &[Queued, Running, Completed, Failed, Archived],
+            Completed => &[Queued, Running, Archived],
+            Failed => &[Queued, Running, Archived],
+            // Restoring from the archive puts a project back in the waiting state
+            Archived => &[Waiting],
+        }
+    }
+
+    pub fn can_transition_to(self, to: ProjectStatus) -> bool {
+        self == to || self.allowed_transitions().contains(&to)
+    }
+}
+
+impl fmt::Display for ProjectStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ProjectStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ProjectStatus::ALL
+            .into_iter()
+            .find(|status| status.as_str() == s)
+            .ok_or_else(|| format!("Unknown project status: {s}"))
+    }
+}
+
+impl ToSql<Text, Sqlite> for ProjectStatus {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Sqlite>) -> serialize::Result {
+        out.set_value(self.as_str());
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<Text, Sqlite> for ProjectStatus {
+    fn from_sql(bytes: SqliteValue<'_, '_, '_>) -> deserialize::Result<Self> {
+        let value = <String as FromSql<Text, Sqlite>>::from_sql(bytes)?;
+        value.parse().map_err(Into::into)
+    }
+}
+
+#[derive(Queryable, Selectable, Serialize, Debug)]
+#[diesel(table_name = projects)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Project {
+    pub id: i32,
+    pub name: String,
+    pub description: String,
+    pub summary: String,
+    pub task_id: Option<String>,
+    /// `None` once the workspace has been removed in archive mode
+    pub workspace_id: Option<i32>,
+    pub status: ProjectStatus,
+    pub start_time: Option<NaiveDateTime>,
+    pub iteration_count: i32,
+    pub worktree_path: Option<String>,
+    pub branch_name: Option<String>,
+    pub created_at: NaiveDateTime,
+    /// Overrides the workspace's variables for this project's sessions
+    pub env_profile_id: Option<i32>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = projects)]
+pub struct NewProject<'a> {
+    pub name: &'a str,
+    pub workspace_id: i32,
+    pub description: &'a str,
+}
+
+/// Payload of `project-progress`, sent when an agent reports where it is.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProjectProgress {
+    pub project_id: i32,
+    pub session_id: Option<String>,
+    pub summary: String,
+    pub percent: Option<u8>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProjectStatusChanged {
+    pub project_id: i32,
+    pub status: ProjectStatus,
+    pub previous: Option<ProjectStatus>,
+    pub reason: String,
+}
+
+#[derive(Queryable, Selectable, Serialize, Debug)]
+#[diesel(table_name = project_status_history)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ProjectStatusHistory {
+    pub id: i32,
+    pub project_id: i32,
+    pub from_status: Option<ProjectStatus>,
+    pub to_status: ProjectStatus,
+    pub reason: String,
+    pub changed_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = project_status_history)]
+pub struct NewProjectStatusHistory<'a> {
+    pub project_id: i32,
+    pub from_status: Option<ProjectStatus>,
+    pub to_status: ProjectStatus,
+    pub reason: &'a str,
+}
@@ -0,0 +1,192 @@
+use std::fmt;
+
+use diesel::prelude::*;
+use serde_json::json;
+
+use crate::db::DbState;
+use crate::event_models::{EventType, NewEvent};
+use crate::event_sink::EventSink;
+use crate::events;
+use crate::project_models::{
+    NewProjectStatusHistory, Project, ProjectStatus, ProjectStatusChanged,
+};
+use crate::schema::{project_status_history, projects};
+
+#[derive(Debug)]
+pub enum TransitionError {
+    NotFound(i32),
+    Illegal {
+        project_id: i32,
+        from: ProjectStatus,
+        to: ProjectStatus,
+    },
+    /// The project's workspace was removed, so it can only stay archived
+    Detached(i32),
+    Db(diesel::result::Error),
+    Unavailable(String),
+}
+
+impl fmt::Display for TransitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransitionError::NotFound(id) => write!(f, "Project with id {id} not found"),
+            TransitionError::Illegal {
+                project_id,
+                from,
+                to,
+            } => write!(
+                f,
+                "Project {project_id} cannot move from '{from}' to '{to}'. Allowed: {:?}",
+                from.allowed_transitions()
+            ),
+            TransitionError::Detached(id) => write!(
+                f,
+                "Project {id} no longer belongs to a workspace and can't be restored"
+            ),
+            TransitionError::Db(e) => write!(f, "Failed to update project status: {e}"),
+            TransitionError::Unavailable(e) => write!(f, "Database unavailable: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for TransitionError {}
+
+impl From<diesel::result::Error> for TransitionError {
+    fn from(e: diesel::result::Error) -> Self {
+        TransitionError::Db(e)
+    }
+}
+
+pub struct Transition {
+    pub project: Project,
+    pub previous: ProjectStatus,
+    pub reason: String,
+}
+
+impl Transition {
+    pub fn changed(&self) -> bool {
+        self.previous != self.project.status
+    }
+
+    pub fn event(&self) -> ProjectStatusChanged {
+        ProjectStatusChanged {
+            project_id: self.project.id,
+            status: self.project.status,
+            previous: Some(self.previous),
+            reason: self.reason.clone(),
+        }
+    }
+}
+
+/// The one place project status changes. Checks the transition table,
+/// writes the new status and appends a history row in a single transaction.
+/// Moving to the current status is a no-op and isn't recorded.
+pub fn transition(
+    conn: &mut SqliteConnection,
+    project_id: i32,
+    to: ProjectStatus,
+    reason: &str,
+) -> Result<Transition, TransitionError> {
+    conn.transaction(|conn| {
+        let project = projects::table
+            .filter(projects::id.eq(project_id))
+            .select(Project::as_select())
+            .first(conn)
+            .optional()?
+            .ok_or(TransitionError::NotFound(project_id))?;
+
+        let from = project.status;
+        if project.workspace_id.is_none() && to != ProjectStatus::Archived {
+            return Err(TransitionError::Detached(project_id));
+        }
+        if !from.can_transition_to(to) {
+            return Err(TransitionError::Illegal {
+                project_id,
+                from,
+                to,
+            });
+        }
+        if from == to {
+            return Ok(Transition {
+                project,
+                previous: from,
+                reason: reason.to_string(),
+            });
+        }
+
+        diesel::update(projects::table.filter(projects::id.eq(project_id)))
+            .set(projects::status.eq(to))
+            .execute(conn)?;
+
+        diesel::insert_into(project_status_history::table)
+            .values(&NewProjectStatusHistory {
+                project_id,
+                from_status: Some(from),
+                to_status: to,
+                reason,
+            })
+            .execute(conn)?;
+
+        events::record(
+            conn,
+            NewEvent::new(EventType::ProjectStatusChanged)
+                .workspace(project.workspace_id)
+                .project(project_id)
+                .payload(json!({ "from": from, "to": to, "reason": reason })),
+        );
+
+        let project = projects::table
+            .filter(projects::id.eq(project_id))
+            .select(Project::as_select())
+            .first(conn)?;
+
+        Ok(Transition {
+            project,
+            previous: from,
+            reason: reason.to_string(),
+        })
+    })
+}
+
+/// History entry for a project's starting status, written when it's created.
+pub fn record_initial(
+    conn: &mut SqliteConnection,
+    project: &Project,
+    reason: &str,
+) -> QueryResult<()> {
+    diesel::insert_into(project_status_history::table)
+        .values(&NewProjectStatusHistory {
+            project_id: project.id,
+            from_status: None,
+            to_status: project.status,
+            reason,
+        })
+        .execute(conn)?;
+    Ok(())
+}
+
+pub fn emit_change(events: &dyn EventSink, transition: &Transition) {
+    if transition.changed() {
+        events.emit("project-status-changed", transition.event());
+    }
+}
+
+/// `transition` for callers that don't already hold the connection, followed
+/// by the `project-status-changed` event.
+pub fn transition_and_emit(
+    db: &DbState,
+    events: &dyn EventSink,
+    project_id: i32,
+    to: ProjectStatus,
+    reason: &str,
+) -> Result<Project, TransitionError> {
+    let result = {
+        let mut conn = db
+            .conn()
+            .map_err(|e| TransitionError::Unavailable(e.to_string()))?;
+        transition(&mut conn, project_id, to, reason)?
+    };
+
+    emit_change(events, &result);
+    Ok(result.project)
+}
@@ -0,0 +1,170 @@
+use diesel::prelude::*;
+use serde_json::json;
+
+use crate::error::{AppError, ResultExt};
+use crate::event_models::{EventType, NewEvent};
+use crate::events;
+use crate::project_models::{NewProject, Project, ProjectStatus, ProjectStatusHistory};
+use crate::project_status;
+use crate::schema::{project_status_history, projects};
+
+// Projects' own records. Status changes go through `project_status`, which
+// also tells listeners about them.
+
+/// Insert a project with its initial status.
+pub fn create(
+    conn: &mut SqliteConnection,
+    workspace_id: i32,
+    name: &str,
+    description: &str,
+) -> Result<Project, AppError> {
+    let new_project = NewProject {
+        name,
+        workspace_id,
+        description,
+    };
+
+    let project = diesel::insert_into(projects::table)
+        .values(&new_project)
+        .returning(Project::as_returning())
+        .get_result(conn)
+        .context("Failed to create project")?;
+
+    project_status::record_initial(conn, &project, "created")
+        .context("Failed to record project status")?;
+
+    events::record(
+        conn,
+        NewEvent::new(EventType::ProjectCreated)
+            .workspace(project.workspace_id)
+            .project(project.id)
+            .payload(json!({ "name": project.name, "description": project.description })),
+    );
+
+    Ok(project)
+}
+
+pub fn get(conn: &mut SqliteConnection, project_id: i32) -> Result<Project, AppError> {
+    projects::table
+        .filter(projects::id.eq(project_id))
+        .select(Project::as_select())
+        .first(conn)
+        .context("Failed to fetch project")
+}
+
+pub fn rename(
+    conn: &mut SqliteConnection,
+    project_id: i32,
+    name: &str,
+) -> Result<Project, AppError> {
+    let previous = get(conn, project_id)?;
+
+    diesel::update(projects::table.filter(projects::id.eq(project_id)))
+        .set(projects::name.eq(name))
+        .execute(conn)
+        .context("Failed to rename project")?;
+
+    events::record(
+        conn,
+        NewEvent::new(EventType::ProjectRenamed)
+            .workspace(previous.workspace_id)
+            .project(project_id)
+            .payload(json!({ "from": previous.name, "to": name })),
+    );
+
+    get(conn, project_id)
+}
+
+/// Replace the project's summary with an agent's latest progress report.
+pub fn set_summary(
+    conn: &mut SqliteConnection,
+    project_id: i32,
+    session_id: Option<&str>,
+    summary: &str,
+    percent: Option<u8>,
+) -> Result<Project, AppError> {
+    let summary = summary.trim();
+    if summary.is_empty() {
+        return Err(AppError::validation("Summary is empty"));
+    }
+    if percent.is_some_and(|p| p > 100) {
+        return Err(AppError::validation("Percent must be between 0 and 100"));
+    }
+    let project = get(conn, project_id)?;
+
+    diesel::update(projects::table.filter(projects::id.eq(project_id)))
+        .set(projects::summary.eq(summary))
+        .execute(conn)
+        .context("Failed to update summary")?;
+
+    let mut event = NewEvent::new(EventType::ProgressReported)
+        .workspace(project.workspace_id)
+        .project(project_id)
+        .payload(json!({ "summary": summary, "percent": percent }));
+    event.session_id = session_id;
+    events::record(conn, event);
+
+    get(conn, project_id)
+}
+
+pub fn delete(conn: &mut SqliteConnection, project_id: i32) -> Result<(), AppError> {
+    let project = get(conn, project_id)?;
+
+    diesel::delete(projects::table.filter(projects::id.eq(project_id)))
+        .execute(conn)
+        .context("Failed to delete project")?;
+
+    events::record(
+        conn,
+        NewEvent::new(EventType::ProjectDeleted)
+            .workspace(project.workspace_id)
+            .project(project_id)
+            .payload(json!({ "name": project.name })),
+    );
+
+    Ok(())
+}
+
+/// Every project that isn't archived, newest first.
+pub fn list_active(conn: &mut SqliteConnection) -> Result<Vec<Project>, AppError> {
+    projects::table
+        .filter(projects::status.ne(ProjectStatus::Archived))
+        .select(Project::as_select())
+        .order(projects::created_at.desc())
+        .load(conn)
+        .context("Failed to list projects")
+}
+
+pub fn list_by_workspace(
+    conn: &mut SqliteConnection,
+    workspace_id: i32,
+) -> Result<Vec<Project>, AppError> {
+    projects::table
+        .filter(projects::workspace_id.eq(workspace_id))
+        .filter(projects::status.ne(ProjectStatus::Archived))
+        .select(Project::as_select())
+        .order(projects::created_at.desc())
+        .load(conn)
+        .context("Failed to list projects")
+}
+
+pub fn list_archived(conn: &mut SqliteConnection) -> Result<Vec<Project>, AppError> {
+    projects::table
+        .filter(projects::status.eq(ProjectStatus::Archived))
+        .select(Project::as_select())
+        .order(projects::created_at.desc())
+        .load(conn)
+        .context("Failed to list archived projects")
+}
+
+pub fn status_history(
+    conn: &mut SqliteConnection,
+    project_id: i32,
+) -> Result<Vec<ProjectStatusHistory>, AppError> {
+    project_status_history::table
+        .filter(project_status_history::project_id.eq(project_id))
+        .select(ProjectStatusHistory::as_select())
+        .order(project_status_history::id.asc())
+        .load(conn)
+        .context("Failed to list status history")
+}
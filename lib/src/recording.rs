@@ -0,0 +1,292 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use diesel::prelude::*;
+use serde_json::{json, Value};
+
+use crate::error::AppError;
+use crate::recording_models::{CastHeader, RecordingInfo};
+use crate::schema::session_runs;
+
+const RECORDINGS_DIR: &str = "recordings";
+const EXTENSION: &str = "cast";
+
+/// One session being written to an asciicast v2 file: a JSON header line,
+/// then one `[seconds, code, data]` line per output ("o") or resize ("r").
+pub struct Recorder {
+    file: File,
+    started: Instant,
+    /// Tail of the last chunk when it ended partway through a UTF-8
+    /// sequence. Cast data is text, so it waits for the rest.
+    pending: Vec<u8>,
+}
+
+impl Recorder {
+    fn create(path: &Path, rows: u16, cols: u16) -> Result<Self, AppError> {
+        let mut file = File::create(path)
+            .map_err(|e| AppError::io(format!("Failed to create {}: {e}", path.display())))?;
+
+        let header = CastHeader {
+            version: 2,
+            width: cols,
+            height: rows,
+            timestamp: Some(chrono::Utc::now().timestamp()),
+            title: None,
+            env: Some(json!({ "TERM": "xterm-256color" })),
+        };
+        let line = serde_json::to_string(&header)
+            .map_err(|e| AppError::internal(format!("Failed to encode cast header: {e}")))?;
+        writeln!(file, "{line}")?;
+
+        Ok(Self {
+            file,
+            started: Instant::now(),
+            pending: Vec::new(),
+        })
+    }
+
+    fn output(&mut self, chunk: &[u8]) -> std::io::Result<()> {
+        self.pending.extend_from_slice(chunk);
+        let text = match std::str::from_utf8(&self.pending) {
+            Ok(text) => {
+                let text = text.to_string();
+                self.pending.clear();
+                text
+            }
+            // Incomplete sequence at the end: keep it for the next chunk
+            Err(e) if e.error_len().is_none() => {
+                let valid = e.valid_up_to();
+                let text = String::from_utf8_lossy(&self.pending[..valid]).to_string();
+                self.pending.drain(..valid);
+                text
+            }
+            Err(_) => String::from_utf8_lossy(&std::mem::take(&mut self.pending)).to_string(),
+        };
+        if text.is_empty() {
+            return Ok(());
+        }
+        self.event("o", &text)
+    }
+
+    fn resize(&mut self, rows: u16, cols: u16) -> std::io::Result<()> {
+        self.event("r", &format!("{cols}x{rows}"))
+    }
+
+    fn event(&mut self, code: &str, data: &str) -> std::io::Result<()> {
+        let time = self.started.elapsed().as_secs_f64();
+        let line = json!([(time * 1_000_000.0).round() / 1_000_000.0, code, data]);
+        // One write per line so a crash never leaves half an event behind
+        self.file.write_all(format!("{line}\n").as_bytes())
+    }
+}
+
+/// Recorders for running sessions, keyed by session id.
+pub struct Recordings {
+    dir: PathBuf,
+    active: Mutex<HashMap<String, Recorder>>,
+}
+
+impl Recordings {
+    pub fn new(app_data_dir: &Path) -> Self {
+        Self {
+            dir: app_data_dir.join(RECORDINGS_DIR),
+            active: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn start(&self, session_id: &str, rows: u16, cols: u16) -> Result<(), AppError> {
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| AppError::io(format!("Failed to create recordings dir: {e}")))?;
+        let recorder = Recorder::create(&self.path(session_id), rows, cols)?;
+        self.active.lock()?.insert(session_id.to_string(), recorder);
+        Ok(())
+    }
+
+    /// Append output for a session. Write failures stop its recording
+    /// rather than the session.
+    pub fn output(&self, session_id: &str, chunk: &[u8]) {
+        self.with_recorder(session_id, |recorder| recorder.output(chunk));
+    }
+
+    pub fn resize(&self, session_id: &str, rows: u16, cols: u16) {
+        self.with_recorder(session_id, |recorder| recorder.resize(rows, cols));
+    }
+
+    pub fn finish(&self, session_id: &str) {
+        if let Ok(mut active) = self.active.lock() {
+            if let Some(mut recorder) = active.remove(session_id) {
+                // Whatever was held back waiting for more of a sequence
+                let rest = String::from_utf8_lossy(&recorder.pending).to_string();
+                if !rest.is_empty() {
+                    let _ = recorder.event("o", &rest);
+                }
+                let _ = recorder.file.flush();
+            }
+        }
+    }
+
+    pub fn is_active(&self, id: &str) -> bool {
+        self.active
+            .lock()
+            .map(|active| active.contains_key(id))
+            .unwrap_or(false)
+    }
+
+    /// Path of a recording, after checking the id can't escape the
+    /// recordings directory.
+    pub fn existing_path(&self, id: &str) -> Result<PathBuf, AppError> {
+        let valid = !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+        let path = self.path(id);
+        if !valid || !path.is_file() {
+            return Err(AppError::not_found(format!("Recording '{id}' not found")));
+        }
+        Ok(path)
+    }
+
+    fn path(&self, session_id: &str) -> PathBuf {
+        self.dir.join(format!("{session_id}.{EXTENSION}"))
+    }
+
+    fn with_recorder(
+        &self,
+        session_id: &str,
+        f: impl FnOnce(&mut Recorder) -> std::io::Result<()>,
+    ) {
+        let Ok(mut active) = self.active.lock() else {
+            return;
+        };
+        let Some(recorder) = active.get_mut(session_id) else {
+            return;
+        };
+        if let Err(e) = f(recorder) {
+            eprintln!("[recording {session_id}] Write failed, stopping: {e}");
+            active.remove(session_id);
+        }
+    }
+}
+
+/// Saved recordings, newest first.
+pub fn list(
+    conn: &mut SqliteConnection,
+    recordings: &Recordings,
+) -> Result<Vec<RecordingInfo>, AppError> {
+    let entries = match fs::read_dir(recordings.dir()) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(AppError::io(format!("Failed to read recordings dir: {e}"))),
+    };
+
+    let mut infos = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(EXTENSION) {
+            continue;
+        }
+        let Some(id) = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .map(str::to_string)
+        else {
+            continue;
+        };
+        // Files that aren't casts, or were cut off before the header, are skipped
+        let Ok(header) = read_header(&path) else {
+            continue;
+        };
+
+        infos.push(RecordingInfo {
+            active: recordings.is_active(&id),
+            id,
+            path: path.to_string_lossy().to_string(),
+            project_id: None,
+            workspace_id: None,
+            width: header.width,
+            height: header.height,
+            started_at: header.timestamp,
+            duration_secs: last_event_time(&path).unwrap_or(0.0),
+            size_bytes: entry.metadata().map(|m| m.len()).unwrap_or(0),
+        });
+    }
+
+    let ids: Vec<&str> = infos.iter().map(|i| i.id.as_str()).collect();
+    let runs: HashMap<String, (i32, i32)> = session_runs::table
+        .filter(session_runs::session_id.eq_any(&ids))
+        .select((
+            session_runs::session_id,
+            session_runs::project_id,
+            session_runs::workspace_id,
+        ))
+        .load::<(String, i32, i32)>(conn)?
+        .into_iter()
+        .map(|(session_id, project_id, workspace_id)| (session_id, (project_id, workspace_id)))
+        .collect();
+    for info in &mut infos {
+        if let Some(&(project_id, workspace_id)) = runs.get(&info.id) {
+            info.project_id = Some(project_id);
+            info.workspace_id = Some(workspace_id);
+        }
+    }
+
+    infos.sort_by_key(|info| std::cmp::Reverse(info.started_at));
+    Ok(infos)
+}
+
+pub fn delete(recordings: &Recordings, id: &str) -> Result<(), AppError> {
+    let path = recordings.existing_path(id)?;
+    if recordings.is_active(id) {
+        return Err(AppError::conflict(format!(
+            "Recording '{id}' is still being written"
+        )));
+    }
+    fs::remove_file(&path).map_err(|e| AppError::io(format!("Failed to delete recording: {e}")))
+}
+
+pub fn read_header(path: &Path) -> Result<CastHeader, AppError> {
+    let file = File::open(path)
+        .map_err(|e| AppError::io(format!("Failed to open {}: {e}", path.display())))?;
+    let mut line = String::new();
+    BufReader::new(file).read_line(&mut line)?;
+    let header: CastHeader = serde_json::from_str(&line)?;
+    if header.version != 2 {
+        return Err(AppError::validation(format!(
+            "Unsupported asciicast version {}",
+            header.version
+        )));
+    }
+    Ok(header)
+}
+
+/// Parse an event line into its time, code and data.
+pub fn parse_event(line: &str) -> Option<(f64, String, String)> {
+    match serde_json::from_str::<Value>(line).ok()? {
+        Value::Array(parts) if parts.len() == 3 => Some((
+            parts[0].as_f64()?,
+            parts[1].as_str()?.to_string(),
+            parts[2].as_str()?.to_string(),
+        )),
+        _ => None,
+    }
+}
+
+/// Time of the last complete event, read from the end of the file.
+fn last_event_time(path: &Path) -> Option<f64> {
+    const TAIL: u64 = 64 * 1024;
+    let mut file = File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(TAIL))).ok()?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail).ok()?;
+    String::from_utf8_lossy(&tail)
+        .lines()
+        .rev()
+        .find_map(parse_event)
+        .map(|(time, _, _)| time)
+}
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+/// First line of an asciicast v2 file.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CastHeader {
+    pub version: u32,
+    pub width: u16,
+    pub height: u16,
+    /// Unix time the recording started
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<serde_json::Value>,
+}
+
+/// A saved recording, named after the session it captured.
+#[derive(Serialize, Debug, Clone)]
+pub struct RecordingInfo {
+    pub id: String,
+    pub path: String,
+    /// From the session's run, if it is still in the history
+    pub project_id: Option<i32>,
+    pub workspace_id: Option<i32>,
+    pub width: u16,
+    pub height: u16,
+    pub started_at: Option<i64>,
+    /// Time of the last event
+    pub duration_secs: f64,
+    pub size_bytes: u64,
+    /// Still being written by a running session
+    pub active: bool,
+}
@@ -0,0 +1,420 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use diesel::prelude::*;
+use serde_json::json;
+
+use crate::constants::DEFAULT_CLI;
+use crate::db::DbState;
+use crate::event_models::{EventType, NewEvent};
+use crate::event_sink::EventSink;
+use crate::events;
+use crate::project_models::{Project, ProjectStatus};
+use crate::project_status;
+use crate::retry_models::{
+    parse_exit_codes, RetryExhausted, RetryPolicy, RetryScheduled, RetryStarted, SessionRun,
+    SCOPE_PROJECT, SCOPE_WORKSPACE,
+};
+use crate::schema::{projects, retry_policies, session_runs};
+use crate::session::SessionExit;
+use crate::spawn::{self, Spawner};
+use crate::workspace_config;
+
+// A session that fails is started again under its retry policy, after a
+// backoff and once the scheduler has a slot for it. Waiting is up to the
+// host; this decides whether to retry and starts the next attempt. A retry
+// only starts if nobody touched the project while it waited.
+
+/// Project policy if one exists, otherwise the workspace policy.
+pub fn effective_policy(
+    conn: &mut SqliteConnection,
+    project_id: i32,
+    workspace_id: Option<i32>,
+) -> QueryResult<Option<RetryPolicy>> {
+    let project_policy = retry_policies::table
+        .filter(retry_policies::scope.eq(SCOPE_PROJECT))
+        .filter(retry_policies::scope_id.eq(project_id))
+        .select(RetryPolicy::as_select())
+        .first(conn)
+        .optional()?;
+
+    if project_policy.is_some() {
+        return Ok(project_policy);
+    }
+    let Some(workspace_id) = workspace_id else {
+        return Ok(None);
+    };
+
+    retry_policies::table
+        .filter(retry_policies::scope.eq(SCOPE_WORKSPACE))
+        .filter(retry_policies::scope_id.eq(workspace_id))
+        .select(RetryPolicy::as_select())
+        .first(conn)
+        .optional()
+}
+
+pub fn is_retryable(policy: &RetryPolicy, exit: &SessionExit) -> bool {
+    if exit.killed || exit.succeeded() {
+        return false;
+    }
+    if exit.signal.is_some() {
+        return policy.retry_on_signal;
+    }
+
+    let codes = parse_exit_codes(&policy.retryable_exit_codes);
+    match exit.exit_code {
+        Some(code) => codes.is_empty() || codes.contains(&(code as i32)),
+        // Couldn't reap the child — only retry when any failure counts
+        None => codes.is_empty(),
+    }
+}
+
+/// Delay before starting `attempt + 1`: exponential from `backoff_secs`, capped.
+pub fn backoff_delay(policy: &RetryPolicy, attempt: i32) -> Duration {
+    let exponent = (attempt - 1).max(0);
+    let secs =
+        f64::from(policy.backoff_secs.max(0)) * policy.backoff_multiplier.max(1.0).powi(exponent);
+    let capped = secs.min(f64::from(policy.max_backoff_secs.max(0)));
+    Duration::from_secs_f64(capped)
+}
+
+fn render_retry_prompt(template: &str, exit: &SessionExit, attempt: i32, max: i32) -> String {
+    let exit_desc = match (&exit.signal, exit.exit_code) {
+        (Some(sig), _) => format!("signal {sig}"),
+        (None, Some(code)) => code.to_string(),
+        (None, None) => "unknown".to_string(),
+    };
+    template
+        .replace("{exit_code}", &exit_desc)
+        .replace("{attempt}", &attempt.to_string())
+        .replace("{max_attempts}", &max.to_string())
+}
+
+fn set_project_status(
+    db: &DbState,
+    events: &dyn EventSink,
+    project_id: i32,
+    status: ProjectStatus,
+    reason: &str,
+) {
+    if let Err(e) = project_status::transition_and_emit(db, events, project_id, status, reason) {
+        eprintln!("[retry] {e}");
+    }
+}
+
+/// Retries waiting to start, by project. Anything the user does to the
+/// project meanwhile, like starting, stopping or moving it, cancels them.
+#[derive(Default)]
+pub struct Retries {
+    /// The ticket of each project's pending retry
+    pending: Mutex<HashMap<i32, u64>>,
+    next_ticket: AtomicU64,
+}
+
+impl Retries {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn schedule(&self, project_id: i32) -> u64 {
+        let ticket = self.next_ticket.fetch_add(1, Ordering::Relaxed);
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.insert(project_id, ticket);
+        }
+        ticket
+    }
+
+    /// Drop the project's pending retry, if it has one.
+    pub fn cancel(&self, project_id: i32) {
+        if let Ok(mut pending) = self.pending.lock() {
+            if pending.remove(&project_id).is_some() {
+                eprintln!("[retry] Cancelled the pending retry of project {project_id}");
+            }
+        }
+    }
+
+    /// Whether `ticket` is still the project's pending retry, taking it if so.
+    fn take(&self, project_id: i32, ticket: u64) -> bool {
+        let Ok(mut pending) = self.pending.lock() else {
+            return false;
+        };
+        if pending.get(&project_id) != Some(&ticket) {
+            return false;
+        }
+        pending.remove(&project_id);
+        true
+    }
+}
+
+/// The next attempt at a failed session, to start after `delay`.
+pub struct Retry {
+    pub previous_session_id: String,
+    pub exit: SessionExit,
+    pub policy: RetryPolicy,
+    /// The attempt this starts
+    pub attempt: i32,
+    pub delay: Duration,
+    /// The status the exit left the project in
+    pub status: ProjectStatus,
+    ticket: u64,
+}
+
+/// Record how a session ended and, if its policy says so, return the retry
+/// the host should start once `Retry::delay` has passed.
+pub fn on_session_exit(
+    db: &DbState,
+    events: &dyn EventSink,
+    retries: &Retries,
+    session_id: &str,
+    exit: &SessionExit,
+) -> Option<Retry> {
+    let project_id = exit.request.project_id;
+    let outcome = if exit.killed {
+        "killed"
+    } else if exit.succeeded() {
+        "succeeded"
+    } else {
+        "failed"
+    };
+
+    let (run, policy, status) = {
+        let mut conn = db.conn().ok()?;
+
+        let _ = diesel::update(session_runs::table.filter(session_runs::session_id.eq(session_id)))
+            .set((
+                session_runs::exit_code.eq(exit.exit_code.map(|c| c as i32)),
+                session_runs::exit_signal.eq(exit.signal.as_deref()),
+                session_runs::outcome.eq(outcome),
+                session_runs::ended_at.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .execute(&mut *conn);
+
+        events::record(
+            &mut conn,
+            NewEvent::new(EventType::SessionExited)
+                .workspace(exit.request.workspace_id)
+                .project(project_id)
+                .session(session_id)
+                .payload(json!({
+                    "exit_code": exit.exit_code,
+                    "signal": exit.signal,
+                    "outcome": outcome,
+                })),
+        );
+
+        if outcome != "failed" {
+            return None;
+        }
+
+        let run = session_runs::table
+            .filter(session_runs::session_id.eq(session_id))
+            .select(SessionRun::as_select())
+            .first(&mut *conn)
+            .ok();
+        let policy = match effective_policy(&mut conn, project_id, Some(exit.request.workspace_id))
+        {
+            Ok(Some(p)) if p.enabled => p,
+            _ => return None,
+        };
+        let status = projects::table
+            .filter(projects::id.eq(project_id))
+            .select(projects::status)
+            .first(&mut *conn)
+            .ok()?;
+        (run, policy, status)
+    };
+
+    if !is_retryable(&policy, exit) {
+        return None;
+    }
+
+    let attempt = run.map(|r| r.attempt).unwrap_or(1);
+    if attempt >= policy.max_attempts {
+        eprintln!("[retry] Project {project_id} gave up after {attempt} attempts");
+        if let Ok(mut conn) = db.conn() {
+            let _ =
+                diesel::update(session_runs::table.filter(session_runs::session_id.eq(session_id)))
+                    .set(session_runs::outcome.eq("gave_up"))
+                    .execute(&mut *conn);
+        }
+        set_project_status(
+            db,
+            events,
+            project_id,
+            ProjectStatus::Failed,
+            "retry attempts exhausted",
+        );
+        events.emit(
+            "session-retry-exhausted",
+            RetryExhausted {
+                project_id,
+                session_id: session_id.to_string(),
+                attempts: attempt,
+                error: None,
+            },
+        );
+        return None;
+    }
+
+    let delay = backoff_delay(&policy, attempt);
+    eprintln!(
+        "[retry] Project {project_id} attempt {attempt} failed, retrying in {}s",
+        delay.as_secs()
+    );
+    if let Ok(mut conn) = db.conn() {
+        events::record(
+            &mut conn,
+            NewEvent::new(EventType::SessionRetryScheduled)
+                .workspace(exit.request.workspace_id)
+                .project(project_id)
+                .session(session_id)
+                .payload(json!({
+                    "next_attempt": attempt + 1,
+                    "max_attempts": policy.max_attempts,
+                    "delay_secs": delay.as_secs(),
+                })),
+        );
+    }
+    events.emit(
+        "session-retry-scheduled",
+        RetryScheduled {
+            project_id,
+            session_id: session_id.to_string(),
+            next_attempt: attempt + 1,
+            max_attempts: policy.max_attempts,
+            delay_secs: delay.as_secs(),
+        },
+    );
+
+    Some(Retry {
+        previous_session_id: session_id.to_string(),
+        exit: exit.clone(),
+        policy,
+        attempt: attempt + 1,
+        delay,
+        status,
+        ticket: retries.schedule(project_id),
+    })
+}
+
+/// Start a retry whose backoff has passed, once it has a slot, unless it was
+/// cancelled or the project's status changed meanwhile. Returns the new
+/// session, which nothing streams yet.
+pub fn respawn(spawner: &Spawner, retries: &Retries, retry: &Retry) -> Option<String> {
+    let Retry {
+        previous_session_id,
+        exit,
+        policy,
+        attempt,
+        ..
+    } = retry;
+    let (attempt, project_id) = (*attempt, exit.request.project_id);
+    if !retries.take(project_id, retry.ticket) {
+        return None;
+    }
+
+    let project = {
+        let mut conn = spawner.db.conn().ok()?;
+        projects::table
+            .filter(projects::id.eq(project_id))
+            .select(Project::as_select())
+            .first(&mut *conn)
+            .ok()?
+    };
+
+    // Moved along while we were backing off — the user took over
+    if project.status != retry.status {
+        eprintln!(
+            "[retry] Not retrying project {project_id}: it's {} now",
+            project.status
+        );
+        return None;
+    }
+
+    let retry_prompt = policy
+        .retry_prompt
+        .as_deref()
+        .map(|t| render_retry_prompt(t, exit, attempt - 1, policy.max_attempts));
+
+    let mut request = exit.request.clone();
+    let resumable = request.cli_command.as_deref().unwrap_or(DEFAULT_CLI) == DEFAULT_CLI;
+    if resumable {
+        // Pick the conversation back up with `--continue` rather than
+        // replaying the original prompt. The agent's own session id isn't
+        // known here, so this resumes the last conversation in the
+        // working directory
+        request.resume_session_id = None;
+        request.continue_last = true;
+        request.initial_prompt = retry_prompt;
+    } else {
+        request.resume_session_id = None;
+        request.continue_last = false;
+        request.initial_prompt = match (request.initial_prompt.take(), retry_prompt) {
+            (Some(original), Some(extra)) => Some(format!("{original}\n\n{extra}")),
+            (original, extra) => original.or(extra),
+        };
+    }
+
+    let spawned = spawner
+        .db
+        .conn()
+        .and_then(|mut conn| workspace_config::load(&mut conn, request.workspace_id))
+        .and_then(|config| spawner.launch(&config, &mut request));
+    let session_id = match spawned {
+        Ok(id) => id,
+        Err(e) => {
+            eprintln!("[retry] Failed to respawn project {project_id}: {e}");
+            set_project_status(
+                spawner.db,
+                spawner.events,
+                project_id,
+                ProjectStatus::Failed,
+                "retry failed to spawn",
+            );
+            spawner.events.emit(
+                "session-retry-exhausted",
+                RetryExhausted {
+                    project_id,
+                    session_id: previous_session_id.clone(),
+                    attempts: attempt - 1,
+                    error: Some(e.to_string()),
+                },
+            );
+            return None;
+        }
+    };
+
+    if let Ok(mut conn) = spawner.db.conn() {
+        let _ = diesel::update(
+            session_runs::table.filter(session_runs::session_id.eq(previous_session_id)),
+        )
+        .set(session_runs::outcome.eq("retried"))
+        .execute(&mut *conn);
+        let _ = spawn::record(&mut conn, &session_id, &request, attempt);
+        let _ = diesel::update(projects::table.filter(projects::id.eq(project_id)))
+            .set(projects::iteration_count.eq(projects::iteration_count + 1))
+            .execute(&mut *conn);
+    }
+
+    set_project_status(
+        spawner.db,
+        spawner.events,
+        project_id,
+        ProjectStatus::Running,
+        &format!("retry attempt {attempt}"),
+    );
+
+    spawner.events.emit(
+        "session-retry-started",
+        RetryStarted {
+            project_id,
+            previous_session_id: previous_session_id.clone(),
+            session_id: session_id.clone(),
+            attempt,
+            resumed: request.resume_session_id.is_some() || request.continue_last,
+        },
+    );
+    Some(session_id)
+}
@@ -0,0 +1,141 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::schema::{retry_policies, session_runs};
+
+pub const SCOPE_WORKSPACE: &str = "workspace";
+pub const SCOPE_PROJECT: &str = "project";
+
+fn serialize_exit_codes<S: Serializer>(raw: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    parse_exit_codes(raw).serialize(serializer)
+}
+
+fn serialize_env<S: Serializer>(raw: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    serde_json::from_str::<BTreeMap<String, String>>(raw)
+        .unwrap_or_default()
+        .serialize(serializer)
+}
+
+pub fn parse_exit_codes(raw: &str) -> Vec<i32> {
+    serde_json::from_str(raw).unwrap_or_default()
+}
+
+#[derive(Queryable, Selectable, Serialize, Debug, Clone)]
+#[diesel(table_name = retry_policies)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct RetryPolicy {
+    pub id: i32,
+    pub scope: String,
+    pub scope_id: i32,
+    pub enabled: bool,
+    pub max_attempts: i32,
+    pub backoff_secs: i32,
+    pub backoff_multiplier: f64,
+    pub max_backoff_secs: i32,
+    /// Exit codes that trigger a retry; empty means any non-zero code
+    #[serde(serialize_with = "serialize_exit_codes")]
+    pub retryable_exit_codes: String,
+    pub retry_on_signal: bool,
+    pub retry_prompt: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// Policy fields as sent by the frontend.
+#[derive(Deserialize, Debug, Clone)]
+pub struct RetryPolicyInput {
+    pub scope: String,
+    pub scope_id: i32,
+    pub enabled: Option<bool>,
+    pub max_attempts: Option<i32>,
+    pub backoff_secs: Option<i32>,
+    pub backoff_multiplier: Option<f64>,
+    pub max_backoff_secs: Option<i32>,
+    pub retryable_exit_codes: Option<Vec<i32>>,
+    pub retry_on_signal: Option<bool>,
+    pub retry_prompt: Option<String>,
+}
+
+#[derive(Insertable, AsChangeset)]
+#[diesel(table_name = retry_policies)]
+#[diesel(treat_none_as_null = true)]
+pub struct NewRetryPolicy<'a> {
+    pub scope: &'a str,
+    pub scope_id: i32,
+    pub enabled: bool,
+    pub max_attempts: i32,
+    pub backoff_secs: i32,
+    pub backoff_multiplier: f64,
+    pub max_backoff_secs: i32,
+    pub retryable_exit_codes: String,
+    pub retry_on_signal: bool,
+    pub retry_prompt: Option<&'a str>,
+}
+
+#[derive(Queryable, Selectable, Serialize, Debug, Clone)]
+#[diesel(table_name = session_runs)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct SessionRun {
+    pub id: i32,
+    pub session_id: String,
+    pub project_id: i32,
+    pub workspace_id: i32,
+    pub attempt: i32,
+    pub resumed: bool,
+    pub exit_code: Option<i32>,
+    pub exit_signal: Option<String>,
+    pub outcome: String,
+    pub started_at: NaiveDateTime,
+    pub ended_at: Option<NaiveDateTime>,
+    /// Variables the session was started with, secrets redacted
+    #[serde(serialize_with = "serialize_env")]
+    pub env: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = session_runs)]
+pub struct NewSessionRun<'a> {
+    pub session_id: &'a str,
+    pub project_id: i32,
+    pub workspace_id: i32,
+    pub attempt: i32,
+    pub resumed: bool,
+    pub env: &'a str,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SessionExited {
+    pub exit_code: Option<u32>,
+    pub signal: Option<String>,
+    /// A retry policy will respawn this project after a backoff
+    pub will_retry: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RetryScheduled {
+    pub project_id: i32,
+    pub session_id: String,
+    pub next_attempt: i32,
+    pub max_attempts: i32,
+    pub delay_secs: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RetryStarted {
+    pub project_id: i32,
+    pub previous_session_id: String,
+    pub session_id: String,
+    pub attempt: i32,
+    pub resumed: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RetryExhausted {
+    pub project_id: i32,
+    pub session_id: String,
+    pub attempts: i32,
+    pub error: Option<String>,
+}
@@ -0,0 +1,36 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use serde::Serialize;
+
+use crate::schema::review_comments;
+
+/// Feedback on a project's changes, optionally pinned to a line.
+#[derive(Queryable, Selectable, Serialize, Debug, Clone)]
+#[diesel(table_name = review_comments)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ReviewComment {
+    pub id: i32,
+    pub project_id: i32,
+    pub file_path: Option<String>,
+    pub line: Option<i32>,
+    pub body: String,
+    pub resolved: bool,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = review_comments)]
+pub struct NewReviewComment {
+    pub project_id: i32,
+    pub file_path: Option<String>,
+    pub line: Option<i32>,
+    pub body: String,
+}
+
+/// Payload of `review-requested`.
+#[derive(Serialize, Clone, Debug)]
+pub struct ReviewRequested {
+    pub project_id: i32,
+    pub session_id: Option<String>,
+    pub summary: String,
+}
@@ -0,0 +1,98 @@
+use diesel::prelude::*;
+use serde_json::json;
+
+use crate::error::{AppError, ResultExt};
+use crate::event_models::{EventType, NewEvent};
+use crate::events;
+use crate::projects;
+use crate::review_models::{NewReviewComment, ReviewComment};
+use crate::schema::review_comments;
+
+pub fn add_comment(
+    conn: &mut SqliteConnection,
+    comment: &NewReviewComment,
+) -> Result<ReviewComment, AppError> {
+    if comment.body.trim().is_empty() {
+        return Err(AppError::validation("Comment is empty"));
+    }
+    let project = projects::get(conn, comment.project_id)?;
+
+    let added = diesel::insert_into(review_comments::table)
+        .values(comment)
+        .returning(ReviewComment::as_returning())
+        .get_result(conn)
+        .context("Failed to add comment")?;
+
+    events::record(
+        conn,
+        NewEvent::new(EventType::ReviewCommentAdded)
+            .workspace(project.workspace_id)
+            .project(project.id)
+            .payload(json!({
+                "comment_id": added.id,
+                "file_path": added.file_path,
+                "line": added.line,
+            })),
+    );
+
+    Ok(added)
+}
+
+/// A project's comments, oldest first. Resolved ones only if asked for.
+pub fn list(
+    conn: &mut SqliteConnection,
+    project_id: i32,
+    include_resolved: bool,
+) -> Result<Vec<ReviewComment>, AppError> {
+    let mut query = review_comments::table
+        .filter(review_comments::project_id.eq(project_id))
+        .into_boxed();
+    if !include_resolved {
+        query = query.filter(review_comments::resolved.eq(false));
+    }
+    query
+        .order(review_comments::id.asc())
+        .select(ReviewComment::as_select())
+        .load(conn)
+        .context("Failed to list comments")
+}
+
+pub fn set_resolved(
+    conn: &mut SqliteConnection,
+    comment_id: i32,
+    resolved: bool,
+) -> Result<ReviewComment, AppError> {
+    let updated = diesel::update(review_comments::table.find(comment_id))
+        .set(review_comments::resolved.eq(resolved))
+        .execute(conn)
+        .context("Failed to update comment")?;
+    if updated == 0 {
+        return Err(AppError::not_found(format!(
+            "Comment with id {comment_id} not found"
+        )));
+    }
+
+    review_comments::table
+        .find(comment_id)
+        .select(ReviewComment::as_select())
+        .first(conn)
+        .context("Failed to fetch comment")
+}
+
+/// Note that the project's agent asked for its work to be reviewed. Moving
+/// the project to waiting is up to the caller.
+pub fn request(
+    conn: &mut SqliteConnection,
+    project_id: i32,
+    session_id: Option<&str>,
+    summary: &str,
+) -> Result<(), AppError> {
+    let project = projects::get(conn, project_id)?;
+    let mut event = NewEvent::new(EventType::ReviewRequested)
+        .workspace(project.workspace_id)
+        .project(project_id)
+        .payload(json!({ "summary": summary.trim() }));
+    event.session_id = session_id;
+    events::record(conn, event);
+    Ok(())
+}
@@ -0,0 +1,671 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use diesel::prelude::*;
+use serde_json::json;
+
+use crate::env_profiles;
+use crate::error::{AppError, ResultExt};
+use crate::event_models::{EventType, NewEvent};
+use crate::events;
+use crate::project_models::ProjectStatus;
+use crate::project_status::{self, Transition, TransitionError};
+use crate::scheduler_models::{
+    NewQueuedSpawn, QueueItemFailed, QueueItemStarted, QueueState, QueuedSpawn,
+};
+use crate::schema::spawn_queue;
+use crate::session::{SessionManager, SpawnRequest};
+use crate::settings_registry;
+use crate::spawn::{self, Spawner};
+use crate::workspace_config::{self, WorkspaceConfig};
+
+// The scheduler sits in front of `SessionManager::spawn` and starts queued
+// sessions as soon as the global and per-workspace limits allow. Slots are
+// handed out under a lock; the starts themselves are returned to the host
+// to run off it, so a slow pre-spawn hook doesn't hold up other pumps.
+
+pub const QUEUE_MODE_FIFO: &str = "fifo";
+pub const QUEUE_MODE_PRIORITY: &str = "priority";
+
+/// Settings that change what the queue may start.
+pub const QUEUE_SETTINGS: &[&str] = &[
+    "queue_paused",
+    "queue_mode",
+    "max_concurrent_sessions",
+    "max_sessions_per_workspace",
+];
+
+/// Limits and mode from the settings registry. A limit of 0 means unlimited.
+pub struct QueueSettings {
+    pub paused: bool,
+    pub mode: String,
+    pub max_concurrent_sessions: u32,
+    pub max_sessions_per_workspace: u32,
+}
+
+pub fn load_queue_settings(conn: &mut SqliteConnection) -> QueueSettings {
+    let limit = |conn: &mut SqliteConnection, key: &str| {
+        settings_registry::get_int(conn, key)
+            .ok()
+            .flatten()
+            .and_then(|n| u32::try_from(n).ok())
+            .unwrap_or(0)
+    };
+
+    QueueSettings {
+        paused: settings_registry::get_bool(conn, "queue_paused").unwrap_or(false),
+        mode: settings_registry::get_text(conn, "queue_mode")
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| QUEUE_MODE_FIFO.to_string()),
+        max_concurrent_sessions: limit(conn, "max_concurrent_sessions"),
+        max_sessions_per_workspace: limit(conn, "max_sessions_per_workspace"),
+    }
+}
+
+/// Pending spawn requests in the order they should be started.
+pub fn load_pending(conn: &mut SqliteConnection, mode: &str) -> QueryResult<Vec<QueuedSpawn>> {
+    let query = spawn_queue::table
+        .filter(spawn_queue::status.eq("pending"))
+        .select(QueuedSpawn::as_select());
+
+    if mode == QUEUE_MODE_PRIORITY {
+        query
+            .order((spawn_queue::priority.desc(), spawn_queue::id.asc()))
+            .load(conn)
+    } else {
+        query.order(spawn_queue::id.asc()).load(conn)
+    }
+}
+
+pub fn queue_state(
+    conn: &mut SqliteConnection,
+    session_mgr: &SessionManager,
+) -> QueryResult<QueueState> {
+    let settings = load_queue_settings(conn);
+    let pending: i64 = spawn_queue::table
+        .filter(spawn_queue::status.eq("pending"))
+        .count()
+        .get_result(conn)?;
+
+    Ok(QueueState {
+        paused: settings.paused,
+        mode: settings.mode,
+        max_concurrent_sessions: settings.max_concurrent_sessions,
+        max_sessions_per_workspace: settings.max_sessions_per_workspace,
+        pending: pending as u32,
+        running: session_mgr.running_count(),
+    })
+}
+
+/// Queue a spawn request and mark its project Queued, in one transaction.
+pub fn enqueue(
+    conn: &mut SqliteConnection,
+    item: &NewQueuedSpawn,
+) -> Result<(QueuedSpawn, Transition), AppError> {
+    conn.immediate_transaction(|conn| {
+        let transition = project_status::transition(
+            conn,
+            item.project_id,
+            ProjectStatus::Queued,
+            "queued for spawn",
+        )?;
+
+        let queued = diesel::insert_into(spawn_queue::table)
+            .values(item)
+            .returning(QueuedSpawn::as_returning())
+            .get_result(conn)
+            .context("Failed to enqueue session")?;
+
+        events::record(
+            conn,
+            NewEvent::new(EventType::SessionQueued)
+                .workspace(item.workspace_id)
+                .project(item.project_id)
+                .payload(json!({ "queue_id": queued.id, "priority": queued.priority })),
+        );
+
+        Ok((queued, transition))
+    })
+}
+
+/// Take a pending item off the queue and put its project back to Waiting,
+/// in one transaction. An item that's already starting can't be cancelled.
+pub fn cancel(conn: &mut SqliteConnection, queue_id: i32) -> Result<Transition, AppError> {
+    conn.immediate_transaction(|conn| {
+        let item = diesel::update(
+            spawn_queue::table
+                .filter(spawn_queue::id.eq(queue_id))
+                .filter(spawn_queue::status.eq("pending")),
+        )
+        .set(spawn_queue::status.eq("cancelled"))
+        .returning(QueuedSpawn::as_returning())
+        .get_result(conn)
+        .optional()
+        .context("Failed to cancel queued session")?
+        .ok_or_else(|| AppError::not_found(format!("Pending queue item {queue_id} not found")))?;
+
+        Ok(project_status::transition(
+            conn,
+            item.project_id,
+            ProjectStatus::Waiting,
+            "removed from queue",
+        )?)
+    })
+}
+
+/// Hands out session slots under the global and per-workspace limits.
+pub struct Scheduler {
+    pump_lock: Mutex<()>,
+    /// Starts waiting for a slot ahead of the queue, in the order they
+    /// came in
+    waiting: Mutex<Vec<Waiting>>,
+    starting: Arc<Mutex<Starting>>,
+}
+
+/// Slots handed to starts still running their pre-spawn hooks. They count
+/// as running sessions until the start finishes.
+#[derive(Default)]
+struct Starting {
+    /// Slots held per workspace
+    workspaces: HashMap<i32, u32>,
+    /// Projects being started, which can't be started again meanwhile
+    projects: HashSet<i32>,
+}
+
+/// Work that has to wait for a slot but isn't a queue item, like a retry
+/// whose backoff has passed.
+struct Waiting {
+    project_id: i32,
+    workspace_id: i32,
+    start: Box<dyn FnOnce() + Send>,
+}
+
+/// A slot held for a start in progress, freed when it drops. The host pumps
+/// again afterwards, since a start that failed leaves room behind.
+pub struct Slot {
+    starting: Arc<Mutex<Starting>>,
+    project_id: i32,
+    workspace_id: i32,
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        if let Ok(mut starting) = self.starting.lock() {
+            starting.projects.remove(&self.project_id);
+            if let Some(count) = starting.workspaces.get_mut(&self.workspace_id) {
+                *count = count.saturating_sub(1);
+                if *count == 0 {
+                    starting.workspaces.remove(&self.workspace_id);
+                }
+            }
+        }
+    }
+}
+
+/// Work a pump has given a slot, for the host to run off the pump lock.
+pub enum Start {
+    /// A start that was waiting for room
+    Waiting {
+        start: Box<dyn FnOnce() + Send>,
+        slot: Slot,
+    },
+    /// A queue item whose project has been claimed
+    Queued(Box<Claim>),
+}
+
+impl Start {
+    /// Run the start, blocking for as long as its pre-spawn hooks do. A
+    /// queue item that starts returns its session, which nothing streams
+    /// yet. The slot is freed once this returns.
+    pub fn run(self, spawner: &Spawner) -> Option<String> {
+        match self {
+            Start::Waiting { start, slot: _slot } => {
+                start();
+                None
+            }
+            Start::Queued(claim) => claim.launch(spawner),
+        }
+    }
+}
+
+/// A queue item claimed by a pump, holding its slot until it launches.
+pub struct Claim {
+    item: QueuedSpawn,
+    config: WorkspaceConfig,
+    transition: Transition,
+    _slot: Slot,
+}
+
+impl Claim {
+    fn launch(self, spawner: &Spawner) -> Option<String> {
+        let mut request = self.item.to_spawn_request();
+        let spawned = spawner
+            .db
+            .conn()
+            .and_then(|mut conn| env_profiles::apply_project(&mut conn, &mut request))
+            .and_then(|_| {
+                self.config.apply(&mut request);
+                spawner.launch(&self.config, &mut request)
+            });
+        match spawned {
+            Ok(session_id) => {
+                project_status::emit_change(spawner.events, &self.transition);
+                // Record the run before streaming so a fast exit can find it
+                mark_started(spawner, &self.item, &request, &session_id);
+                Some(session_id)
+            }
+            Err(e) => {
+                mark_failed(spawner, &self.item, e.to_string());
+                None
+            }
+        }
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self {
+            pump_lock: Mutex::new(()),
+            waiting: Mutex::new(Vec::new()),
+            starting: Arc::default(),
+        }
+    }
+
+    /// Hand `start` to a pump once `workspace_id` has a free slot. It goes
+    /// ahead of the queue, and a paused queue holds it too. It's dropped if
+    /// the project has been started some other way meanwhile.
+    pub fn start_when_room(
+        &self,
+        project_id: i32,
+        workspace_id: i32,
+        start: impl FnOnce() + Send + 'static,
+    ) {
+        if let Ok(mut waiting) = self.waiting.lock() {
+            waiting.push(Waiting {
+                project_id,
+                workspace_id,
+                start: Box::new(start),
+            });
+        }
+    }
+
+    /// Give slots to as many waiting starts and pending requests as the
+    /// limits allow and return them to be run. Called after enqueueing,
+    /// when a session exits and when the queue is resumed.
+    pub fn pump(&self, spawner: &Spawner) -> Vec<Start> {
+        // Serialize pumps so two exits at once can't both claim the last slot
+        let _guard = match self.pump_lock.lock() {
+            Ok(g) => g,
+            Err(_) => return Vec::new(),
+        };
+
+        let (settings, pending) = {
+            let mut conn = match spawner.db.conn() {
+                Ok(c) => c,
+                Err(_) => return Vec::new(),
+            };
+            let settings = load_queue_settings(&mut conn);
+            if settings.paused {
+                return Vec::new();
+            }
+            match load_pending(&mut conn, &settings.mode) {
+                Ok(p) => (settings, p),
+                Err(e) => {
+                    eprintln!("[scheduler] Failed to load queue: {e}");
+                    return Vec::new();
+                }
+            }
+        };
+
+        let mut configs: HashMap<i32, WorkspaceConfig> = HashMap::new();
+        let mut starts = self.start_waiting(spawner, &settings, &mut configs);
+
+        for item in pending {
+            if !self.has_room(spawner.sessions, &settings, None) {
+                break;
+            }
+
+            // A broken config file fails the item rather than silently
+            // starting with the wrong settings
+            let config = match load_config(spawner, &mut configs, item.workspace_id) {
+                Ok(config) => config,
+                Err(e) => {
+                    mark_failed(spawner, &item, e.to_string());
+                    continue;
+                }
+            };
+
+            // Skip, don't stop: other workspaces may still have room
+            if !self.has_room(spawner.sessions, &settings, Some(&config)) {
+                continue;
+            }
+
+            if let Some(why) = self.busy(spawner.sessions, item.project_id) {
+                mark_dropped(spawner, &item, format!("Project {why}"));
+                continue;
+            }
+
+            // Claim the item and its project before letting go of the lock,
+            // so the next pump doesn't load it again while it launches. An
+            // item whose project was archived while it waited is dropped
+            let claimed = match spawner.db.conn() {
+                Ok(mut conn) => claim(&mut conn, &item),
+                Err(_) => break,
+            };
+            let transition = match claimed {
+                Ok(Some(t)) => t,
+                Ok(None) => continue,
+                Err(e) => {
+                    mark_dropped(spawner, &item, e.to_string());
+                    continue;
+                }
+            };
+
+            starts.push(Start::Queued(Box::new(Claim {
+                _slot: self.reserve(item.project_id, item.workspace_id),
+                item,
+                config,
+                transition,
+            })));
+        }
+        starts
+    }
+
+    /// Start `request` right away, the way the UI and the command line do.
+    /// It takes a slot under the limits and claims the project, or fails
+    /// with a conflict if either is taken. Unlike the queue it doesn't
+    /// wait, and a paused queue doesn't stop it. Blocks for as long as the
+    /// pre-spawn hooks run.
+    pub fn start_now(
+        &self,
+        spawner: &Spawner,
+        mut request: SpawnRequest,
+        reason: &str,
+    ) -> Result<String, AppError> {
+        env_profiles::validate_vars(&request.env)?;
+        let config = spawn::prepare(&mut *spawner.db.conn()?, &mut request)?;
+        let project_id = request.project_id;
+
+        // Held until the session is up, when it counts as running itself
+        let _slot = self.try_reserve(spawner, project_id, &config)?;
+        let transition = project_status::transition(
+            &mut *spawner.db.conn()?,
+            project_id,
+            ProjectStatus::Running,
+            reason,
+        )?;
+        let session_id = match spawner.launch(&config, &mut request) {
+            Ok(session_id) => session_id,
+            Err(e) => {
+                let reason = format!("session failed to start: {e}");
+                if let Err(failed) = project_status::transition_and_emit(
+                    spawner.db,
+                    spawner.events,
+                    project_id,
+                    ProjectStatus::Failed,
+                    &reason,
+                ) {
+                    eprintln!("[scheduler] Project {project_id} not marked failed: {failed}");
+                }
+                return Err(e);
+            }
+        };
+        project_status::emit_change(spawner.events, &transition);
+
+        let mut conn = spawner.db.conn()?;
+        spawn::record(&mut conn, &session_id, &request, 1).context("Failed to record session")?;
+        Ok(session_id)
+    }
+
+    /// Take a slot for a start made outside the queue, or a conflict if the
+    /// project is already running or starting, or the limits leave none.
+    /// Checked and taken under the pump lock, so two starts can't both get
+    /// the last slot or the same project.
+    fn try_reserve(
+        &self,
+        spawner: &Spawner,
+        project_id: i32,
+        config: &WorkspaceConfig,
+    ) -> Result<Slot, AppError> {
+        let settings = load_queue_settings(&mut *spawner.db.conn()?);
+        let _guard = self.pump_lock.lock()?;
+        if let Some(why) = self.busy(spawner.sessions, project_id) {
+            return Err(AppError::conflict(format!("Project {project_id} {why}"))
+                .with_details(json!({ "project_id": project_id })));
+        }
+        if !self.has_room(spawner.sessions, &settings, Some(config)) {
+            return Err(
+                AppError::conflict("No free session slot; the session limit is reached")
+                    .with_details(json!({
+                        "workspace_id": config.workspace_id,
+                        "max_concurrent_sessions": settings.max_concurrent_sessions,
+                        "max_sessions": config.max_sessions,
+                    })),
+            );
+        }
+        Ok(self.reserve(project_id, config.workspace_id))
+    }
+
+    /// Why a project can't be started again, if it can't: it has a running
+    /// session or a slot is held for it.
+    fn busy(&self, sessions: &SessionManager, project_id: i32) -> Option<String> {
+        let starting = self
+            .starting
+            .lock()
+            .is_ok_and(|starting| starting.projects.contains(&project_id));
+        if starting {
+            return Some("is already starting".to_string());
+        }
+        sessions
+            .running_for_project(project_id)
+            .map(|session_id| format!("is already running as {session_id}"))
+    }
+
+    /// Hold a slot in `workspace_id` for `project_id` until the returned
+    /// slot drops.
+    fn reserve(&self, project_id: i32, workspace_id: i32) -> Slot {
+        if let Ok(mut starting) = self.starting.lock() {
+            *starting.workspaces.entry(workspace_id).or_default() += 1;
+            starting.projects.insert(project_id);
+        }
+        Slot {
+            starting: self.starting.clone(),
+            project_id,
+            workspace_id,
+        }
+    }
+
+    /// Whether another session may start: under the global limit and, given
+    /// its config, the workspace's. Starts still in progress count too.
+    fn has_room(
+        &self,
+        sessions: &SessionManager,
+        settings: &QueueSettings,
+        config: Option<&WorkspaceConfig>,
+    ) -> bool {
+        let starting = match self.starting.lock() {
+            Ok(starting) => starting.workspaces.clone(),
+            Err(_) => return false,
+        };
+        if settings.max_concurrent_sessions > 0
+            && sessions.running_count() + starting.values().sum::<u32>()
+                >= settings.max_concurrent_sessions
+        {
+            return false;
+        }
+        match config {
+            Some(config) if config.max_sessions > 0 => {
+                let workspace_id = config.workspace_id;
+                sessions.running_count_for_workspace(workspace_id)
+                    + starting.get(&workspace_id).copied().unwrap_or(0)
+                    < config.max_sessions
+            }
+            _ => true,
+        }
+    }
+
+    /// Give the waiting starts that have room a slot, keeping the rest in
+    /// order.
+    fn start_waiting(
+        &self,
+        spawner: &Spawner,
+        settings: &QueueSettings,
+        configs: &mut HashMap<i32, WorkspaceConfig>,
+    ) -> Vec<Start> {
+        let waiting = match self.waiting.lock() {
+            Ok(mut waiting) => std::mem::take(&mut *waiting),
+            Err(_) => return Vec::new(),
+        };
+        let mut starts = Vec::new();
+        let mut still_waiting = Vec::new();
+        for entry in waiting {
+            if let Some(why) = self.busy(spawner.sessions, entry.project_id) {
+                eprintln!(
+                    "[scheduler] Dropping a waiting start: project {} {why}",
+                    entry.project_id
+                );
+                continue;
+            }
+            // Without a config there's no workspace limit to hold it to;
+            // the start reports the broken file itself
+            let config = load_config(spawner, configs, entry.workspace_id).ok();
+            if self.has_room(spawner.sessions, settings, config.as_ref()) {
+                starts.push(Start::Waiting {
+                    slot: self.reserve(entry.project_id, entry.workspace_id),
+                    start: entry.start,
+                });
+            } else {
+                still_waiting.push(entry);
+            }
+        }
+        if let Ok(mut waiting) = self.waiting.lock() {
+            // Anything added meanwhile goes after what was already waiting
+            still_waiting.append(&mut waiting);
+            *waiting = still_waiting;
+        }
+        starts
+    }
+}
+
+/// Move a pending item to `starting` and its project to Running in one
+/// transaction. `None` if the item was no longer pending.
+fn claim(
+    conn: &mut SqliteConnection,
+    item: &QueuedSpawn,
+) -> Result<Option<Transition>, TransitionError> {
+    conn.immediate_transaction(|conn| {
+        let taken = diesel::update(
+            spawn_queue::table
+                .filter(spawn_queue::id.eq(item.id))
+                .filter(spawn_queue::status.eq("pending")),
+        )
+        .set(spawn_queue::status.eq("starting"))
+        .execute(conn)?;
+        if taken == 0 {
+            return Ok(None);
+        }
+
+        project_status::transition(
+            conn,
+            item.project_id,
+            ProjectStatus::Running,
+            "started by scheduler",
+        )
+        .map(Some)
+    })
+}
+
+/// Put items a previous run claimed but never started back in the queue.
+/// Their projects were already claimed, so they start as before.
+pub fn requeue_interrupted(conn: &mut SqliteConnection) -> QueryResult<usize> {
+    diesel::update(spawn_queue::table.filter(spawn_queue::status.eq("starting")))
+        .set(spawn_queue::status.eq("pending"))
+        .execute(conn)
+}
+
+fn mark_started(spawner: &Spawner, item: &QueuedSpawn, request: &SpawnRequest, session_id: &str) {
+    eprintln!(
+        "[scheduler] Started queued item {} for project {} as {session_id}",
+        item.id, item.project_id
+    );
+    if let Ok(mut conn) = spawner.db.conn() {
+        let _ = spawn::record(&mut conn, session_id, request, 1);
+        let _ = diesel::update(spawn_queue::table.filter(spawn_queue::id.eq(item.id)))
+            .set((
+                spawn_queue::status.eq("started"),
+                spawn_queue::session_id.eq(session_id),
+                spawn_queue::started_at.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .execute(&mut *conn);
+    }
+
+    spawner.events.emit(
+        "queue-item-started",
+        QueueItemStarted {
+            queue_id: item.id,
+            project_id: item.project_id,
+            workspace_id: item.workspace_id,
+            session_id: session_id.to_string(),
+        },
+    );
+}
+
+fn mark_failed(spawner: &Spawner, item: &QueuedSpawn, error: String) {
+    eprintln!(
+        "[scheduler] Queued item {} failed to start: {error}",
+        item.id
+    );
+    finish_item(spawner, item, "failed", &error);
+    if let Err(e) = project_status::transition_and_emit(
+        spawner.db,
+        spawner.events,
+        item.project_id,
+        ProjectStatus::Failed,
+        "queued session failed to start",
+    ) {
+        eprintln!("[scheduler] {e}");
+    }
+
+    spawner.events.emit(
+        "queue-item-failed",
+        QueueItemFailed {
+            queue_id: item.id,
+            project_id: item.project_id,
+            error,
+        },
+    );
+}
+
+fn mark_dropped(spawner: &Spawner, item: &QueuedSpawn, error: String) {
+    eprintln!("[scheduler] Dropping queued item {}: {error}", item.id);
+    finish_item(spawner, item, "cancelled", &error);
+}
+
+fn finish_item(spawner: &Spawner, item: &QueuedSpawn, status: &str, error: &str) {
+    if let Ok(mut conn) = spawner.db.conn() {
+        let _ = diesel::update(spawn_queue::table.filter(spawn_queue::id.eq(item.id)))
+            .set((spawn_queue::status.eq(status), spawn_queue::error.eq(error)))
+            .execute(&mut *conn);
+    }
+}
+
+/// A workspace's config, loaded once per pump.
+fn load_config(
+    spawner: &Spawner,
+    configs: &mut HashMap<i32, WorkspaceConfig>,
+    workspace_id: i32,
+) -> Result<WorkspaceConfig, AppError> {
+    if let Some(config) = configs.get(&workspace_id) {
+        return Ok(config.clone());
+    }
+    let config = workspace_config::load(&mut *spawner.db.conn()?, workspace_id)?;
+    configs.insert(workspace_id, config.clone());
+    Ok(config)
+}
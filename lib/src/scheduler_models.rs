@@ -0,0 +1,86 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::schema::spawn_queue;
+use crate::session::SpawnRequest;
+
+#[derive(Queryable, Selectable, Serialize, Debug, Clone)]
+#[diesel(table_name = spawn_queue)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct QueuedSpawn {
+    pub id: i32,
+    pub project_id: i32,
+    pub workspace_id: i32,
+    pub working_dir: String,
+    pub initial_prompt: Option<String>,
+    pub cli_command: Option<String>,
+    pub model: Option<String>,
+    pub rows: i32,
+    pub cols: i32,
+    pub priority: i32,
+    pub status: String,
+    pub session_id: Option<String>,
+    pub error: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub started_at: Option<NaiveDateTime>,
+}
+
+impl QueuedSpawn {
+    pub fn to_spawn_request(&self) -> SpawnRequest {
+        SpawnRequest {
+            project_id: self.project_id,
+            workspace_id: self.workspace_id,
+            working_dir: self.working_dir.clone(),
+            initial_prompt: self.initial_prompt.clone(),
+            cli_command: self.cli_command.clone(),
+            model: self.model.clone(),
+            rows: self.rows as u16,
+            cols: self.cols as u16,
+            resume_session_id: None,
+            continue_last: false,
+            env: Default::default(),
+            secret_env: Default::default(),
+            persistent: false,
+        }
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = spawn_queue)]
+pub struct NewQueuedSpawn<'a> {
+    pub project_id: i32,
+    pub workspace_id: i32,
+    pub working_dir: &'a str,
+    pub initial_prompt: Option<&'a str>,
+    pub cli_command: Option<&'a str>,
+    pub model: Option<&'a str>,
+    pub rows: i32,
+    pub cols: i32,
+    pub priority: i32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QueueItemStarted {
+    pub queue_id: i32,
+    pub project_id: i32,
+    pub workspace_id: i32,
+    pub session_id: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QueueItemFailed {
+    pub queue_id: i32,
+    pub project_id: i32,
+    pub error: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QueueState {
+    pub paused: bool,
+    pub mode: String,
+    pub max_concurrent_sessions: u32,
+    pub max_sessions_per_workspace: u32,
+    pub pending: u32,
+    pub running: u32,
+}
@@ -0,0 +1,200 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use rand::RngCore;
+
+use crate::error::AppError;
+
+/// Passphrase the file's key is derived from. The file backend can't be
+/// used without it; the key itself is never written anywhere.
+pub const KEY_ENV: &str = "DACM_SECRETS_KEY";
+
+const FILE_NAME: &str = "secrets.bin";
+
+/// Format marker and version at the start of the file.
+const MAGIC: &[u8; 6] = b"DACMS2";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+/// Secrets for machines without a keyring, as one encrypted JSON map.
+///
+/// Layout: magic, Argon2id salt, 24-byte nonce, then the map sealed with
+/// XChaCha20-Poly1305 under a key derived from the `DACM_SECRETS_KEY`
+/// passphrase. Magic and salt are authenticated along with it. Every save
+/// picks a fresh random nonce.
+pub struct SecretFile {
+    path: PathBuf,
+    passphrase: Option<String>,
+    /// The last key derived and the salt it was derived with, since
+    /// Argon2 is slow on purpose
+    key: Mutex<Option<([u8; SALT_LEN], Key)>>,
+}
+
+impl SecretFile {
+    /// A file in `app_data_dir`, keyed by the passphrase in `KEY_ENV`.
+    pub fn new(app_data_dir: &Path) -> Self {
+        let passphrase = std::env::var(KEY_ENV).ok().filter(|p| !p.is_empty());
+        Self::with_passphrase(app_data_dir, passphrase)
+    }
+
+    pub fn with_passphrase(app_data_dir: &Path, passphrase: Option<String>) -> Self {
+        Self {
+            path: app_data_dir.join(FILE_NAME),
+            passphrase,
+            key: Mutex::new(None),
+        }
+    }
+
+    /// Whether there's a passphrase to derive the key from.
+    pub fn available(&self) -> bool {
+        self.passphrase.is_some()
+    }
+
+    pub fn get(&self, name: &str) -> Result<Option<String>, AppError> {
+        Ok(self.load()?.map.remove(name))
+    }
+
+    pub fn set(&self, name: &str, value: &str) -> Result<(), AppError> {
+        let mut contents = self.load()?;
+        contents.map.insert(name.to_string(), value.to_string());
+        self.save(&contents)
+    }
+
+    pub fn delete(&self, name: &str) -> Result<(), AppError> {
+        let mut contents = self.load()?;
+        if contents.map.remove(name).is_some() {
+            self.save(&contents)?;
+        }
+        Ok(())
+    }
+
+    fn load(&self) -> Result<Contents, AppError> {
+        let data = match fs::read(&self.path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Contents {
+                    salt: None,
+                    map: BTreeMap::new(),
+                })
+            }
+            Err(e) => return Err(AppError::io(format!("Failed to read secrets file: {e}"))),
+        };
+
+        let header = MAGIC.len() + SALT_LEN + NONCE_LEN;
+        if data.len() < header || &data[..MAGIC.len()] != MAGIC {
+            return Err(AppError::io("Secrets file is not in a known format"));
+        }
+        let salt: [u8; SALT_LEN] = data[MAGIC.len()..MAGIC.len() + SALT_LEN]
+            .try_into()
+            .expect("slice has salt length");
+        let nonce = XNonce::from_slice(&data[MAGIC.len() + SALT_LEN..header]);
+
+        let cipher = XChaCha20Poly1305::new(&self.key(salt)?);
+        let body = cipher
+            .decrypt(
+                nonce,
+                Payload {
+                    msg: &data[header..],
+                    aad: &data[..MAGIC.len() + SALT_LEN],
+                },
+            )
+            .map_err(|_| {
+                AppError::io(format!(
+                    "Secrets file failed its integrity check; it was changed or {KEY_ENV} is wrong"
+                ))
+            })?;
+        let map = serde_json::from_slice(&body)
+            .map_err(|e| AppError::io(format!("Secrets file is corrupt: {e}")))?;
+        Ok(Contents {
+            salt: Some(salt),
+            map,
+        })
+    }
+
+    fn save(&self, contents: &Contents) -> Result<(), AppError> {
+        // A new file gets a new salt; an existing one keeps its own
+        let salt = contents.salt.unwrap_or_else(|| {
+            let mut salt = [0u8; SALT_LEN];
+            OsRng.fill_bytes(&mut salt);
+            salt
+        });
+        let cipher = XChaCha20Poly1305::new(&self.key(salt)?);
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+        let mut aad = Vec::with_capacity(MAGIC.len() + SALT_LEN);
+        aad.extend_from_slice(MAGIC);
+        aad.extend_from_slice(&salt);
+        let body = serde_json::to_vec(&contents.map)
+            .map_err(|e| AppError::internal(format!("Failed to encode secrets: {e}")))?;
+        let sealed = cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: &body,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| AppError::internal("Failed to encrypt secrets"))?;
+
+        let mut data = aad;
+        data.extend_from_slice(&nonce);
+        data.extend_from_slice(&sealed);
+
+        // Write beside the file and rename, so a crash never leaves half of it
+        let tmp = self.path.with_extension("bin.tmp");
+        write_private(&tmp, &data)?;
+        fs::rename(&tmp, &self.path)
+            .map_err(|e| AppError::io(format!("Failed to write secrets file: {e}")))
+    }
+
+    /// The key for `salt`, derived from the passphrase with Argon2id.
+    fn key(&self, salt: [u8; SALT_LEN]) -> Result<Key, AppError> {
+        let passphrase = self.passphrase.as_deref().ok_or_else(|| {
+            AppError::io(format!(
+                "The secrets file needs a passphrase in {KEY_ENV}; set it or use the keyring"
+            ))
+        })?;
+
+        let mut cached = self.key.lock()?;
+        if let Some((cached_salt, key)) = cached.as_ref() {
+            if *cached_salt == salt {
+                return Ok(*key);
+            }
+        }
+        let mut key = Key::default();
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|e| AppError::internal(format!("Failed to derive secrets key: {e}")))?;
+        *cached = Some((salt, key));
+        Ok(key)
+    }
+}
+
+/// The decrypted map, with the salt of the file it came from.
+struct Contents {
+    salt: Option<[u8; SALT_LEN]>,
+    map: BTreeMap<String, String>,
+}
+
+fn write_private(path: &Path, data: &[u8]) -> Result<(), AppError> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let write = || -> std::io::Result<()> {
+        use std::io::Write;
+        let mut file = options.open(path)?;
+        file.write_all(data)?;
+        file.sync_all()
+    };
+    write().map_err(|e| AppError::io(format!("Failed to write {}: {e}", path.display())))
+}
@@ -0,0 +1,88 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+use crate::error::AppError;
+
+/// Attribute every item is stored under, next to `name`.
+const SERVICE: &str = "dacm";
+
+/// Whether a Secret Service keyring can be reached. Needs `secret-tool`
+/// (libsecret) and a session bus, which headless machines usually lack.
+pub fn available() -> bool {
+    static AVAILABLE: OnceLock<bool> = OnceLock::new();
+    *AVAILABLE.get_or_init(|| {
+        std::env::var_os("DBUS_SESSION_BUS_ADDRESS").is_some()
+            && Command::new("secret-tool")
+                .arg("--help")
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .is_ok()
+    })
+}
+
+pub fn get(name: &str) -> Result<Option<String>, AppError> {
+    let output = Command::new("secret-tool")
+        .args(["lookup", "service", SERVICE, "name", name])
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| AppError::io(format!("Failed to run secret-tool: {e}")))?;
+
+    // lookup exits non-zero with nothing on stderr when there's no match
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return match stderr.trim() {
+            "" => Ok(None),
+            err => Err(AppError::io(format!("Keyring lookup failed: {err}"))),
+        };
+    }
+
+    String::from_utf8(output.stdout)
+        .map(Some)
+        .map_err(|_| AppError::io(format!("Keyring item '{name}' is not valid UTF-8")))
+}
+
+pub fn set(name: &str, value: &str) -> Result<(), AppError> {
+    let mut child = Command::new("secret-tool")
+        .args(["store", &format!("--label=dacm: {name}")])
+        .args(["service", SERVICE, "name", name])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| AppError::io(format!("Failed to run secret-tool: {e}")))?;
+
+    // The value goes over stdin so it never shows up in the process list
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(value.as_bytes())
+            .map_err(|e| AppError::io(format!("Failed to write to secret-tool: {e}")))?;
+    }
+
+    let output = child
+        .wait_with_output()
+        .map_err(|e| AppError::io(format!("Failed to run secret-tool: {e}")))?;
+    if !output.status.success() {
+        return Err(AppError::io(format!(
+            "Keyring store failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+pub fn delete(name: &str) -> Result<(), AppError> {
+    // clear succeeds whether or not anything matched
+    let output = Command::new("secret-tool")
+        .args(["clear", "service", SERVICE, "name", name])
+        .output()
+        .map_err(|e| AppError::io(format!("Failed to run secret-tool: {e}")))?;
+    if !output.status.success() {
+        return Err(AppError::io(format!(
+            "Keyring delete failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
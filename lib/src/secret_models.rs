@@ -0,0 +1,74 @@
+use std::str::FromStr;
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::schema::{secret_mappings, secrets};
+
+/// Where a secret's value is kept.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretBackend {
+    /// The desktop keyring, through the Secret Service API
+    Keyring,
+    /// `secrets.bin` in the app data directory, encrypted with a key
+    /// derived from the `DACM_SECRETS_KEY` passphrase
+    File,
+}
+
+impl SecretBackend {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SecretBackend::Keyring => "keyring",
+            SecretBackend::File => "file",
+        }
+    }
+}
+
+impl FromStr for SecretBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keyring" => Ok(SecretBackend::Keyring),
+            "file" => Ok(SecretBackend::File),
+            _ => Err(format!("Unknown secret backend: {s}")),
+        }
+    }
+}
+
+/// A stored secret as the webview sees it: its name, never its value.
+#[derive(Queryable, Selectable, Serialize, Debug, Clone)]
+#[diesel(table_name = secrets)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct SecretInfo {
+    pub name: String,
+    pub backend: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = secrets)]
+pub struct NewSecret<'a> {
+    pub name: &'a str,
+    pub backend: &'a str,
+}
+
+/// Which backend new secrets go to, and which backends could be used.
+#[derive(Serialize, Debug, Clone)]
+pub struct SecretsStatus {
+    pub backend: SecretBackend,
+    pub keyring_available: bool,
+    /// Whether the file backend has a passphrase to derive its key from
+    pub file_available: bool,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = secret_mappings)]
+pub struct NewSecretMapping<'a> {
+    pub workspace_id: i32,
+    pub env_var: &'a str,
+    pub secret_name: &'a str,
+}
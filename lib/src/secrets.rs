@@ -0,0 +1,284 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+use chrono::Utc;
+use diesel::prelude::*;
+use serde_json::json;
+
+use crate::db::DbState;
+use crate::env_profiles;
+use crate::error::AppError;
+use crate::schema::{secret_mappings, secrets, workspaces};
+use crate::secret_file::SecretFile;
+use crate::secret_keyring;
+use crate::secret_models::{NewSecret, NewSecretMapping, SecretBackend, SecretInfo, SecretsStatus};
+use crate::settings_registry;
+use crate::workspace_config::WorkspaceConfig;
+
+const MAX_NAME_LEN: usize = 128;
+
+/// Secret values, kept out of SQLite. The `secrets` table only records
+/// which names exist and which backend holds each one.
+pub struct SecretStore {
+    file: SecretFile,
+    /// Serializes read-modify-write cycles on the secrets file
+    lock: Mutex<()>,
+}
+
+impl SecretStore {
+    pub fn new(app_data_dir: &Path) -> Self {
+        Self::with_file(SecretFile::new(app_data_dir))
+    }
+
+    /// A store whose file backend uses `file`, e.g. one with a fixed
+    /// passphrase in tests.
+    pub fn with_file(file: SecretFile) -> Self {
+        Self {
+            file,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// The backend new secrets go to, from the `secrets_backend` setting.
+    pub fn status(&self, conn: &mut SqliteConnection) -> Result<SecretsStatus, AppError> {
+        let keyring_available = secret_keyring::available();
+        let backend = match settings_registry::get_raw(conn, "secrets_backend")?.as_str() {
+            "keyring" if !keyring_available => return Err(keyring_unavailable()),
+            "keyring" => SecretBackend::Keyring,
+            "file" => SecretBackend::File,
+            _ if keyring_available => SecretBackend::Keyring,
+            _ => SecretBackend::File,
+        };
+        Ok(SecretsStatus {
+            backend,
+            keyring_available,
+            file_available: self.file.available(),
+        })
+    }
+
+    /// Store a value under `name`, replacing any previous one.
+    pub fn set(
+        &self,
+        conn: &mut SqliteConnection,
+        name: &str,
+        value: &str,
+    ) -> Result<SecretInfo, AppError> {
+        validate_name(name)?;
+        let backend = self.status(conn)?.backend;
+        let previous = stored_backend(conn, name)?;
+
+        {
+            let _guard = self.lock.lock()?;
+            write(&self.file, backend, name, value)?;
+            // A secret moving backends shouldn't leave its old copy behind
+            if let Some(old) = previous.filter(|old| *old != backend) {
+                if let Err(e) = remove(&self.file, old, name) {
+                    eprintln!("[secrets] Failed to remove old copy of '{name}': {e}");
+                }
+            }
+        }
+
+        diesel::insert_into(secrets::table)
+            .values(&NewSecret {
+                name,
+                backend: backend.as_str(),
+            })
+            .on_conflict(secrets::name)
+            .do_update()
+            .set((
+                secrets::backend.eq(backend.as_str()),
+                secrets::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .execute(conn)?;
+
+        Ok(secrets::table
+            .find(name)
+            .select(SecretInfo::as_select())
+            .first(conn)?)
+    }
+
+    pub fn delete(&self, conn: &mut SqliteConnection, name: &str) -> Result<(), AppError> {
+        let backend = stored_backend(conn, name)?
+            .ok_or_else(|| AppError::not_found(format!("Secret '{name}' not found")))?;
+
+        {
+            let _guard = self.lock.lock()?;
+            remove(&self.file, backend, name)?;
+        }
+
+        diesel::delete(secrets::table.find(name)).execute(conn)?;
+        Ok(())
+    }
+
+    /// A secret's value, for the backend's own use only. Never hand this
+    /// to the webview.
+    pub fn get(&self, conn: &mut SqliteConnection, name: &str) -> Result<String, AppError> {
+        let missing = || AppError::not_found(format!("Secret '{name}' not found"));
+        let backend = stored_backend(conn, name)?.ok_or_else(missing)?;
+
+        let _guard = self.lock.lock()?;
+        let value = match backend {
+            SecretBackend::Keyring => {
+                if !secret_keyring::available() {
+                    return Err(keyring_unavailable());
+                }
+                secret_keyring::get(name)?
+            }
+            SecretBackend::File => self.file.get(name)?,
+        };
+        value.ok_or_else(missing)
+    }
+}
+
+pub fn list(conn: &mut SqliteConnection) -> Result<Vec<SecretInfo>, AppError> {
+    Ok(secrets::table
+        .order(secrets::name.asc())
+        .select(SecretInfo::as_select())
+        .load(conn)?)
+}
+
+/// A workspace's secret mappings, env var to secret name.
+pub fn mappings(
+    conn: &mut SqliteConnection,
+    workspace_id: i32,
+) -> Result<BTreeMap<String, String>, AppError> {
+    let rows: Vec<(String, String)> = secret_mappings::table
+        .filter(secret_mappings::workspace_id.eq(workspace_id))
+        .select((secret_mappings::env_var, secret_mappings::secret_name))
+        .load(conn)?;
+    Ok(rows.into_iter().collect())
+}
+
+/// Replace a workspace's secret mappings. They're only set from the app,
+/// never from `.dacm.toml`, so a repository can't pick which secrets its
+/// agents are given. Every secret named has to exist.
+pub fn set_mappings(
+    conn: &mut SqliteConnection,
+    workspace_id: i32,
+    mappings: &BTreeMap<String, String>,
+) -> Result<BTreeMap<String, String>, AppError> {
+    env_profiles::validate_vars(mappings)?;
+
+    conn.immediate_transaction(|conn| {
+        let exists: i64 = workspaces::table
+            .filter(workspaces::id.eq(workspace_id))
+            .count()
+            .get_result(conn)?;
+        if exists == 0 {
+            return Err(AppError::not_found(format!(
+                "Workspace with id {workspace_id} not found"
+            )));
+        }
+        for (var, name) in mappings {
+            if stored_backend(conn, name)?.is_none() {
+                return Err(
+                    AppError::not_found(format!("Secret '{name}' for {var} not found"))
+                        .with_details(json!({ "env": var, "secret": name })),
+                );
+            }
+        }
+
+        diesel::delete(
+            secret_mappings::table.filter(secret_mappings::workspace_id.eq(workspace_id)),
+        )
+        .execute(conn)?;
+        let rows: Vec<NewSecretMapping> = mappings
+            .iter()
+            .map(|(var, name)| NewSecretMapping {
+                workspace_id,
+                env_var: var,
+                secret_name: name,
+            })
+            .collect();
+        diesel::insert_into(secret_mappings::table)
+            .values(&rows)
+            .execute(conn)?;
+
+        self::mappings(conn, workspace_id)
+    })
+}
+
+/// Values for the workspace's secret mappings, keyed by env var. Kept
+/// apart from `SpawnRequest::env` so they're never written to the queue or
+/// the run history.
+pub fn resolve_env(
+    db: &DbState,
+    store: &SecretStore,
+    config: &WorkspaceConfig,
+) -> Result<BTreeMap<String, String>, AppError> {
+    if config.secrets.is_empty() {
+        return Ok(BTreeMap::new());
+    }
+
+    let mut conn = db.conn()?;
+
+    config
+        .secrets
+        .iter()
+        .map(|(var, name)| {
+            let value = store.get(&mut conn, name).map_err(|e| {
+                AppError::new(
+                    e.code,
+                    format!("Secret '{name}' for {var} could not be read: {e}"),
+                )
+                .with_details(json!({ "env": var, "secret": name }))
+            })?;
+            Ok((var.clone(), value))
+        })
+        .collect()
+}
+
+fn validate_name(name: &str) -> Result<(), AppError> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::validation(format!(
+            "Secret names use letters, digits, '_', '-' and '.', up to {MAX_NAME_LEN} characters"
+        ))
+        .with_details(json!({ "name": name })))
+    }
+}
+
+fn stored_backend(
+    conn: &mut SqliteConnection,
+    name: &str,
+) -> Result<Option<SecretBackend>, AppError> {
+    let backend: Option<String> = secrets::table
+        .find(name)
+        .select(secrets::backend)
+        .first(conn)
+        .optional()?;
+    backend
+        .map(|b| b.parse().map_err(AppError::internal))
+        .transpose()
+}
+
+fn write(
+    file: &SecretFile,
+    backend: SecretBackend,
+    name: &str,
+    value: &str,
+) -> Result<(), AppError> {
+    match backend {
+        SecretBackend::Keyring => secret_keyring::set(name, value),
+        SecretBackend::File => file.set(name, value),
+    }
+}
+
+fn remove(file: &SecretFile, backend: SecretBackend, name: &str) -> Result<(), AppError> {
+    match backend {
+        SecretBackend::Keyring if !secret_keyring::available() => Err(keyring_unavailable()),
+        SecretBackend::Keyring => secret_keyring::delete(name),
+        SecretBackend::File => file.delete(name),
+    }
+}
+
+fn keyring_unavailable() -> AppError {
+    AppError::io("No Secret Service keyring is reachable; set secrets_backend to file or auto")
+}
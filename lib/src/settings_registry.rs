@@ -0,0 +1,442 @@
+use chrono::Utc;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::error::AppError;
+use crate::event_sink::EventSink;
+use crate::schema::settings;
+use crate::settings_models::NewSetting;
+
+/// What a setting holds and which values it accepts. Values are still
+/// stored as text; this decides how that text is read and written.
+#[derive(Serialize, Clone, Copy, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SettingKind {
+    /// Stored as `true` / `false`
+    Bool,
+    Integer {
+        min: i64,
+        max: i64,
+    },
+    Text {
+        allow_empty: bool,
+    },
+    Choice {
+        options: &'static [&'static str],
+    },
+}
+
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct SettingDef {
+    pub key: &'static str,
+    #[serde(flatten)]
+    pub kind: SettingKind,
+    /// Stored form of the default; `None` for settings that start unset
+    #[serde(skip)]
+    pub default: Option<&'static str>,
+    /// Whether the setting can be cleared back to null
+    pub nullable: bool,
+    pub section: &'static str,
+    pub description: &'static str,
+}
+
+const FONT_SIZE: SettingKind = SettingKind::Integer { min: 8, max: 24 };
+const SESSION_LIMIT: SettingKind = SettingKind::Integer { min: 0, max: 64 };
+const ID: SettingKind = SettingKind::Integer {
+    min: 1,
+    max: i32::MAX as i64,
+};
+
+/// Every setting the app knows about. Keys not listed here are rejected.
+pub const REGISTRY: &[SettingDef] = &[
+    SettingDef {
+        key: "theme",
+        kind: SettingKind::Choice {
+            options: &["light", "dark", "system"],
+        },
+        default: Some("system"),
+        nullable: false,
+        section: "appearance",
+        description: "Color theme; system follows the OS",
+    },
+    SettingDef {
+        key: "code_font_family",
+        kind: SettingKind::Text { allow_empty: false },
+        default: Some(r#""SF Mono", "Fira Code", monospace"#),
+        nullable: false,
+        section: "appearance",
+        description: "CSS font-family for code",
+    },
+    SettingDef {
+        key: "code_font_size",
+        kind: FONT_SIZE,
+        default: Some("13"),
+        nullable: false,
+        section: "appearance",
+        description: "Code font size in pixels",
+    },
+    SettingDef {
+        key: "terminal_font_family",
+        kind: SettingKind::Text { allow_empty: false },
+        default: Some(r#""SF Mono", "Fira Code", "Menlo", monospace"#),
+        nullable: false,
+        section: "appearance",
+        description: "CSS font-family for terminals",
+    },
+    SettingDef {
+        key: "terminal_font_size",
+        kind: FONT_SIZE,
+        default: Some("13"),
+        nullable: false,
+        section: "appearance",
+        description: "Terminal font size in pixels",
+    },
+    SettingDef {
+        key: "prevent_sleep",
+        kind: SettingKind::Bool,
+        default: Some("false"),
+        nullable: false,
+        section: "system",
+        description: "Keep the machine awake while sessions are running",
+    },
+    SettingDef {
+        key: "record_sessions",
+        kind: SettingKind::Bool,
+        default: Some("false"),
+        nullable: false,
+        section: "system",
+        description: "Save each session's terminal output as an asciicast file for replay",
+    },
+    SettingDef {
+        key: "persistent_sessions",
+        kind: SettingKind::Bool,
+        default: Some("false"),
+        nullable: false,
+        section: "system",
+        description: "Run agents inside tmux so they keep running after the app closes",
+    },
+    SettingDef {
+        key: "hook_timeout_secs",
+        kind: SettingKind::Integer { min: 0, max: 86400 },
+        default: Some("600"),
+        nullable: false,
+        section: "system",
+        description:
+            "Seconds a hook may run before it's killed and counts as failed; 0 for no limit",
+    },
+    SettingDef {
+        key: "binary_output",
+        kind: SettingKind::Bool,
+        default: Some("false"),
+        nullable: false,
+        section: "system",
+        description:
+            "Send terminal output to the window as raw bytes over a channel instead of JSON events",
+    },
+    SettingDef {
+        key: "secrets_backend",
+        kind: SettingKind::Choice {
+            options: &["auto", "keyring", "file"],
+        },
+        default: Some("auto"),
+        nullable: false,
+        section: "system",
+        description:
+            "Where new secrets are stored; auto uses the keyring if reachable, else the file keyed by DACM_SECRETS_KEY",
+    },
+    SettingDef {
+        key: "worktree_base_path",
+        kind: SettingKind::Text { allow_empty: true },
+        default: Some(""),
+        nullable: false,
+        section: "worktrees",
+        description: "Directory new worktrees go in; empty puts them next to the repository",
+    },
+    SettingDef {
+        key: "worktree_branch_pattern",
+        kind: SettingKind::Text { allow_empty: false },
+        default: Some("feature/{project_name}"),
+        nullable: false,
+        section: "worktrees",
+        description: "Branch name for new worktrees; {project_name} and {project_id} are replaced",
+    },
+    SettingDef {
+        key: "max_concurrent_sessions",
+        kind: SESSION_LIMIT,
+        default: Some("4"),
+        nullable: false,
+        section: "queue",
+        description: "Sessions the scheduler runs at once; 0 for no limit",
+    },
+    SettingDef {
+        key: "max_sessions_per_workspace",
+        kind: SESSION_LIMIT,
+        default: Some("0"),
+        nullable: false,
+        section: "queue",
+        description: "Sessions per workspace the scheduler runs at once; 0 for no limit",
+    },
+    SettingDef {
+        key: "queue_mode",
+        kind: SettingKind::Choice {
+            options: &["fifo", "priority"],
+        },
+        default: Some("fifo"),
+        nullable: false,
+        section: "queue",
+        description: "Start queued sessions in arrival order or by priority",
+    },
+    SettingDef {
+        key: "queue_paused",
+        kind: SettingKind::Bool,
+        default: Some("false"),
+        nullable: false,
+        section: "queue",
+        description: "Hold queued sessions instead of starting them",
+    },
+    SettingDef {
+        key: "selected_model_id",
+        kind: SettingKind::Text { allow_empty: false },
+        default: None,
+        nullable: true,
+        section: "state",
+        description: "Model picked in the toolbar",
+    },
+    SettingDef {
+        key: "last_workspace_id",
+        kind: ID,
+        default: None,
+        nullable: true,
+        section: "state",
+        description: "Workspace selected when the app was last closed",
+    },
+    SettingDef {
+        key: "last_project_id",
+        kind: ID,
+        default: None,
+        nullable: true,
+        section: "state",
+        description: "Project selected when the app was last closed",
+    },
+];
+
+/// A registry entry as sent to the settings UI, with its default decoded.
+#[derive(Serialize, Debug)]
+pub struct SettingSchema {
+    #[serde(flatten)]
+    pub def: SettingDef,
+    pub default: Value,
+}
+
+/// A setting's current value, typed.
+#[derive(Serialize, Debug)]
+pub struct SettingValue {
+    pub key: &'static str,
+    pub value: Value,
+    /// True when nothing is stored and the default applies
+    pub is_default: bool,
+}
+
+pub const SETTINGS_CHANGED: &str = "settings-changed";
+
+/// Payload of `settings-changed`. Emitted after any write that changed a
+/// value, and listened to by the backend as well as every window.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SettingChanged {
+    pub key: String,
+    pub old_value: Value,
+    pub new_value: Value,
+}
+
+pub fn def(key: &str) -> Result<&'static SettingDef, AppError> {
+    REGISTRY.iter().find(|d| d.key == key).ok_or_else(|| {
+        AppError::validation(format!("Unknown setting '{key}'")).with_details(json!({ "key": key }))
+    })
+}
+
+pub fn schema() -> Vec<SettingSchema> {
+    REGISTRY
+        .iter()
+        .map(|def| SettingSchema {
+            def: *def,
+            default: default_value(def),
+        })
+        .collect()
+}
+
+fn default_value(def: &SettingDef) -> Value {
+    def.default
+        .and_then(|raw| decode(def, raw).ok())
+        .unwrap_or(Value::Null)
+}
+
+/// Parse a stored string into a typed value, checking it against the
+/// setting's rules.
+pub fn decode(def: &SettingDef, raw: &str) -> Result<Value, String> {
+    if def.nullable && raw.is_empty() {
+        return Ok(Value::Null);
+    }
+    match def.kind {
+        SettingKind::Bool => match raw {
+            "true" => Ok(Value::Bool(true)),
+            "false" => Ok(Value::Bool(false)),
+            _ => Err("expected true or false".to_string()),
+        },
+        SettingKind::Integer { min, max } => {
+            let n: i64 = raw
+                .trim()
+                .parse()
+                .map_err(|_| "expected a whole number".to_string())?;
+            if n < min || n > max {
+                return Err(format!("must be between {min} and {max}"));
+            }
+            Ok(json!(n))
+        }
+        SettingKind::Text { allow_empty } => {
+            if !allow_empty && raw.trim().is_empty() {
+                return Err("must not be empty".to_string());
+            }
+            Ok(Value::String(raw.to_string()))
+        }
+        SettingKind::Choice { options } => {
+            if options.contains(&raw) {
+                Ok(Value::String(raw.to_string()))
+            } else {
+                Err(format!("must be one of {}", options.join(", ")))
+            }
+        }
+    }
+}
+
+/// Turn a typed value into its stored form. `None` clears the setting.
+fn encode(def: &SettingDef, value: &Value) -> Result<Option<String>, String> {
+    let raw = match (value, def.kind) {
+        (Value::Null, _) if def.nullable => return Ok(None),
+        (Value::Null, _) => return Err("can't be cleared".to_string()),
+        (Value::Bool(b), SettingKind::Bool) => b.to_string(),
+        (Value::Number(n), SettingKind::Integer { .. }) => n.to_string(),
+        (Value::String(s), SettingKind::Text { .. } | SettingKind::Choice { .. }) => s.clone(),
+        _ => return Err(format!("wrong type: {value}")),
+    };
+    // Round-trip through decode so both paths apply the same rules
+    decode(def, &raw)?;
+    Ok(Some(raw))
+}
+
+fn invalid(def: &SettingDef, value: impl std::fmt::Display, reason: String) -> AppError {
+    AppError::validation(format!("Invalid value for '{}': {reason}", def.key))
+        .with_details(json!({ "key": def.key, "value": value.to_string(), "reason": reason }))
+}
+
+fn stored(conn: &mut SqliteConnection, key: &str) -> QueryResult<Option<String>> {
+    settings::table
+        .filter(settings::key.eq(key))
+        .select(settings::value)
+        .first::<String>(conn)
+        .optional()
+}
+
+/// A setting's stored string, or its default. Unset nullable settings and
+/// stored values that no longer validate come back as their default.
+pub fn get_raw(conn: &mut SqliteConnection, key: &str) -> Result<String, AppError> {
+    let def = def(key)?;
+    let raw = stored(conn, key)?.filter(|raw| decode(def, raw).is_ok());
+    Ok(raw.unwrap_or_else(|| def.default.unwrap_or("").to_string()))
+}
+
+pub fn get(conn: &mut SqliteConnection, key: &str) -> Result<SettingValue, AppError> {
+    let def = def(key)?;
+    let value = stored(conn, key)?.and_then(|raw| decode(def, &raw).ok());
+    Ok(SettingValue {
+        key: def.key,
+        is_default: value.is_none(),
+        value: value.unwrap_or_else(|| default_value(def)),
+    })
+}
+
+/// Internal reads that just need a bool.
+pub fn get_bool(conn: &mut SqliteConnection, key: &str) -> Result<bool, AppError> {
+    Ok(get(conn, key)?.value.as_bool().unwrap_or(false))
+}
+
+/// Internal reads of integer settings. Unset nullable settings are `None`.
+pub fn get_int(conn: &mut SqliteConnection, key: &str) -> Result<Option<i64>, AppError> {
+    Ok(get(conn, key)?.value.as_i64())
+}
+
+/// Internal reads of text settings, with empty treated as unset.
+pub fn get_text(conn: &mut SqliteConnection, key: &str) -> Result<Option<String>, AppError> {
+    Ok(get(conn, key)?
+        .value
+        .as_str()
+        .filter(|s| !s.is_empty())
+        .map(str::to_string))
+}
+
+/// Read a value given in its stored string form, as `set` expects it.
+pub fn parse_raw(key: &str, raw: &str) -> Result<Value, AppError> {
+    let def = def(key)?;
+    decode(def, raw).map_err(|reason| invalid(def, raw, reason))
+}
+
+/// Validate and store a typed value. Null clears a nullable setting.
+pub fn set(
+    conn: &mut SqliteConnection,
+    key: &str,
+    value: &Value,
+) -> Result<SettingValue, AppError> {
+    let def = def(key)?;
+    let raw = encode(def, value).map_err(|reason| invalid(def, value, reason))?;
+
+    match raw {
+        Some(raw) => {
+            diesel::insert_into(settings::table)
+                .values((
+                    &NewSetting {
+                        key: def.key,
+                        value: &raw,
+                    },
+                    settings::updated_at.eq(Utc::now().naive_utc()),
+                ))
+                .on_conflict(settings::key)
+                .do_update()
+                .set((
+                    settings::value.eq(&raw),
+                    settings::updated_at.eq(Utc::now().naive_utc()),
+                ))
+                .execute(conn)?;
+        }
+        None => {
+            diesel::delete(settings::table.filter(settings::key.eq(def.key))).execute(conn)?;
+        }
+    }
+
+    get(conn, key)
+}
+
+/// `set`, plus the change to broadcast if the effective value moved.
+/// Resetting a setting to a stored value equal to its default is no change.
+pub fn update(
+    conn: &mut SqliteConnection,
+    key: &str,
+    value: &Value,
+) -> Result<(SettingValue, Option<SettingChanged>), AppError> {
+    let old = get(conn, key)?;
+    let new = set(conn, key, value)?;
+
+    let change = (old.value != new.value).then(|| SettingChanged {
+        key: new.key.to_string(),
+        old_value: old.value,
+        new_value: new.value.clone(),
+    });
+    Ok((new, change))
+}
+
+/// Emit `settings-changed` for each change. Call once the write has
+/// committed, so listeners that read the setting back see the new value.
+pub fn broadcast(events: &dyn EventSink, changes: &[SettingChanged]) {
+    for change in changes {
+        events.emit(SETTINGS_CHANGED, change);
+    }
+}
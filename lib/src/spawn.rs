@@ -0,0 +1,106 @@
+use diesel::prelude::*;
+use serde_json::json;
+
+use crate::db::DbState;
+use crate::env_profiles;
+use crate::error::{AppError, ResultExt};
+use crate::event_models::{EventType, NewEvent};
+use crate::event_sink::EventSink;
+use crate::events;
+use crate::hooks;
+use crate::retry_models::NewSessionRun;
+use crate::schema::session_runs;
+use crate::secrets::{self, SecretStore};
+use crate::session::{SessionManager, SpawnRequest};
+use crate::workspace_config::{self, WorkspaceConfig};
+
+// Starting a session takes more than `SessionManager::spawn`: the request
+// picks up the project's env profile and the workspace config, secrets are
+// resolved and pre-spawn hooks run. Direct spawns, the queue and retries
+// all go through here, each from the step that applies to them.
+
+/// What a spawn needs from its host.
+pub struct Spawner<'a> {
+    pub db: &'a DbState,
+    pub secrets: &'a SecretStore,
+    pub sessions: &'a SessionManager,
+    pub events: &'a dyn EventSink,
+}
+
+impl Spawner<'_> {
+    /// Start a session for a new request and record it as a first attempt.
+    /// Blocks for as long as the pre-spawn hooks run.
+    pub fn spawn(&self, mut request: SpawnRequest) -> Result<String, AppError> {
+        env_profiles::validate_vars(&request.env)?;
+        let config = {
+            let mut conn = self.db.conn()?;
+            prepare(&mut conn, &mut request)?
+        };
+        let session_id = self.launch(&config, &mut request)?;
+
+        let mut conn = self.db.conn()?;
+        record(&mut conn, &session_id, &request, 1).context("Failed to record session")?;
+        Ok(session_id)
+    }
+
+    /// Resolve the request's secrets, run pre-spawn hooks and start it.
+    /// The request is expected to be filled in already.
+    pub fn launch(
+        &self,
+        config: &WorkspaceConfig,
+        request: &mut SpawnRequest,
+    ) -> Result<String, AppError> {
+        request.secret_env = secrets::resolve_env(self.db, self.secrets, config)?;
+        hooks::before_spawn(self.db, self.events, config, request)?;
+        self.sessions.spawn(request.clone())
+    }
+}
+
+/// Fill in a request from its project's env profile and its workspace's
+/// config, returning the config for `Spawner::launch`.
+pub fn prepare(
+    conn: &mut SqliteConnection,
+    request: &mut SpawnRequest,
+) -> Result<WorkspaceConfig, AppError> {
+    env_profiles::apply_project(conn, request)?;
+    let config = workspace_config::load(conn, request.workspace_id)?;
+    config.apply(request);
+    Ok(config)
+}
+
+/// Record a freshly spawned session so its exit can be matched to an attempt.
+pub fn record(
+    conn: &mut SqliteConnection,
+    session_id: &str,
+    request: &SpawnRequest,
+    attempt: i32,
+) -> QueryResult<()> {
+    let env = serde_json::to_string(&request.recorded_env()).unwrap_or_else(|_| "{}".into());
+    diesel::insert_into(session_runs::table)
+        .values(&NewSessionRun {
+            session_id,
+            project_id: request.project_id,
+            workspace_id: request.workspace_id,
+            attempt,
+            resumed: request.resume_session_id.is_some() || request.continue_last,
+            env: &env,
+        })
+        .execute(conn)?;
+
+    events::record(
+        conn,
+        NewEvent::new(EventType::SessionSpawned)
+            .workspace(request.workspace_id)
+            .project(request.project_id)
+            .session(session_id)
+            .payload(json!({
+                "attempt": attempt,
+                "cli_command": request.cli_command,
+                "model": request.model,
+                "working_dir": request.working_dir,
+                "resume_session_id": request.resume_session_id,
+                "continue_last": request.continue_last,
+            })),
+    );
+    Ok(())
+}
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use portable_pty::CommandBuilder;
+
+use crate::error::AppError;
+use crate::tmux_models::{TmuxPane, TmuxSession};
+
+// Sessions on the user's tmux server can be opened like our own: a
+// `tmux attach-session` client runs in a PTY and its output is streamed as
+// usual. Stopping one detaches that client, so the tmux session and
+// whatever runs in it carry on.
+//
+// Persistent sessions (see `persistent`) run on a separate server we own,
+// so they never show up among, or get configured like, the user's.
+
+const PROGRAM: &str = "tmux";
+
+/// Socket name of the server persistent sessions run on.
+const OWNED_SOCKET: &str = "dacm";
+
+/// Which tmux server a command talks to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Server {
+    /// The user's, or the one the app was started inside
+    User,
+    /// Ours, started without the user's config
+    Owned,
+}
+
+impl Server {
+    fn args(self) -> Vec<String> {
+        match self {
+            // When the app itself runs inside tmux, `$TMUX` names that
+            // server's socket. Passing it explicitly keeps listing and
+            // attaching on the same server once the variable is cleared
+            // for the client.
+            Server::User => std::env::var("TMUX")
+                .ok()
+                .and_then(|value| value.split(',').next().map(str::to_string))
+                .filter(|socket| !socket.is_empty())
+                .map(|socket| vec!["-S".to_string(), socket])
+                .unwrap_or_default(),
+            Server::Owned => ["-L", OWNED_SOCKET, "-f", "/dev/null"]
+                .map(str::to_string)
+                .to_vec(),
+        }
+    }
+}
+
+/// Whether the tmux binary can be run at all.
+pub fn available() -> bool {
+    Command::new(PROGRAM)
+        .arg("-V")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+const SESSION_FORMAT: &str =
+    "#{session_id}\t#{session_name}\t#{session_windows}\t#{session_attached}\t#{session_created}";
+
+const PANE_FORMAT: &str = "#{session_id}\t#{window_index}\t#{pane_index}\t#{pane_id}\t\
+     #{pane_current_path}\t#{pane_current_command}\t#{pane_pid}\t#{pane_width}\t\
+     #{pane_height}\t#{pane_active}";
+
+/// Sessions on the user's tmux server. No server running means no
+/// sessions rather than an error.
+pub fn list() -> Result<Vec<TmuxSession>, AppError> {
+    let Some(sessions) = run(Server::User, &["list-sessions", "-F", SESSION_FORMAT])? else {
+        return Ok(Vec::new());
+    };
+    let panes = run(Server::User, &["list-panes", "-a", "-F", PANE_FORMAT])?.unwrap_or_default();
+
+    let mut panes_by_session: HashMap<String, Vec<TmuxPane>> = HashMap::new();
+    for line in panes.lines() {
+        if let Some((session_id, pane)) = parse_pane(line) {
+            panes_by_session.entry(session_id).or_default().push(pane);
+        }
+    }
+
+    let mut sessions: Vec<TmuxSession> = sessions
+        .lines()
+        .filter_map(parse_session)
+        .map(|mut session| {
+            session.panes = panes_by_session.remove(&session.id).unwrap_or_default();
+            session
+        })
+        .collect();
+    sessions.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(sessions)
+}
+
+/// Check `target` names a live session or pane, returning the directory
+/// the attached client should start in. Targets are a session name or a
+/// pane id like `%7`.
+pub fn resolve(target: &str) -> Result<String, AppError> {
+    let sessions = list()?;
+    let found = if target.starts_with('%') {
+        sessions
+            .iter()
+            .flat_map(|s| &s.panes)
+            .find(|p| p.id == target)
+    } else {
+        sessions
+            .iter()
+            .find(|s| s.name == target)
+            .map(|s| s.panes.iter().find(|p| p.active).or(s.panes.first()))
+            .ok_or_else(|| AppError::not_found(format!("tmux session '{target}' not found")))?
+    };
+    let found =
+        found.ok_or_else(|| AppError::not_found(format!("tmux pane '{target}' not found")))?;
+    Ok(found.current_path.clone())
+}
+
+/// The client to run in a PTY. A pane target is focused first so the
+/// client opens on it.
+pub fn attach_command(server: Server, target: &str, working_dir: &str) -> CommandBuilder {
+    let mut cmd = CommandBuilder::new(PROGRAM);
+    cmd.args(server.args());
+    if target.starts_with('%') {
+        cmd.args(["select-window", "-t", target, ";"]);
+        cmd.args(["select-pane", "-t", target, ";"]);
+        cmd.args(["attach-session", "-t", target]);
+    } else {
+        // `=` makes tmux match the name exactly rather than as a prefix
+        cmd.args(["attach-session", "-t", &format!("={target}")]);
+    }
+    if Path::new(working_dir).is_dir() {
+        cmd.cwd(working_dir);
+    }
+    // With this set tmux refuses to nest; the server is already chosen above
+    cmd.env_remove("TMUX");
+    cmd
+}
+
+/// Detach the client on `tty`, leaving its session running.
+pub fn detach_client(server: Server, tty: &Path) -> Result<(), AppError> {
+    let tty = tty.to_string_lossy();
+    run(server, &["detach-client", "-t", &tty])?
+        .map(|_| ())
+        .ok_or_else(|| AppError::pty(format!("No tmux client on {tty}")))
+}
+
+/// Run a tmux command, returning its output, or `None` when there's no
+/// server to talk to.
+pub fn run(server: Server, args: &[&str]) -> Result<Option<String>, AppError> {
+    let output = Command::new(PROGRAM)
+        .args(server.args())
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => AppError::io("tmux is not installed"),
+            _ => AppError::io(format!("Failed to run tmux: {e}")),
+        })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stderr = stderr.trim();
+        if stderr.starts_with("no server running") || stderr.starts_with("error connecting to") {
+            return Ok(None);
+        }
+        return Err(AppError::io(format!("tmux {} failed: {stderr}", args[0])));
+    }
+    Ok(Some(String::from_utf8_lossy(&output.stdout).into_owned()))
+}
+
+fn parse_session(line: &str) -> Option<TmuxSession> {
+    let mut fields = line.split('\t');
+    Some(TmuxSession {
+        id: fields.next()?.to_string(),
+        name: fields.next()?.to_string(),
+        windows: fields.next()?.parse().ok()?,
+        attached: fields.next()?.parse().ok()?,
+        created_at: fields.next()?.parse().ok()?,
+        panes: Vec::new(),
+    })
+}
+
+fn parse_pane(line: &str) -> Option<(String, TmuxPane)> {
+    let mut fields = line.split('\t');
+    let session_id = fields.next()?.to_string();
+    let pane = TmuxPane {
+        window_index: fields.next()?.parse().ok()?,
+        pane_index: fields.next()?.parse().ok()?,
+        id: fields.next()?.to_string(),
+        current_path: fields.next()?.to_string(),
+        current_command: fields.next()?.to_string(),
+        pid: fields.next()?.parse().ok(),
+        width: fields.next()?.parse().ok()?,
+        height: fields.next()?.parse().ok()?,
+        active: fields.next()? == "1",
+    };
+    Some((session_id, pane))
+}
@@ -0,0 +1,29 @@
+use serde::Serialize;
+
+/// A session on the local tmux server, with its panes.
+#[derive(Serialize, Debug, Clone)]
+pub struct TmuxSession {
+    /// tmux's own id, like `$3`
+    pub id: String,
+    pub name: String,
+    pub windows: u32,
+    /// Clients attached to it, ours included
+    pub attached: u32,
+    pub created_at: i64,
+    pub panes: Vec<TmuxPane>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct TmuxPane {
+    /// tmux's pane id, like `%7`; usable as an attach target
+    pub id: String,
+    pub window_index: u32,
+    pub pane_index: u32,
+    pub current_path: String,
+    pub current_command: String,
+    pub pid: Option<u32>,
+    pub width: u16,
+    pub height: u16,
+    /// The active pane of its window
+    pub active: bool,
+}
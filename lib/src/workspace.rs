@@ -0,0 +1,409 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::db::DbState;
+use crate::error::{AppError, ResultExt};
+use crate::event_models::{EventType, NewEvent};
+use crate::event_sink::EventSink;
+use crate::events;
+use crate::git::{self, RepoInfo};
+use crate::hook_models::HookStage;
+use crate::hooks::{self, HookContext};
+use crate::models::{NewWorkspace, Workspace, WorkspaceBulkAdd};
+use crate::project_models::{Project, ProjectStatus};
+use crate::project_status::{self, Transition};
+use crate::retry_models::SCOPE_WORKSPACE;
+use crate::schema::{projects, retry_policies, workspaces};
+use crate::workspace_config::{self, WorkspaceConfig};
+use crate::worktree;
+
+/// What happens to a workspace's projects when the workspace is removed.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RemoveMode {
+    /// Fail if the workspace still has projects
+    #[default]
+    Refuse,
+    /// Archive its projects and keep them, detached from any workspace
+    Archive,
+    /// Delete its projects along with their history and worktrees
+    Cascade,
+}
+
+/// Check that `path` is a directory inside a git repository and return its
+/// canonical form along with the repository's details. The repository root
+/// is canonicalized too, as the key that stops a repository being
+/// registered twice.
+fn resolve_path(path: &str) -> Result<(PathBuf, RepoInfo), AppError> {
+    let path = path.trim();
+    if path.is_empty() {
+        return Err(AppError::validation("Workspace path is empty"));
+    }
+
+    let canonical = std::fs::canonicalize(path).map_err(|e| {
+        AppError::not_found(format!("Path {path} is not accessible: {e}"))
+            .with_details(json!({ "path": path }))
+    })?;
+    if !canonical.is_dir() {
+        return Err(AppError::validation(format!("{path} is not a directory"))
+            .with_details(json!({ "path": path })));
+    }
+
+    let mut repo = git::inspect(&canonical)?.ok_or_else(|| {
+        AppError::validation(format!("{path} is not inside a git repository"))
+            .with_details(json!({ "path": path }))
+    })?;
+    if let Ok(root) = std::fs::canonicalize(&repo.root) {
+        repo.root = root.to_string_lossy().to_string();
+    }
+
+    Ok((canonical, repo))
+}
+
+/// Map the repository root of every registered workspace to its id, so a
+/// subdirectory of a registered repository is caught as a duplicate. Rows
+/// without git details fall back to their own path. Older rows may have
+/// been stored unresolved, so both are canonicalized here too.
+pub fn registered_roots(conn: &mut SqliteConnection) -> QueryResult<HashMap<PathBuf, i32>> {
+    let registered: Vec<(i32, String, Option<String>)> = workspaces::table
+        .select((workspaces::id, workspaces::path, workspaces::repo_root))
+        .load(conn)?;
+
+    Ok(registered
+        .into_iter()
+        .map(|(id, path, repo_root)| {
+            let root = repo_root.unwrap_or(path);
+            (
+                std::fs::canonicalize(&root).unwrap_or_else(|_| root.into()),
+                id,
+            )
+        })
+        .collect())
+}
+
+/// Register a git repository as a workspace. The path is canonicalized
+/// and the repository's details are recorded alongside it. A repository
+/// can only be registered once, from its root or any directory inside it.
+pub fn add(conn: &mut SqliteConnection, path: &str) -> Result<Workspace, AppError> {
+    let (canonical, repo) = resolve_path(path)?;
+
+    conn.transaction(|conn| {
+        let registered = registered_roots(conn).context("Failed to check workspace path")?;
+        if let Some(workspace_id) = registered.get(Path::new(&repo.root)) {
+            return Err(
+                AppError::conflict("Repository already registered").with_details(json!({
+                    "path": canonical.to_string_lossy(),
+                    "repo_root": repo.root,
+                    "workspace_id": workspace_id,
+                })),
+            );
+        }
+
+        insert(conn, &canonical, &repo)
+    })
+}
+
+/// Register several repositories at once. Either every new path is added
+/// or none is; paths already registered are reported and skipped.
+pub fn add_many(
+    conn: &mut SqliteConnection,
+    paths: &[String],
+) -> Result<WorkspaceBulkAdd, AppError> {
+    let mut resolved = Vec::new();
+    let mut errors = Vec::new();
+    for path in paths {
+        match resolve_path(path) {
+            Ok(entry) => resolved.push(entry),
+            Err(e) => errors.push(format!("{path}: {}", e.message)),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(AppError::validation(format!(
+            "{} of {} path(s) can't be added",
+            errors.len(),
+            paths.len()
+        ))
+        .with_details(json!({ "errors": errors })));
+    }
+
+    conn.transaction(|conn| {
+        let mut registered = registered_roots(conn).context("Failed to check workspace paths")?;
+        let mut report = WorkspaceBulkAdd::default();
+
+        for (canonical, repo) in &resolved {
+            let root = PathBuf::from(&repo.root);
+            if registered.contains_key(&root) {
+                report
+                    .already_registered
+                    .push(canonical.to_string_lossy().to_string());
+                continue;
+            }
+            let workspace = insert(conn, canonical, repo)?;
+            // Two paths into the same repository in one request
+            registered.insert(root, workspace.id);
+            report.added.push(workspace);
+        }
+
+        Ok(report)
+    })
+}
+
+/// Register an already-resolved repository and record its git details.
+/// Callers check for duplicates first.
+fn insert(
+    conn: &mut SqliteConnection,
+    canonical: &Path,
+    repo: &RepoInfo,
+) -> Result<Workspace, AppError> {
+    let path = canonical.to_string_lossy().to_string();
+    let name = canonical
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unnamed")
+        .to_string();
+
+    diesel::insert_into(workspaces::table)
+        .values(&NewWorkspace {
+            name: &name,
+            path: &path,
+        })
+        .execute(conn)
+        .context("Failed to add workspace")?;
+
+    diesel::update(workspaces::table.filter(workspaces::path.eq(&path)))
+        .set(&repo.changeset())
+        .execute(conn)
+        .context("Failed to save repository details")?;
+
+    let workspace = workspaces::table
+        .filter(workspaces::path.eq(&path))
+        .select(Workspace::as_select())
+        .first(conn)
+        .context("Failed to fetch workspace")?;
+
+    events::record(
+        conn,
+        NewEvent::new(EventType::WorkspaceAdded)
+            .workspace(workspace.id)
+            .payload(json!({ "name": workspace.name, "path": workspace.path })),
+    );
+
+    Ok(workspace)
+}
+
+/// Re-read a workspace's repository details from git.
+pub fn refresh(conn: &mut SqliteConnection, workspace_id: i32) -> Result<Workspace, AppError> {
+    let workspace = workspaces::table
+        .filter(workspaces::id.eq(workspace_id))
+        .select(Workspace::as_select())
+        .first(conn)
+        .optional()?
+        .ok_or_else(|| {
+            AppError::not_found(format!("Workspace with id {workspace_id} not found"))
+        })?;
+
+    let path = Path::new(&workspace.path);
+    if !path.is_dir() {
+        return Err(AppError::not_found(format!(
+            "Workspace path {} no longer exists",
+            workspace.path
+        ))
+        .with_details(json!({ "workspace_id": workspace_id, "path": workspace.path })));
+    }
+    let repo = git::inspect(path)?.ok_or_else(|| {
+        AppError::validation(format!("{} is no longer a git repository", workspace.path))
+            .with_details(json!({ "workspace_id": workspace_id, "path": workspace.path }))
+    })?;
+
+    diesel::update(workspaces::table.filter(workspaces::id.eq(workspace_id)))
+        .set(&repo.changeset())
+        .execute(conn)?;
+
+    Ok(workspaces::table
+        .filter(workspaces::id.eq(workspace_id))
+        .select(Workspace::as_select())
+        .first(conn)?)
+}
+
+/// What `remove_workspace` did, returned to the caller.
+#[derive(Serialize, Debug)]
+pub struct WorkspaceRemoval {
+    pub workspace_id: i32,
+    pub mode: RemoveMode,
+    pub archived_project_ids: Vec<i32>,
+    pub deleted_project_ids: Vec<i32>,
+    pub removed_worktrees: Vec<String>,
+    pub worktree_errors: Vec<String>,
+}
+
+pub struct Removal {
+    pub report: WorkspaceRemoval,
+    pub transitions: Vec<Transition>,
+    repo_path: String,
+    /// (project id, worktree path) for each deleted project with a worktree
+    worktrees: Vec<(i32, String)>,
+    config: Option<WorkspaceConfig>,
+}
+
+/// Delete a workspace and deal with its projects according to `mode`, in a
+/// single transaction. Worktrees are left for `remove_worktrees` so git runs
+/// after the database work has committed.
+pub fn remove(
+    conn: &mut SqliteConnection,
+    workspace_id: i32,
+    mode: RemoveMode,
+) -> Result<Removal, AppError> {
+    conn.transaction(|conn| {
+        let workspace = workspaces::table
+            .filter(workspaces::id.eq(workspace_id))
+            .select(Workspace::as_select())
+            .first(conn)
+            .optional()?
+            .ok_or_else(|| {
+                AppError::not_found(format!("Workspace with id {workspace_id} not found"))
+            })?;
+
+        let owned: Vec<Project> = projects::table
+            .filter(projects::workspace_id.eq(workspace_id))
+            .select(Project::as_select())
+            .load(conn)?;
+        let project_ids: Vec<i32> = owned.iter().map(|p| p.id).collect();
+
+        let mut removal = Removal {
+            report: WorkspaceRemoval {
+                workspace_id,
+                mode,
+                archived_project_ids: Vec::new(),
+                deleted_project_ids: Vec::new(),
+                removed_worktrees: Vec::new(),
+                worktree_errors: Vec::new(),
+            },
+            transitions: Vec::new(),
+            repo_path: workspace.path.clone(),
+            worktrees: Vec::new(),
+            config: None,
+        };
+
+        match mode {
+            RemoveMode::Refuse => {
+                if !owned.is_empty() {
+                    return Err(AppError::conflict(format!(
+                        "Workspace '{}' still has {} project(s)",
+                        workspace.name,
+                        owned.len()
+                    ))
+                    .with_details(json!({
+                        "workspace_id": workspace_id,
+                        "project_ids": project_ids,
+                    })));
+                }
+            }
+            RemoveMode::Archive => {
+                for project in &owned {
+                    let transition = project_status::transition(
+                        conn,
+                        project.id,
+                        ProjectStatus::Archived,
+                        "workspace removed",
+                    )?;
+                    removal.transitions.push(transition);
+                }
+                removal.report.archived_project_ids = project_ids;
+            }
+            RemoveMode::Cascade => {
+                removal.worktrees = owned
+                    .iter()
+                    .filter_map(|p| Some((p.id, p.worktree_path.clone()?)))
+                    .collect();
+                // Read now, while the workspace row still exists, for the
+                // pre-remove hooks. A broken config just means no hooks.
+                if !removal.worktrees.is_empty() {
+                    removal.config = workspace_config::load(conn, workspace_id)
+                        .map_err(|e| eprintln!("[workspace] Skipping pre-remove hooks: {e}"))
+                        .ok();
+                }
+
+                diesel::delete(projects::table.filter(projects::workspace_id.eq(workspace_id)))
+                    .execute(conn)?;
+
+                for project in &owned {
+                    events::record(
+                        conn,
+                        NewEvent::new(EventType::ProjectDeleted)
+                            .workspace(workspace_id)
+                            .project(project.id)
+                            .payload(
+                                json!({ "name": project.name, "reason": "workspace removed" }),
+                            ),
+                    );
+                }
+                removal.report.deleted_project_ids = project_ids;
+            }
+        }
+
+        diesel::delete(
+            retry_policies::table
+                .filter(retry_policies::scope.eq(SCOPE_WORKSPACE))
+                .filter(retry_policies::scope_id.eq(workspace_id)),
+        )
+        .execute(conn)?;
+
+        // Archived projects are detached by the ON DELETE SET NULL
+        diesel::delete(workspaces::table.filter(workspaces::id.eq(workspace_id))).execute(conn)?;
+
+        events::record(
+            conn,
+            NewEvent::new(EventType::WorkspaceRemoved)
+                .workspace(workspace_id)
+                .payload(json!({
+                    "name": workspace.name,
+                    "path": workspace.path,
+                    "mode": mode,
+                    "archived": removal.report.archived_project_ids,
+                    "deleted": removal.report.deleted_project_ids,
+                })),
+        );
+
+        Ok(removal)
+    })
+}
+
+/// Remove the worktrees of projects deleted by a cascade, running the
+/// workspace's pre-remove hooks in each first. Failures are reported rather
+/// than returned since the database change already stands.
+pub fn remove_worktrees(db: &DbState, events: &dyn EventSink, removal: &mut Removal) {
+    for (project_id, path) in std::mem::take(&mut removal.worktrees) {
+        if let Some(config) = &removal.config {
+            let stage_hooks = config.hooks.for_stage(HookStage::PreWorktreeRemove);
+            if !stage_hooks.is_empty() && Path::new(&path).is_dir() {
+                let ran = hooks::run_stage(
+                    db,
+                    events,
+                    &HookContext {
+                        stage: HookStage::PreWorktreeRemove,
+                        workspace_id: Some(removal.report.workspace_id),
+                        project_id: Some(project_id),
+                        session_id: None,
+                        dir: Path::new(&path),
+                        source_dir: Path::new(&removal.repo_path),
+                        env: &config.env,
+                        timeout: config.hook_timeout(),
+                    },
+                    stage_hooks,
+                );
+                if let Err(e) = ran {
+                    eprintln!("[workspace] Pre-remove hooks for {path}: {e}");
+                }
+            }
+        }
+
+        match worktree::remove(&removal.repo_path, &path) {
+            Ok(()) => removal.report.removed_worktrees.push(path),
+            Err(e) => removal.report.worktree_errors.push(format!("{path}: {e}")),
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::constants::DEFAULT_CLI;
+use crate::env_profiles;
+use crate::error::AppError;
+use crate::hook_models::{Hook, Hooks};
+use crate::schema::workspaces;
+use crate::secrets;
+use crate::session::SpawnRequest;
+use crate::settings_registry;
+
+/// Looked up in this order; the first one found wins.
+const CONFIG_FILES: &[&str] = &[".dacm.toml", ".dacm/config.toml"];
+
+/// A workspace's `.dacm.toml` as written. Every field is optional and falls
+/// back to the global settings.
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    cli: Option<String>,
+    model: Option<String>,
+    /// Sessions allowed to run at once in this workspace
+    max_sessions: Option<u32>,
+    /// Run agents in tmux so they keep going after the app closes
+    persistent_sessions: Option<bool>,
+    /// Seconds before a hook is killed; 0 for no limit
+    hook_timeout_secs: Option<u64>,
+    /// Shorthand for `run` hooks after a worktree is created
+    #[serde(default)]
+    setup_commands: Vec<String>,
+    /// Commands run after a clean exit to decide if the work is done
+    #[serde(default)]
+    exit_criteria: Vec<String>,
+    #[serde(default)]
+    env: BTreeMap<String, String>,
+    /// Ignored: a repository mustn't choose which secrets its agents get.
+    /// Still accepted so files written for older versions load
+    secrets: Option<toml::Value>,
+    #[serde(default)]
+    worktree: WorktreeSection,
+    #[serde(default)]
+    hooks: Hooks,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct WorktreeSection {
+    base_path: Option<String>,
+    branch_pattern: Option<String>,
+}
+
+/// Settings in effect for one workspace: its config file merged over the
+/// global `settings` table.
+#[derive(Serialize, Debug, Clone)]
+pub struct WorkspaceConfig {
+    pub workspace_id: i32,
+    pub workspace_path: String,
+    /// The file that was read, if the workspace has one
+    pub config_file: Option<String>,
+    pub cli: String,
+    pub model: Option<String>,
+    /// 0 means no per-workspace limit
+    pub max_sessions: u32,
+    pub persistent_sessions: bool,
+    /// 0 means hooks may run as long as they like
+    pub hook_timeout_secs: u64,
+    /// Commands that must succeed for a session's work to count as done
+    /// (see `hooks::check_exit_criteria`)
+    pub exit_criteria: Vec<String>,
+    /// The file's `env` over the workspace's env profile
+    pub env: BTreeMap<String, String>,
+    /// Env var name to secret name, from the mappings set in the app (see
+    /// `secrets::set_mappings`). Only names; values stay in the store
+    pub secrets: BTreeMap<String, String>,
+    pub worktree_base_path: Option<String>,
+    pub worktree_branch_pattern: String,
+    pub hooks: Hooks,
+    /// Keys the config file set, so callers can tell its values from defaults
+    pub overridden: Vec<String>,
+}
+
+impl WorkspaceConfig {
+    /// How long each hook may run, if there's a limit.
+    pub fn hook_timeout(&self) -> Option<Duration> {
+        (self.hook_timeout_secs > 0).then(|| Duration::from_secs(self.hook_timeout_secs))
+    }
+
+    /// Fill in whatever the request left open and add the workspace's
+    /// environment. Values passed explicitly by the caller win.
+    pub fn apply(&self, request: &mut SpawnRequest) {
+        if request.cli_command.is_none() {
+            request.cli_command = Some(self.cli.clone());
+        }
+        if request.model.is_none() {
+            request.model = self.model.clone();
+        }
+        request.persistent = self.persistent_sessions;
+        for (key, value) in &self.env {
+            request
+                .env
+                .entry(key.clone())
+                .or_insert_with(|| value.clone());
+        }
+    }
+}
+
+/// Effective config for a workspace, read fresh from disk every time so
+/// edits to the file apply to the next spawn.
+pub fn load(conn: &mut SqliteConnection, workspace_id: i32) -> Result<WorkspaceConfig, AppError> {
+    let path: String = workspaces::table
+        .filter(workspaces::id.eq(workspace_id))
+        .select(workspaces::path)
+        .first(conn)
+        .optional()?
+        .ok_or_else(|| {
+            AppError::not_found(format!("Workspace with id {workspace_id} not found"))
+        })?;
+
+    let (config_file, mut file) = match find_config_file(Path::new(&path)) {
+        Some(file_path) => {
+            let parsed = parse(&file_path)?;
+            (Some(file_path.to_string_lossy().to_string()), parsed)
+        }
+        None => (None, ConfigFile::default()),
+    };
+
+    let mut overridden = Vec::new();
+    let mut mark = |key: &str, set: bool| {
+        if set {
+            overridden.push(key.to_string());
+        }
+    };
+    mark("cli", file.cli.is_some());
+    mark("model", file.model.is_some());
+    mark("max_sessions", file.max_sessions.is_some());
+    mark("persistent_sessions", file.persistent_sessions.is_some());
+    mark("hook_timeout_secs", file.hook_timeout_secs.is_some());
+    mark("setup_commands", !file.setup_commands.is_empty());
+    mark("hooks", !file.hooks.is_empty());
+    mark("exit_criteria", !file.exit_criteria.is_empty());
+    mark("env", !file.env.is_empty());
+    mark("worktree.base_path", file.worktree.base_path.is_some());
+    mark(
+        "worktree.branch_pattern",
+        file.worktree.branch_pattern.is_some(),
+    );
+
+    if file.secrets.is_some() {
+        eprintln!(
+            "[config] Ignoring [secrets] in {}; map secrets to workspaces in the app",
+            config_file.as_deref().unwrap_or_default()
+        );
+    }
+
+    let max_sessions = match file.max_sessions {
+        Some(limit) => limit,
+        None => settings_registry::get_int(conn, "max_sessions_per_workspace")?
+            .and_then(|n| u32::try_from(n).ok())
+            .unwrap_or(0),
+    };
+
+    let setup = file
+        .setup_commands
+        .drain(..)
+        .map(|command| Hook::Run { command });
+    file.hooks.post_worktree_create.splice(0..0, setup);
+
+    Ok(WorkspaceConfig {
+        workspace_id,
+        workspace_path: path,
+        config_file,
+        cli: file.cli.unwrap_or_else(|| DEFAULT_CLI.to_string()),
+        model: match file.model {
+            Some(model) => Some(model),
+            None => settings_registry::get_text(conn, "selected_model_id")?,
+        },
+        max_sessions,
+        persistent_sessions: match file.persistent_sessions {
+            Some(persistent) => persistent,
+            None => settings_registry::get_bool(conn, "persistent_sessions")?,
+        },
+        hook_timeout_secs: match file.hook_timeout_secs {
+            Some(secs) => secs,
+            None => settings_registry::get_int(conn, "hook_timeout_secs")?
+                .and_then(|n| u64::try_from(n).ok())
+                .unwrap_or(0),
+        },
+        exit_criteria: file.exit_criteria,
+        env: {
+            // The file's variables win over the workspace's env profile
+            let mut env = env_profiles::workspace_vars(conn, workspace_id)?;
+            env.extend(file.env);
+            env
+        },
+        secrets: secrets::mappings(conn, workspace_id)?,
+        worktree_base_path: match file.worktree.base_path {
+            Some(base) => Some(base),
+            None => settings_registry::get_text(conn, "worktree_base_path")?,
+        },
+        worktree_branch_pattern: match file.worktree.branch_pattern {
+            Some(pattern) => pattern,
+            None => settings_registry::get_raw(conn, "worktree_branch_pattern")?,
+        },
+        hooks: file.hooks,
+        overridden,
+    })
+}
+
+fn find_config_file(root: &Path) -> Option<PathBuf> {
+    CONFIG_FILES
+        .iter()
+        .map(|name| root.join(name))
+        .find(|path| path.is_file())
+}
+
+fn parse(path: &Path) -> Result<ConfigFile, AppError> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| AppError::io(format!("Failed to read {}: {e}", path.display())))?;
+
+    toml::from_str(&contents).map_err(|e| {
+        let (line, column) = e
+            .span()
+            .map(|span| line_and_column(&contents, span.start))
+            .unwrap_or((1, 1));
+        AppError::validation(format!(
+            "{}:{line}:{column}: {}",
+            path.display(),
+            e.message()
+        ))
+        .with_details(json!({
+            "path": path.to_string_lossy(),
+            "line": line,
+            "column": column,
+            "message": e.message(),
+        }))
+    })
+}
+
+/// 1-based line and column of a byte offset.
+fn line_and_column(contents: &str, offset: usize) -> (usize, usize) {
+    let before = contents.get(..offset).unwrap_or(contents);
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rfind('\n')
+        .map_or(before.len(), |i| before.len() - i - 1)
+        + 1;
+    (line, column)
+}
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::error::AppError;
+use crate::git;
+use crate::models::{ScanProgress, WorkspaceCandidate};
+
+pub const DEFAULT_MAX_DEPTH: usize = 4;
+pub const MAX_DEPTH: usize = 10;
+
+/// Directories that never hold repositories worth registering and can be
+/// enormous.
+const SKIPPED_DIRS: &[&str] = &["node_modules", "target"];
+
+/// Progress is reported every this many directories, as well as whenever a
+/// repository turns up.
+const PROGRESS_EVERY: usize = 100;
+
+/// Find git repositories under `root`, at most `max_depth` levels down.
+/// Repositories aren't descended into, and worktrees and submodules (whose
+/// `.git` is a file rather than a directory) aren't reported. Symlinks are
+/// not followed.
+pub fn scan(
+    root: &str,
+    max_depth: usize,
+    registered: &HashMap<PathBuf, i32>,
+    mut on_progress: impl FnMut(ScanProgress),
+) -> Result<Vec<WorkspaceCandidate>, AppError> {
+    let root = std::fs::canonicalize(root.trim())
+        .map_err(|e| AppError::not_found(format!("Path {root} is not accessible: {e}")))?;
+    if !root.is_dir() {
+        return Err(AppError::validation(format!(
+            "{} is not a directory",
+            root.display()
+        )));
+    }
+    let root_label = root.to_string_lossy().to_string();
+
+    let mut candidates = Vec::new();
+    let mut dirs_scanned = 0;
+    let mut pending = vec![(root, 0)];
+
+    while let Some((dir, depth)) = pending.pop() {
+        dirs_scanned += 1;
+
+        let is_repo = match classify(&dir) {
+            GitDir::Repository => true,
+            GitDir::Linked => continue,
+            GitDir::None => false,
+        };
+
+        if is_repo {
+            if let Some(candidate) = candidate(&dir, registered) {
+                candidates.push(candidate);
+            }
+        } else if depth < max_depth {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                // Unreadable directories are common (permissions) and not fatal
+                continue;
+            };
+            for entry in entries.flatten() {
+                let Ok(file_type) = entry.file_type() else {
+                    continue;
+                };
+                if !file_type.is_dir() {
+                    continue;
+                }
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                if name.starts_with('.') || SKIPPED_DIRS.contains(&name.as_ref()) {
+                    continue;
+                }
+                pending.push((entry.path(), depth + 1));
+            }
+        }
+
+        if is_repo || dirs_scanned % PROGRESS_EVERY == 0 {
+            on_progress(ScanProgress {
+                root: root_label.clone(),
+                dirs_scanned,
+                repos_found: candidates.len(),
+                current: dir.to_string_lossy().to_string(),
+            });
+        }
+    }
+
+    on_progress(ScanProgress {
+        root: root_label.clone(),
+        dirs_scanned,
+        repos_found: candidates.len(),
+        current: root_label,
+    });
+
+    candidates.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(candidates)
+}
+
+enum GitDir {
+    /// `.git` is a directory: a regular repository
+    Repository,
+    /// `.git` is a file pointing elsewhere: a worktree or submodule
+    Linked,
+    None,
+}
+
+fn classify(dir: &Path) -> GitDir {
+    match std::fs::symlink_metadata(dir.join(".git")) {
+        Ok(meta) if meta.is_dir() => GitDir::Repository,
+        Ok(_) => GitDir::Linked,
+        Err(_) => GitDir::None,
+    }
+}
+
+fn candidate(dir: &Path, registered: &HashMap<PathBuf, i32>) -> Option<WorkspaceCandidate> {
+    let repo = match git::inspect(dir) {
+        Ok(Some(repo)) => repo,
+        Ok(None) => return None,
+        Err(e) => {
+            eprintln!("[scan] Skipping {}: {e}", dir.display());
+            return None;
+        }
+    };
+    let workspace_id = registered.get(dir).copied();
+
+    Some(WorkspaceCandidate {
+        path: dir.to_string_lossy().to_string(),
+        name: dir
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("unnamed")
+            .to_string(),
+        default_branch: repo.default_branch,
+        head_branch: repo.head_branch,
+        remotes: repo.remotes,
+        workspace_id,
+        registered: workspace_id.is_some(),
+    })
+}
@@ -0,0 +1,110 @@
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::error::AppError;
+use crate::git;
+use crate::hook_models::HookRun;
+use crate::project_models::Project;
+use crate::workspace_config::WorkspaceConfig;
+
+/// Result of `create_worktree`.
+#[derive(Serialize, Debug)]
+pub struct WorktreeCreated {
+    pub project: Project,
+    pub path: String,
+    pub branch: String,
+    /// Post-create hooks that ran, stopping at the first failure
+    pub hooks: Vec<HookRun>,
+}
+
+/// Where a project's worktree lives and which branch it checks out, from the
+/// workspace config's base path and branch pattern.
+pub fn plan(repo_path: &str, project: &Project, config: &WorkspaceConfig) -> (PathBuf, String) {
+    let slug = slugify(&project.name, project.id);
+    let branch = config
+        .worktree_branch_pattern
+        .replace("{project_name}", &slug)
+        .replace("{project_id}", &project.id.to_string());
+
+    let repo = Path::new(repo_path);
+    let base = match config.worktree_base_path.as_deref() {
+        // Relative base paths are taken from the repository root
+        Some(base) => repo.join(base),
+        None => {
+            let repo_name = repo
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| "repo".to_string());
+            repo.parent()
+                .unwrap_or(repo)
+                .join(format!("{repo_name}-worktrees"))
+        }
+    };
+
+    (base.join(&slug), branch)
+}
+
+fn slugify(name: &str, id: i32) -> String {
+    let mut slug = String::new();
+    for c in name.trim().chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        format!("project-{id}")
+    } else {
+        slug.to_string()
+    }
+}
+
+/// Add a worktree at `path` on `branch`, creating the branch from
+/// `start_point` unless it already exists.
+pub fn create(
+    repo_path: &str,
+    path: &Path,
+    branch: &str,
+    start_point: Option<&str>,
+) -> Result<(), AppError> {
+    if path.exists() {
+        return Err(AppError::conflict(format!(
+            "Worktree path {} already exists",
+            path.display()
+        )));
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let repo = Path::new(repo_path);
+    let path_arg = path.to_string_lossy();
+    let reference = format!("refs/heads/{branch}");
+    let branch_exists = git::run(repo, &["show-ref", "--verify", "--quiet", &reference]).is_ok();
+
+    if branch_exists {
+        git::run(repo, &["worktree", "add", &path_arg, branch])?;
+    } else {
+        let mut args = vec!["worktree", "add", "-b", branch, &path_arg];
+        if let Some(start) = start_point {
+            args.push(start);
+        }
+        git::run(repo, &args)?;
+    }
+    Ok(())
+}
+
+/// Remove a project's worktree through git so the repository's worktree list
+/// stays in sync. A directory that's already gone is pruned instead.
+pub fn remove(repo_path: &str, worktree_path: &str) -> Result<(), AppError> {
+    let repo = Path::new(repo_path);
+    if !Path::new(worktree_path).exists() {
+        git::run(repo, &["worktree", "prune"])?;
+        return Ok(());
+    }
+    git::run(repo, &["worktree", "remove", "--force", worktree_path])?;
+    Ok(())
+}
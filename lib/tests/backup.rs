@@ -0,0 +1,131 @@
+//! Export and import of user data between two throwaway databases.
+
+mod common;
+
+use diesel::prelude::*;
+use serde_json::json;
+
+use common::Harness;
+use dacm_lib::backup;
+use dacm_lib::decisions;
+use dacm_lib::error::ErrorCode;
+use dacm_lib::plan_models::{PlanContent, StepStatus};
+use dacm_lib::plans;
+use dacm_lib::review_models::NewReviewComment;
+use dacm_lib::reviews;
+use dacm_lib::schema::projects;
+
+fn content() -> PlanContent {
+    serde_json::from_value(json!({
+        "goal": "Ship it",
+        "phases": [{
+            "id": "1",
+            "name": "Build",
+            "steps": [{ "id": "1.1", "name": "Write it" }],
+        }],
+    }))
+    .unwrap()
+}
+
+#[test]
+fn project_records_follow_their_project() {
+    let from = Harness::new();
+    let mut conn = from.db.conn().unwrap();
+    plans::save(&mut conn, from.project_id, "First", &content()).unwrap();
+    plans::save(&mut conn, from.project_id, "Second", &content()).unwrap();
+    plans::update_step(
+        &mut conn,
+        from.project_id,
+        "1.1",
+        StepStatus::Blocked,
+        Some("why"),
+    )
+    .unwrap();
+    let comment = reviews::add_comment(
+        &mut conn,
+        &NewReviewComment {
+            project_id: from.project_id,
+            file_path: Some("src/main.rs".into()),
+            line: Some(3),
+            body: "Handle the error".into(),
+        },
+    )
+    .unwrap();
+    reviews::set_resolved(&mut conn, comment.id, true).unwrap();
+    decisions::log(
+        &mut conn,
+        from.project_id,
+        Some("session-1"),
+        "Use SQLite",
+        "It's embedded",
+        &["Postgres".to_string()],
+    )
+    .unwrap();
+
+    let bundle = backup::export(&mut conn).unwrap();
+    assert_eq!(
+        (
+            bundle.plans.len(),
+            bundle.review_comments.len(),
+            bundle.decisions.len()
+        ),
+        (2, 1, 1)
+    );
+
+    // The second database already has a project, so ids can't line up
+    let to = Harness::new();
+    let mut conn = to.db.conn().unwrap();
+    let report = backup::import(&mut conn, &bundle).unwrap();
+    assert_eq!(report.plans_imported, 2);
+    assert_eq!(report.review_comments_imported, 1);
+    assert_eq!(report.decisions_imported, 1);
+
+    let imported: i32 = projects::table
+        .filter(projects::id.ne(to.project_id))
+        .select(projects::id)
+        .first(&mut conn)
+        .unwrap();
+
+    let history = plans::history(&mut conn, imported).unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].name, "Second");
+    let step = history[0].content().steps().next().cloned().unwrap();
+    assert_eq!(
+        (step.status, step.note.as_deref()),
+        (StepStatus::Blocked, Some("why"))
+    );
+
+    let comments = reviews::list(&mut conn, imported, true).unwrap();
+    assert_eq!(comments.len(), 1);
+    assert!(comments[0].resolved);
+    assert_eq!(comments[0].line, Some(3));
+
+    let logged = decisions::list(&mut conn, imported).unwrap();
+    assert_eq!(logged.len(), 1);
+    assert_eq!(logged[0].decision, "Use SQLite");
+    assert_eq!(logged[0].alternatives, bundle.decisions[0].alternatives);
+
+    assert!(plans::history(&mut conn, to.project_id).unwrap().is_empty());
+}
+
+#[test]
+fn records_of_unknown_projects_are_rejected() {
+    let h = Harness::new();
+    let mut conn = h.db.conn().unwrap();
+    plans::save(&mut conn, h.project_id, "Plan", &content()).unwrap();
+    let mut bundle = backup::export(&mut conn).unwrap();
+    bundle.projects.clear();
+
+    let err = backup::import(&mut conn, &bundle).unwrap_err();
+    assert_eq!(err.code, ErrorCode::Validation);
+    let errors = err.details.unwrap()["errors"].clone();
+    assert!(
+        errors
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|e| e.as_str().unwrap().starts_with("Plan ")),
+        "{errors}"
+    );
+    assert_eq!(plans::history(&mut conn, h.project_id).unwrap().len(), 1);
+}
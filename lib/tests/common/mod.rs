@@ -0,0 +1,279 @@
+//! Harness for the integration tests: a throwaway data dir with a migrated
+//! database, one workspace and project, and sessions running `fake-agent`
+//! in real PTYs.
+
+#![allow(dead_code)]
+
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use diesel::prelude::*;
+use tempfile::TempDir;
+
+use dacm_lib::db::DbState;
+use dacm_lib::event_sink::CollectedEvents;
+use dacm_lib::mcp::McpServer;
+use dacm_lib::mcp_models::Agent;
+use dacm_lib::models::NewWorkspace;
+use dacm_lib::projects;
+use dacm_lib::schema::workspaces;
+use dacm_lib::secret_file::SecretFile;
+use dacm_lib::secrets::SecretStore;
+use dacm_lib::session::{SessionExit, SessionManager, SpawnRequest};
+use dacm_lib::spawn::Spawner;
+
+pub const FAKE_AGENT: &str = env!("CARGO_BIN_EXE_fake-agent");
+
+/// How long to wait for output or an exit before failing the test.
+pub const TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct Harness {
+    pub db: DbState,
+    pub secrets: SecretStore,
+    pub sessions: SessionManager,
+    pub events: CollectedEvents,
+    pub workspace_id: i32,
+    pub project_id: i32,
+    pub data_dir: PathBuf,
+    /// Where the workspace lives; sessions start here
+    pub workspace_dir: PathBuf,
+    // Last, so everything above is dropped before the files go
+    _dir: TempDir,
+}
+
+impl Harness {
+    pub fn new() -> Self {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let data_dir = dir.path().join("data");
+        let workspace_dir = dir.path().join("workspace");
+        std::fs::create_dir_all(&data_dir).expect("create data dir");
+        std::fs::create_dir_all(&workspace_dir).expect("create workspace dir");
+
+        let db = DbState::open(&data_dir);
+        let (workspace_id, project_id) = {
+            let mut conn = db.conn().expect("open database");
+            let path = workspace_dir.to_string_lossy();
+            diesel::insert_into(workspaces::table)
+                .values(&NewWorkspace {
+                    name: "workspace",
+                    path: &path,
+                })
+                .execute(&mut conn)
+                .expect("insert workspace");
+            let workspace_id = workspaces::table
+                .select(workspaces::id)
+                .first(&mut conn)
+                .expect("fetch workspace");
+            let project = projects::create(&mut conn, workspace_id, "project", "fix the tests")
+                .expect("create project");
+            (workspace_id, project.id)
+        };
+
+        Self {
+            secrets: SecretStore::with_file(SecretFile::with_passphrase(
+                &data_dir,
+                Some("test passphrase".to_string()),
+            )),
+            sessions: SessionManager::new(&data_dir),
+            events: CollectedEvents::new(),
+            db,
+            workspace_id,
+            project_id,
+            data_dir,
+            workspace_dir,
+            _dir: dir,
+        }
+    }
+
+    /// A request that runs `fake-agent` with `script` in the workspace.
+    pub fn request(&self, script: &str) -> SpawnRequest {
+        SpawnRequest {
+            project_id: self.project_id,
+            workspace_id: self.workspace_id,
+            working_dir: self.workspace_dir.to_string_lossy().to_string(),
+            initial_prompt: None,
+            cli_command: Some(FAKE_AGENT.to_string()),
+            model: None,
+            rows: 24,
+            cols: 80,
+            resume_session_id: None,
+            continue_last: false,
+            env: BTreeMap::from([("FAKE_AGENT_SCRIPT".to_string(), script.to_string())]),
+            secret_env: BTreeMap::new(),
+            persistent: false,
+        }
+    }
+
+    /// An MCP server answering `agent` the way the app's socket does.
+    pub fn mcp<'a>(&'a self, agent: &'a Agent) -> McpServer<'a> {
+        McpServer {
+            db: &self.db,
+            events: &self.events,
+            agent,
+        }
+    }
+
+    pub fn spawner(&self) -> Spawner<'_> {
+        Spawner {
+            db: &self.db,
+            secrets: &self.secrets,
+            sessions: &self.sessions,
+            events: &self.events,
+        }
+    }
+
+    /// Start `script` straight on the session manager and follow its output.
+    pub fn spawn(&self, script: &str) -> Session {
+        self.start(self.request(script))
+    }
+
+    pub fn start(&self, request: SpawnRequest) -> Session {
+        let id = self.sessions.spawn(request).expect("spawn session");
+        self.follow(id)
+    }
+
+    /// Follow the output of a session that's already running.
+    pub fn follow(&self, id: String) -> Session {
+        let reader = self.sessions.take_reader(&id).expect("take reader");
+        Session {
+            output: Output::read(reader),
+            id,
+        }
+    }
+
+    /// What the app does once a session's output ends: reap it, mark it
+    /// exited and drop it from the manager.
+    pub fn finish(&self, session: &Session) -> SessionExit {
+        session.output.wait_closed();
+        let exit = self
+            .sessions
+            .wait_exit(&session.id)
+            .expect("session to have exited");
+        self.sessions.mark_exited(&session.id);
+        self.sessions.remove(&session.id).expect("remove session");
+        exit
+    }
+}
+
+pub struct Session {
+    pub id: String,
+    pub output: Output,
+}
+
+impl Session {
+    pub fn wait_for(&self, text: &str) -> String {
+        self.output.wait_for(text)
+    }
+}
+
+/// Everything a session has printed, collected on a thread of its own the
+/// way the app's output stream reads it.
+#[derive(Clone)]
+pub struct Output {
+    shared: Arc<(Mutex<Collected>, Condvar)>,
+}
+
+#[derive(Default)]
+struct Collected {
+    bytes: Vec<u8>,
+    closed: bool,
+}
+
+impl Output {
+    fn read(mut reader: Box<dyn Read + Send>) -> Self {
+        let output = Self {
+            shared: Arc::default(),
+        };
+        let shared = output.shared.clone();
+        std::thread::spawn(move || {
+            let mut buf = [0u8; 4096];
+            loop {
+                let n = reader.read(&mut buf).unwrap_or(0);
+                let (collected, changed) = &*shared;
+                let mut collected = collected.lock().unwrap();
+                if n == 0 {
+                    collected.closed = true;
+                } else {
+                    collected.bytes.extend_from_slice(&buf[..n]);
+                }
+                changed.notify_all();
+                if collected.closed {
+                    break;
+                }
+            }
+        });
+        output
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.shared.0.lock().unwrap().bytes).into_owned()
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.shared.0.lock().unwrap().closed
+    }
+
+    /// Wait until `text` has been printed, returning everything so far.
+    pub fn wait_for(&self, text: &str) -> String {
+        let collected = self.wait_until(&format!("{text:?}"), |c| {
+            String::from_utf8_lossy(&c.bytes).contains(text)
+        });
+        String::from_utf8_lossy(&collected.bytes).into_owned()
+    }
+
+    /// Wait for the PTY to close, returning everything it printed.
+    pub fn wait_closed(&self) -> String {
+        let collected = self.wait_until("the output to end", |c| c.closed);
+        String::from_utf8_lossy(&collected.bytes).into_owned()
+    }
+
+    fn wait_until(
+        &self,
+        what: &str,
+        done: impl Fn(&Collected) -> bool,
+    ) -> MutexGuard<'_, Collected> {
+        let (collected, changed) = &*self.shared;
+        let deadline = Instant::now() + TIMEOUT;
+        let mut collected = collected.lock().unwrap();
+        while !done(&collected) {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                panic!(
+                    "timed out waiting for {what}; output so far:\n{}",
+                    String::from_utf8_lossy(&collected.bytes)
+                );
+            }
+            collected = changed.wait_timeout(collected, remaining).unwrap().0;
+        }
+        collected
+    }
+}
+
+/// Whether `pid` is still a live process. Zombies count as gone: they've
+/// exited and are only waiting for a parent to reap them.
+#[cfg(target_os = "linux")]
+pub fn is_alive(pid: u32) -> bool {
+    let Ok(stat) = std::fs::read_to_string(format!("/proc/{pid}/stat")) else {
+        return false;
+    };
+    // The state follows the parenthesized command name
+    let state = stat
+        .rsplit_once(") ")
+        .and_then(|(_, rest)| rest.chars().next());
+    !matches!(state, None | Some('Z') | Some('X'))
+}
+
+/// Poll `condition` until it holds or `TIMEOUT` passes.
+pub fn eventually(mut condition: impl FnMut() -> bool) -> bool {
+    let deadline = Instant::now() + TIMEOUT;
+    while Instant::now() < deadline {
+        if condition() {
+            return true;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    condition()
+}
@@ -0,0 +1,341 @@
+//! The MCP server agents report back through, against a throwaway database.
+
+#![cfg(unix)]
+
+mod common;
+
+use serde_json::{json, Value};
+
+use common::Harness;
+use dacm_lib::decisions;
+use dacm_lib::mcp::{McpServer, ServerCommand};
+use dacm_lib::mcp_models::Agent;
+use dacm_lib::plan_models::{PlanContent, PlanPhase, PlanStep, StepStatus};
+use dacm_lib::plans;
+use dacm_lib::project_models::ProjectStatus;
+use dacm_lib::projects;
+use dacm_lib::review_models::NewReviewComment;
+use dacm_lib::reviews;
+use dacm_lib::session::SessionManager;
+
+fn agent(h: &Harness) -> Agent {
+    Agent {
+        project_id: h.project_id,
+        session_id: Some("session-1".to_string()),
+    }
+}
+
+fn request(server: &McpServer, method: &str, params: Value) -> Value {
+    let response = server
+        .handle(json!({ "jsonrpc": "2.0", "id": 7, "method": method, "params": params }))
+        .expect("a response");
+    assert_eq!(response["id"], 7);
+    response
+}
+
+/// Call a tool, returning its text and whether it failed.
+fn call(server: &McpServer, tool: &str, arguments: Value) -> (String, bool) {
+    let response = request(
+        server,
+        "tools/call",
+        json!({ "name": tool, "arguments": arguments }),
+    );
+    let result = &response["result"];
+    let text = result["content"][0]["text"].as_str().unwrap().to_string();
+    (text, result["isError"].as_bool().unwrap())
+}
+
+fn event_names(h: &Harness) -> Vec<String> {
+    h.events.take().into_iter().map(|(name, _)| name).collect()
+}
+
+fn save_plan(h: &Harness) {
+    let step = |id: &str, name: &str| PlanStep {
+        id: id.to_string(),
+        name: name.to_string(),
+        status: StepStatus::Pending,
+        details: String::new(),
+        files: vec![],
+        note: None,
+    };
+    let content = PlanContent {
+        goal: "Ship it".to_string(),
+        constraints: vec![],
+        phases: vec![PlanPhase {
+            id: "1".to_string(),
+            name: "Build".to_string(),
+            risk: None,
+            exit_criteria: Some("cargo test".to_string()),
+            steps: vec![step("1.1", "Write it"), step("1.2", "Test it")],
+        }],
+    };
+    let mut conn = h.db.conn().unwrap();
+    plans::save(&mut conn, h.project_id, "Ship it", &content).unwrap();
+}
+
+#[test]
+fn initialize_and_list_tools() {
+    let h = Harness::new();
+    let agent = agent(&h);
+    let server = h.mcp(&agent);
+
+    let response = request(
+        &server,
+        "initialize",
+        json!({ "protocolVersion": "2025-03-26", "capabilities": {} }),
+    );
+    assert_eq!(response["result"]["protocolVersion"], "2025-03-26");
+    assert_eq!(response["result"]["serverInfo"]["name"], "dacm");
+
+    let notification = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+    assert!(server.handle(notification).is_none());
+
+    let response = request(&server, "tools/list", json!({}));
+    let mut tools: Vec<&str> = response["result"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["name"].as_str().unwrap())
+        .collect();
+    tools.sort();
+    assert_eq!(
+        tools,
+        [
+            "get_plan",
+            "get_review_comments",
+            "log_decision",
+            "report_progress",
+            "request_review",
+            "update_plan_step",
+        ]
+    );
+
+    let response = request(&server, "resources/list", json!({}));
+    assert_eq!(response["error"]["code"], -32601);
+    let response = request(&server, "tools/call", json!({ "name": "nope" }));
+    assert_eq!(response["error"]["code"], -32602);
+}
+
+#[test]
+fn plan_is_read_and_updated() {
+    let h = Harness::new();
+    let agent = agent(&h);
+    let server = h.mcp(&agent);
+
+    let (text, failed) = call(&server, "get_plan", json!({}));
+    assert!(!failed);
+    assert!(text.contains("no plan"), "{text}");
+
+    save_plan(&h);
+    let (text, _) = call(&server, "get_plan", json!({}));
+    let plan: Value = serde_json::from_str(&text).unwrap();
+    assert_eq!(plan["plan"]["phases"][0]["steps"][1]["id"], "1.2");
+
+    let (_, failed) = call(
+        &server,
+        "update_plan_step",
+        json!({ "step_id": "1.2", "status": "blocked", "note": "needs a fixture" }),
+    );
+    assert!(!failed);
+    assert_eq!(event_names(&h), ["plan-changed"]);
+
+    let mut conn = h.db.conn().unwrap();
+    let content = plans::current(&mut conn, h.project_id)
+        .unwrap()
+        .unwrap()
+        .content();
+    let step = content.steps().find(|s| s.id == "1.2").unwrap();
+    assert_eq!(step.status, StepStatus::Blocked);
+    assert_eq!(step.note.as_deref(), Some("needs a fixture"));
+
+    let (text, failed) = call(
+        &server,
+        "update_plan_step",
+        json!({ "step_id": "9.9", "status": "completed" }),
+    );
+    assert!(failed);
+    assert!(text.contains("9.9"), "{text}");
+    let (_, failed) = call(
+        &server,
+        "update_plan_step",
+        json!({ "step_id": "1.1", "status": "done" }),
+    );
+    assert!(failed);
+}
+
+#[test]
+fn progress_updates_the_summary() {
+    let h = Harness::new();
+    let agent = agent(&h);
+    let server = h.mcp(&agent);
+
+    let (_, failed) = call(
+        &server,
+        "report_progress",
+        json!({ "summary": "Tests written, fixing the last one", "percent": 80 }),
+    );
+    assert!(!failed);
+
+    let events = h.events.take();
+    let (name, payload) = &events[0];
+    assert_eq!(name, "project-progress");
+    assert_eq!(payload["percent"], 80);
+    assert_eq!(payload["session_id"], "session-1");
+
+    let mut conn = h.db.conn().unwrap();
+    let project = projects::get(&mut conn, h.project_id).unwrap();
+    assert_eq!(project.summary, "Tests written, fixing the last one");
+
+    let (_, failed) = call(&server, "report_progress", json!({ "summary": "  " }));
+    assert!(failed);
+}
+
+#[test]
+fn review_request_waits_for_the_user() {
+    let h = Harness::new();
+    let agent = agent(&h);
+    let server = h.mcp(&agent);
+
+    let (_, failed) = call(
+        &server,
+        "request_review",
+        json!({ "summary": "Look at the parser" }),
+    );
+    assert!(!failed);
+    assert_eq!(
+        event_names(&h),
+        ["project-status-changed", "review-requested"]
+    );
+    let mut conn = h.db.conn().unwrap();
+    let project = projects::get(&mut conn, h.project_id).unwrap();
+    assert_eq!(project.status, ProjectStatus::Waiting);
+
+    // Asking again changes nothing but is still passed on
+    drop(conn);
+    call(&server, "request_review", json!({ "summary": "Again" }));
+    assert_eq!(event_names(&h), ["review-requested"]);
+}
+
+#[test]
+fn review_comments_are_read_back() {
+    let h = Harness::new();
+    let agent = agent(&h);
+    let server = h.mcp(&agent);
+
+    let (text, _) = call(&server, "get_review_comments", json!({}));
+    assert!(text.contains("no review comments"), "{text}");
+
+    let (first, second) = {
+        let mut conn = h.db.conn().unwrap();
+        let mut add = |body: &str| {
+            reviews::add_comment(
+                &mut conn,
+                &NewReviewComment {
+                    project_id: h.project_id,
+                    file_path: Some("src/lib.rs".to_string()),
+                    line: Some(12),
+                    body: body.to_string(),
+                },
+            )
+            .unwrap()
+        };
+        (add("Handle the empty case"), add("Rename this"))
+    };
+    let mut conn = h.db.conn().unwrap();
+    reviews::set_resolved(&mut conn, first.id, true).unwrap();
+    drop(conn);
+
+    let (text, _) = call(&server, "get_review_comments", json!({}));
+    let comments: Vec<Value> = serde_json::from_str(&text).unwrap();
+    assert_eq!(comments.len(), 1);
+    assert_eq!(comments[0]["id"], second.id);
+    assert_eq!(comments[0]["line"], 12);
+
+    let (text, _) = call(
+        &server,
+        "get_review_comments",
+        json!({ "include_resolved": true }),
+    );
+    let comments: Vec<Value> = serde_json::from_str(&text).unwrap();
+    assert_eq!(comments.len(), 2);
+}
+
+#[test]
+fn decisions_are_logged() {
+    let h = Harness::new();
+    let agent = agent(&h);
+    let server = h.mcp(&agent);
+
+    let (_, failed) = call(
+        &server,
+        "log_decision",
+        json!({
+            "decision": "Use SQLite for the cache",
+            "rationale": "Already a dependency",
+            "alternatives": ["sled", "files"],
+        }),
+    );
+    assert!(!failed);
+    assert_eq!(event_names(&h), ["decision-logged"]);
+
+    let mut conn = h.db.conn().unwrap();
+    let logged = decisions::list(&mut conn, h.project_id).unwrap();
+    assert_eq!(logged.len(), 1);
+    assert_eq!(logged[0].session_id.as_deref(), Some("session-1"));
+    let encoded = serde_json::to_value(&logged[0]).unwrap();
+    assert_eq!(encoded["alternatives"], json!(["sled", "files"]));
+}
+
+#[test]
+fn default_cli_gets_the_mcp_config() {
+    let mut h = Harness::new();
+    h.sessions = SessionManager::new(&h.data_dir).with_mcp(ServerCommand {
+        program: "/opt/dacm/dacm".into(),
+        socket: "/tmp/dacm.sock".into(),
+    });
+    // Only the default CLI is given the flag, so run the fake under its name
+    let claude = h.data_dir.join("claude");
+    std::os::unix::fs::symlink(common::FAKE_AGENT, &claude).unwrap();
+
+    let mut request = h.request("args;exit:0");
+    request.cli_command = Some(claude.to_string_lossy().to_string());
+    request.initial_prompt = Some("fix the tests".to_string());
+    let session = h.start(request);
+    let output = session.output.wait_closed();
+    h.finish(&session);
+
+    let args = output
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("args: "))
+        .unwrap_or_else(|| panic!("no args in {output:?}"));
+    let (flag, prompt) = args.split_once(' ').unwrap();
+    assert_eq!(prompt, "fix the tests");
+    let config: Value = serde_json::from_str(flag.strip_prefix("--mcp-config=").unwrap()).unwrap();
+    let server = &config["mcpServers"]["dacm"];
+    assert_eq!(server["command"], "/opt/dacm/dacm");
+    assert_eq!(
+        server["args"],
+        json!([
+            "--socket",
+            "/tmp/dacm.sock",
+            "mcp",
+            "--project",
+            h.project_id.to_string(),
+            "--session",
+            session.id,
+        ])
+    );
+}
+
+#[test]
+fn other_clis_are_left_alone() {
+    let mut h = Harness::new();
+    h.sessions = SessionManager::new(&h.data_dir).with_mcp(ServerCommand {
+        program: "/opt/dacm/dacm".into(),
+        socket: "/tmp/dacm.sock".into(),
+    });
+    let session = h.spawn("args;exit:0");
+    let output = session.output.wait_closed();
+    h.finish(&session);
+    assert!(!output.contains("--mcp-config"), "{output}");
+}
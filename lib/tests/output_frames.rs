@@ -0,0 +1,64 @@
+//! Gathering PTY reads into frames, on a made-up clock.
+
+use std::time::{Duration, Instant};
+
+use dacm_lib::output_frames::Coalescer;
+
+const INTERVAL: Duration = Duration::from_millis(16);
+const MAX_BYTES: usize = 10;
+
+fn ms(n: u64) -> Duration {
+    Duration::from_millis(n)
+}
+
+#[test]
+fn the_first_read_after_a_quiet_spell_goes_out_at_once() {
+    let start = Instant::now();
+    let mut frames = Coalescer::new(INTERVAL, MAX_BYTES);
+    assert!(frames.wait(start).is_none());
+
+    let frame = frames.push(start, b"a").expect("an immediate frame");
+    assert_eq!(frame.bytes, b"a");
+    assert_eq!(frame.read_at, start);
+    assert!(frames.wait(start).is_none());
+
+    // Quiet for a whole interval since that flush, so this one is too
+    let later = start + INTERVAL;
+    let frame = frames.push(later, b"b").expect("an immediate frame");
+    assert_eq!(frame.bytes, b"b");
+}
+
+#[test]
+fn reads_that_keep_coming_are_held_for_the_interval() {
+    let start = Instant::now();
+    let mut frames = Coalescer::new(INTERVAL, MAX_BYTES);
+    frames.push(start, b"a").unwrap();
+
+    assert!(frames.push(start + ms(2), b"b").is_none());
+    assert!(frames.push(start + ms(5), b"c").is_none());
+    // The wait counts from the frame's first read, not the latest one
+    assert_eq!(frames.wait(start + ms(10)), Some(ms(8)));
+    assert_eq!(frames.wait(start + ms(30)), Some(Duration::ZERO));
+
+    let frame = frames.flush(start + ms(18)).expect("the held reads");
+    assert_eq!(frame.bytes, b"bc");
+    assert_eq!(frame.read_at, start + ms(2));
+    assert!(frames.flush(start + ms(19)).is_none());
+
+    // Straight after that flush, so held again
+    assert!(frames.push(start + ms(20), b"d").is_none());
+}
+
+#[test]
+fn a_full_frame_goes_out_without_waiting() {
+    let start = Instant::now();
+    let mut frames = Coalescer::new(INTERVAL, MAX_BYTES);
+    frames.push(start, b"a").unwrap();
+
+    assert!(frames.push(start + ms(1), b"12345").is_none());
+    let frame = frames
+        .push(start + ms(2), b"678901")
+        .expect("a frame at the size limit");
+    assert_eq!(frame.bytes, b"12345678901");
+    assert!(frames.wait(start + ms(2)).is_none());
+}
@@ -0,0 +1,172 @@
+//! The project status state machine, against a throwaway database.
+
+mod common;
+
+use diesel::prelude::*;
+
+use common::Harness;
+use dacm_lib::project_models::ProjectStatus::{self, *};
+use dacm_lib::project_status::{self, TransitionError};
+use dacm_lib::projects;
+use dacm_lib::schema::projects as projects_table;
+
+/// Every move between two different statuses that's allowed. Anything not
+/// listed must be rejected.
+const ALLOWED: &[(ProjectStatus, ProjectStatus)] = &[
+    (Queued, Running),
+    (Queued, Waiting),
+    (Queued, Failed),
+    (Queued, Archived),
+    (Running, Waiting),
+    (Running, Completed),
+    (Running, Failed),
+    (Running, Archived),
+    (Waiting, Queued),
+    (Waiting, Running),
+    (Waiting, Completed),
+    (Waiting, Failed),
+    (Waiting, Archived),
+    (Completed, Queued),
+    (Completed, Running),
+    (Completed, Archived),
+    (Failed, Queued),
+    (Failed, Running),
+    (Failed, Archived),
+    (Archived, Waiting),
+];
+
+/// Force a project into `status`, bypassing the state machine.
+fn set_status(h: &Harness, project_id: i32, status: ProjectStatus) {
+    let mut conn = h.db.conn().unwrap();
+    diesel::update(projects_table::table.filter(projects_table::id.eq(project_id)))
+        .set(projects_table::status.eq(status))
+        .execute(&mut conn)
+        .unwrap();
+}
+
+fn history(h: &Harness) -> Vec<(Option<ProjectStatus>, ProjectStatus, String)> {
+    let mut conn = h.db.conn().unwrap();
+    projects::status_history(&mut conn, h.project_id)
+        .unwrap()
+        .into_iter()
+        .map(|row| (row.from_status, row.to_status, row.reason))
+        .collect()
+}
+
+#[test]
+fn transitions_follow_the_table() {
+    let h = Harness::new();
+    for from in ProjectStatus::ALL {
+        for to in ProjectStatus::ALL {
+            set_status(&h, h.project_id, from);
+            let mut conn = h.db.conn().unwrap();
+            let result = project_status::transition(&mut conn, h.project_id, to, "test");
+
+            let allowed = from == to || ALLOWED.contains(&(from, to));
+            assert_eq!(
+                from.can_transition_to(to),
+                allowed,
+                "can_transition_to {from} -> {to}"
+            );
+            match result {
+                Ok(transition) => {
+                    assert!(allowed, "{from} -> {to} should be rejected");
+                    assert_eq!(transition.previous, from);
+                    assert_eq!(transition.project.status, to);
+                    assert_eq!(transition.changed(), from != to);
+                }
+                Err(TransitionError::Illegal {
+                    from: rejected_from,
+                    to: rejected_to,
+                    ..
+                }) => {
+                    assert!(!allowed, "{from} -> {to} should be allowed");
+                    assert_eq!((rejected_from, rejected_to), (from, to));
+                    // A rejected move leaves the project where it was
+                    assert_eq!(projects::get(&mut conn, h.project_id).unwrap().status, from);
+                }
+                Err(e) => panic!("{from} -> {to}: {e}"),
+            }
+        }
+    }
+}
+
+#[test]
+fn history_records_each_change() {
+    let h = Harness::new();
+    let mut conn = h.db.conn().unwrap();
+    let steps = [
+        (Waiting, "review requested"),
+        (Waiting, "asked again"),
+        (Running, "resumed"),
+        (Completed, "done"),
+        (Archived, "tidied away"),
+        (Waiting, "restored"),
+    ];
+    for (to, reason) in steps {
+        project_status::transition(&mut conn, h.project_id, to, reason).unwrap();
+        if to == Completed {
+            let rejected = project_status::transition(&mut conn, h.project_id, Waiting, "no");
+            assert!(matches!(rejected, Err(TransitionError::Illegal { .. })));
+        }
+    }
+    drop(conn);
+
+    // The no-op and the rejected move leave no trace
+    assert_eq!(
+        history(&h),
+        [
+            (None, Running, "created".to_string()),
+            (Some(Running), Waiting, "review requested".to_string()),
+            (Some(Waiting), Running, "resumed".to_string()),
+            (Some(Running), Completed, "done".to_string()),
+            (Some(Completed), Archived, "tidied away".to_string()),
+            (Some(Archived), Waiting, "restored".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn detached_projects_can_only_be_archived() {
+    let h = Harness::new();
+    {
+        let mut conn = h.db.conn().unwrap();
+        diesel::update(projects_table::table.filter(projects_table::id.eq(h.project_id)))
+            .set(projects_table::workspace_id.eq(None::<i32>))
+            .execute(&mut conn)
+            .unwrap();
+    }
+
+    let mut conn = h.db.conn().unwrap();
+    let result = project_status::transition(&mut conn, h.project_id, Waiting, "test");
+    assert!(matches!(result, Err(TransitionError::Detached(id)) if id == h.project_id));
+    project_status::transition(&mut conn, h.project_id, Archived, "workspace removed").unwrap();
+    let result = project_status::transition(&mut conn, h.project_id, Waiting, "restore");
+    assert!(matches!(result, Err(TransitionError::Detached(_))));
+}
+
+#[test]
+fn missing_project_is_not_found() {
+    let h = Harness::new();
+    let mut conn = h.db.conn().unwrap();
+    let result = project_status::transition(&mut conn, h.project_id + 1, Waiting, "test");
+    assert!(matches!(result, Err(TransitionError::NotFound(_))));
+}
+
+#[test]
+fn only_changes_are_emitted() {
+    let h = Harness::new();
+    let emit = |to| {
+        project_status::transition_and_emit(&h.db, &h.events, h.project_id, to, "test").unwrap();
+        h.events.take()
+    };
+
+    let events = emit(Waiting);
+    assert_eq!(events.len(), 1);
+    let (name, payload) = &events[0];
+    assert_eq!(name, "project-status-changed");
+    assert_eq!(payload["status"], "waiting");
+    assert_eq!(payload["previous"], "running");
+
+    assert!(emit(Waiting).is_empty());
+}
@@ -0,0 +1,122 @@
+//! Retrying failed sessions under their retry policy.
+
+mod common;
+
+use diesel::prelude::*;
+
+use common::Harness;
+use dacm_lib::project_models::ProjectStatus;
+use dacm_lib::project_status;
+use dacm_lib::retry::{self, Retries};
+use dacm_lib::retry_models::{NewRetryPolicy, SessionRun, SCOPE_WORKSPACE};
+use dacm_lib::schema::{retry_policies, session_runs};
+
+fn set_policy(h: &Harness, max_attempts: i32) {
+    let mut conn = h.db.conn().unwrap();
+    diesel::insert_into(retry_policies::table)
+        .values(&NewRetryPolicy {
+            scope: SCOPE_WORKSPACE,
+            scope_id: h.workspace_id,
+            enabled: true,
+            max_attempts,
+            backoff_secs: 0,
+            backoff_multiplier: 2.0,
+            max_backoff_secs: 0,
+            retryable_exit_codes: "[]".to_string(),
+            retry_on_signal: false,
+            retry_prompt: Some("Attempt {attempt} exited with {exit_code}"),
+        })
+        .execute(&mut conn)
+        .unwrap();
+}
+
+/// Start `script` the way the app does and return its id once it has exited.
+fn run_to_exit(h: &Harness, script: &str) -> (String, dacm_lib::session::SessionExit) {
+    let session_id = h.spawner().spawn(h.request(script)).unwrap();
+    let session = h.follow(session_id.clone());
+    (session_id, h.finish(&session))
+}
+
+#[test]
+fn a_failed_session_is_retried_until_attempts_run_out() {
+    let h = Harness::new();
+    let retries = Retries::new();
+    set_policy(&h, 2);
+
+    let (first, exit) = run_to_exit(&h, "exit:3");
+    let next = retry::on_session_exit(&h.db, &h.events, &retries, &first, &exit).expect("a retry");
+    assert_eq!(next.attempt, 2);
+
+    let second = retry::respawn(&h.spawner(), &retries, &next).expect("the retry to start");
+    let session = h.follow(second.clone());
+    let exit = h.finish(&session);
+
+    let mut conn = h.db.conn().unwrap();
+    let runs: Vec<SessionRun> = session_runs::table
+        .order(session_runs::id.asc())
+        .select(SessionRun::as_select())
+        .load(&mut conn)
+        .unwrap();
+    assert_eq!(runs.len(), 2);
+    assert_eq!(runs[0].outcome, "retried");
+    assert_eq!(
+        (runs[1].session_id.as_str(), runs[1].attempt),
+        (second.as_str(), 2)
+    );
+
+    // The original request's script fails again, and that was the last attempt
+    assert!(retry::on_session_exit(&h.db, &h.events, &retries, &second, &exit).is_none());
+    let events = h.events.take();
+    assert!(events
+        .iter()
+        .any(|(name, _)| name == "session-retry-exhausted"));
+}
+
+#[test]
+fn successes_and_kills_are_not_retried() {
+    let h = Harness::new();
+    let retries = Retries::new();
+    set_policy(&h, 3);
+
+    let (session_id, exit) = run_to_exit(&h, "exit:0");
+    assert!(retry::on_session_exit(&h.db, &h.events, &retries, &session_id, &exit).is_none());
+
+    let session = h.start(h.request("echo"));
+    h.sessions.kill(&session.id).unwrap();
+    let exit = h.finish(&session);
+    assert!(exit.killed);
+    assert!(retry::on_session_exit(&h.db, &h.events, &retries, &session.id, &exit).is_none());
+}
+
+#[test]
+fn a_cancelled_retry_does_not_start() {
+    let h = Harness::new();
+    let retries = Retries::new();
+    set_policy(&h, 3);
+
+    let (first, exit) = run_to_exit(&h, "exit:3");
+    let next = retry::on_session_exit(&h.db, &h.events, &retries, &first, &exit).expect("a retry");
+    retries.cancel(h.project_id);
+
+    assert!(retry::respawn(&h.spawner(), &retries, &next).is_none());
+    assert!(h.sessions.running_for_project(h.project_id).is_none());
+}
+
+#[test]
+fn a_retry_does_not_start_once_the_project_has_moved_on() {
+    let h = Harness::new();
+    let retries = Retries::new();
+    set_policy(&h, 3);
+
+    let (first, exit) = run_to_exit(&h, "exit:3");
+    let next = retry::on_session_exit(&h.db, &h.events, &retries, &first, &exit).expect("a retry");
+    assert_eq!(next.status, ProjectStatus::Running);
+
+    // The user parked the project while the retry backed off
+    let mut conn = h.db.conn().unwrap();
+    project_status::transition(&mut conn, h.project_id, ProjectStatus::Waiting, "test").unwrap();
+    drop(conn);
+
+    assert!(retry::respawn(&h.spawner(), &retries, &next).is_none());
+    assert!(h.sessions.running_for_project(h.project_id).is_none());
+}
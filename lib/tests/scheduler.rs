@@ -0,0 +1,124 @@
+//! Starting queued sessions under the session limits.
+
+mod common;
+
+use std::time::Duration;
+
+use diesel::prelude::*;
+use serde_json::json;
+
+use common::{Harness, FAKE_AGENT};
+use dacm_lib::error::ErrorCode;
+use dacm_lib::project_models::ProjectStatus;
+use dacm_lib::project_status;
+use dacm_lib::scheduler::{self, Scheduler};
+use dacm_lib::scheduler_models::{NewQueuedSpawn, QueuedSpawn};
+use dacm_lib::schema::spawn_queue;
+use dacm_lib::settings_registry;
+
+fn enqueue(h: &Harness) -> i32 {
+    let mut conn = h.db.conn().unwrap();
+    // New projects start out running
+    project_status::transition(&mut conn, h.project_id, ProjectStatus::Waiting, "test").unwrap();
+    let working_dir = h.workspace_dir.to_string_lossy();
+    let (queued, transition) = scheduler::enqueue(
+        &mut conn,
+        &NewQueuedSpawn {
+            project_id: h.project_id,
+            workspace_id: h.workspace_id,
+            working_dir: &working_dir,
+            initial_prompt: None,
+            cli_command: Some(FAKE_AGENT),
+            model: None,
+            rows: 24,
+            cols: 80,
+            priority: 0,
+        },
+    )
+    .unwrap();
+    assert_eq!(transition.project.status, ProjectStatus::Queued);
+    queued.id
+}
+
+#[test]
+fn an_item_starts_once_while_its_hooks_run() {
+    let h = Harness::new();
+    std::fs::write(
+        h.workspace_dir.join(".dacm.toml"),
+        "[hooks]\npre_session_spawn = [\"sleep 1\"]",
+    )
+    .unwrap();
+    {
+        let mut conn = h.db.conn().unwrap();
+        settings_registry::update(&mut conn, "max_concurrent_sessions", &json!(2)).unwrap();
+    }
+    let queue_id = enqueue(&h);
+
+    let scheduler = Scheduler::new();
+    let spawner = h.spawner();
+    let mut starts = scheduler.pump(&spawner);
+    assert_eq!(starts.len(), 1);
+
+    // There's room for a second session, but not a second start of the item
+    let session_id = std::thread::scope(|s| {
+        let first = starts.remove(0);
+        let launching = s.spawn(|| first.run(&spawner));
+        std::thread::sleep(Duration::from_millis(200));
+        assert!(scheduler.pump(&spawner).is_empty());
+        launching.join().unwrap().expect("item to start")
+    });
+    assert_eq!(h.sessions.running_count(), 1);
+
+    let mut conn = h.db.conn().unwrap();
+    let item: QueuedSpawn = spawn_queue::table
+        .filter(spawn_queue::id.eq(queue_id))
+        .select(QueuedSpawn::as_select())
+        .first(&mut conn)
+        .unwrap();
+    assert_eq!(item.status, "started");
+    assert_eq!(item.session_id.as_deref(), Some(session_id.as_str()));
+
+    h.sessions.kill(&session_id).unwrap();
+}
+
+#[test]
+fn a_project_is_only_started_once() {
+    let h = Harness::new();
+    std::fs::write(
+        h.workspace_dir.join(".dacm.toml"),
+        "[hooks]\npre_session_spawn = [\"sleep 1\"]",
+    )
+    .unwrap();
+
+    let scheduler = Scheduler::new();
+    let spawner = h.spawner();
+    let start = || scheduler.start_now(&spawner, h.request("echo"), "started by the test");
+
+    let session_id = std::thread::scope(|s| {
+        let first = s.spawn(start);
+        std::thread::sleep(Duration::from_millis(200));
+        let err = start().unwrap_err();
+        assert_eq!(err.code, ErrorCode::Conflict);
+        assert!(err.message.contains("already starting"), "{}", err.message);
+        first.join().unwrap().expect("first start to succeed")
+    });
+
+    let err = start().unwrap_err();
+    assert!(err.message.contains(&session_id), "{}", err.message);
+    assert_eq!(h.sessions.running_count(), 1);
+
+    h.sessions.kill(&session_id).unwrap();
+}
+
+#[test]
+fn only_pending_items_can_be_cancelled() {
+    let h = Harness::new();
+    let queue_id = enqueue(&h);
+    let mut conn = h.db.conn().unwrap();
+
+    let transition = scheduler::cancel(&mut conn, queue_id).unwrap();
+    assert_eq!(transition.project.status, ProjectStatus::Waiting);
+
+    let err = scheduler::cancel(&mut conn, queue_id).err().unwrap();
+    assert_eq!(err.code, ErrorCode::NotFound);
+}
@@ -0,0 +1,91 @@
+//! The passphrase-encrypted secrets file used when there's no keyring.
+
+use dacm_lib::error::ErrorCode;
+use dacm_lib::secret_file::{SecretFile, KEY_ENV};
+
+fn file(dir: &tempfile::TempDir, passphrase: &str) -> SecretFile {
+    SecretFile::with_passphrase(dir.path(), Some(passphrase.to_string()))
+}
+
+fn contents(dir: &tempfile::TempDir) -> Vec<u8> {
+    std::fs::read(dir.path().join("secrets.bin")).unwrap()
+}
+
+#[test]
+fn values_round_trip_without_leaving_plaintext() {
+    let dir = tempfile::tempdir().unwrap();
+    let secrets = file(&dir, "correct horse");
+    assert_eq!(secrets.get("token").unwrap(), None);
+
+    secrets.set("token", "hunter2-very-secret").unwrap();
+    secrets.set("other", "value").unwrap();
+    assert_eq!(
+        secrets.get("token").unwrap().as_deref(),
+        Some("hunter2-very-secret")
+    );
+
+    let data = contents(&dir);
+    let text = String::from_utf8_lossy(&data);
+    assert!(!text.contains("hunter2"), "{text}");
+    assert!(!text.contains("token"), "{text}");
+    // Only the sealed file; the key is never written
+    let names: Vec<_> = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(names, ["secrets.bin"]);
+
+    // Another handle with the same passphrase reads it back
+    let reopened = file(&dir, "correct horse");
+    assert_eq!(reopened.get("other").unwrap().as_deref(), Some("value"));
+    reopened.delete("other").unwrap();
+    assert_eq!(secrets.get("other").unwrap(), None);
+}
+
+#[test]
+fn every_save_uses_a_fresh_nonce() {
+    let dir = tempfile::tempdir().unwrap();
+    let secrets = file(&dir, "correct horse");
+    secrets.set("token", "same").unwrap();
+    let first = contents(&dir);
+    secrets.set("token", "same").unwrap();
+    let second = contents(&dir);
+
+    // Same magic and salt, different nonce and ciphertext
+    assert_eq!(first.len(), second.len());
+    assert_eq!(first[..22], second[..22]);
+    assert_ne!(first[22..46], second[22..46]);
+    assert_ne!(first[46..], second[46..]);
+}
+
+#[test]
+fn wrong_passphrase_or_tampering_is_caught() {
+    let dir = tempfile::tempdir().unwrap();
+    file(&dir, "correct horse").set("token", "value").unwrap();
+
+    let e = file(&dir, "battery staple").get("token").unwrap_err();
+    assert_eq!(e.code, ErrorCode::Io);
+    assert!(e.message.contains("integrity"), "{}", e.message);
+
+    // Flip a bit in the salt, which is authenticated but not encrypted
+    let mut data = contents(&dir);
+    data[8] ^= 1;
+    std::fs::write(dir.path().join("secrets.bin"), &data).unwrap();
+    let e = file(&dir, "correct horse").get("token").unwrap_err();
+    assert!(e.message.contains("integrity"), "{}", e.message);
+
+    std::fs::write(dir.path().join("secrets.bin"), b"DACMS1 old format").unwrap();
+    let e = file(&dir, "correct horse").get("token").unwrap_err();
+    assert!(e.message.contains("known format"), "{}", e.message);
+}
+
+#[test]
+fn no_passphrase_means_no_file_backend() {
+    let dir = tempfile::tempdir().unwrap();
+    let secrets = SecretFile::with_passphrase(dir.path(), None);
+    assert!(!secrets.available());
+
+    let e = secrets.set("token", "value").unwrap_err();
+    assert!(e.message.contains(KEY_ENV), "{}", e.message);
+    assert!(!dir.path().join("secrets.bin").exists());
+}
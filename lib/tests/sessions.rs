@@ -0,0 +1,298 @@
+//! Sessions end to end: `fake-agent` in a real PTY, driven through the
+//! session manager against a throwaway database.
+
+#![cfg(unix)]
+
+mod common;
+
+use std::time::Duration;
+
+use diesel::prelude::*;
+
+use common::{eventually, Harness};
+use dacm_lib::error::ErrorCode;
+use dacm_lib::project_models::ProjectStatus;
+use dacm_lib::project_status;
+use dacm_lib::projects;
+use dacm_lib::retry_models::SessionRun;
+use dacm_lib::schema::session_runs;
+use dacm_lib::session::SCROLLBACK_BYTES;
+
+#[test]
+fn spawn_passes_model_and_prompt() {
+    let h = Harness::new();
+    let mut request = h.request("args;exit:0");
+    request.model = Some("test-model".into());
+    request.initial_prompt = Some("fix the tests".into());
+    let session = h.start(request);
+
+    session.wait_for("args: --model test-model fix the tests");
+    let exit = h.finish(&session);
+    assert!(exit.succeeded());
+    assert_eq!(exit.exit_code, Some(0));
+}
+
+#[test]
+fn spawn_sets_env_and_working_dir() {
+    let h = Harness::new();
+    let mut request = h.request("env:DACM_TEST;env:TERM");
+    request.env.insert("DACM_TEST".into(), "hello".into());
+    let session = h.start(request);
+
+    let output = session.output.wait_closed();
+    assert!(output.contains("DACM_TEST=hello"), "{output}");
+    assert!(output.contains("TERM=xterm-256color"), "{output}");
+    let info = h.sessions.get(&session.id).unwrap();
+    assert_eq!(info.working_dir, h.workspace_dir.to_string_lossy());
+    assert_eq!(info.project_id, h.project_id);
+    h.finish(&session);
+}
+
+#[test]
+fn missing_cli_fails_to_spawn() {
+    let h = Harness::new();
+    let mut request = h.request("");
+    request.cli_command = Some("/nonexistent/agent".into());
+
+    let err = h.sessions.spawn(request).unwrap_err();
+    assert_eq!(err.code, ErrorCode::Pty);
+    assert!(h.sessions.list().unwrap().is_empty());
+}
+
+#[test]
+fn write_reaches_the_agent() {
+    let h = Harness::new();
+    let session = h.spawn("echo");
+
+    h.sessions.write(&session.id, b"hello\r").unwrap();
+    session.wait_for("got: hello");
+    h.sessions.write(&session.id, b"/exit\r").unwrap();
+
+    assert!(h.finish(&session).succeeded());
+}
+
+#[test]
+fn permission_prompt_takes_an_answer() {
+    let h = Harness::new();
+    let session = h.spawn("prompt:Allow edit?;prompt:Allow rm?");
+
+    session.wait_for("Allow edit? [y/n]");
+    h.sessions.write(&session.id, b"y\r").unwrap();
+    session.wait_for("allowed");
+    session.wait_for("Allow rm? [y/n]");
+    h.sessions.write(&session.id, b"n\r").unwrap();
+    session.wait_for("denied");
+
+    assert!(h.finish(&session).succeeded());
+}
+
+#[test]
+fn resize_reaches_the_pty() {
+    let h = Harness::new();
+    let session = h.spawn("size;read;size");
+
+    session.wait_for("size 24x80");
+    h.sessions.resize(&session.id, 40, 120).unwrap();
+    assert_eq!(h.sessions.size(&session.id).unwrap(), (40, 120));
+    h.sessions.write(&session.id, b"\r").unwrap();
+    session.wait_for("size 40x120");
+
+    h.finish(&session);
+}
+
+#[test]
+fn exit_code_is_reported() {
+    let h = Harness::new();
+    let session = h.spawn("print:bye;exit:3");
+
+    let exit = h.finish(&session);
+    assert_eq!(exit.exit_code, Some(3));
+    assert!(!exit.killed);
+    assert!(!exit.succeeded());
+    assert_eq!(exit.request.project_id, h.project_id);
+}
+
+#[test]
+fn output_is_kept_in_order() {
+    let h = Harness::new();
+    let lines: Vec<String> = (1..=200).map(|i| format!("line {i}")).collect();
+    let script: Vec<String> = lines.iter().map(|l| format!("print:{l}")).collect();
+    let session = h.spawn(&script.join(";"));
+
+    let output = session.output.wait_closed();
+    let printed: Vec<&str> = output.lines().map(|l| l.trim_end_matches('\r')).collect();
+    assert_eq!(printed, lines);
+    h.finish(&session);
+}
+
+#[test]
+fn scrollback_keeps_the_latest_output() {
+    let h = Harness::new();
+    // About 500 KiB, twice what's kept
+    let session = h.spawn("count:40000;read");
+
+    let output = session.wait_for("line 40000\r\n");
+    let scrollback = h.sessions.scrollback(&session.id).unwrap();
+    assert_eq!(scrollback.len(), SCROLLBACK_BYTES);
+    assert!(output.as_bytes().ends_with(&scrollback));
+    let text = String::from_utf8_lossy(&scrollback);
+    assert!(!text.contains("\nline 1\r\n"));
+
+    h.sessions.kill(&session.id).unwrap();
+    h.finish(&session);
+}
+
+#[test]
+fn reader_can_only_be_taken_once() {
+    let h = Harness::new();
+    let session = h.spawn("read");
+
+    assert!(h.sessions.is_streaming(&session.id).unwrap());
+    let err = h.sessions.take_reader(&session.id).err().unwrap();
+    assert_eq!(err.code, ErrorCode::Conflict);
+
+    h.sessions.kill(&session.id).unwrap();
+    h.finish(&session);
+}
+
+#[test]
+fn kill_ends_an_agent_that_ignores_ctrl_c() {
+    let h = Harness::new();
+    let session = h.spawn("ignore-sigint;print:ready;sleep:600000");
+    session.wait_for("ready");
+
+    h.sessions.write(&session.id, b"\x03").unwrap();
+    std::thread::sleep(Duration::from_millis(300));
+    assert!(!session.output.is_closed());
+    assert_eq!(h.sessions.get(&session.id).unwrap().status, "running");
+
+    h.sessions.kill(&session.id).unwrap();
+    assert_eq!(h.sessions.running_count(), 0);
+    let exit = h.finish(&session);
+    assert!(exit.killed);
+    assert!(!exit.succeeded());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn kill_takes_grandchildren_with_it() {
+    let h = Harness::new();
+    let session = h.spawn("spawn:2;print:spawned;sleep:600000");
+
+    let output = session.wait_for("spawned");
+    let children: Vec<u32> = output
+        .lines()
+        .filter_map(|l| l.trim().strip_prefix("child "))
+        .map(|pid| pid.parse().unwrap())
+        .collect();
+    assert_eq!(children.len(), 2, "{output}");
+    assert!(children.iter().all(|&pid| common::is_alive(pid)));
+
+    h.sessions.kill(&session.id).unwrap();
+    h.finish(&session);
+    assert!(
+        eventually(|| children.iter().all(|&pid| !common::is_alive(pid))),
+        "grandchildren outlived the session: {children:?}"
+    );
+}
+
+#[test]
+fn finished_sessions_are_cleaned_up() {
+    let h = Harness::new();
+    let first = h.spawn("exit:0");
+    let second = h.spawn("read");
+    assert_eq!(h.sessions.list().unwrap().len(), 2);
+    assert_eq!(h.sessions.running_count_for_workspace(h.workspace_id), 2);
+
+    h.finish(&first);
+    assert_eq!(h.sessions.running_count(), 1);
+    let err = h.sessions.write(&first.id, b"x").unwrap_err();
+    assert_eq!(err.code, ErrorCode::NotFound);
+
+    h.sessions.kill_all();
+    h.finish(&second);
+    assert!(h.sessions.list().unwrap().is_empty());
+    assert!(h.sessions.get_pids().is_empty());
+}
+
+#[test]
+fn spawner_records_the_run() {
+    let h = Harness::new();
+    let mut request = h.request("exit:0");
+    request.initial_prompt = Some("fix the tests".into());
+
+    let session_id = h.spawner().spawn(request).unwrap();
+    let session = h.follow(session_id.clone());
+    h.finish(&session);
+
+    let mut conn = h.db.conn().unwrap();
+    let run: SessionRun = session_runs::table
+        .filter(session_runs::session_id.eq(&session_id))
+        .select(SessionRun::as_select())
+        .first(&mut conn)
+        .unwrap();
+    assert_eq!(run.project_id, h.project_id);
+    assert_eq!(run.attempt, 1);
+    assert!(!run.resumed);
+}
+
+#[test]
+fn status_follows_the_session() {
+    let h = Harness::new();
+    // New projects start out running
+    for (to, reason) in [
+        (ProjectStatus::Waiting, "waiting for input"),
+        (ProjectStatus::Running, "started"),
+    ] {
+        let project =
+            project_status::transition_and_emit(&h.db, &h.events, h.project_id, to, reason)
+                .unwrap();
+        assert_eq!(project.status, to);
+    }
+
+    let session = h.spawn("exit:1");
+    let exit = h.finish(&session);
+    let to = if exit.succeeded() {
+        ProjectStatus::Completed
+    } else {
+        ProjectStatus::Failed
+    };
+    project_status::transition_and_emit(&h.db, &h.events, h.project_id, to, "session ended")
+        .unwrap();
+
+    let events = h.events.take();
+    let statuses: Vec<&str> = events
+        .iter()
+        .filter(|(name, _)| name == "project-status-changed")
+        .map(|(_, payload)| payload["status"].as_str().unwrap())
+        .collect();
+    assert_eq!(statuses, ["waiting", "running", "failed"]);
+
+    let mut conn = h.db.conn().unwrap();
+    let history = projects::status_history(&mut conn, h.project_id).unwrap();
+    let to_statuses: Vec<ProjectStatus> = history.iter().map(|row| row.to_status).collect();
+    assert_eq!(
+        to_statuses,
+        [
+            ProjectStatus::Running,
+            ProjectStatus::Waiting,
+            ProjectStatus::Running,
+            ProjectStatus::Failed
+        ]
+    );
+
+    // Failed can't go straight to completed
+    let err = project_status::transition_and_emit(
+        &h.db,
+        &h.events,
+        h.project_id,
+        ProjectStatus::Completed,
+        "oops",
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        project_status::TransitionError::Illegal { .. }
+    ));
+    assert!(h.events.take().is_empty());
+}
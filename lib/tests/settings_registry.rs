@@ -0,0 +1,208 @@
+//! Settings validation against the registry, with a throwaway database.
+
+mod common;
+
+use std::collections::HashSet;
+
+use diesel::prelude::*;
+use serde_json::{json, Value};
+
+use common::Harness;
+use dacm_lib::error::ErrorCode;
+use dacm_lib::schema::settings;
+use dacm_lib::settings_registry::{self, REGISTRY};
+
+/// Store `raw` as-is, the way an older version of the app might have.
+fn store_raw(h: &Harness, key: &str, raw: &str) {
+    let mut conn = h.db.conn().unwrap();
+    diesel::replace_into(settings::table)
+        .values((settings::key.eq(key), settings::value.eq(raw)))
+        .execute(&mut conn)
+        .unwrap();
+}
+
+#[test]
+fn registry_is_consistent() {
+    let mut keys = HashSet::new();
+    for def in REGISTRY {
+        assert!(keys.insert(def.key), "{} is listed twice", def.key);
+        match def.default {
+            Some(raw) => {
+                let decoded = settings_registry::decode(def, raw);
+                assert!(decoded.is_ok(), "default of {}: {decoded:?}", def.key);
+            }
+            None => assert!(def.nullable, "{} has no default to fall back on", def.key),
+        }
+    }
+    assert_eq!(settings_registry::schema().len(), REGISTRY.len());
+}
+
+#[test]
+fn values_are_checked_against_their_kind() {
+    let h = Harness::new();
+    // Key, value to set, then the stored value or the start of the reason
+    // it's rejected
+    let cases: &[(&str, Value, Result<Value, &str>)] = &[
+        ("prevent_sleep", json!(true), Ok(json!(true))),
+        ("prevent_sleep", json!("yes"), Err("wrong type")),
+        ("prevent_sleep", json!(1), Err("wrong type")),
+        ("terminal_font_size", json!(14), Ok(json!(14))),
+        ("terminal_font_size", json!(8), Ok(json!(8))),
+        (
+            "terminal_font_size",
+            json!(7),
+            Err("must be between 8 and 24"),
+        ),
+        (
+            "terminal_font_size",
+            json!(25),
+            Err("must be between 8 and 24"),
+        ),
+        (
+            "terminal_font_size",
+            json!(13.5),
+            Err("expected a whole number"),
+        ),
+        ("terminal_font_size", json!("14"), Err("wrong type")),
+        ("max_concurrent_sessions", json!(0), Ok(json!(0))),
+        (
+            "max_concurrent_sessions",
+            json!(-1),
+            Err("must be between 0 and 64"),
+        ),
+        (
+            "hook_timeout_secs",
+            json!(86401),
+            Err("must be between 0 and 86400"),
+        ),
+        ("theme", json!("dark"), Ok(json!("dark"))),
+        (
+            "theme",
+            json!("Dark"),
+            Err("must be one of light, dark, system"),
+        ),
+        ("queue_mode", json!("priority"), Ok(json!("priority"))),
+        (
+            "queue_mode",
+            json!("lifo"),
+            Err("must be one of fifo, priority"),
+        ),
+        (
+            "code_font_family",
+            json!("monospace"),
+            Ok(json!("monospace")),
+        ),
+        ("code_font_family", json!("  "), Err("must not be empty")),
+        ("selected_model_id", json!("opus"), Ok(json!("opus"))),
+        ("selected_model_id", Value::Null, Ok(Value::Null)),
+        ("theme", Value::Null, Err("can't be cleared")),
+        ("last_project_id", json!(0), Err("must be between 1 and")),
+    ];
+
+    let mut conn = h.db.conn().unwrap();
+    for (key, value, expected) in cases {
+        let result = settings_registry::set(&mut conn, key, value);
+        match (result, expected) {
+            (Ok(stored), Ok(expected)) => {
+                assert_eq!(&stored.value, expected, "{key} = {value}");
+            }
+            (Err(e), Err(reason)) => {
+                assert_eq!(e.code, ErrorCode::Validation, "{key} = {value}");
+                let details = e.details.unwrap();
+                assert_eq!(details["key"], *key);
+                let got = details["reason"].as_str().unwrap();
+                assert!(got.starts_with(reason), "{key} = {value}: {got}");
+            }
+            (result, expected) => panic!("{key} = {value}: got {result:?}, expected {expected:?}"),
+        }
+    }
+}
+
+#[test]
+fn raw_values_parse_like_stored_ones() {
+    let cases: &[(&str, &str, Option<Value>)] = &[
+        ("record_sessions", "true", Some(json!(true))),
+        ("record_sessions", "TRUE", None),
+        ("code_font_size", " 12 ", Some(json!(12))),
+        ("code_font_size", "twelve", None),
+        ("last_workspace_id", "", Some(Value::Null)),
+        ("worktree_branch_pattern", "", None),
+    ];
+    for (key, raw, expected) in cases {
+        let parsed = settings_registry::parse_raw(key, raw).ok();
+        assert_eq!(&parsed, expected, "{key} = {raw:?}");
+    }
+}
+
+#[test]
+fn unknown_keys_are_rejected() {
+    let h = Harness::new();
+    let mut conn = h.db.conn().unwrap();
+    for result in [
+        settings_registry::get(&mut conn, "colour").err(),
+        settings_registry::set(&mut conn, "colour", &json!("red")).err(),
+        settings_registry::parse_raw("colour", "red").err(),
+    ] {
+        let e = result.expect("an unknown key is an error");
+        assert_eq!(e.code, ErrorCode::Validation);
+        assert_eq!(e.details.unwrap()["key"], "colour");
+    }
+}
+
+#[test]
+fn invalid_stored_values_read_as_the_default() {
+    let h = Harness::new();
+    store_raw(&h, "terminal_font_size", "99");
+    store_raw(&h, "theme", "sepia");
+
+    let mut conn = h.db.conn().unwrap();
+    let size = settings_registry::get(&mut conn, "terminal_font_size").unwrap();
+    assert_eq!(size.value, json!(13));
+    assert!(size.is_default);
+    assert_eq!(
+        settings_registry::get_raw(&mut conn, "theme").unwrap(),
+        "system"
+    );
+
+    let unset = settings_registry::get(&mut conn, "selected_model_id").unwrap();
+    assert_eq!(unset.value, Value::Null);
+    assert!(unset.is_default);
+    assert_eq!(
+        settings_registry::get_text(&mut conn, "selected_model_id").unwrap(),
+        None
+    );
+}
+
+#[test]
+fn only_changes_are_broadcast() {
+    let h = Harness::new();
+    let mut conn = h.db.conn().unwrap();
+
+    let (value, change) =
+        settings_registry::update(&mut conn, "queue_paused", &json!(true)).unwrap();
+    assert_eq!(value.value, json!(true));
+    assert!(!value.is_default);
+    let change = change.expect("a change");
+    assert_eq!(
+        (change.old_value.clone(), change.new_value.clone()),
+        (json!(false), json!(true))
+    );
+    settings_registry::broadcast(&h.events, &[change]);
+    let events = h.events.take();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].0, settings_registry::SETTINGS_CHANGED);
+    assert_eq!(events[0].1["key"], "queue_paused");
+
+    let (_, change) = settings_registry::update(&mut conn, "queue_paused", &json!(true)).unwrap();
+    assert!(change.is_none());
+
+    // Storing the default explicitly moves nothing either
+    let (value, change) =
+        settings_registry::update(&mut conn, "queue_mode", &json!("fifo")).unwrap();
+    assert!(!value.is_default);
+    assert!(change.is_none());
+
+    // A rejected write leaves the old value in place
+    assert!(settings_registry::update(&mut conn, "queue_paused", &json!("no")).is_err());
+    assert!(settings_registry::get_bool(&mut conn, "queue_paused").unwrap());
+}
@@ -0,0 +1,63 @@
+//! Registering repositories as workspaces.
+
+mod common;
+
+use std::path::Path;
+use std::process::Command;
+
+use common::Harness;
+use dacm_lib::error::ErrorCode;
+use dacm_lib::workspace;
+
+fn git_init(dir: &Path) {
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    let status = Command::new("git")
+        .args(["init", "--quiet"])
+        .current_dir(dir)
+        .status()
+        .expect("run git init");
+    assert!(status.success());
+}
+
+#[test]
+fn a_repository_is_registered_once() {
+    let h = Harness::new();
+    let repo = h.data_dir.join("repo");
+    git_init(&repo);
+    let mut conn = h.db.conn().unwrap();
+
+    let added = workspace::add(&mut conn, &repo.to_string_lossy()).unwrap();
+    assert_eq!(added.name, "repo");
+
+    let err = workspace::add(&mut conn, &repo.join("src").to_string_lossy()).unwrap_err();
+    assert_eq!(err.code, ErrorCode::Conflict);
+    assert_eq!(err.details.unwrap()["workspace_id"], added.id);
+}
+
+#[test]
+fn bulk_add_skips_repositories_already_in_the_request() {
+    let h = Harness::new();
+    let first = h.data_dir.join("first");
+    let second = h.data_dir.join("second");
+    git_init(&first);
+    git_init(&second);
+    let mut conn = h.db.conn().unwrap();
+
+    let paths = [&first, &first.join("src"), &second]
+        .map(|p| p.to_string_lossy().to_string())
+        .to_vec();
+    let report = workspace::add_many(&mut conn, &paths).unwrap();
+    assert_eq!(report.added.len(), 2);
+    assert_eq!(report.already_registered.len(), 1);
+
+    // One bad path and nothing is added
+    let third = h.data_dir.join("third");
+    git_init(&third);
+    let paths = vec![
+        third.to_string_lossy().to_string(),
+        h.data_dir.join("missing").to_string_lossy().to_string(),
+    ];
+    let err = workspace::add_many(&mut conn, &paths).unwrap_err();
+    assert_eq!(err.code, ErrorCode::Validation);
+    assert!(workspace::add(&mut conn, &third.to_string_lossy()).is_ok());
+}
@@ -0,0 +1,372 @@
+//! Workspace config files merged over the global settings, against a
+//! throwaway database.
+
+mod common;
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use diesel::prelude::*;
+use serde_json::json;
+
+use common::Harness;
+use dacm_lib::constants::DEFAULT_CLI;
+use dacm_lib::env_profiles;
+use dacm_lib::error::{AppError, ErrorCode};
+use dacm_lib::hook_models::{Hook, HookStage};
+use dacm_lib::hooks;
+use dacm_lib::schema::hook_runs;
+use dacm_lib::secrets;
+use dacm_lib::settings_registry;
+use dacm_lib::workspace_config::{self, WorkspaceConfig};
+
+fn write(h: &Harness, name: &str, contents: &str) {
+    let path = h.workspace_dir.join(name);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, contents).unwrap();
+}
+
+fn load(h: &Harness) -> Result<WorkspaceConfig, AppError> {
+    let mut conn = h.db.conn().unwrap();
+    workspace_config::load(&mut conn, h.workspace_id)
+}
+
+fn run(command: &str) -> Hook {
+    Hook::Run {
+        command: command.to_string(),
+    }
+}
+
+#[test]
+fn defaults_come_from_settings() {
+    let h = Harness::new();
+    let config = load(&h).unwrap();
+    assert_eq!(config.config_file, None);
+    assert_eq!(config.cli, DEFAULT_CLI);
+    assert_eq!(config.model, None);
+    assert_eq!(config.max_sessions, 0);
+    assert!(!config.persistent_sessions);
+    assert_eq!(config.hook_timeout(), Some(Duration::from_secs(600)));
+    assert_eq!(config.worktree_branch_pattern, "feature/{project_name}");
+    assert!(config.exit_criteria.is_empty());
+    assert!(config.overridden.is_empty());
+
+    {
+        let mut conn = h.db.conn().unwrap();
+        for (key, value) in [
+            ("selected_model_id", json!("opus")),
+            ("max_sessions_per_workspace", json!(3)),
+            ("persistent_sessions", json!(true)),
+            ("worktree_base_path", json!("/tmp/trees")),
+            ("hook_timeout_secs", json!(0)),
+        ] {
+            settings_registry::set(&mut conn, key, &value).unwrap();
+        }
+    }
+    let config = load(&h).unwrap();
+    assert_eq!(config.model.as_deref(), Some("opus"));
+    assert_eq!(config.max_sessions, 3);
+    assert!(config.persistent_sessions);
+    assert_eq!(config.worktree_base_path.as_deref(), Some("/tmp/trees"));
+    assert_eq!(config.hook_timeout(), None);
+    assert!(config.overridden.is_empty());
+}
+
+#[test]
+fn file_overrides_settings() {
+    let h = Harness::new();
+    {
+        let mut conn = h.db.conn().unwrap();
+        settings_registry::set(&mut conn, "selected_model_id", &json!("opus")).unwrap();
+        settings_registry::set(&mut conn, "max_sessions_per_workspace", &json!(3)).unwrap();
+    }
+
+    // Each line of a config file and the key it should mark as overridden
+    let cases: &[(&str, &str)] = &[
+        ("cli = \"aider\"", "cli"),
+        ("model = \"haiku\"", "model"),
+        ("max_sessions = 1", "max_sessions"),
+        ("persistent_sessions = true", "persistent_sessions"),
+        ("hook_timeout_secs = 5", "hook_timeout_secs"),
+        ("exit_criteria = [\"cargo test\"]", "exit_criteria"),
+        ("env = { RUST_LOG = \"debug\" }", "env"),
+        (
+            "worktree = { base_path = \"../trees\" }",
+            "worktree.base_path",
+        ),
+        (
+            "worktree = { branch_pattern = \"agent/{project_id}\" }",
+            "worktree.branch_pattern",
+        ),
+        ("setup_commands = [\"npm ci\"]", "setup_commands"),
+        ("hooks = { pre_session_spawn = [\"make\"] }", "hooks"),
+    ];
+    for (line, key) in cases {
+        write(&h, ".dacm.toml", line);
+        let config = load(&h).unwrap_or_else(|e| panic!("{line}: {e}"));
+        assert_eq!(config.overridden, [*key], "{line}");
+    }
+
+    write(
+        &h,
+        ".dacm.toml",
+        r#"
+cli = "aider"
+model = "haiku"
+max_sessions = 1
+persistent_sessions = true
+exit_criteria = ["cargo test", "cargo clippy"]
+
+[worktree]
+branch_pattern = "agent/{project_id}"
+"#,
+    );
+    let config = load(&h).unwrap();
+    assert!(config.config_file.unwrap().ends_with(".dacm.toml"));
+    assert_eq!(config.cli, "aider");
+    assert_eq!(config.model.as_deref(), Some("haiku"));
+    assert_eq!(config.max_sessions, 1);
+    assert!(config.persistent_sessions);
+    assert_eq!(config.exit_criteria, ["cargo test", "cargo clippy"]);
+    assert_eq!(config.worktree_branch_pattern, "agent/{project_id}");
+}
+
+#[test]
+fn dacm_toml_wins_over_the_config_dir() {
+    let h = Harness::new();
+    write(&h, ".dacm/config.toml", "cli = \"from-dir\"");
+    assert_eq!(load(&h).unwrap().cli, "from-dir");
+
+    write(&h, ".dacm.toml", "cli = \"from-file\"");
+    let config = load(&h).unwrap();
+    assert_eq!(config.cli, "from-file");
+    assert!(config.config_file.unwrap().ends_with(".dacm.toml"));
+}
+
+#[test]
+fn errors_point_at_the_line() {
+    let h = Harness::new();
+    // Contents, then the line and column the error should name
+    let cases: &[(&str, usize, usize)] = &[
+        ("cli = \"aider\"\ncolour = \"blue\"", 2, 1),
+        ("model = \"haiku\"\n\nmax_sessions = \"two\"", 3, 16),
+        ("[worktree]\nbase = \"../trees\"", 2, 1),
+        (
+            "[hooks]\npre_session_spawn = [{ run = \"a\", copy = \"b\" }]",
+            2,
+            21,
+        ),
+        ("cli = ", 1, 7),
+    ];
+    for (contents, line, column) in cases {
+        write(&h, ".dacm.toml", contents);
+        let e = load(&h).expect_err(contents);
+        assert_eq!(e.code, ErrorCode::Validation, "{contents}");
+        let details = e.details.unwrap();
+        assert_eq!(
+            (details["line"].clone(), details["column"].clone()),
+            (json!(line), json!(column)),
+            "{contents}: {}",
+            e.message
+        );
+        assert!(
+            e.message.contains(&format!(".dacm.toml:{line}:{column}:")),
+            "{}",
+            e.message
+        );
+    }
+}
+
+#[test]
+fn setup_commands_run_before_worktree_hooks() {
+    let h = Harness::new();
+    write(
+        &h,
+        ".dacm.toml",
+        r#"
+setup_commands = ["npm ci", "npm run build"]
+
+[hooks]
+post_worktree_create = [{ copy = ".env" }, "make"]
+pre_session_spawn = [{ run = "git fetch" }]
+"#,
+    );
+    let hooks = load(&h).unwrap().hooks;
+    assert_eq!(
+        hooks.for_stage(HookStage::PostWorktreeCreate),
+        [
+            run("npm ci"),
+            run("npm run build"),
+            Hook::Copy {
+                from: ".env".to_string(),
+                to: ".env".to_string(),
+            },
+            run("make"),
+        ]
+    );
+    assert_eq!(
+        hooks.for_stage(HookStage::PreSessionSpawn),
+        [run("git fetch")]
+    );
+    assert!(hooks.for_stage(HookStage::ExitCriteria).is_empty());
+}
+
+#[test]
+fn file_env_wins_over_the_profile() {
+    let h = Harness::new();
+    {
+        let mut conn = h.db.conn().unwrap();
+        let vars = BTreeMap::from([
+            ("RUST_LOG".to_string(), "info".to_string()),
+            ("REGION".to_string(), "eu".to_string()),
+        ]);
+        let profile = env_profiles::create(&mut conn, "shared", &vars).unwrap();
+        env_profiles::attach_to_workspace(&mut conn, h.workspace_id, Some(profile.id)).unwrap();
+    }
+    write(&h, ".dacm.toml", "[env]\nRUST_LOG = \"debug\"");
+    let config = load(&h).unwrap();
+    assert_eq!(
+        config.env,
+        BTreeMap::from([
+            ("REGION".to_string(), "eu".to_string()),
+            ("RUST_LOG".to_string(), "debug".to_string()),
+        ])
+    );
+
+    // Whatever the request already sets wins over both
+    let mut request = h.request("exit:0");
+    request
+        .env
+        .insert("RUST_LOG".to_string(), "trace".to_string());
+    config.apply(&mut request);
+    assert_eq!(request.env["RUST_LOG"], "trace");
+    assert_eq!(request.env["REGION"], "eu");
+}
+
+#[test]
+fn exit_criteria_stop_at_the_first_failure() {
+    let h = Harness::new();
+    let request = h.request("exit:0");
+    let check = || {
+        let config = load(&h).unwrap();
+        hooks::check_exit_criteria(&h.db, &h.events, &config, &request, "session-1").unwrap()
+    };
+    assert_eq!(check(), None);
+
+    write(
+        &h,
+        ".dacm.toml",
+        "exit_criteria = [\"true\", \"exit 3\", \"touch ran\"]",
+    );
+    assert_eq!(check(), Some(false));
+    assert!(!h.workspace_dir.join("ran").exists());
+
+    let mut conn = h.db.conn().unwrap();
+    let runs: Vec<(String, String, Option<i32>)> = hook_runs::table
+        .select((hook_runs::stage, hook_runs::hook, hook_runs::exit_code))
+        .order(hook_runs::id)
+        .load(&mut conn)
+        .unwrap();
+    assert_eq!(
+        runs,
+        [
+            ("exit_criteria".to_string(), "true".to_string(), Some(0)),
+            ("exit_criteria".to_string(), "exit 3".to_string(), Some(3)),
+        ]
+    );
+    drop(conn);
+
+    write(&h, ".dacm.toml", "exit_criteria = [\"true\"]");
+    assert_eq!(check(), Some(true));
+}
+
+#[test]
+fn a_hook_that_cannot_be_recorded_does_not_run() {
+    let h = Harness::new();
+    let request = h.request("exit:0");
+    write(&h, ".dacm.toml", "exit_criteria = [\"touch ran\"]");
+    let config = load(&h).unwrap();
+
+    let mut conn = h.db.conn().unwrap();
+    diesel::sql_query("DROP TABLE hook_runs")
+        .execute(&mut conn)
+        .unwrap();
+    drop(conn);
+
+    let passed = hooks::check_exit_criteria(&h.db, &h.events, &config, &request, "session-1");
+    assert!(passed.is_err());
+    assert!(!h.workspace_dir.join("ran").exists());
+}
+
+#[test]
+fn hooks_are_killed_at_the_timeout() {
+    let h = Harness::new();
+    let request = h.request("exit:0");
+    // The pipe keeps output open after the shell itself is killed, unless
+    // the whole group goes
+    write(
+        &h,
+        ".dacm.toml",
+        "hook_timeout_secs = 1\nexit_criteria = [\"echo started; sleep 30 | cat\"]",
+    );
+    let config = load(&h).unwrap();
+
+    let started = Instant::now();
+    let passed = hooks::check_exit_criteria(&h.db, &h.events, &config, &request, "session-1");
+    assert_eq!(passed.unwrap(), Some(false));
+    assert!(started.elapsed() < Duration::from_secs(10));
+
+    let mut conn = h.db.conn().unwrap();
+    let (exit_code, output): (Option<i32>, String) = hook_runs::table
+        .select((hook_runs::exit_code, hook_runs::output))
+        .first(&mut conn)
+        .unwrap();
+    assert_eq!(exit_code, None);
+    assert_eq!(output, "started\nHook timed out after 1s\n");
+}
+
+#[test]
+fn secrets_are_mapped_in_the_app_not_the_file() {
+    let h = Harness::new();
+    write(
+        &h,
+        ".dacm.toml",
+        "[secrets]\nGITHUB_TOKEN = \"github-token\"",
+    );
+    assert!(load(&h).unwrap().secrets.is_empty());
+
+    {
+        let mut conn = h.db.conn().unwrap();
+        settings_registry::set(&mut conn, "secrets_backend", &json!("file")).unwrap();
+        h.secrets.set(&mut conn, "github-token", "ghp_123").unwrap();
+
+        let missing = BTreeMap::from([("NPM_TOKEN".to_string(), "npm-token".to_string())]);
+        let e = secrets::set_mappings(&mut conn, h.workspace_id, &missing).unwrap_err();
+        assert_eq!(e.code, ErrorCode::NotFound);
+        let e = secrets::set_mappings(&mut conn, h.workspace_id + 1, &BTreeMap::new()).unwrap_err();
+        assert_eq!(e.code, ErrorCode::NotFound);
+
+        let mapped = BTreeMap::from([("GH_TOKEN".to_string(), "github-token".to_string())]);
+        let saved = secrets::set_mappings(&mut conn, h.workspace_id, &mapped).unwrap();
+        assert_eq!(saved, mapped);
+    }
+
+    let config = load(&h).unwrap();
+    assert_eq!(
+        config.secrets,
+        BTreeMap::from([("GH_TOKEN".to_string(), "github-token".to_string())])
+    );
+    assert!(!config.overridden.contains(&"secrets".to_string()));
+    let env = secrets::resolve_env(&h.db, &h.secrets, &config).unwrap();
+    assert_eq!(
+        env,
+        BTreeMap::from([("GH_TOKEN".to_string(), "ghp_123".to_string())])
+    );
+
+    // Saving replaces rather than adds
+    let mut conn = h.db.conn().unwrap();
+    secrets::set_mappings(&mut conn, h.workspace_id, &BTreeMap::new()).unwrap();
+    assert!(secrets::mappings(&mut conn, h.workspace_id)
+        .unwrap()
+        .is_empty());
+}
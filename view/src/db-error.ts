@@ -0,0 +1,75 @@
+import { invoke } from "@tauri-apps/api/core";
+import type { DbStatus } from "./types.ts";
+import { escapeHtml, formatError } from "./utils.ts";
+
+type DbErrorStatus = Extract<DbStatus, { state: "error" }>;
+
+const TITLES: Record<DbErrorStatus["kind"], string> = {
+  open: "The database could not be opened",
+  corrupt: "The database file is damaged",
+  newer_schema: "The database is from a newer version of DACM",
+  migration: "The database could not be upgraded",
+};
+
+/**
+ * Full-window screen shown when the backend started in degraded mode.
+ * `onRecovered` runs once the database opens successfully.
+ */
+export function renderDbError(
+  container: HTMLElement,
+  status: DbErrorStatus,
+  onRecovered: () => void,
+): void {
+  const restored = status.restored_snapshot
+    ? `<p class="db-error-note">Your data was restored from ${escapeHtml(status.restored_snapshot)}.</p>`
+    : "";
+
+  container.innerHTML = `
+    <div class="no-session-body">
+      <div class="db-error">
+        <h2 class="db-error-title">${TITLES[status.kind]}</h2>
+        <p class="db-error-message">${escapeHtml(status.message)}</p>
+        <p class="db-error-note">${escapeHtml(status.path)}</p>
+        ${restored}
+        <div class="db-error-actions">
+          <button class="btn btn-restart" data-action="retry">Retry</button>
+          <button class="btn" data-action="fresh">Start with a fresh database</button>
+          <button class="btn" data-action="reveal">Show file</button>
+        </div>
+        <p class="db-error-note" id="db-error-feedback"></p>
+      </div>
+    </div>`;
+
+  const feedback = container.querySelector<HTMLElement>("#db-error-feedback");
+
+  async function recover(command: string): Promise<void> {
+    try {
+      const next = await invoke<DbStatus>(command);
+      if (next.state === "ready") {
+        onRecovered();
+      } else {
+        renderDbError(container, next, onRecovered);
+      }
+    } catch (e) {
+      if (feedback) feedback.textContent = formatError(e);
+    }
+  }
+
+  container.querySelector("[data-action='retry']")?.addEventListener("click", () => {
+    recover("retry_db_init");
+  });
+
+  container.querySelector("[data-action='fresh']")?.addEventListener("click", () => {
+    if (confirm("Move the current database aside and start with an empty one?")) {
+      recover("reset_database");
+    }
+  });
+
+  container.querySelector("[data-action='reveal']")?.addEventListener("click", async () => {
+    try {
+      await invoke("reveal_db_file");
+    } catch (e) {
+      if (feedback) feedback.textContent = formatError(e);
+    }
+  });
+}
@@ -1,7 +1,7 @@
 import { invoke } from "@tauri-apps/api/core";
 import type { Project } from "./types.ts";
 import type { SessionInfo } from "./types.ts";
-import { escapeHtml } from "./utils.ts";
+import { escapeHtml, formatError } from "./utils.ts";
 
 export interface DebugPanelCallbacks {
   onClose: () => void;
@@ -94,7 +94,7 @@ export function renderDebugPanel(
         });
       });
     } catch (e) {
-      body.innerHTML = `<p class="debug-empty">Error: ${e}</p>`;
+      body.innerHTML = `<p class="debug-empty">Error: ${escapeHtml(formatError(e))}</p>`;
     }
   }
 
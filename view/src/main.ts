@@ -4,18 +4,23 @@ import { open } from "@tauri-apps/plugin-dialog";
 import { renderSidebar, triggerRenameSelected } from "./sidebar.ts";
 import { renderProjectDetail, destroyTerminalForSession, detachActiveTerminal } from "./project-detail.ts";
 import { renderToolbar } from "./toolbar.ts";
-import { clearStream, markStreamStarted, isSessionActive, hasReceivedOutput } from "./terminal.ts";
+import { clearStream, isSessionActive, hasReceivedOutput } from "./terminal.ts";
 import { renderDebugPanel } from "./debug-panel.ts";
+import { renderDbError } from "./db-error.ts";
 import { togglePerfOverlay } from "./perf-overlay.ts";
 import { renderSettingsNav } from "./settings-nav.ts";
 import { renderGeneralSettings } from "./settings-general.ts";
+import { renderWorkspaceSettings } from "./settings-workspaces.ts";
 import { renderWorktreeSettings } from "./settings-worktrees.ts";
+import { renderEnvironmentSettings } from "./settings-environment.ts";
+import { renderSecretSettings } from "./settings-secrets.ts";
+import { renderRecordingSettings } from "./settings-recordings.ts";
 import { renderArchivedSettings } from "./settings-archived.ts";
-import { initTheme } from "./theme.ts";
+import { followTheme, initTheme } from "./theme.ts";
 import { getSetting, setSetting } from "./settings-api.ts";
-import type { Workspace, Project, ProjectStatus, ProjectStatusChangedEvent, SessionInfo, SettingsPage } from "./types.ts";
+import type { DbStatus, Workspace, Project, ProjectStatus, ProjectStatusChangedEvent, SessionExitedEvent, SessionInfo, SettingChangedEvent, SettingsPage, ThemeMode, WorkspaceConfig, WorkspaceRemoval } from "./types.ts";
 import { DEFAULT_CLI, DEFAULT_MODEL_ID } from "./constants.ts";
-import { findModel } from "./utils.ts";
+import { findModel, formatError, isAppError } from "./utils.ts";
 import "./style.css";
 
 interface AppState {
@@ -71,7 +76,7 @@ function getSelectedProject(): Project | null {
   return state.projects.find((p) => p.id === state.selectedProjectId) ?? null;
 }
 
-function getWorkspacePath(workspaceId: number): string | null {
+function getWorkspacePath(workspaceId: number | null): string | null {
   const workspace = state.workspaces.find((w) => w.id === workspaceId);
   return workspace?.path ?? null;
 }
@@ -122,6 +127,35 @@ async function handleWorkspaceChange(newWorkspaceId: number): Promise<void> {
 
   setSetting("last_workspace_id", String(newWorkspaceId));
   render();
+  refreshWorkspaceGit(newWorkspaceId);
+}
+
+/** Pick up branch changes made outside the app without blocking the switch. */
+function refreshWorkspaceGit(workspaceId: number): void {
+  invoke<Workspace>("refresh_workspace", { id: workspaceId })
+    .then((updated) => {
+      state.workspaces = state.workspaces.map((w) => (w.id === updated.id ? updated : w));
+      render();
+    })
+    .catch((e) => console.warn("Failed to refresh workspace:", formatError(e)));
+}
+
+async function addWorkspaceFromDialog(): Promise<void> {
+  try {
+    const selected = await open({ directory: true, multiple: false });
+    if (!selected) return;
+    await invoke("add_workspace", { path: selected });
+    await refresh();
+  } catch (e) {
+    // Picking a folder that's already registered just switches to it
+    const existingId = isAppError(e, "conflict") ? e.details?.workspace_id : undefined;
+    if (typeof existingId === "number") {
+      await refresh();
+      await handleWorkspaceChange(existingId);
+      return;
+    }
+    console.error("Failed to add workspace:", formatError(e));
+  }
 }
 
 async function autoSpawnNewProject(workspaceId: number): Promise<void> {
@@ -158,12 +192,16 @@ async function autoSpawnNewProject(workspaceId: number): Promise<void> {
 }
 
 async function spawnSessionForProject(project: Project): Promise<void> {
-  const path = getWorkspacePath(project.workspace_id);
-  if (!path) return;
+  const path = project.worktree_path ?? getWorkspacePath(project.workspace_id);
+  if (!path || project.workspace_id === null) return;
 
   try {
+    const config = await invoke<WorkspaceConfig>("get_workspace_config", { id: project.workspace_id });
     const model = findModel(state.selectedModelId);
-    const cliCommand = model?.interface ?? DEFAULT_CLI;
+    // Values from the repo's .dacm.toml win over the model picker; passing
+    // null lets the backend fill them in
+    const cliCommand = config.overridden.includes("cli") ? null : model?.interface ?? DEFAULT_CLI;
+    const modelId = config.overridden.includes("model") ? null : state.selectedModelId;
 
     const sessionId = await invoke<string>("spawn_session", {
       projectId: project.id,
@@ -171,42 +209,87 @@ async function spawnSessionForProject(project: Project): Promise<void> {
       workingDir: path,
       initialPrompt: project.description || null,
       cliCommand,
-      model: state.selectedModelId,
+      model: modelId,
+    });
+    state.activeSessions.set(project.id, sessionId);
+    await watchSessionExit(project, sessionId);
+    await markProjectRunning(project);
+  } catch (e) {
+    if (isAppError(e, "hook")) {
+      // A pre-spawn hook failed; its output explains why
+      console.error(formatError(e), e.details?.output ?? "");
+      return;
+    }
+    console.error("Failed to spawn session:", e);
+  }
+}
+
+/** Open an existing tmux session or pane as a new project in the workspace. */
+async function attachTmuxSession(workspaceId: number, target: string): Promise<void> {
+  state.debugMode = false;
+  try {
+    const project = await invoke<Project>("create_project", {
+      workspaceId,
+      name: `tmux ${target}`,
+      description: "",
+    });
+    state.projects.push(project);
+    state.selectedProjectId = project.id;
+    state.selectedWorkspaceId = workspaceId;
+    render();
+
+    const sessionId = await invoke<string>("attach_tmux_session", {
+      projectId: project.id,
+      workspaceId,
+      target,
     });
     state.activeSessions.set(project.id, sessionId);
+    await watchSessionExit(project, sessionId);
+    await markProjectRunning(project);
+    render();
+  } catch (e) {
+    console.error("Failed to attach tmux session:", formatError(e));
+  }
+}
 
-    const unlisten = await listen<void>(`session-exit-${sessionId}`, async () => {
-      const unsub = state.sessionUnlisteners.get(project.id);
-      if (unsub) { unsub(); state.sessionUnlisteners.delete(project.id); }
+/** Archive a project once its session ends, unless a retry is coming. */
+async function watchSessionExit(project: Project, sessionId: string): Promise<void> {
+  const unlisten = await listen<SessionExitedEvent>(`session-exit-${sessionId}`, async (event) => {
+    const unsub = state.sessionUnlisteners.get(project.id);
+    if (unsub) { unsub(); state.sessionUnlisteners.delete(project.id); }
 
-      destroyTerminalForSession(sessionId);
-      state.activeSessions.delete(project.id);
-      clearStream(sessionId);
+    destroyTerminalForSession(sessionId);
+    state.activeSessions.delete(project.id);
+    clearStream(sessionId);
 
-      try {
-        await invoke<Project>("archive_project", { projectId: project.id });
-      } catch (_e) {
-        // Project may have been archived already
-      }
-      state.projects = state.projects.filter((p) => p.id !== project.id);
-      if (state.selectedProjectId === project.id) {
-        const siblings = state.projects.filter((p) => p.workspace_id === project.workspace_id);
-        state.selectedProjectId = siblings[0]?.id ?? null;
-      }
+    // A retry policy is about to respawn this project — keep it around
+    if (event.payload?.will_retry) {
       render();
-    });
-    state.sessionUnlisteners.set(project.id, unlisten);
+      return;
+    }
 
-    if (project.status !== "running") {
-      const updated = await invoke<Project>("update_project_status", {
-        projectId: project.id,
-        status: "running",
-      });
-      state.projects = state.projects.map((p) => (p.id === updated.id ? updated : p));
+    try {
+      await invoke<Project>("archive_project", { projectId: project.id });
+    } catch (_e) {
+      // Project may have been archived already
     }
-  } catch (e) {
-    console.error("Failed to spawn session:", e);
-  }
+    state.projects = state.projects.filter((p) => p.id !== project.id);
+    if (state.selectedProjectId === project.id) {
+      const siblings = state.projects.filter((p) => p.workspace_id === project.workspace_id);
+      state.selectedProjectId = siblings[0]?.id ?? null;
+    }
+    render();
+  });
+  state.sessionUnlisteners.set(project.id, unlisten);
+}
+
+async function markProjectRunning(project: Project): Promise<void> {
+  if (project.status === "running") return;
+  const updated = await invoke<Project>("update_project_status", {
+    projectId: project.id,
+    status: "running",
+  });
+  state.projects = state.projects.map((p) => (p.id === updated.id ? updated : p));
 }
 
 async function killSessionForProject(projectId: number): Promise<void> {
@@ -262,18 +345,31 @@ function renderSettingsView(): void {
   switch (state.settingsPage) {
     case "general":
       renderGeneralSettings(mainContentEl, {
-        async onPreventSleepChange(prevent: boolean) {
-          try {
-            await invoke("set_prevent_sleep", { prevent });
-          } catch (e) {
-            console.error("Failed to set prevent sleep:", e);
-          }
+        async onDataImported() {
+          await refresh();
+        },
+      });
+      break;
+    case "workspaces":
+      renderWorkspaceSettings(mainContentEl, {
+        onWorkspacesAdded(added: Workspace[]) {
+          // No re-render here, which would throw away the scan results
+          state.workspaces = [...added, ...state.workspaces];
         },
       });
       break;
     case "worktrees":
       renderWorktreeSettings(mainContentEl);
       break;
+    case "environment":
+      renderEnvironmentSettings(mainContentEl, state.workspaces);
+      break;
+    case "secrets":
+      renderSecretSettings(mainContentEl, state.workspaces);
+      break;
+    case "recordings":
+      renderRecordingSettings(mainContentEl, state.projects);
+      break;
     case "archived":
       renderArchivedSettings(mainContentEl, state.workspaces, {
         async onRestore(projectId: number) {
@@ -330,15 +426,11 @@ function getSidebarCallbacks(): import("./sidebar.ts").SidebarCallbacks {
       setSetting("last_workspace_id", String(workspaceId));
       await autoSpawnNewProject(workspaceId);
     },
+    async onAttachTmux(workspaceId: number, target: string) {
+      await attachTmuxSession(workspaceId, target);
+    },
     async onAddWorkspace() {
-      try {
-        const selected = await open({ directory: true, multiple: false });
-        if (!selected) return;
-        await invoke("add_workspace", { path: selected });
-        await refresh();
-      } catch (e) {
-        console.error("Failed to add workspace:", e);
-      }
+      await addWorkspaceFromDialog();
     },
     async onArchiveProject(projectId: number) {
       try {
@@ -374,7 +466,14 @@ function getSidebarCallbacks(): import("./sidebar.ts").SidebarCallbacks {
     },
     async onRemoveWorkspace(workspaceId: number) {
       try {
-        await invoke("remove_workspace", { id: workspaceId });
+        // Projects are kept in the archive, detached from the workspace
+        const removal = await invoke<WorkspaceRemoval>("remove_workspace", {
+          id: workspaceId,
+          mode: "archive",
+        });
+        if (removal.worktree_errors.length > 0) {
+          console.warn("Some worktrees were not removed:", removal.worktree_errors);
+        }
         state.projects = state.projects.filter((p) => p.workspace_id !== workspaceId);
         if (state.selectedProjectId !== null) {
           const selected = state.projects.find((p) => p.id === state.selectedProjectId);
@@ -382,7 +481,7 @@ function getSidebarCallbacks(): import("./sidebar.ts").SidebarCallbacks {
         }
         await refresh();
       } catch (e) {
-        console.error("Failed to remove workspace:", e);
+        console.error("Failed to remove workspace:", formatError(e));
       }
     },
   };
@@ -446,14 +545,7 @@ function render() {
           </div>
         </div>`;
       mainContentEl.querySelector("#btn-add-workspace")?.addEventListener("click", async () => {
-        try {
-          const selected = await open({ directory: true, multiple: false });
-          if (!selected) return;
-          await invoke("add_workspace", { path: selected });
-          await refresh();
-        } catch (e) {
-          console.error("Failed to add workspace:", e);
-        }
+        await addWorkspaceFromDialog();
       });
       renderToolbar(mainContentEl, {
         selectedModelId: state.selectedModelId,
@@ -466,14 +558,7 @@ function render() {
         },
         async onWorkspaceChange() {},
         async onAddWorkspace() {
-          try {
-            const selected = await open({ directory: true, multiple: false });
-            if (!selected) return;
-            await invoke("add_workspace", { path: selected });
-            await refresh();
-          } catch (e) {
-            console.error("Failed to add workspace:", e);
-          }
+          await addWorkspaceFromDialog();
         },
       });
       return;
@@ -494,7 +579,7 @@ function render() {
         selectedModelId: state.selectedModelId,
         selectedWorkspace,
         workspaces: state.workspaces,
-        branchName: null,
+        branchName: selectedWorkspace?.head_branch ?? null,
       }, {
         async onModelChange(modelId: string) {
           await handleModelChange(modelId);
@@ -503,14 +588,7 @@ function render() {
           await handleWorkspaceChange(newWorkspaceId);
         },
         async onAddWorkspace() {
-          try {
-            const selected = await open({ directory: true, multiple: false });
-            if (!selected) return;
-            await invoke("add_workspace", { path: selected });
-            await refresh();
-          } catch (e) {
-            console.error("Failed to add workspace:", e);
-          }
+          await addWorkspaceFromDialog();
         },
       });
       return;
@@ -534,7 +612,7 @@ function render() {
       selectedModelId: state.selectedModelId,
       selectedWorkspace,
       workspaces: state.workspaces,
-      branchName: null,
+      branchName: selectedWorkspace?.head_branch ?? null,
     }, {
       async onModelChange(modelId: string) {
         await handleModelChange(modelId);
@@ -543,14 +621,7 @@ function render() {
         await handleWorkspaceChange(newWorkspaceId);
       },
       async onAddWorkspace() {
-        try {
-          const selected = await open({ directory: true, multiple: false });
-          if (!selected) return;
-          await invoke("add_workspace", { path: selected });
-          await refresh();
-        } catch (e) {
-          console.error("Failed to add workspace:", e);
-        }
+        await addWorkspaceFromDialog();
       },
     });
     return;
@@ -562,7 +633,7 @@ function render() {
     selectedModelId: state.selectedModelId,
     selectedWorkspace,
     workspaces: state.workspaces,
-    branchName: project.branch_name,
+    branchName: project.branch_name ?? selectedWorkspace?.head_branch ?? null,
   };
 
   renderProjectDetail(mainContentEl, project, activeSessionId, toolbarProps, {
@@ -633,14 +704,7 @@ function render() {
       await handleWorkspaceChange(workspaceId);
     },
     async onAddWorkspace() {
-      try {
-        const selected = await open({ directory: true, multiple: false });
-        if (!selected) return;
-        await invoke("add_workspace", { path: selected });
-        await refresh();
-      } catch (e) {
-        console.error("Failed to add workspace:", e);
-      }
+      await addWorkspaceFromDialog();
     },
   });
 }
@@ -673,7 +737,6 @@ async function refresh() {
     for (const s of sessions) {
       if (s.status === "running" && !state.activeSessions.has(s.project_id)) {
         state.activeSessions.set(s.project_id, s.session_id);
-        markStreamStarted(s.session_id);
       }
     }
 
@@ -703,6 +766,29 @@ listen<ProjectStatusChangedEvent>("project-status-changed", (event) => {
   render();
 });
 
+// Projects and sessions created from the `dacm` command line
+listen("projects-changed", () => {
+  refresh();
+});
+
+// Keep this window in step with settings saved elsewhere, e.g. another
+// window or a backup import
+listen<SettingChangedEvent>("settings-changed", (event) => {
+  const { key, new_value } = event.payload;
+  const root = document.documentElement.style;
+  switch (key) {
+    case "theme":
+      followTheme(new_value as ThemeMode);
+      break;
+    case "code_font_family":
+      root.setProperty("--code-font-family", String(new_value));
+      break;
+    case "code_font_size":
+      root.setProperty("--code-font-size", new_value + "px");
+      break;
+  }
+});
+
 document.addEventListener("keydown", (e) => {
   const inTerminal = document.activeElement?.closest(".terminal-container") !== null;
 
@@ -840,6 +926,12 @@ setInterval(() => {
 
 async function init(): Promise<void> {
   await initTheme();
+
+  const dbStatus = await invoke<DbStatus>("get_db_status");
+  if (dbStatus.state === "error") {
+    renderDbError(mainContentEl, dbStatus, () => window.location.reload());
+    return;
+  }
   await Promise.all([refresh(), loadCodeFontSettings()]);
   await loadPersistedState();
 
@@ -1,5 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { SystemStats } from "./types.ts";
+import type { StreamStats, SystemStats } from "./types.ts";
 
 let overlayEl: HTMLElement | null = null;
 let intervalId: ReturnType<typeof setInterval> | null = null;
@@ -24,6 +24,23 @@ function getColor(percent: number): string {
   return "#68d391";
 }
 
+/** Output delivery summed over live sessions; lag and drops turn red. */
+function formatStreams(streams: StreamStats[]): string {
+  if (streams.length === 0) return "";
+  const frames = streams.reduce((n, s) => n + s.frames, 0);
+  const chunks = streams.reduce((n, s) => n + s.chunks, 0);
+  const dropped = streams.reduce((n, s) => n + s.dropped_frames, 0);
+  const stalls = streams.reduce((n, s) => n + s.stalls, 0);
+  const lag = Math.max(...streams.map((s) => s.last_lag_ms));
+  const maxLag = Math.max(...streams.map((s) => s.max_lag_ms));
+  const lagColor = lag > 250 ? "#e53e3e" : lag > 50 ? "#ecc94b" : "#68d391";
+  const binary = streams.filter((s) => s.binary).length;
+  return `
+      <span class="perf-line">OUT ${frames}/${chunks} fr${binary > 0 ? ` &middot; ${binary} bin` : ""}</span>
+      <span class="perf-line" style="color:${lagColor}">LAG ${lag}ms (max ${maxLag})</span>
+      <span class="perf-line" style="color:${dropped > 0 ? "#e53e3e" : "inherit"}">DROP ${dropped} &middot; STALL ${stalls}</span>`;
+}
+
 async function update(): Promise<void> {
   if (!overlayEl) return;
   try {
@@ -32,7 +49,8 @@ async function update(): Promise<void> {
     overlayEl.innerHTML = `
       <span class="perf-line">CPU ${stats.cpu_usage.toFixed(1)}</span>
       <span class="perf-line" style="color:${memColor}">MEM ${formatMb(stats.memory_used_mb)}/${formatMb(stats.memory_total_mb)} (${stats.memory_percent.toFixed(0)}%)</span>
-      <span class="perf-line">PTY ${stats.child_count} &middot; ${formatMb(stats.child_memory_mb)}</span>`;
+      <span class="perf-line">PTY ${stats.child_count} &middot; ${formatMb(stats.child_memory_mb)}</span>
+      ${formatStreams(stats.streams)}`;
   } catch {
     overlayEl.innerHTML = `<span class="perf-line" style="color:#e53e3e">stats err</span>`;
   }
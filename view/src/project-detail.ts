@@ -1,6 +1,6 @@
 import type { Project, ProjectStatus } from "./types.ts";
 import { createTerminalSession, type TerminalSession } from "./terminal.ts";
-import { escapeHtml } from "./utils.ts";
+import { escapeHtml, formatError } from "./utils.ts";
 import { renderToolbar, type ToolbarProps } from "./toolbar.ts";
 
 export interface ProjectDetailCallbacks {
@@ -130,7 +130,7 @@ function renderTerminalView(
     session.terminal.focus();
   }).catch((e) => {
     console.error("Failed to create terminal session:", e);
-    wrapper.textContent = `Failed to start terminal: ${e}`;
+    wrapper.textContent = `Failed to start terminal: ${formatError(e)}`;
   });
 }
 
@@ -1,5 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { Setting } from "./types.ts";
+import type { SettingJson, SettingSchema, SettingValue } from "./types.ts";
 
 export async function getSetting(key: string): Promise<string> {
   return invoke<string>("get_setting", { key });
@@ -9,6 +9,22 @@ export async function setSetting(key: string, value: string): Promise<void> {
   await invoke("set_setting", { key, value });
 }
 
-export async function listSettings(): Promise<Setting[]> {
-  return invoke<Setting[]>("list_settings");
+export async function listSettings(): Promise<SettingValue[]> {
+  return invoke<SettingValue[]>("list_settings");
+}
+
+export async function getSettingValue(key: string): Promise<SettingValue> {
+  return invoke<SettingValue>("get_setting_value", { key });
+}
+
+export async function setSettingValue(key: string, value: SettingJson): Promise<SettingValue> {
+  return invoke<SettingValue>("set_setting_value", { key, value });
+}
+
+let schemaCache: Promise<SettingSchema[]> | null = null;
+
+/** The backend's settings registry. It doesn't change at runtime, so it's fetched once. */
+export function getSettingsSchema(): Promise<SettingSchema[]> {
+  schemaCache ??= invoke<SettingSchema[]>("get_settings_schema");
+  return schemaCache;
 }
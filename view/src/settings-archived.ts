@@ -26,8 +26,9 @@ export async function renderArchivedSettings(
       ? `<p class="archived-empty">No archived projects.</p>`
       : archivedProjects
           .map((project) => {
-            const workspace = workspaceMap.get(project.workspace_id);
-            const workspaceName = workspace ? escapeHtml(workspace.name) : "Unknown";
+            const workspace =
+              project.workspace_id === null ? undefined : workspaceMap.get(project.workspace_id);
+            const workspaceName = workspace ? escapeHtml(workspace.name) : "Removed workspace";
             const date = new Date(project.created_at).toLocaleDateString();
             return `
               <div class="archived-project-row" data-project-id="${project.id}">
@@ -36,7 +37,7 @@ export async function renderArchivedSettings(
                   <div class="archived-project-meta">${workspaceName} &middot; ${date}</div>
                 </div>
                 <div class="archived-project-actions">
-                  <button class="btn" data-action="restore" data-project-id="${project.id}">Restore</button>
+                  ${project.workspace_id === null ? "" : `<button class="btn" data-action="restore" data-project-id="${project.id}">Restore</button>`}
                   <button class="btn btn-archive" data-action="delete" data-project-id="${project.id}">Delete</button>
                 </div>
               </div>`;
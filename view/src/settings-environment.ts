@@ -0,0 +1,155 @@
+import { invoke } from "@tauri-apps/api/core";
+import type { EnvProfile, Workspace } from "./types.ts";
+import { escapeHtml, formatError } from "./utils.ts";
+
+/** `KEY=value` lines; blank lines and `#` comments are skipped. */
+export function parseVars(text: string): Record<string, string> {
+  const vars: Record<string, string> = {};
+  for (const line of text.split("\n")) {
+    const trimmed = line.trim();
+    if (!trimmed || trimmed.startsWith("#")) continue;
+    const eq = trimmed.indexOf("=");
+    if (eq <= 0) throw new Error(`Expected KEY=value, got "${trimmed}"`);
+    vars[trimmed.slice(0, eq).trim()] = trimmed.slice(eq + 1);
+  }
+  return vars;
+}
+
+export function formatVars(vars: Record<string, string>): string {
+  return Object.entries(vars)
+    .map(([key, value]) => `${key}=${value}`)
+    .join("\n");
+}
+
+export async function renderEnvironmentSettings(
+  container: HTMLElement,
+  workspaces: Workspace[],
+): Promise<void> {
+  let profiles: EnvProfile[] = [];
+  try {
+    profiles = await invoke<EnvProfile[]>("list_env_profiles");
+  } catch (e) {
+    console.error("Failed to load env profiles:", e);
+  }
+
+  const profileOptions = (selected: number | null) =>
+    [`<option value="">None</option>`]
+      .concat(
+        profiles.map(
+          (p) => `<option value="${p.id}" ${p.id === selected ? "selected" : ""}>${escapeHtml(p.name)}</option>`,
+        ),
+      )
+      .join("");
+
+  const profilesHtml =
+    profiles.length === 0
+      ? `<p class="archived-empty">No env profiles yet.</p>`
+      : profiles
+          .map(
+            (p) => `
+              <div class="settings-row env-profile" data-profile-id="${p.id}">
+                <div class="env-profile-body">
+                  <input type="text" class="settings-input" data-field="name" value="${escapeHtml(p.name)}" />
+                  <textarea class="form-textarea env-profile-vars" data-field="vars" spellcheck="false">${escapeHtml(formatVars(p.vars))}</textarea>
+                </div>
+                <div class="settings-row-actions">
+                  <button class="btn" data-action="save">Save</button>
+                  <button class="btn btn-archive" data-action="delete">Delete</button>
+                </div>
+              </div>`,
+          )
+          .join("");
+
+  const workspacesHtml = workspaces
+    .map(
+      (w) => `
+        <div class="settings-row">
+          <div>
+            <div class="settings-row-label">${escapeHtml(w.name)}</div>
+            <div class="settings-row-sublabel">${escapeHtml(w.path)}</div>
+          </div>
+          <select class="form-select" data-workspace-id="${w.id}">${profileOptions(w.env_profile_id)}</select>
+        </div>`,
+    )
+    .join("");
+
+  container.innerHTML = `
+    <div class="settings-page">
+      <h2 class="settings-page-title">Environment</h2>
+
+      <div class="settings-section">
+        <div class="settings-section-title">Env profiles</div>
+        <div class="settings-row-sublabel">Named sets of variables for agent sessions, one <code>KEY=value</code> per line. Use Secrets for tokens and keys.</div>
+        ${profilesHtml}
+        <div class="settings-row">
+          <div class="settings-row-actions">
+            <input type="text" class="settings-input" id="env-profile-name" placeholder="New profile name" />
+            <button class="btn" id="env-profile-create">Create</button>
+          </div>
+        </div>
+        <div class="settings-row-sublabel" id="env-status"></div>
+      </div>
+
+      <div class="settings-section">
+        <div class="settings-section-title">Workspace profiles</div>
+        <div class="settings-row-sublabel">Applied to every session in the workspace. A workspace's .dacm.toml <code>env</code> and a project's own profile take precedence.</div>
+        ${workspacesHtml}
+      </div>
+    </div>`;
+
+  const statusEl = container.querySelector<HTMLElement>("#env-status")!;
+  const rerender = () => renderEnvironmentSettings(container, workspaces);
+
+  container.querySelector("#env-profile-create")?.addEventListener("click", async () => {
+    const name = container.querySelector<HTMLInputElement>("#env-profile-name")!.value.trim();
+    if (!name) return;
+    try {
+      await invoke<EnvProfile>("create_env_profile", { name, vars: {} });
+      await rerender();
+    } catch (e) {
+      statusEl.textContent = formatError(e);
+    }
+  });
+
+  container.querySelectorAll<HTMLElement>(".env-profile").forEach((row) => {
+    const id = Number(row.dataset.profileId);
+
+    row.querySelector("[data-action='save']")?.addEventListener("click", async () => {
+      const name = row.querySelector<HTMLInputElement>("[data-field='name']")!.value.trim();
+      const text = row.querySelector<HTMLTextAreaElement>("[data-field='vars']")!.value;
+      try {
+        await invoke<EnvProfile>("update_env_profile", { id, name, vars: parseVars(text) });
+        statusEl.textContent = `Saved ${name}`;
+      } catch (e) {
+        statusEl.textContent = formatError(e);
+      }
+    });
+
+    row.querySelector("[data-action='delete']")?.addEventListener("click", async () => {
+      if (!confirm("Delete this env profile? Workspaces and projects using it are detached.")) return;
+      try {
+        await invoke("delete_env_profile", { id });
+        for (const w of workspaces) {
+          if (w.env_profile_id === id) w.env_profile_id = null;
+        }
+        await rerender();
+      } catch (e) {
+        statusEl.textContent = formatError(e);
+      }
+    });
+  });
+
+  container.querySelectorAll<HTMLSelectElement>("select[data-workspace-id]").forEach((select) => {
+    select.addEventListener("change", async () => {
+      const workspaceId = Number(select.dataset.workspaceId);
+      const profileId = select.value ? Number(select.value) : null;
+      try {
+        await invoke("set_workspace_env_profile", { workspaceId, profileId });
+        const workspace = workspaces.find((w) => w.id === workspaceId);
+        if (workspace) workspace.env_profile_id = profileId;
+      } catch (e) {
+        statusEl.textContent = formatError(e);
+      }
+    });
+  });
+}
@@ -1,9 +1,12 @@
-import { getSetting, setSetting } from "./settings-api.ts";
+import { invoke } from "@tauri-apps/api/core";
+import { open, save } from "@tauri-apps/plugin-dialog";
+import { getSetting, getSettingsSchema, setSetting } from "./settings-api.ts";
 import { setTheme, getEffectiveTheme } from "./theme.ts";
-import type { ThemeMode } from "./types.ts";
+import type { BackupInfo, ExportSummary, ImportReport, SettingSchema, ThemeMode } from "./types.ts";
+import { escapeHtml, formatError } from "./utils.ts";
 
 export interface GeneralSettingsCallbacks {
-  onPreventSleepChange: (prevent: boolean) => void;
+  onDataImported: () => void;
 }
 
 async function loadSetting(key: string, fallback: string): Promise<string> {
@@ -19,13 +22,23 @@ export async function renderGeneralSettings(
   callbacks: GeneralSettingsCallbacks,
 ): Promise<void> {
   const [
+    schema,
     preventSleep,
+    recordSessions,
+    persistentSessions,
+    hookTimeout,
+    binaryOutput,
     codeFontFamily,
     codeFontSize,
     termFontFamily,
     termFontSize,
   ] = await Promise.all([
+    getSettingsSchema().catch(() => [] as SettingSchema[]),
     loadSetting("prevent_sleep", "false"),
+    loadSetting("record_sessions", "false"),
+    loadSetting("persistent_sessions", "false"),
+    loadSetting("hook_timeout_secs", "600"),
+    loadSetting("binary_output", "false"),
     loadSetting("code_font_family", '"SF Mono", "Fira Code", monospace'),
     loadSetting("code_font_size", "13"),
     loadSetting("terminal_font_family", '"SF Mono", "Fira Code", "Menlo", monospace'),
@@ -43,6 +56,12 @@ export async function renderGeneralSettings(
     // default
   }
 
+  // Input limits come from the backend registry so the two can't disagree
+  function rangeAttrs(key: string): string {
+    const entry = schema.find((s) => s.key === key);
+    return entry?.type === "integer" ? `min="${entry.min}" max="${entry.max}"` : "";
+  }
+
   function activeClass(mode: ThemeMode): string {
     return mode === currentMode ? "segmented-btn--active" : "";
   }
@@ -78,6 +97,43 @@ export async function renderGeneralSettings(
             <span class="toggle-slider"></span>
           </label>
         </div>
+        <div class="settings-row">
+          <div>
+            <div class="settings-row-label">Record sessions</div>
+            <div class="settings-row-sublabel">Save terminal output for replay under Settings &rsaquo; Recordings</div>
+          </div>
+          <label class="toggle-switch">
+            <input type="checkbox" id="record-sessions-toggle" ${recordSessions === "true" ? "checked" : ""} />
+            <span class="toggle-slider"></span>
+          </label>
+        </div>
+        <div class="settings-row">
+          <div>
+            <div class="settings-row-label">Keep sessions running</div>
+            <div class="settings-row-sublabel">Run agents inside tmux so they survive closing the app and reattach on launch. A workspace's .dacm.toml <code>persistent_sessions</code> takes precedence</div>
+          </div>
+          <label class="toggle-switch">
+            <input type="checkbox" id="persistent-sessions-toggle" ${persistentSessions === "true" ? "checked" : ""} />
+            <span class="toggle-slider"></span>
+          </label>
+        </div>
+        <div class="settings-row">
+          <div>
+            <div class="settings-row-label">Hook timeout</div>
+            <div class="settings-row-sublabel">Seconds a hook may run before it's killed and fails; 0 for no limit. A workspace's .dacm.toml <code>hook_timeout_secs</code> takes precedence</div>
+          </div>
+          <input type="number" class="settings-input settings-input--narrow" id="hook-timeout" value="${hookTimeout}" ${rangeAttrs("hook_timeout_secs")} />
+        </div>
+        <div class="settings-row">
+          <div>
+            <div class="settings-row-label">Binary terminal output</div>
+            <div class="settings-row-sublabel">Send output as raw bytes instead of JSON. Applies to terminals opened afterwards</div>
+          </div>
+          <label class="toggle-switch">
+            <input type="checkbox" id="binary-output-toggle" ${binaryOutput === "true" ? "checked" : ""} />
+            <span class="toggle-slider"></span>
+          </label>
+        </div>
       </div>
 
       <div class="settings-section">
@@ -88,7 +144,7 @@ export async function renderGeneralSettings(
         </div>
         <div class="settings-row">
           <div class="settings-row-label">Size</div>
-          <input type="number" class="settings-input settings-input--narrow" id="code-font-size" value="${codeFontSize}" min="8" max="24" />
+          <input type="number" class="settings-input settings-input--narrow" id="code-font-size" value="${codeFontSize}" ${rangeAttrs("code_font_size")} />
         </div>
       </div>
 
@@ -100,9 +156,31 @@ export async function renderGeneralSettings(
         </div>
         <div class="settings-row">
           <div class="settings-row-label">Size</div>
-          <input type="number" class="settings-input settings-input--narrow" id="term-font-size" value="${termFontSize}" min="8" max="24" />
+          <input type="number" class="settings-input settings-input--narrow" id="term-font-size" value="${termFontSize}" ${rangeAttrs("terminal_font_size")} />
         </div>
       </div>
+
+      <div class="settings-section">
+        <div class="settings-section-title">Data</div>
+        <div class="settings-row">
+          <div>
+            <div class="settings-row-label">Back up database</div>
+            <div class="settings-row-sublabel">Save a copy of all projects and history</div>
+          </div>
+          <button class="btn" id="backup-database">Back up…</button>
+        </div>
+        <div class="settings-row">
+          <div>
+            <div class="settings-row-label">Export / import</div>
+            <div class="settings-row-sublabel">Move workspaces, projects and settings to another machine</div>
+          </div>
+          <div class="settings-row-actions">
+            <button class="btn" id="export-database">Export…</button>
+            <button class="btn" id="import-database">Import…</button>
+          </div>
+        </div>
+        <div class="settings-row-sublabel" id="data-status"></div>
+      </div>
     </div>`;
 
   // Theme buttons
@@ -115,11 +193,30 @@ export async function renderGeneralSettings(
     });
   });
 
-  // Prevent sleep toggle
+  // Prevent sleep toggle; the backend starts or stops inhibiting on save
   container.querySelector("#prevent-sleep-toggle")?.addEventListener("change", (e) => {
     const checked = (e.target as HTMLInputElement).checked;
     setSetting("prevent_sleep", String(checked));
-    callbacks.onPreventSleepChange(checked);
+  });
+
+  // Applies to sessions whose stream starts after the change
+  container.querySelector("#record-sessions-toggle")?.addEventListener("change", (e) => {
+    const checked = (e.target as HTMLInputElement).checked;
+    setSetting("record_sessions", String(checked));
+  });
+
+  container.querySelector("#persistent-sessions-toggle")?.addEventListener("change", (e) => {
+    const checked = (e.target as HTMLInputElement).checked;
+    setSetting("persistent_sessions", String(checked));
+  });
+
+  container.querySelector("#hook-timeout")?.addEventListener("change", (e) => {
+    saveOrRevert(e.target as HTMLInputElement, "hook_timeout_secs");
+  });
+
+  container.querySelector("#binary-output-toggle")?.addEventListener("change", (e) => {
+    const checked = (e.target as HTMLInputElement).checked;
+    setSetting("binary_output", String(checked));
   });
 
   // Code font family
@@ -130,10 +227,11 @@ export async function renderGeneralSettings(
   });
 
   // Code font size
-  container.querySelector("#code-font-size")?.addEventListener("change", (e) => {
+  container.querySelector("#code-font-size")?.addEventListener("change", async (e) => {
     const val = (e.target as HTMLInputElement).value;
-    setSetting("code_font_size", val);
-    document.documentElement.style.setProperty("--code-font-size", val + "px");
+    if (await saveOrRevert(e.target as HTMLInputElement, "code_font_size")) {
+      document.documentElement.style.setProperty("--code-font-size", val + "px");
+    }
   });
 
   // Terminal font family
@@ -142,13 +240,75 @@ export async function renderGeneralSettings(
     setSetting("terminal_font_family", val);
   });
 
+  const dataStatus = container.querySelector<HTMLElement>("#data-status");
+  function showDataStatus(text: string): void {
+    if (dataStatus) dataStatus.innerHTML = escapeHtml(text);
+  }
+
+  container.querySelector("#backup-database")?.addEventListener("click", async () => {
+    const dest = await save({ defaultPath: "dacm-backup.db" });
+    if (!dest) return;
+    try {
+      const info = await invoke<BackupInfo>("backup_database", { dest });
+      showDataStatus(`Backed up ${(info.size_bytes / 1024).toFixed(0)} KB to ${info.path}`);
+    } catch (e) {
+      showDataStatus(`Backup failed: ${formatError(e)}`);
+    }
+  });
+
+  container.querySelector("#export-database")?.addEventListener("click", async () => {
+    const dest = await save({
+      defaultPath: "dacm-export.json",
+      filters: [{ name: "JSON", extensions: ["json"] }],
+    });
+    if (!dest) return;
+    try {
+      const summary = await invoke<ExportSummary>("export_database", { dest });
+      showDataStatus(`Exported ${summary.workspaces} workspaces and ${summary.projects} projects`);
+    } catch (e) {
+      showDataStatus(`Export failed: ${formatError(e)}`);
+    }
+  });
+
+  container.querySelector("#import-database")?.addEventListener("click", async () => {
+    const src = await open({
+      multiple: false,
+      filters: [{ name: "JSON", extensions: ["json"] }],
+    });
+    if (!src) return;
+    try {
+      const report = await invoke<ImportReport>("import_database", { src });
+      showDataStatus(
+        `Imported ${report.projects_imported} projects into ${report.workspaces_created} new and ${report.workspaces_merged} existing workspaces`,
+      );
+      callbacks.onDataImported();
+    } catch (e) {
+      showDataStatus(`Import failed: ${formatError(e)}`);
+    }
+  });
+
   // Terminal font size
-  container.querySelector("#term-font-size")?.addEventListener("change", (e) => {
-    const val = (e.target as HTMLInputElement).value;
-    setSetting("terminal_font_size", val);
+  container.querySelector("#term-font-size")?.addEventListener("change", async (e) => {
+    await saveOrRevert(e.target as HTMLInputElement, "terminal_font_size");
   });
 }
 
+/** Save an input's value, putting back the previous one if the backend rejects it. */
+async function saveOrRevert(input: HTMLInputElement, key: string): Promise<boolean> {
+  try {
+    await setSetting(key, input.value);
+    input.defaultValue = input.value;
+    input.classList.remove("settings-input--invalid");
+    input.title = "";
+    return true;
+  } catch (e) {
+    input.value = input.defaultValue;
+    input.classList.add("settings-input--invalid");
+    input.title = formatError(e);
+    return false;
+  }
+}
+
 function escapeAttr(text: string): string {
   return text.replace(/&/g, "&amp;").replace(/"/g, "&quot;").replace(/</g, "&lt;").replace(/>/g, "&gt;");
 }
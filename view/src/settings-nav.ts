@@ -9,7 +9,11 @@ const ARROW_LEFT = `<svg width="16" height="16" viewBox="0 0 16 16" fill="none"
 
 const NAV_ITEMS: { page: SettingsPage; label: string }[] = [
   { page: "general", label: "General" },
+  { page: "workspaces", label: "Workspaces" },
   { page: "worktrees", label: "Worktrees" },
+  { page: "environment", label: "Environment" },
+  { page: "secrets", label: "Secrets" },
+  { page: "recordings", label: "Recordings" },
   { page: "archived", label: "Archived" },
 ];
 
@@ -0,0 +1,112 @@
+import { invoke } from "@tauri-apps/api/core";
+import type { Project, RecordingInfo } from "./types.ts";
+import { createTerminalSession, type TerminalSession } from "./terminal.ts";
+import { escapeHtml, formatError } from "./utils.ts";
+
+const SPEEDS = [1, 2, 4, 8];
+
+// The replay playing on this page, torn down whenever the page re-renders
+let player: TerminalSession | null = null;
+
+function stopPlayer(): void {
+  if (!player) return;
+  invoke("stop_replay", { replayId: player.sessionId }).catch(() => {});
+  player.destroy();
+  player = null;
+}
+
+function formatDuration(secs: number): string {
+  const m = Math.floor(secs / 60);
+  const s = Math.round(secs % 60);
+  return m > 0 ? `${m}m ${s}s` : `${s}s`;
+}
+
+export async function renderRecordingSettings(
+  container: HTMLElement,
+  projects: Project[],
+): Promise<void> {
+  stopPlayer();
+
+  let recordings: RecordingInfo[] = [];
+  try {
+    recordings = await invoke<RecordingInfo[]>("list_recordings");
+  } catch (e) {
+    console.error("Failed to load recordings:", e);
+  }
+
+  const projectNames = new Map(projects.map((p) => [p.id, p.name]));
+
+  const listHtml =
+    recordings.length === 0
+      ? `<p class="archived-empty">No recordings. Turn on Record sessions under General.</p>`
+      : recordings
+          .map((r) => {
+            const name = (r.project_id !== null && projectNames.get(r.project_id)) || r.id.slice(0, 8);
+            const date = r.started_at ? new Date(r.started_at * 1000).toLocaleString() : "unknown date";
+            return `
+              <div class="archived-project-row">
+                <div class="archived-project-info">
+                  <div class="archived-project-name">${escapeHtml(name)}${r.active ? " &middot; recording" : ""}</div>
+                  <div class="archived-project-meta">${date} &middot; ${formatDuration(r.duration_secs)} &middot; ${r.width}&times;${r.height}</div>
+                </div>
+                <div class="archived-project-actions">
+                  <button class="btn" data-action="play" data-id="${r.id}">Play</button>
+                  <button class="btn btn-archive" data-action="delete" data-id="${r.id}" ${r.active ? "disabled" : ""}>Delete</button>
+                </div>
+              </div>`;
+          })
+          .join("");
+
+  container.innerHTML = `
+    <div class="settings-page">
+      <h2 class="settings-page-title">Recordings</h2>
+      <div class="settings-section">
+        <div class="settings-row">
+          <div class="settings-row-sublabel">Saved as asciicast v2 files, playable with asciinema.</div>
+          <select class="form-select" id="replay-speed">
+            ${SPEEDS.map((s) => `<option value="${s}">${s}&times;</option>`).join("")}
+          </select>
+        </div>
+        <div class="settings-row-sublabel" id="recording-status"></div>
+        <div class="replay-player" id="replay-player" hidden></div>
+        <div class="archived-project-list">
+          ${listHtml}
+        </div>
+      </div>
+    </div>`;
+
+  const statusEl = container.querySelector<HTMLElement>("#recording-status")!;
+  const playerEl = container.querySelector<HTMLElement>("#replay-player")!;
+  const speedEl = container.querySelector<HTMLSelectElement>("#replay-speed")!;
+
+  container.querySelectorAll<HTMLElement>("[data-action='play']").forEach((btn) => {
+    btn.addEventListener("click", async () => {
+      stopPlayer();
+      playerEl.innerHTML = "";
+      playerEl.hidden = false;
+      try {
+        const replayId = await invoke<string>("replay_recording", {
+          id: btn.dataset.id,
+          speed: Number(speedEl.value),
+        });
+        // Same terminal as a live session; playback starts once it attaches
+        player = await createTerminalSession(playerEl, replayId, () => {});
+      } catch (e) {
+        playerEl.hidden = true;
+        statusEl.textContent = formatError(e);
+      }
+    });
+  });
+
+  container.querySelectorAll<HTMLElement>("[data-action='delete']").forEach((btn) => {
+    btn.addEventListener("click", async () => {
+      if (!confirm("Delete this recording?")) return;
+      try {
+        await invoke("delete_recording", { id: btn.dataset.id });
+        await renderRecordingSettings(container, projects);
+      } catch (e) {
+        statusEl.textContent = formatError(e);
+      }
+    });
+  });
+}
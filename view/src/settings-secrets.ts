@@ -0,0 +1,143 @@
+import { invoke } from "@tauri-apps/api/core";
+import { formatVars, parseVars } from "./settings-environment.ts";
+import type { SecretInfo, SecretsStatus, Workspace } from "./types.ts";
+import { escapeHtml, formatError } from "./utils.ts";
+
+const BACKEND_LABELS: Record<SecretsStatus["backend"], string> = {
+  keyring: "OS keyring",
+  file: "encrypted file",
+};
+
+export async function renderSecretSettings(
+  container: HTMLElement,
+  workspaces: Workspace[],
+): Promise<void> {
+  let secrets: SecretInfo[] = [];
+  let status: SecretsStatus | null = null;
+  let mappings: Record<string, string>[] = [];
+  let loadError = "";
+  try {
+    [secrets, status, mappings] = await Promise.all([
+      invoke<SecretInfo[]>("list_secrets"),
+      invoke<SecretsStatus>("get_secrets_status"),
+      Promise.all(
+        workspaces.map((w) => invoke<Record<string, string>>("get_secret_mappings", { workspaceId: w.id })),
+      ),
+    ]);
+  } catch (e) {
+    loadError = formatError(e);
+  }
+  const rerender = () => renderSecretSettings(container, workspaces);
+
+  const statusText = status
+    ? `New secrets are stored in the ${BACKEND_LABELS[status.backend]}${status.keyring_available ? "" : " (no keyring found)"}${status.backend === "file" && !status.file_available ? ", which needs a passphrase in DACM_SECRETS_KEY" : ""}`
+    : loadError;
+
+  const listHtml =
+    secrets.length === 0
+      ? `<p class="archived-empty">No secrets stored.</p>`
+      : secrets
+          .map(
+            (secret) => `
+              <div class="archived-project-row">
+                <div class="archived-project-info">
+                  <div class="archived-project-name">${escapeHtml(secret.name)}</div>
+                  <div class="archived-project-meta">${BACKEND_LABELS[secret.backend]} &middot; updated ${new Date(secret.updated_at).toLocaleDateString()}</div>
+                </div>
+                <div class="archived-project-actions">
+                  <button class="btn btn-archive" data-action="delete" data-name="${escapeHtml(secret.name)}">Delete</button>
+                </div>
+              </div>`,
+          )
+          .join("");
+
+  const mappingsHtml = workspaces
+    .map(
+      (w, i) => `
+        <div class="settings-row env-profile" data-workspace-id="${w.id}">
+          <div class="env-profile-body">
+            <div class="settings-row-label">${escapeHtml(w.name)}</div>
+            <textarea class="form-textarea env-profile-vars" data-field="mappings" spellcheck="false" placeholder="GITHUB_TOKEN=github-token">${escapeHtml(formatVars(mappings[i] ?? {}))}</textarea>
+          </div>
+          <div class="settings-row-actions">
+            <button class="btn" data-action="save-mappings">Save</button>
+          </div>
+        </div>`,
+    )
+    .join("");
+
+  container.innerHTML = `
+    <div class="settings-page">
+      <h2 class="settings-page-title">Secrets</h2>
+
+      <div class="settings-section">
+        <div class="settings-section-title">Stored secrets</div>
+        <div class="settings-row-sublabel">${escapeHtml(statusText)}. Map them to environment variables per workspace below.</div>
+        <div class="archived-project-list">
+          ${listHtml}
+        </div>
+      </div>
+
+      <div class="settings-section">
+        <div class="settings-section-title">Workspace mappings</div>
+        <div class="settings-row-sublabel">Which secrets a workspace's sessions get, one <code>ENV_VAR=secret-name</code> per line. A <code>[secrets]</code> table in a repository's .dacm.toml is ignored.</div>
+        ${mappingsHtml}
+        <div class="settings-row-sublabel" id="mapping-status"></div>
+      </div>
+
+      <div class="settings-section">
+        <div class="settings-section-title">Add or replace</div>
+        <div class="settings-row">
+          <div class="settings-row-actions">
+            <input type="text" class="settings-input" id="secret-name" placeholder="name, e.g. github-token" autocomplete="off" spellcheck="false" />
+            <input type="password" class="settings-input" id="secret-value" placeholder="value" autocomplete="off" />
+            <button class="btn" id="secret-save">Save</button>
+          </div>
+        </div>
+        <div class="settings-row-sublabel" id="secret-status"></div>
+      </div>
+    </div>`;
+
+  const nameInput = container.querySelector<HTMLInputElement>("#secret-name")!;
+  const valueInput = container.querySelector<HTMLInputElement>("#secret-value")!;
+  const statusEl = container.querySelector<HTMLElement>("#secret-status")!;
+
+  container.querySelector("#secret-save")?.addEventListener("click", async () => {
+    const name = nameInput.value.trim();
+    if (!name || !valueInput.value) return;
+    try {
+      await invoke<SecretInfo>("set_secret", { name, value: valueInput.value });
+      await rerender();
+    } catch (e) {
+      statusEl.textContent = formatError(e);
+    }
+  });
+
+  container.querySelectorAll<HTMLElement>("[data-action='delete']").forEach((btn) => {
+    btn.addEventListener("click", async () => {
+      const name = btn.dataset.name!;
+      if (!confirm(`Delete secret "${name}"?`)) return;
+      try {
+        await invoke("delete_secret", { name });
+        await rerender();
+      } catch (e) {
+        statusEl.textContent = formatError(e);
+      }
+    });
+  });
+
+  const mappingStatus = container.querySelector<HTMLElement>("#mapping-status")!;
+  container.querySelectorAll<HTMLElement>("[data-workspace-id]").forEach((row) => {
+    const workspaceId = Number(row.dataset.workspaceId);
+    row.querySelector("[data-action='save-mappings']")?.addEventListener("click", async () => {
+      const text = row.querySelector<HTMLTextAreaElement>("[data-field='mappings']")!.value;
+      try {
+        await invoke("set_secret_mappings", { workspaceId, mappings: parseVars(text) });
+        const workspace = workspaces.find((w) => w.id === workspaceId);
+        mappingStatus.textContent = `Saved mappings for ${workspace?.name ?? "workspace"}`;
+      } catch (e) {
+        mappingStatus.textContent = formatError(e);
+      }
+    });
+  });
+}
@@ -0,0 +1,133 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { open } from "@tauri-apps/plugin-dialog";
+import type { ScanProgress, Workspace, WorkspaceBulkAdd, WorkspaceCandidate } from "./types.ts";
+import { escapeHtml, formatError } from "./utils.ts";
+
+export interface WorkspaceSettingsCallbacks {
+  onWorkspacesAdded: (added: Workspace[]) => void;
+}
+
+const DEFAULT_SCAN_DEPTH = 4;
+
+export function renderWorkspaceSettings(
+  container: HTMLElement,
+  callbacks: WorkspaceSettingsCallbacks,
+): void {
+  let candidates: WorkspaceCandidate[] = [];
+  const selected = new Set<string>();
+
+  container.innerHTML = `
+    <div class="settings-page">
+      <h2 class="settings-page-title">Workspaces</h2>
+
+      <div class="settings-section">
+        <div class="settings-section-title">Discover repositories</div>
+        <div class="settings-row">
+          <div>
+            <div class="settings-row-label">Scan a folder</div>
+            <div class="settings-row-sublabel">Find git repositories and add several at once</div>
+          </div>
+          <div class="settings-row-actions">
+            <input type="number" class="settings-input settings-input--narrow" id="scan-depth" value="${DEFAULT_SCAN_DEPTH}" min="1" max="10" title="Maximum depth" />
+            <button class="btn" id="scan-folder">Scan…</button>
+          </div>
+        </div>
+        <div class="settings-row-sublabel" id="scan-status"></div>
+        <div class="archived-project-list" id="scan-results"></div>
+        <div class="settings-row-actions scan-footer" id="scan-footer" hidden>
+          <button class="btn" id="scan-select-all">Select all</button>
+          <button class="btn btn-restart" id="scan-add-selected">Add selected</button>
+        </div>
+      </div>
+    </div>`;
+
+  const statusEl = container.querySelector<HTMLElement>("#scan-status")!;
+  const resultsEl = container.querySelector<HTMLElement>("#scan-results")!;
+  const footerEl = container.querySelector<HTMLElement>("#scan-footer")!;
+
+  function showStatus(text: string): void {
+    statusEl.innerHTML = escapeHtml(text);
+  }
+
+  function renderResults(): void {
+    const addable = candidates.filter((c) => !c.registered);
+    footerEl.hidden = addable.length === 0;
+    resultsEl.innerHTML = candidates
+      .map((c) => {
+        const branch = c.head_branch ?? c.default_branch ?? "detached";
+        const remote = c.remotes[0]?.url ?? "no remote";
+        return `
+          <label class="archived-project-row">
+            <input type="checkbox" data-path="${escapeHtml(c.path)}" ${c.registered ? "disabled checked" : selected.has(c.path) ? "checked" : ""} />
+            <div class="archived-project-info">
+              <div class="archived-project-name">${escapeHtml(c.name)}${c.registered ? " &middot; already added" : ""}</div>
+              <div class="archived-project-meta">${escapeHtml(c.path)} &middot; ${escapeHtml(branch)} &middot; ${escapeHtml(remote)}</div>
+            </div>
+          </label>`;
+      })
+      .join("");
+
+    resultsEl.querySelectorAll<HTMLInputElement>("input[data-path]:not([disabled])").forEach((input) => {
+      input.addEventListener("change", () => {
+        const path = input.dataset.path!;
+        if (input.checked) selected.add(path);
+        else selected.delete(path);
+      });
+    });
+  }
+
+  container.querySelector("#scan-folder")?.addEventListener("click", async () => {
+    const root = await open({ directory: true, multiple: false });
+    if (!root) return;
+    const depthInput = container.querySelector<HTMLInputElement>("#scan-depth");
+    const maxDepth = Number(depthInput?.value) || DEFAULT_SCAN_DEPTH;
+
+    candidates = [];
+    selected.clear();
+    renderResults();
+    showStatus("Scanning…");
+
+    const unlisten = await listen<ScanProgress>("workspace-scan-progress", (event) => {
+      const p = event.payload;
+      showStatus(`Scanning… ${p.dirs_scanned} folders, ${p.repos_found} repositories`);
+    });
+    try {
+      candidates = await invoke<WorkspaceCandidate[]>("scan_for_workspaces", { root, maxDepth });
+      for (const c of candidates) {
+        if (!c.registered) selected.add(c.path);
+      }
+      const fresh = candidates.filter((c) => !c.registered).length;
+      showStatus(`Found ${candidates.length} repositories, ${fresh} not yet added`);
+      renderResults();
+    } catch (e) {
+      showStatus(`Scan failed: ${formatError(e)}`);
+    } finally {
+      unlisten();
+    }
+  });
+
+  container.querySelector("#scan-select-all")?.addEventListener("click", () => {
+    for (const c of candidates) {
+      if (!c.registered) selected.add(c.path);
+    }
+    renderResults();
+  });
+
+  container.querySelector("#scan-add-selected")?.addEventListener("click", async () => {
+    if (selected.size === 0) return;
+    try {
+      const report = await invoke<WorkspaceBulkAdd>("add_workspaces", { paths: [...selected] });
+      const addedPaths = new Set(report.added.map((w) => w.path));
+      candidates = candidates.map((c) =>
+        addedPaths.has(c.path) || report.already_registered.includes(c.path) ? { ...c, registered: true } : c,
+      );
+      selected.clear();
+      showStatus(`Added ${report.added.length} workspaces`);
+      renderResults();
+      callbacks.onWorkspacesAdded(report.added);
+    } catch (e) {
+      showStatus(`Adding workspaces failed: ${formatError(e)}`);
+    }
+  });
+}
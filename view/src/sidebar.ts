@@ -1,13 +1,15 @@
-import type { Workspace, Project } from "./types.ts";
+import { invoke } from "@tauri-apps/api/core";
+import type { Workspace, Project, TmuxSession } from "./types.ts";
 import { setTheme, getEffectiveTheme } from "./theme.ts";
 import type { ThemeMode } from "./types.ts";
-import { escapeHtml, formatAge } from "./utils.ts";
+import { escapeHtml, formatAge, formatError } from "./utils.ts";
 
 export interface SidebarCallbacks {
   onProjectSelect: (projectId: number) => void;
   onRenameProject: (projectId: number, name: string) => void;
   onNewThread: () => void;
   onNewProjectForWorkspace: (workspaceId: number) => void;
+  onAttachTmux: (workspaceId: number, target: string) => void;
   onAddWorkspace: () => void;
   onRemoveWorkspace: (workspaceId: number) => void;
   onArchiveProject: (projectId: number) => void;
@@ -19,6 +21,8 @@ const collapsedWorkspaces = new Set<number>();
 
 function statusIndicatorHtml(status: string): string {
   switch (status) {
+    case "queued":
+      return `<span class="status-indicator status-queued" title="Queued"></span>`;
     case "running":
       return `<span class="status-indicator status-running" title="Running"></span>`;
     case "waiting":
@@ -90,6 +94,7 @@ export function renderSidebar(
 
   const projectsByWorkspace = new Map<number, Project[]>();
   for (const project of projects) {
+    if (project.workspace_id === null) continue;
     const list = projectsByWorkspace.get(project.workspace_id) ?? [];
     list.push(project);
     projectsByWorkspace.set(project.workspace_id, list);
@@ -248,6 +253,7 @@ export function renderSidebar(
       const menu = document.createElement("div");
       menu.className = "context-menu";
       menu.innerHTML = `
+        <button class="context-menu-item" data-action="tmux">Attach tmux session&hellip;</button>
         <button class="context-menu-item context-menu-item--danger" data-action="remove">
           Remove "${escapeHtml(workspace.name)}"
         </button>`;
@@ -258,9 +264,15 @@ export function renderSidebar(
       document.body.appendChild(menu);
       activeContextMenu = menu;
 
+      menu.querySelector("[data-action='tmux']")?.addEventListener("click", (ev) => {
+        // Keep the menu open; it turns into the list of sessions
+        ev.stopPropagation();
+        showTmuxTargets(menu, workspaceId, callbacks);
+      });
+
       menu.querySelector("[data-action='remove']")?.addEventListener("click", () => {
         closeContextMenu();
-        if (confirm(`Remove workspace "${workspace.name}"? Projects will be preserved.`)) {
+        if (confirm(`Remove workspace "${workspace.name}"? Its projects will be moved to the archive.`)) {
           callbacks.onRemoveWorkspace(workspaceId);
         }
       });
@@ -293,6 +305,50 @@ export function renderSidebar(
   });
 }
 
+/** Replace a workspace's context menu with the tmux sessions and panes it can attach to. */
+async function showTmuxTargets(
+  menu: HTMLElement,
+  workspaceId: number,
+  callbacks: SidebarCallbacks,
+): Promise<void> {
+  menu.innerHTML = `<div class="context-menu-item context-menu-item--muted">Loading&hellip;</div>`;
+  let sessions: TmuxSession[];
+  try {
+    sessions = await invoke<TmuxSession[]>("list_tmux_sessions");
+  } catch (e) {
+    menu.innerHTML = `<div class="context-menu-item context-menu-item--muted">${escapeHtml(formatError(e))}</div>`;
+    return;
+  }
+  if (sessions.length === 0) {
+    menu.innerHTML = `<div class="context-menu-item context-menu-item--muted">No tmux sessions</div>`;
+    return;
+  }
+
+  menu.innerHTML = sessions
+    .map((s) => {
+      const panes = s.panes.length > 1
+        ? s.panes
+            .map((p) => `
+              <button class="context-menu-item context-menu-item--nested" data-target="${escapeHtml(p.id)}" title="${escapeHtml(p.current_path)}">
+                ${p.window_index}.${p.pane_index} ${escapeHtml(p.current_command)}
+              </button>`)
+            .join("")
+        : "";
+      return `
+        <button class="context-menu-item" data-target="${escapeHtml(s.name)}">
+          ${escapeHtml(s.name)}${s.attached > 0 ? " &middot; attached" : ""}
+        </button>${panes}`;
+    })
+    .join("");
+
+  menu.querySelectorAll<HTMLElement>("[data-target]").forEach((item) => {
+    item.addEventListener("click", () => {
+      closeContextMenu();
+      callbacks.onAttachTmux(workspaceId, item.dataset.target!);
+    });
+  });
+}
+
 function startInlineRename(nameEl: HTMLElement, projectId: number, callbacks: SidebarCallbacks): void {
   if (nameEl.tagName === "INPUT") return;
   const currentName = nameEl.textContent ?? "";
@@ -1,4 +1,4 @@
-import { invoke } from "@tauri-apps/api/core";
+import { Channel, invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { Terminal } from "@xterm/xterm";
 import { FitAddon } from "@xterm/addon-fit";
@@ -13,9 +13,6 @@ export interface TerminalSession {
   destroy: () => void;
 }
 
-// Sessions whose PTY stream has already been started (reader taken)
-const startedStreams = new Set<string>();
-
 // Track last output time per session for activity detection
 const lastOutputTimes = new Map<string, number>();
 
@@ -29,12 +26,7 @@ window.addEventListener("dacm-theme-changed", () => {
   }
 });
 
-export function markStreamStarted(sessionId: string): void {
-  startedStreams.add(sessionId);
-}
-
 export function clearStream(sessionId: string): void {
-  startedStreams.delete(sessionId);
   lastOutputTimes.delete(sessionId);
 }
 
@@ -81,13 +73,51 @@ export async function createTerminalSession(
 
   const decoder = new TextDecoder("utf-8", { fatal: false });
 
+  // Output that arrives before the scrollback is written waits behind it
+  let pending: Uint8Array[] | null = [];
+  const writeOutput = (bytes: Uint8Array) => {
+    lastOutputTimes.set(sessionId, Date.now());
+    if (pending) {
+      pending.push(bytes);
+      return;
+    }
+    terminal.write(decoder.decode(bytes, { stream: true }));
+  };
+
+  // Output arrives as events unless the binary channel is on and attached;
+  // the backend only ever uses one of the two for a given frame
   const unlistenOutput = await listen<number[]>(
     `session-output-${sessionId}`,
+    (event) => writeOutput(new Uint8Array(event.payload)),
+  );
+
+  let outputChannel: Channel<ArrayBuffer> | null = null;
+  let binaryOutput = false;
+  try {
+    binaryOutput = (await getSetting("binary_output")) === "true";
+  } catch { /* use events */ }
+  if (binaryOutput) {
+    const channel = new Channel<ArrayBuffer>();
+    channel.onmessage = (data) => writeOutput(new Uint8Array(data));
+    try {
+      await invoke("attach_session_output", { sessionId, channel });
+      outputChannel = channel;
+    } catch (e) {
+      console.error("Failed to attach output channel:", e);
+    }
+  }
+
+  // Replays hold the view at the recorded size instead of fitting it, since
+  // their output was laid out for that size
+  let fixedSize = false;
+  const fit = () => {
+    if (!fixedSize) fitAddon.fit();
+  };
+  const unlistenResize = await listen<{ rows: number; cols: number }>(
+    `session-resize-${sessionId}`,
     (event) => {
-      lastOutputTimes.set(sessionId, Date.now());
-      const bytes = new Uint8Array(event.payload);
-      const text = decoder.decode(bytes, { stream: true });
-      terminal.write(text);
+      fixedSize = true;
+      terminal.resize(event.payload.cols, event.payload.rows);
     },
   );
 
@@ -126,13 +156,20 @@ export async function createTerminalSession(
   });
 
   const resizeObserver = new ResizeObserver(() => {
-    fitAddon.fit();
+    fit();
   });
   resizeObserver.observe(container);
 
-  if (!startedStreams.has(sessionId)) {
-    startedStreams.add(sessionId);
-    await invoke("start_session_stream", { sessionId });
+  // Starts the stream, or for one that's already running returns what it
+  // has printed so far. That can repeat the first chunk after it.
+  let scrollback: number[] = [];
+  try {
+    scrollback = await invoke<number[]>("start_session_stream", { sessionId });
+  } finally {
+    const queued = pending;
+    pending = null;
+    terminal.write(decoder.decode(new Uint8Array(scrollback), { stream: true }));
+    for (const bytes of queued ?? []) writeOutput(bytes);
   }
 
   await invoke("resize_session", {
@@ -143,6 +180,10 @@ export async function createTerminalSession(
 
   function destroy() {
     unlistenOutput();
+    if (outputChannel) {
+      invoke("detach_session_output", { sessionId, channelId: outputChannel.id }).catch(() => {});
+    }
+    unlistenResize();
     unlistenExit();
     onDataDisposable.dispose();
     onResizeDisposable.dispose();
@@ -151,5 +192,5 @@ export async function createTerminalSession(
     terminal.dispose();
   }
 
-  return { terminal, sessionId, fit: () => fitAddon.fit(), destroy };
+  return { terminal, sessionId, fit, destroy };
 }
@@ -75,6 +75,13 @@ export function getTerminalTheme(): ITheme {
 }
 
 export async function setTheme(mode: ThemeMode): Promise<void> {
+  followTheme(mode);
+  await setSetting("theme", mode);
+}
+
+/** Switch to a mode that was already saved, e.g. by another window. */
+export function followTheme(mode: ThemeMode): void {
+  if (mode === currentMode) return;
   currentMode = mode;
 
   if (mediaQuery) {
@@ -89,8 +96,6 @@ export async function setTheme(mode: ThemeMode): Promise<void> {
   } else {
     applyTheme(mode);
   }
-
-  await setSetting("theme", mode);
 }
 
 export async function initTheme(): Promise<void> {
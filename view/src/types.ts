@@ -1,11 +1,24 @@
+export interface GitRemote {
+  name: string;
+  url: string;
+}
+
 export interface Workspace {
   id: number;
   name: string;
   path: string;
   created_at: string;
+  repo_root: string | null;
+  default_branch: string | null;
+  remotes: GitRemote[];
+  /** null when HEAD is detached */
+  head_branch: string | null;
+  head_commit: string | null;
+  git_refreshed_at: string | null;
+  env_profile_id: number | null;
 }
 
-export type ProjectStatus = "running" | "waiting" | "completed" | "failed" | "archived";
+export type ProjectStatus = "queued" | "running" | "waiting" | "completed" | "failed" | "archived";
 
 export interface Project {
   id: number;
@@ -13,30 +26,228 @@ export interface Project {
   description: string;
   summary: string;
   task_id: string | null;
-  workspace_id: number;
+  /** Null once the project's workspace has been removed */
+  workspace_id: number | null;
   status: ProjectStatus;
   start_time: string | null;
   iteration_count: number;
   worktree_path: string | null;
   branch_name: string | null;
   created_at: string;
+  /** Overrides the workspace's variables for this project's sessions */
+  env_profile_id: number | null;
 }
 
 export interface ProjectStatusChangedEvent {
   project_id: number;
   status: ProjectStatus;
+  previous: ProjectStatus | null;
+  reason: string;
+}
+
+export interface ProjectStatusHistory {
+  id: number;
+  project_id: number;
+  from_status: ProjectStatus | null;
+  to_status: ProjectStatus;
+  reason: string;
+  changed_at: string;
+}
+
+/** Payload of `project-progress`, when an agent reports where it is */
+export interface ProjectProgressEvent {
+  project_id: number;
+  session_id: string | null;
+  summary: string;
+  percent: number | null;
+}
+
+// --- Plans, reviews and decisions ---
+
+export type StepStatus = "pending" | "in_progress" | "completed" | "blocked" | "skipped";
+
+export interface PlanStep {
+  id: string;
+  name: string;
+  status: StepStatus;
+  details: string;
+  files: string[];
+  note?: string;
+}
+
+export interface PlanPhase {
+  id: string;
+  name: string;
+  risk?: string;
+  exit_criteria?: string;
+  steps: PlanStep[];
+}
+
+export interface PlanContent {
+  goal: string;
+  constraints: string[];
+  phases: PlanPhase[];
+}
+
+export interface Plan {
+  id: number;
+  project_id: number;
+  name: string;
+  content: PlanContent;
+  created_at: string;
+  updated_at: string;
+}
+
+/** Payload of `plan-changed` */
+export interface PlanChangedEvent {
+  project_id: number;
+  plan: Plan;
+}
+
+export interface ReviewComment {
+  id: number;
+  project_id: number;
+  file_path: string | null;
+  line: number | null;
+  body: string;
+  resolved: boolean;
+  created_at: string;
+}
+
+/** Payload of `review-requested` */
+export interface ReviewRequestedEvent {
+  project_id: number;
+  session_id: string | null;
+  summary: string;
+}
+
+export interface Decision {
+  id: number;
+  project_id: number;
+  session_id: string | null;
+  decision: string;
+  rationale: string;
+  alternatives: string[];
+  created_at: string;
+}
+
+export interface QueuedSpawn {
+  id: number;
+  project_id: number;
+  workspace_id: number;
+  working_dir: string;
+  initial_prompt: string | null;
+  cli_command: string | null;
+  model: string | null;
+  rows: number;
+  cols: number;
+  priority: number;
+  status: "pending" | "starting" | "started" | "cancelled" | "failed";
+  session_id: string | null;
+  error: string | null;
+  created_at: string;
+  started_at: string | null;
+}
+
+export interface QueueState {
+  paused: boolean;
+  mode: "fifo" | "priority";
+  max_concurrent_sessions: number;
+  max_sessions_per_workspace: number;
+  pending: number;
+  running: number;
+}
+
+export interface QueueItemStartedEvent {
+  queue_id: number;
+  project_id: number;
+  workspace_id: number;
+  session_id: string;
+}
+
+export interface SessionExitedEvent {
+  exit_code: number | null;
+  signal: string | null;
+  will_retry: boolean;
+}
+
+export interface RetryPolicy {
+  id: number;
+  scope: "workspace" | "project";
+  scope_id: number;
+  enabled: boolean;
+  max_attempts: number;
+  backoff_secs: number;
+  backoff_multiplier: number;
+  max_backoff_secs: number;
+  retryable_exit_codes: number[];
+  retry_on_signal: boolean;
+  retry_prompt: string | null;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface SessionRun {
+  id: number;
+  session_id: string;
+  project_id: number;
+  workspace_id: number;
+  attempt: number;
+  resumed: boolean;
+  exit_code: number | null;
+  exit_signal: string | null;
+  outcome: "running" | "succeeded" | "failed" | "killed" | "retried" | "gave_up";
+  started_at: string;
+  ended_at: string | null;
+  /** Variables the session was started with, secrets redacted */
+  env: Record<string, string>;
+}
+
+export interface RetryStartedEvent {
+  project_id: number;
+  previous_session_id: string;
+  session_id: string;
+  attempt: number;
+  resumed: boolean;
 }
 
 export type SessionStatus = "running" | "exited";
 
-export interface Setting {
+export type SettingJson = boolean | number | string | null;
+
+/** A setting's current value, typed by the backend registry. */
+export interface SettingValue {
   key: string;
-  value: string;
+  value: SettingJson;
+  /** True when nothing is stored and the default applies */
+  is_default: boolean;
 }
 
+/** Payload of `settings-changed`, sent to every window on each write that changed a value. */
+export interface SettingChangedEvent {
+  key: string;
+  old_value: SettingJson;
+  new_value: SettingJson;
+}
+
+export type SettingKind =
+  | { type: "bool" }
+  | { type: "integer"; min: number; max: number }
+  | { type: "text"; allow_empty: boolean }
+  | { type: "choice"; options: string[] };
+
+/** One entry of `get_settings_schema`. */
+export type SettingSchema = SettingKind & {
+  key: string;
+  nullable: boolean;
+  section: string;
+  description: string;
+  default: SettingJson;
+};
+
 export type ThemeMode = "light" | "dark" | "system";
 
-export type SettingsPage = "general" | "worktrees" | "archived";
+export type SettingsPage = "general" | "workspaces" | "worktrees" | "environment" | "secrets" | "recordings" | "archived";
 
 export interface SessionInfo {
   session_id: string;
@@ -47,6 +258,32 @@ export interface SessionInfo {
   started_at_epoch: number;
   status: SessionStatus;
   working_dir: string;
+  source: SessionSource;
+  tmux_target: string | null;
+  persistent: boolean;
+}
+
+export type SessionSource = "direct" | "tmux";
+
+export interface TmuxPane {
+  id: string;
+  window_index: number;
+  pane_index: number;
+  current_path: string;
+  current_command: string;
+  pid: number | null;
+  width: number;
+  height: number;
+  active: boolean;
+}
+
+export interface TmuxSession {
+  id: string;
+  name: string;
+  windows: number;
+  attached: number;
+  created_at: number;
+  panes: TmuxPane[];
 }
 
 export interface SystemStats {
@@ -56,6 +293,19 @@ export interface SystemStats {
   memory_percent: number;
   child_memory_mb: number;
   child_count: number;
+  streams: StreamStats[];
+}
+
+export interface StreamStats {
+  session_id: string;
+  binary: boolean;
+  bytes: number;
+  chunks: number;
+  frames: number;
+  dropped_frames: number;
+  stalls: number;
+  last_lag_ms: number;
+  max_lag_ms: number;
 }
 
 // --- Model / Interface types ---
@@ -75,3 +325,256 @@ export interface ModelGroup {
   label: string;
   models: ModelOption[];
 }
+
+// --- Audit log ---
+
+export type EventType =
+  | "workspace_added"
+  | "workspace_removed"
+  | "project_created"
+  | "project_renamed"
+  | "project_status_changed"
+  | "project_deleted"
+  | "worktree_created"
+  | "session_queued"
+  | "session_spawned"
+  | "session_killed"
+  | "session_exited"
+  | "session_retry_scheduled"
+  | "session_attached"
+  | "session_detached"
+  | "plan_saved"
+  | "plan_step_updated"
+  | "progress_reported"
+  | "review_requested"
+  | "review_comment_added"
+  | "decision_logged";
+
+export interface AuditEvent {
+  id: number;
+  event_type: EventType;
+  workspace_id: number | null;
+  project_id: number | null;
+  session_id: string | null;
+  payload: Record<string, unknown>;
+  created_at: string;
+}
+
+export interface EventFilter {
+  project_id?: number;
+  workspace_id?: number;
+  event_types?: EventType[];
+  since?: string;
+  until?: string;
+  before_id?: number;
+  limit?: number;
+}
+
+export interface EventPage {
+  events: AuditEvent[];
+  next_cursor: number | null;
+}
+
+export type ErrorCode =
+  | "not_found"
+  | "conflict"
+  | "validation"
+  | "db"
+  | "pty"
+  | "git"
+  | "hook"
+  | "io"
+  | "unavailable"
+  | "internal";
+
+/** Error shape returned by every backend command. */
+export interface AppError {
+  code: ErrorCode;
+  message: string;
+  details?: Record<string, unknown>;
+}
+
+export type RemoveMode = "refuse" | "archive" | "cascade";
+
+/** A workspace's `.dacm.toml` merged over the global settings. */
+export type HookStage = "post_worktree_create" | "pre_session_spawn" | "post_session_exit" | "pre_worktree_remove" | "exit_criteria";
+
+export type Hook =
+  | { kind: "run"; command: string }
+  | { kind: "copy"; from: string; to: string };
+
+/** Exit criteria are configured on their own, not under `[hooks]` */
+export type Hooks = Record<Exclude<HookStage, "exit_criteria">, Hook[]>;
+
+export interface HookRun {
+  id: number;
+  workspace_id: number | null;
+  project_id: number | null;
+  session_id: string | null;
+  stage: HookStage;
+  hook: string;
+  working_dir: string;
+  exit_code: number | null;
+  /** null while the hook is still running */
+  success: boolean | null;
+  output: string;
+  started_at: string;
+  finished_at: string | null;
+}
+
+/** Payload of `hook-output`, one per line */
+export interface HookOutputEvent {
+  run_id: number;
+  project_id: number | null;
+  stage: HookStage;
+  line: string;
+}
+
+/** Payload of `hook-finished` */
+export interface HookFinishedEvent {
+  run_id: number;
+  workspace_id: number | null;
+  project_id: number | null;
+  stage: HookStage;
+  hook: string;
+  exit_code: number | null;
+  success: boolean;
+}
+
+export interface WorkspaceConfig {
+  workspace_id: number;
+  workspace_path: string;
+  config_file: string | null;
+  cli: string;
+  model: string | null;
+  max_sessions: number;
+  persistent_sessions: boolean;
+  /** 0 means no limit */
+  hook_timeout_secs: number;
+  exit_criteria: string[];
+  env: Record<string, string>;
+  /** Env var name to secret name; values are never sent to the webview */
+  secrets: Record<string, string>;
+  worktree_base_path: string | null;
+  worktree_branch_pattern: string;
+  hooks: Hooks;
+  /** Keys the config file set, e.g. "model" or "worktree.base_path" */
+  overridden: string[];
+}
+
+export interface WorktreeCreated {
+  project: Project;
+  path: string;
+  branch: string;
+  /** Post-create hooks that ran, stopping at the first failure */
+  hooks: HookRun[];
+}
+
+export interface WorkspaceCandidate {
+  path: string;
+  name: string;
+  default_branch: string | null;
+  head_branch: string | null;
+  remotes: GitRemote[];
+  workspace_id: number | null;
+  registered: boolean;
+}
+
+export interface ScanProgress {
+  root: string;
+  dirs_scanned: number;
+  repos_found: number;
+  current: string;
+}
+
+export interface WorkspaceBulkAdd {
+  added: Workspace[];
+  already_registered: string[];
+}
+
+export interface WorkspaceRemoval {
+  workspace_id: number;
+  mode: RemoveMode;
+  archived_project_ids: number[];
+  deleted_project_ids: number[];
+  removed_worktrees: string[];
+  worktree_errors: string[];
+}
+
+export interface BackupInfo {
+  path: string;
+  size_bytes: number;
+}
+
+export interface ExportSummary {
+  path: string;
+  workspaces: number;
+  projects: number;
+  settings: number;
+  plans: number;
+  review_comments: number;
+  decisions: number;
+}
+
+export interface ImportReport {
+  workspaces_created: number;
+  workspaces_merged: number;
+  projects_imported: number;
+  settings_imported: number;
+  plans_imported: number;
+  review_comments_imported: number;
+  decisions_imported: number;
+}
+
+export type DbStatus =
+  | { state: "ready"; path: string }
+  | {
+      state: "error";
+      path: string;
+      kind: "open" | "corrupt" | "newer_schema" | "migration";
+      message: string;
+      restored_snapshot: string | null;
+    };
+
+export type SecretBackend = "keyring" | "file";
+
+/** A stored secret's name and location. Values never leave the backend. */
+export interface SecretInfo {
+  name: string;
+  backend: SecretBackend;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface SecretsStatus {
+  /** Where new secrets are stored */
+  backend: SecretBackend;
+  keyring_available: boolean;
+  /** Whether DACM_SECRETS_KEY is set, so the file backend can be used */
+  file_available: boolean;
+}
+
+/** A named set of environment variables attached to workspaces or projects. */
+export interface EnvProfile {
+  id: number;
+  name: string;
+  vars: Record<string, string>;
+  created_at: string;
+  updated_at: string;
+}
+
+/** A session saved as an asciicast v2 file. `id` is the session id. */
+export interface RecordingInfo {
+  id: string;
+  path: string;
+  project_id: number | null;
+  workspace_id: number | null;
+  width: number;
+  height: number;
+  /** Unix seconds */
+  started_at: number | null;
+  duration_secs: number;
+  size_bytes: number;
+  /** Still being written by a running session */
+  active: boolean;
+}
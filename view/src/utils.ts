@@ -1,4 +1,4 @@
-import type { ModelOption } from "./types.ts";
+import type { AppError, ErrorCode, ModelOption } from "./types.ts";
 import { ALL_MODELS } from "./constants.ts";
 
 export function escapeHtml(text: string): string {
@@ -26,3 +26,14 @@ export function formatAge(dateStr: string): string {
   if (diffDays < 30) return `${diffDays}d`;
   return `${diffMonths}mo`;
 }
+
+export function isAppError(e: unknown, code?: ErrorCode): e is AppError {
+  if (typeof e !== "object" || e === null || !("code" in e) || !("message" in e)) return false;
+  return code === undefined || (e as AppError).code === code;
+}
+
+export function formatError(e: unknown): string {
+  if (isAppError(e)) return e.message;
+  if (e instanceof Error) return e.message;
+  return String(e);
+}